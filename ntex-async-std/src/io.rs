@@ -23,6 +23,10 @@ impl Handle for TcpStream {
             if let Ok(addr) = self.0.peer_addr() {
                 return Some(Box::new(types::PeerAddr(addr)));
             }
+        } else if id == any::TypeId::of::<types::LocalAddr>() {
+            if let Ok(addr) = self.0.local_addr() {
+                return Some(Box::new(types::LocalAddr(addr)));
+            }
         }
         None
     }
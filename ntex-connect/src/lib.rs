@@ -18,7 +18,7 @@ pub mod rustls;
 
 pub use self::error::ConnectError;
 pub use self::message::{Address, Connect};
-pub use self::resolve::Resolver;
+pub use self::resolve::{CachingResolver, Resolve, Resolver, SystemResolver};
 pub use self::service::Connector;
 
 use ntex_io::Io;
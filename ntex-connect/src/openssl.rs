@@ -10,9 +10,10 @@ use ntex_tls::openssl::SslConnector as IoSslConnector;
 use ntex_util::future::{BoxFuture, Ready};
 
 use super::{Address, Connect, ConnectError, Connector as BaseConnector};
+use super::{Resolve, SystemResolver};
 
-pub struct Connector<T> {
-    connector: Pipeline<BaseConnector<T>>,
+pub struct Connector<T, R = SystemResolver> {
+    connector: Pipeline<BaseConnector<T, R>>,
     openssl: SslConnector,
 }
 
@@ -24,27 +25,50 @@ impl<T: Address> Connector<T> {
             openssl: connector,
         }
     }
+}
+
+impl<T: Address, R> Connector<T, R> {
+    /// Use custom dns resolver
+    pub fn resolver<U: Resolve>(self, resolver: U) -> Connector<T, U> {
+        self.map(|conn| conn.resolver(resolver))
+    }
 
     /// Set memory pool.
     ///
     /// Use specified memory pool for memory allocations. By default P0
     /// memory pool is used.
     pub fn memory_pool(self, id: PoolId) -> Self {
+        self.map(|conn| conn.memory_pool(id))
+    }
+
+    /// Use custom tcp connector
+    ///
+    /// Tcp connector's resolver, memory pool and connection attempt
+    /// settings are used for opening connections.
+    pub fn connector<U: Resolve>(self, connector: BaseConnector<T, U>) -> Connector<T, U> {
+        Connector {
+            connector: connector.into(),
+            openssl: self.openssl,
+        }
+    }
+
+    fn map<U, F>(self, f: F) -> Connector<T, U>
+    where
+        F: FnOnce(BaseConnector<T, R>) -> BaseConnector<T, U>,
+    {
         let connector = self
             .connector
             .into_service()
-            .expect("Connector has been cloned")
-            .memory_pool(id)
-            .into();
+            .expect("Connector has been cloned");
 
-        Self {
-            connector,
+        Connector {
+            connector: f(connector).into(),
             openssl: self.openssl,
         }
     }
 }
 
-impl<T: Address> Connector<T> {
+impl<T: Address, R: Resolve> Connector<T, R> {
     /// Resolve and connect to remote host
     pub async fn connect<U>(&self, message: U) -> Result<Io<Layer<SslFilter>>, ConnectError>
     where
@@ -80,7 +104,7 @@ impl<T: Address> Connector<T> {
     }
 }
 
-impl<T> Clone for Connector<T> {
+impl<T, R> Clone for Connector<T, R> {
     fn clone(&self) -> Self {
         Connector {
             connector: self.connector.clone(),
@@ -89,7 +113,7 @@ impl<T> Clone for Connector<T> {
     }
 }
 
-impl<T> fmt::Debug for Connector<T> {
+impl<T, R: fmt::Debug> fmt::Debug for Connector<T, R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Connector(openssl)")
             .field("connector", &self.connector)
@@ -98,10 +122,10 @@ impl<T> fmt::Debug for Connector<T> {
     }
 }
 
-impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T> {
+impl<T: Address, R: Resolve, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T, R> {
     type Response = Io<Layer<SslFilter>>;
     type Error = ConnectError;
-    type Service = Connector<T>;
+    type Service = Connector<T, R>;
     type InitError = ();
     type Future<'f> = Ready<Self::Service, Self::InitError> where Self: 'f;
 
@@ -111,7 +135,7 @@ impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T> {
     }
 }
 
-impl<T: Address> Service<Connect<T>> for Connector<T> {
+impl<T: Address, R: Resolve> Service<Connect<T>> for Connector<T, R> {
     type Response = Io<Layer<SslFilter>>;
     type Error = ConnectError;
     type Future<'f> = BoxFuture<'f, Result<Self::Response, Self::Error>>;
@@ -142,4 +166,31 @@ mod tests {
             .await;
         assert!(result.is_err());
     }
+
+    #[ntex::test]
+    async fn test_openssl_resolver() {
+        use std::sync::{atomic::AtomicUsize, atomic::Ordering, Arc};
+
+        let conns = Arc::new(AtomicUsize::new(0));
+        let conns2 = conns.clone();
+        let server = ntex::server::test_server(move || {
+            let conns = conns2.clone();
+            ntex::service::fn_service(move |_| {
+                conns.fetch_add(1, Ordering::Relaxed);
+                async { Ok::<_, ()>(()) }
+            })
+        });
+
+        let resolver =
+            crate::CachingResolver::new().host("ntex.test", "127.0.0.1".parse().unwrap());
+        let ssl = SslConnector::builder(SslMethod::tls()).unwrap();
+        let connector = Connector::new(ssl.build()).resolver(resolver);
+        assert!(format!("{:?}", connector).contains("ntex.test"));
+
+        let result = connector
+            .connect(format!("ntex.test:{}", server.addr().port()))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(conns.load(Ordering::Relaxed), 1);
+    }
 }
@@ -1,80 +1,323 @@
-use std::{fmt, io, marker, net};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use std::{cell::RefCell, fmt, io, marker, net, rc::Rc};
 
 use ntex_rt::spawn_blocking;
 use ntex_service::{Service, ServiceCtx, ServiceFactory};
 use ntex_util::future::{BoxFuture, Either, Ready};
+use ntex_util::time::{now, Seconds};
 
 use crate::{Address, Connect, ConnectError};
 
-#[derive(Copy)]
+/// Host name resolution
+///
+/// Resolver receives host name without port, port is used for
+/// returned socket addresses.
+pub trait Resolve: fmt::Debug + 'static {
+    /// Resolve host name to socket addresses
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, io::Result<Vec<net::SocketAddr>>>;
+}
+
+impl<R: Resolve + ?Sized> Resolve for Rc<R> {
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, io::Result<Vec<net::SocketAddr>>> {
+        (**self).resolve(host, port)
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+/// System resolver
+///
+/// Uses `ToSocketAddrs` in blocking thread pool.
+pub struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, io::Result<Vec<net::SocketAddr>>> {
+        let host = host.to_string();
+        Box::pin(async move {
+            let fut = spawn_blocking(move || {
+                net::ToSocketAddrs::to_socket_addrs(&(host, port)).map(|a| a.collect())
+            });
+            match fut.await {
+                Ok(res) => res,
+                Err(e) => Err(io::Error::new(io::ErrorKind::Other, e)),
+            }
+        })
+    }
+}
+
+/// Caching resolver
+///
+/// Resolved addresses are cached for configured ttl, failed lookups
+/// are cached for negative ttl. Static host overrides take precedence
+/// over cache and inner resolver. Cloned resolvers share the cache,
+/// expired entries are removed when new lookup result is cached.
+///
+/// ```rust
+/// use ntex_connect::{CachingResolver, Connector};
+/// use ntex_util::time::Seconds;
+///
+/// let resolver = CachingResolver::new()
+///     .ttl(Seconds(300))
+///     .host("api.example.com", "127.0.0.1".parse().unwrap());
+/// let connector = Connector::<String>::new().resolver(resolver);
+/// ```
+pub struct CachingResolver<R = SystemResolver> {
+    resolver: R,
+    ttl: Seconds,
+    negative_ttl: Seconds,
+    hosts: Rc<HashMap<String, Vec<net::IpAddr>>>,
+    cache: Rc<RefCell<HashMap<String, CacheEntry>>>,
+}
+
+struct CacheEntry {
+    expires: Instant,
+    result: Result<Vec<net::IpAddr>, (io::ErrorKind, String)>,
+}
+
+impl CachingResolver {
+    /// Create caching resolver for system resolver
+    pub fn new() -> Self {
+        CachingResolver::with_resolver(SystemResolver)
+    }
+}
+
+impl Default for CachingResolver {
+    fn default() -> Self {
+        CachingResolver::new()
+    }
+}
+
+impl<R: Resolve> CachingResolver<R> {
+    /// Create caching resolver for custom resolver
+    pub fn with_resolver(resolver: R) -> Self {
+        CachingResolver {
+            resolver,
+            ttl: Seconds(60),
+            negative_ttl: Seconds(5),
+            hosts: Rc::new(HashMap::new()),
+            cache: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Set ttl for resolved addresses
+    ///
+    /// Zero ttl disables caching. By default ttl is 60 seconds.
+    pub fn ttl(mut self, ttl: Seconds) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set ttl for failed lookups
+    ///
+    /// Zero ttl disables negative caching. By default ttl is 5 seconds.
+    pub fn negative_ttl(mut self, ttl: Seconds) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Add static host override
+    ///
+    /// Host name resolves to provided addresses, could be called
+    /// multiple times for the same host.
+    pub fn host<H: AsRef<str>>(mut self, host: H, addr: net::IpAddr) -> Self {
+        Rc::make_mut(&mut self.hosts)
+            .entry(host.as_ref().to_ascii_lowercase())
+            .or_default()
+            .push(addr);
+        self
+    }
+
+    /// Remove all cached entries
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    fn store(&self, host: String, res: &io::Result<Vec<net::SocketAddr>>) {
+        let (ttl, result) = match res {
+            Ok(addrs) if !addrs.is_empty() => {
+                (self.ttl, Ok(addrs.iter().map(|a| a.ip()).collect()))
+            }
+            Ok(_) => (self.negative_ttl, Ok(Vec::new())),
+            Err(e) => (self.negative_ttl, Err((e.kind(), e.to_string()))),
+        };
+        if !ttl.is_zero() {
+            let now = now();
+            let mut cache = self.cache.borrow_mut();
+            // drop expired entries of other hosts
+            cache.retain(|_, entry| entry.expires > now);
+            cache.insert(
+                host,
+                CacheEntry {
+                    result,
+                    expires: now + Duration::from(ttl),
+                },
+            );
+        }
+    }
+}
+
+impl<R: Resolve> Resolve for CachingResolver<R> {
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, io::Result<Vec<net::SocketAddr>>> {
+        Box::pin(async move {
+            let key = host.to_ascii_lowercase();
+            if let Some(ips) = self.hosts.get(&key) {
+                return Ok(with_port(ips, port));
+            }
+
+            {
+                let mut cache = self.cache.borrow_mut();
+                if let Some(entry) = cache.get(&key) {
+                    if entry.expires > now() {
+                        trace!("DNS resolver: cache hit for {:?}", host);
+                        return match entry.result {
+                            Ok(ref ips) => Ok(with_port(ips, port)),
+                            Err((kind, ref msg)) => Err(io::Error::new(kind, msg.clone())),
+                        };
+                    }
+                    cache.remove(&key);
+                }
+            }
+
+            let res = self.resolver.resolve(host, port).await;
+            self.store(key, &res);
+            res
+        })
+    }
+}
+
+impl<R: Clone> Clone for CachingResolver<R> {
+    fn clone(&self) -> Self {
+        CachingResolver {
+            resolver: self.resolver.clone(),
+            ttl: self.ttl,
+            negative_ttl: self.negative_ttl,
+            hosts: self.hosts.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for CachingResolver<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingResolver")
+            .field("resolver", &self.resolver)
+            .field("ttl", &self.ttl)
+            .field("negative_ttl", &self.negative_ttl)
+            .field("hosts", &self.hosts)
+            .field("cached", &self.cache.borrow().len())
+            .finish()
+    }
+}
+
+fn with_port(ips: &[net::IpAddr], port: u16) -> Vec<net::SocketAddr> {
+    ips.iter()
+        .map(|ip| net::SocketAddr::new(*ip, port))
+        .collect()
+}
+
+/// Split host into name and port parts
+fn split_host(host: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = host.strip_prefix('[') {
+        // ipv6 address in brackets
+        if let Some((name, rest)) = rest.split_once(']') {
+            return (name, rest.strip_prefix(':'));
+        }
+    }
+    match host.split_once(':') {
+        Some((name, port)) => (name, Some(port)),
+        None => (host, None),
+    }
+}
+
 /// DNS Resolver Service
-pub struct Resolver<T>(marker::PhantomData<T>);
+pub struct Resolver<T, R = SystemResolver> {
+    resolver: R,
+    _t: marker::PhantomData<T>,
+}
 
 impl<T> Resolver<T> {
-    /// Create new resolver instance with custom configuration and options.
+    /// Create new resolver instance with system resolver.
     pub fn new() -> Self {
-        Resolver(marker::PhantomData)
+        Resolver::with(SystemResolver)
+    }
+}
+
+impl<T, R> Resolver<T, R> {
+    /// Create new resolver instance with custom resolver.
+    pub fn with(resolver: R) -> Self {
+        Resolver {
+            resolver,
+            _t: marker::PhantomData,
+        }
     }
 }
 
-impl<T: Address> Resolver<T> {
+impl<T: Address, R: Resolve> Resolver<T, R> {
     /// Lookup ip addresses for provided host
     pub async fn lookup(&self, mut req: Connect<T>) -> Result<Connect<T>, ConnectError> {
         if req.addr.is_some() || req.req.addr().is_some() {
-            Ok(req)
+            return Ok(req);
         } else if let Ok(ip) = req.host().parse() {
             req.addr = Some(Either::Left(net::SocketAddr::new(ip, req.port())));
-            Ok(req)
-        } else {
-            trace!("DNS resolver: resolving host {:?}", req.host());
+            return Ok(req);
+        }
 
-            let host = if req.host().contains(':') {
-                req.host().to_string()
-            } else {
-                format!("{}:{}", req.host(), req.port())
-            };
+        let (name, port) = split_host(req.host());
+        if let Some(port) = port {
+            if port.parse::<u16>().is_err() {
+                return Err(ConnectError::Resolver(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid port value",
+                )));
+            }
+        }
+        if let Ok(ip) = name.parse() {
+            req.addr = Some(Either::Left(net::SocketAddr::new(ip, req.port())));
+            return Ok(req);
+        }
 
-            let fut = spawn_blocking(move || net::ToSocketAddrs::to_socket_addrs(&host));
-            match fut.await {
-                Ok(Ok(ips)) => {
-                    let port = req.port();
-                    let req = req.set_addrs(ips.map(|mut ip| {
-                        ip.set_port(port);
-                        ip
-                    }));
-
-                    trace!(
-                        "DNS resolver: host {:?} resolved to {:?}",
-                        req.host(),
-                        req.addrs()
-                    );
-
-                    if req.addr.is_none() {
-                        Err(ConnectError::NoRecords)
-                    } else {
-                        Ok(req)
-                    }
-                }
-                Ok(Err(e)) => {
-                    trace!(
-                        "DNS resolver: failed to resolve host {:?} err: {}",
-                        req.host(),
-                        e
-                    );
-                    Err(ConnectError::Resolver(e))
-                }
-                Err(e) => {
-                    trace!(
-                        "DNS resolver: failed to resolve host {:?} err: {}",
-                        req.host(),
-                        e
-                    );
-                    Err(ConnectError::Resolver(io::Error::new(
-                        io::ErrorKind::Other,
-                        e,
-                    )))
+        trace!("DNS resolver: resolving host {:?}", req.host());
+
+        match self.resolver.resolve(name, req.port()).await {
+            Ok(addrs) => {
+                let req = req.set_addrs(addrs);
+
+                trace!(
+                    "DNS resolver: host {:?} resolved to {:?}",
+                    req.host(),
+                    req.addrs()
+                );
+
+                if req.addr.is_none() {
+                    Err(ConnectError::NoRecords)
+                } else {
+                    Ok(req)
                 }
             }
+            Err(e) => {
+                trace!(
+                    "DNS resolver: failed to resolve host {:?} err: {}",
+                    req.host(),
+                    e
+                );
+                Err(ConnectError::Resolver(e))
+            }
         }
     }
 }
@@ -85,22 +328,28 @@ impl<T> Default for Resolver<T> {
     }
 }
 
-impl<T> Clone for Resolver<T> {
+impl<T, R: Copy> Copy for Resolver<T, R> {}
+
+impl<T, R: Clone> Clone for Resolver<T, R> {
     fn clone(&self) -> Self {
-        Resolver(marker::PhantomData)
+        Resolver::with(self.resolver.clone())
     }
 }
 
-impl<T> fmt::Debug for Resolver<T> {
+impl<T, R: fmt::Debug> fmt::Debug for Resolver<T, R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Resolver").finish()
+        f.debug_struct("Resolver")
+            .field("resolver", &self.resolver)
+            .finish()
     }
 }
 
-impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Resolver<T> {
+impl<T: Address, R: Resolve + Clone, C: 'static> ServiceFactory<Connect<T>, C>
+    for Resolver<T, R>
+{
     type Response = Connect<T>;
     type Error = ConnectError;
-    type Service = Resolver<T>;
+    type Service = Resolver<T, R>;
     type InitError = ();
     type Future<'f> = Ready<Self::Service, Self::InitError>;
 
@@ -110,7 +359,7 @@ impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Resolver<T> {
     }
 }
 
-impl<T: Address> Service<Connect<T>> for Resolver<T> {
+impl<T: Address, R: Resolve> Service<Connect<T>> for Resolver<T, R> {
     type Response = Connect<T>;
     type Error = ConnectError;
     type Future<'f> = BoxFuture<'f, Result<Connect<T>, Self::Error>>;
@@ -148,5 +397,104 @@ mod tests {
         let addrs: Vec<_> = res.addrs().collect();
         assert_eq!(addrs.len(), 1);
         assert!(addrs.contains(&addr));
+
+        let res = srv.call(Connect::new("localhost:99999")).await;
+        assert!(res.is_err());
+
+        let res = srv.call(Connect::new("[::1]:8080")).await.unwrap();
+        let addrs: Vec<_> = res.addrs().collect();
+        assert_eq!(addrs, vec!["[::1]:8080".parse().unwrap()]);
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct Counter(Rc<std::cell::Cell<usize>>);
+
+    impl Resolve for Counter {
+        fn resolve<'a>(
+            &'a self,
+            host: &'a str,
+            port: u16,
+        ) -> BoxFuture<'a, io::Result<Vec<net::SocketAddr>>> {
+            self.0.set(self.0.get() + 1);
+            let res = if host == "test.local" {
+                Ok(vec![net::SocketAddr::new([127, 0, 0, 1].into(), port)])
+            } else {
+                Err(io::Error::new(io::ErrorKind::NotFound, "not found"))
+            };
+            Box::pin(async move { res })
+        }
+    }
+
+    #[ntex::test]
+    async fn caching_resolver() {
+        let counter = Counter::default();
+        let resolver = CachingResolver::with_resolver(counter.clone())
+            .host("Override.Local", "10.0.0.1".parse().unwrap())
+            .host("override.local", "10.0.0.2".parse().unwrap());
+        assert!(format!("{:?}", resolver).contains("CachingResolver"));
+        let srv = Resolver::with(resolver.clone()).pipeline(()).await.unwrap();
+
+        // positive caching
+        let res = srv.call(Connect::new("test.local:80")).await.unwrap();
+        assert_eq!(
+            res.addrs().collect::<Vec<_>>(),
+            vec!["127.0.0.1:80".parse().unwrap()]
+        );
+        let res = srv.call(Connect::new("TEST.local:90")).await.unwrap();
+        assert_eq!(
+            res.addrs().collect::<Vec<_>>(),
+            vec!["127.0.0.1:90".parse().unwrap()]
+        );
+        assert_eq!(counter.0.get(), 1);
+
+        // negative caching
+        assert!(srv.call(Connect::new("missing.local")).await.is_err());
+        let err = srv.call(Connect::new("missing.local")).await.unwrap_err();
+        assert!(
+            matches!(err, ConnectError::Resolver(e) if e.kind() == io::ErrorKind::NotFound)
+        );
+        assert_eq!(counter.0.get(), 2);
+
+        // host overrides
+        let res = srv.call(Connect::new("override.local:8080")).await.unwrap();
+        let addrs: Vec<_> = res.addrs().collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "10.0.0.1:8080".parse().unwrap(),
+                "10.0.0.2:8080".parse().unwrap()
+            ]
+        );
+        assert_eq!(counter.0.get(), 2);
+
+        // clones share cache
+        resolver.clear();
+        srv.call(Connect::new("test.local")).await.unwrap();
+        assert_eq!(counter.0.get(), 3);
+
+        // expired entries are removed on store
+        resolver.cache.borrow_mut().insert(
+            "expired.local".to_string(),
+            CacheEntry {
+                expires: now() - Duration::from_secs(1),
+                result: Ok(Vec::new()),
+            },
+        );
+        assert!(srv.call(Connect::new("other.local")).await.is_err());
+        let cache = resolver.cache.borrow();
+        assert!(!cache.contains_key("expired.local"));
+        assert!(cache.contains_key("other.local"));
+        drop(cache);
+
+        // caching disabled
+        let resolver = CachingResolver::with_resolver(counter.clone())
+            .ttl(Seconds::ZERO)
+            .negative_ttl(Seconds::ZERO);
+        let srv = Resolver::with(resolver);
+        srv.lookup(Connect::new("test.local")).await.unwrap();
+        srv.lookup(Connect::new("test.local")).await.unwrap();
+        assert!(srv.lookup(Connect::new("missing.local")).await.is_err());
+        assert!(srv.lookup(Connect::new("missing.local")).await.is_err());
+        assert_eq!(counter.0.get(), 7);
     }
 }
@@ -10,10 +10,11 @@ use ntex_tls::rustls::TlsConnector;
 use ntex_util::future::{BoxFuture, Ready};
 
 use super::{Address, Connect, ConnectError, Connector as BaseConnector};
+use super::{Resolve, SystemResolver};
 
 /// Rustls connector factory
-pub struct Connector<T> {
-    connector: Pipeline<BaseConnector<T>>,
+pub struct Connector<T, R = SystemResolver> {
+    connector: Pipeline<BaseConnector<T, R>>,
     inner: TlsConnector,
 }
 
@@ -33,26 +34,50 @@ impl<T: Address> Connector<T> {
             connector: BaseConnector::default().into(),
         }
     }
+}
+
+impl<T: Address, R> Connector<T, R> {
+    /// Use custom dns resolver
+    pub fn resolver<U: Resolve>(self, resolver: U) -> Connector<T, U> {
+        self.map(|conn| conn.resolver(resolver))
+    }
 
     /// Set memory pool.
     ///
     /// Use specified memory pool for memory allocations. By default P0
     /// memory pool is used.
     pub fn memory_pool(self, id: PoolId) -> Self {
+        self.map(|conn| conn.memory_pool(id))
+    }
+
+    /// Use custom tcp connector
+    ///
+    /// Tcp connector's resolver, memory pool and connection attempt
+    /// settings are used for opening connections.
+    pub fn connector<U: Resolve>(self, connector: BaseConnector<T, U>) -> Connector<T, U> {
+        Connector {
+            connector: connector.into(),
+            inner: self.inner,
+        }
+    }
+
+    fn map<U, F>(self, f: F) -> Connector<T, U>
+    where
+        F: FnOnce(BaseConnector<T, R>) -> BaseConnector<T, U>,
+    {
         let connector = self
             .connector
             .into_service()
-            .unwrap()
-            .memory_pool(id)
-            .into();
-        Self {
-            connector,
+            .expect("Connector has been cloned");
+
+        Connector {
+            connector: f(connector).into(),
             inner: self.inner,
         }
     }
 }
 
-impl<T: Address + 'static> Connector<T> {
+impl<T: Address + 'static, R: Resolve> Connector<T, R> {
     /// Resolve and connect to remote host
     pub async fn connect<U>(&self, message: U) -> Result<Io<Layer<TlsFilter>>, ConnectError>
     where
@@ -84,7 +109,7 @@ impl<T: Address + 'static> Connector<T> {
     }
 }
 
-impl<T> Clone for Connector<T> {
+impl<T, R> Clone for Connector<T, R> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
@@ -93,7 +118,7 @@ impl<T> Clone for Connector<T> {
     }
 }
 
-impl<T> fmt::Debug for Connector<T> {
+impl<T, R: fmt::Debug> fmt::Debug for Connector<T, R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Connector(rustls)")
             .field("connector", &self.connector)
@@ -101,10 +126,10 @@ impl<T> fmt::Debug for Connector<T> {
     }
 }
 
-impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T> {
+impl<T: Address, R: Resolve, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T, R> {
     type Response = Io<Layer<TlsFilter>>;
     type Error = ConnectError;
-    type Service = Connector<T>;
+    type Service = Connector<T, R>;
     type InitError = ();
     type Future<'f> = Ready<Self::Service, Self::InitError> where C: 'f;
 
@@ -114,7 +139,7 @@ impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T> {
     }
 }
 
-impl<T: Address> Service<Connect<T>> for Connector<T> {
+impl<T: Address, R: Resolve> Service<Connect<T>> for Connector<T, R> {
     type Response = Io<Layer<TlsFilter>>;
     type Error = ConnectError;
     type Future<'f> = BoxFuture<'f, Result<Self::Response, Self::Error>>;
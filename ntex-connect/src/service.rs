@@ -5,23 +5,58 @@ use ntex_bytes::{PoolId, PoolRef};
 use ntex_io::{types, Io};
 use ntex_service::{Service, ServiceCtx, ServiceFactory};
 use ntex_util::future::{BoxFuture, Either, Ready};
+use ntex_util::time::{Millis, Sleep};
 
+use crate::resolve::{Resolve, SystemResolver};
 use crate::{net::tcp_connect_in, Address, Connect, ConnectError, Resolver};
 
-#[derive(Copy)]
-pub struct Connector<T> {
-    resolver: Resolver<T>,
+pub struct Connector<T, R = SystemResolver> {
+    resolver: Resolver<T, R>,
+    cfg: ConnectConfig,
+}
+
+type ConnectFn = fn(SocketAddr, PoolRef) -> BoxFuture<'static, io::Result<Io>>;
+
+#[derive(Copy, Clone, Debug)]
+struct ConnectConfig {
     pool: PoolRef,
     tag: &'static str,
+    delay: Millis,
+    attempt_timeout: Millis,
+    timeout: Millis,
+    /// opens tcp connection, replaced in tests
+    connect: ConnectFn,
+}
+
+impl Default for ConnectConfig {
+    fn default() -> Self {
+        ConnectConfig {
+            pool: PoolId::P0.pool_ref(),
+            tag: "",
+            delay: Millis(250),
+            attempt_timeout: Millis::ZERO,
+            timeout: Millis::ZERO,
+            connect: tcp_connect,
+        }
+    }
 }
 
 impl<T> Connector<T> {
-    /// Construct new connect service with custom dns resolver
+    /// Construct new connect service with system dns resolver
     pub fn new() -> Self {
         Connector {
             resolver: Resolver::new(),
-            pool: PoolId::P0.pool_ref(),
-            tag: "",
+            cfg: ConnectConfig::default(),
+        }
+    }
+}
+
+impl<T, R> Connector<T, R> {
+    /// Use custom dns resolver
+    pub fn resolver<U: Resolve>(self, resolver: U) -> Connector<T, U> {
+        Connector {
+            resolver: Resolver::with(resolver),
+            cfg: self.cfg,
         }
     }
 
@@ -30,7 +65,7 @@ impl<T> Connector<T> {
     /// Use specified memory pool for memory allocations. By default P0
     /// memory pool is used.
     pub fn memory_pool(mut self, id: PoolId) -> Self {
-        self.pool = id.pool_ref();
+        self.cfg.pool = id.pool_ref();
         self
     }
 
@@ -38,22 +73,52 @@ impl<T> Connector<T> {
     ///
     /// Set tag to opened io object.
     pub fn tag(mut self, tag: &'static str) -> Self {
-        self.tag = tag;
+        self.cfg.tag = tag;
+        self
+    }
+
+    /// Set connection attempt delay
+    ///
+    /// Resolved addresses are tried concurrently according to Happy Eyeballs
+    /// algorithm (RFC 8305), address families are interleaved. Next connection
+    /// attempt starts if previous attempts are not completed within the delay,
+    /// pending attempts are cancelled once connection is established.
+    /// Zero delay disables concurrent attempts, addresses are tried one after
+    /// another. By default delay is 250 millis.
+    pub fn attempt_delay<U: Into<Millis>>(mut self, delay: U) -> Self {
+        self.cfg.delay = delay.into();
+        self
+    }
+
+    /// Set timeout for single connection attempt
+    ///
+    /// Timed out attempt fails and next address is tried. By default
+    /// attempt timeout is not set.
+    pub fn attempt_timeout<U: Into<Millis>>(mut self, timeout: U) -> Self {
+        self.cfg.attempt_timeout = timeout.into();
+        self
+    }
+
+    /// Set connect timeout
+    ///
+    /// Timeout includes host name resolution and all connection attempts.
+    /// By default timeout is not set.
+    pub fn timeout<U: Into<Millis>>(mut self, timeout: U) -> Self {
+        self.cfg.timeout = timeout.into();
         self
     }
 }
 
-impl<T: Address> Connector<T> {
+impl<T: Address, R: Resolve> Connector<T, R> {
     /// Resolve and connect to remote host
     pub async fn connect<U>(&self, message: U) -> Result<Io, ConnectError>
     where
         Connect<T>: From<U>,
     {
-        ConnectServiceResponse {
-            state: ConnectState::Resolve(Box::pin(self.resolver.lookup(message.into()))),
-            tag: self.tag,
-            pool: self.pool,
-        }
+        ConnectServiceResponse::with_config(
+            Box::pin(self.resolver.lookup(message.into())),
+            self.cfg,
+        )
         .await
     }
 }
@@ -64,30 +129,36 @@ impl<T> Default for Connector<T> {
     }
 }
 
-impl<T> Clone for Connector<T> {
+impl<T, R: Copy> Copy for Connector<T, R> {}
+
+impl<T, R: Clone> Clone for Connector<T, R> {
     fn clone(&self) -> Self {
         Connector {
             resolver: self.resolver.clone(),
-            tag: self.tag,
-            pool: self.pool,
+            cfg: self.cfg,
         }
     }
 }
 
-impl<T> fmt::Debug for Connector<T> {
+impl<T, R: fmt::Debug> fmt::Debug for Connector<T, R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Connector")
-            .field("tagr", &self.tag)
+            .field("tag", &self.cfg.tag)
             .field("resolver", &self.resolver)
-            .field("memory_pool", &self.pool)
+            .field("memory_pool", &self.cfg.pool)
+            .field("attempt_delay", &self.cfg.delay)
+            .field("attempt_timeout", &self.cfg.attempt_timeout)
+            .field("timeout", &self.cfg.timeout)
             .finish()
     }
 }
 
-impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T> {
+impl<T: Address, R: Resolve + Clone, C: 'static> ServiceFactory<Connect<T>, C>
+    for Connector<T, R>
+{
     type Response = Io;
     type Error = ConnectError;
-    type Service = Connector<T>;
+    type Service = Connector<T, R>;
     type InitError = ();
     type Future<'f> = Ready<Self::Service, Self::InitError> where Self: 'f;
 
@@ -97,18 +168,14 @@ impl<T: Address, C: 'static> ServiceFactory<Connect<T>, C> for Connector<T> {
     }
 }
 
-impl<T: Address> Service<Connect<T>> for Connector<T> {
+impl<T: Address, R: Resolve> Service<Connect<T>> for Connector<T, R> {
     type Response = Io;
     type Error = ConnectError;
     type Future<'f> = ConnectServiceResponse<'f, T>;
 
     #[inline]
     fn call<'a>(&'a self, req: Connect<T>, _: ServiceCtx<'a, Self>) -> Self::Future<'a> {
-        ConnectServiceResponse {
-            state: ConnectState::Resolve(Box::pin(self.resolver.lookup(req))),
-            pool: PoolId::P0.pool_ref(),
-            tag: self.tag,
-        }
+        ConnectServiceResponse::with_config(Box::pin(self.resolver.lookup(req)), self.cfg)
     }
 }
 
@@ -120,16 +187,27 @@ enum ConnectState<'f, T: Address> {
 #[doc(hidden)]
 pub struct ConnectServiceResponse<'f, T: Address> {
     state: ConnectState<'f, T>,
-    pool: PoolRef,
-    tag: &'static str,
+    cfg: ConnectConfig,
+    timeout: Option<Sleep>,
 }
 
 impl<'f, T: Address> ConnectServiceResponse<'f, T> {
     pub(super) fn new(fut: BoxFuture<'f, Result<Connect<T>, ConnectError>>) -> Self {
+        Self::with_config(fut, ConnectConfig::default())
+    }
+
+    fn with_config(
+        fut: BoxFuture<'f, Result<Connect<T>, ConnectError>>,
+        cfg: ConnectConfig,
+    ) -> Self {
         Self {
+            cfg,
             state: ConnectState::Resolve(fut),
-            pool: PoolId::P0.pool_ref(),
-            tag: "",
+            timeout: if cfg.timeout.is_zero() {
+                None
+            } else {
+                Some(Sleep::new(cfg.timeout))
+            },
         }
     }
 }
@@ -137,8 +215,8 @@ impl<'f, T: Address> ConnectServiceResponse<'f, T> {
 impl<'f, T: Address> fmt::Debug for ConnectServiceResponse<'f, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ConnectServiceResponse")
-            .field("tag", &self.tag)
-            .field("pool", &self.pool)
+            .field("tag", &self.cfg.tag)
+            .field("pool", &self.cfg.pool)
             .finish()
     }
 }
@@ -147,6 +225,16 @@ impl<'f, T: Address> Future for ConnectServiceResponse<'f, T> {
     type Output = Result<Io, ConnectError>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(ref timeout) = self.timeout {
+            if timeout.poll_elapsed(cx).is_ready() {
+                trace!("{}: TCP connector: connect timed out", self.cfg.tag);
+                return Poll::Ready(Err(ConnectError::Io(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Connect timed out",
+                ))));
+            }
+        }
+
         match self.state {
             ConnectState::Resolve(ref mut fut) => match Pin::new(fut).poll(cx)? {
                 Poll::Pending => Poll::Pending,
@@ -156,7 +244,7 @@ impl<'f, T: Address> Future for ConnectServiceResponse<'f, T> {
 
                     if let Some(addr) = addr {
                         self.state = ConnectState::Connect(TcpConnectorResponse::new(
-                            req, port, addr, self.tag, self.pool,
+                            req, port, addr, &self.cfg,
                         ));
                         self.poll(cx)
                     } else if let Some(addr) = req.addr() {
@@ -164,12 +252,11 @@ impl<'f, T: Address> Future for ConnectServiceResponse<'f, T> {
                             req,
                             addr.port(),
                             Either::Left(addr),
-                            self.tag,
-                            self.pool,
+                            &self.cfg,
                         ));
                         self.poll(cx)
                     } else {
-                        error!("{}: TCP connector: got unresolved address", self.tag);
+                        error!("{}: TCP connector: got unresolved address", self.cfg.tag);
                         Poll::Ready(Err(ConnectError::Unresolved))
                     }
                 }
@@ -180,14 +267,28 @@ impl<'f, T: Address> Future for ConnectServiceResponse<'f, T> {
 }
 
 /// Tcp stream connector response future
+///
+/// Connection attempts are made according to Happy Eyeballs algorithm,
+/// RFC 8305. Next attempt starts if previous attempts do not complete
+/// within attempt delay or if attempt fails.
 struct TcpConnectorResponse<T> {
     req: Option<T>,
     port: u16,
-    addrs: Option<VecDeque<SocketAddr>>,
-    #[allow(clippy::type_complexity)]
-    stream: Option<BoxFuture<'static, Result<Io, io::Error>>>,
+    addrs: VecDeque<SocketAddr>,
+    attempts: Vec<Attempt>,
+    delay: Millis,
+    next: Option<Sleep>,
+    attempt_timeout: Millis,
+    error: Option<io::Error>,
     tag: &'static str,
     pool: PoolRef,
+    connect: ConnectFn,
+}
+
+struct Attempt {
+    addr: SocketAddr,
+    fut: BoxFuture<'static, Result<Io, io::Error>>,
+    timeout: Option<Sleep>,
 }
 
 impl<T: Address> TcpConnectorResponse<T> {
@@ -195,46 +296,62 @@ impl<T: Address> TcpConnectorResponse<T> {
         req: T,
         port: u16,
         addr: Either<SocketAddr, VecDeque<SocketAddr>>,
-        tag: &'static str,
-        pool: PoolRef,
+        cfg: &ConnectConfig,
     ) -> TcpConnectorResponse<T> {
         trace!(
             "{}TCP connector - connecting to {:?} addr:{:?} port:{}",
-            tag,
+            cfg.tag,
             req.host(),
             addr,
             port
         );
 
-        match addr {
-            Either::Left(addr) => TcpConnectorResponse {
-                req: Some(req),
-                addrs: None,
-                stream: Some(Box::pin(tcp_connect_in(addr, pool))),
-                tag,
-                pool,
-                port,
-            },
-            Either::Right(addrs) => TcpConnectorResponse {
-                tag,
-                port,
-                pool,
-                req: Some(req),
-                addrs: Some(addrs),
-                stream: None,
-            },
-        }
+        let addrs = match addr {
+            Either::Left(addr) => VecDeque::from(vec![addr]),
+            Either::Right(addrs) => interleave(addrs),
+        };
+        let mut fut = TcpConnectorResponse {
+            port,
+            addrs,
+            req: Some(req),
+            attempts: Vec::new(),
+            delay: cfg.delay,
+            next: None,
+            attempt_timeout: cfg.attempt_timeout,
+            error: None,
+            tag: cfg.tag,
+            pool: cfg.pool,
+            connect: cfg.connect,
+        };
+        fut.start_attempt();
+        fut
     }
 
-    fn can_continue(&self, err: &io::Error) -> bool {
-        trace!(
-            "{}TCP connector - failed to connect to {:?} port: {} err: {:?}",
-            self.tag,
-            self.req.as_ref().unwrap().host(),
-            self.port,
-            err
-        );
-        !(self.addrs.is_none() || self.addrs.as_ref().unwrap().is_empty())
+    /// Start connection attempt to next address
+    fn start_attempt(&mut self) -> bool {
+        if let Some(addr) = self.addrs.pop_front() {
+            trace!("{}TCP connector - connecting to {:?}", self.tag, addr);
+            self.attempts.push(Attempt {
+                addr,
+                fut: (self.connect)(addr, self.pool),
+                timeout: if self.attempt_timeout.is_zero() {
+                    None
+                } else {
+                    Some(Sleep::new(self.attempt_timeout))
+                },
+            });
+
+            if self.delay.is_zero() || self.addrs.is_empty() {
+                self.next = None;
+            } else if let Some(ref next) = self.next {
+                next.reset(self.delay);
+            } else {
+                self.next = Some(Sleep::new(self.delay));
+            }
+            true
+        } else {
+            false
+        }
     }
 }
 
@@ -244,11 +361,36 @@ impl<T: Address> Future for TcpConnectorResponse<T> {
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
-        // connect
         loop {
-            if let Some(new) = this.stream.as_mut() {
-                match new.as_mut().poll(cx) {
-                    Poll::Ready(Ok(sock)) => {
+            let mut failed = false;
+            let mut idx = 0;
+            while idx < this.attempts.len() {
+                let attempt = &mut this.attempts[idx];
+                let result = match attempt.fut.as_mut().poll(cx) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => {
+                        let elapsed = attempt
+                            .timeout
+                            .as_ref()
+                            .map(|t| t.poll_elapsed(cx).is_ready())
+                            .unwrap_or(false);
+                        if !elapsed {
+                            idx += 1;
+                            continue;
+                        }
+                        Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "Connection attempt timed out",
+                        ))
+                    }
+                };
+
+                let attempt = this.attempts.remove(idx);
+                match result {
+                    Ok(sock) => {
+                        // cancel other attempts
+                        this.attempts.clear();
+
                         let req = this.req.take().unwrap();
                         trace!(
                             "{}TCP connector - successfully connected to connecting to {:?} - {:?}",
@@ -259,22 +401,64 @@ impl<T: Address> Future for TcpConnectorResponse<T> {
                         sock.set_tag(this.tag);
                         return Poll::Ready(Ok(sock));
                     }
-                    Poll::Pending => return Poll::Pending,
-                    Poll::Ready(Err(err)) => {
-                        if !this.can_continue(&err) {
-                            return Poll::Ready(Err(err.into()));
-                        }
+                    Err(err) => {
+                        trace!(
+                            "{}TCP connector - failed to connect to {:?} port: {} addr: {:?} err: {:?}",
+                            this.tag,
+                            this.req.as_ref().unwrap().host(),
+                            this.port,
+                            attempt.addr,
+                            err
+                        );
+                        failed = true;
+                        this.error = Some(err);
                     }
                 }
             }
 
-            // try to connect
-            let addr = this.addrs.as_mut().unwrap().pop_front().unwrap();
-            this.stream = Some(Box::pin(tcp_connect_in(addr, this.pool)));
+            // start next attempt if delay is elapsed or previous attempt failed
+            let start = failed
+                || this.attempts.is_empty()
+                || this
+                    .next
+                    .as_ref()
+                    .map(|t| t.poll_elapsed(cx).is_ready())
+                    .unwrap_or(false);
+            if start && this.start_attempt() {
+                continue;
+            }
+
+            return if this.attempts.is_empty() {
+                Poll::Ready(Err(this
+                    .error
+                    .take()
+                    .map(ConnectError::Io)
+                    .unwrap_or(ConnectError::Unresolved)))
+            } else {
+                Poll::Pending
+            };
         }
     }
 }
 
+fn tcp_connect(addr: SocketAddr, pool: PoolRef) -> BoxFuture<'static, io::Result<Io>> {
+    Box::pin(tcp_connect_in(addr, pool))
+}
+
+/// Interleave address families, first family is the family of first address
+fn interleave(addrs: VecDeque<SocketAddr>) -> VecDeque<SocketAddr> {
+    let v6 = addrs.front().map(|addr| addr.is_ipv6()).unwrap_or(false);
+    let (mut first, mut second): (VecDeque<_>, VecDeque<_>) =
+        addrs.into_iter().partition(|addr| addr.is_ipv6() == v6);
+
+    let mut result = VecDeque::with_capacity(first.len() + second.len());
+    while !first.is_empty() || !second.is_empty() {
+        result.extend(first.pop_front());
+        result.extend(second.pop_front());
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -308,4 +492,148 @@ mod tests {
         let result = crate::connect(msg).await;
         assert!(result.is_ok());
     }
+
+    thread_local! {
+        static ATTEMPTS: std::cell::RefCell<Vec<(SocketAddr, std::time::Instant)>> =
+            Default::default();
+    }
+
+    /// Connections to port 1 never complete, other ports connect immediately
+    fn stalled_connect(addr: SocketAddr, _: PoolRef) -> BoxFuture<'static, io::Result<Io>> {
+        ATTEMPTS.with(|a| a.borrow_mut().push((addr, std::time::Instant::now())));
+        if addr.port() == 1 {
+            Box::pin(std::future::pending())
+        } else {
+            Box::pin(async { Ok(Io::new(ntex_io::testing::IoTest::create().0)) })
+        }
+    }
+
+    fn attempts() -> Vec<(SocketAddr, std::time::Instant)> {
+        ATTEMPTS.with(|a| std::mem::take(&mut *a.borrow_mut()))
+    }
+
+    #[ntex::test]
+    async fn test_happy_eyeballs() {
+        let stalled: SocketAddr = "10.0.0.1:1".parse().unwrap();
+        let stalled2: SocketAddr = "10.0.0.2:1".parse().unwrap();
+        let ok: SocketAddr = "10.0.0.3:80".parse().unwrap();
+
+        // second attempt starts after attempt delay
+        let mut srv = Connector::default().attempt_delay(Millis(100));
+        srv.cfg.connect = stalled_connect;
+        let result = srv
+            .connect(Connect::new("test").set_addrs(vec![stalled, ok]))
+            .await;
+        assert!(result.is_ok());
+        let att = attempts();
+        assert_eq!(
+            att.iter().map(|a| a.0).collect::<Vec<_>>(),
+            vec![stalled, ok]
+        );
+        assert!(att[1].1 - att[0].1 >= std::time::Duration::from_millis(90));
+
+        // attempt timeout, attempts are sequential
+        let mut srv = Connector::default()
+            .attempt_delay(Millis::ZERO)
+            .attempt_timeout(Millis(100));
+        srv.cfg.connect = stalled_connect;
+        let result = srv
+            .connect(Connect::new("test").set_addrs(vec![stalled, ok]))
+            .await;
+        assert!(result.is_ok());
+        let att = attempts();
+        assert_eq!(
+            att.iter().map(|a| a.0).collect::<Vec<_>>(),
+            vec![stalled, ok]
+        );
+        assert!(att[1].1 - att[0].1 >= std::time::Duration::from_millis(90));
+
+        // all attempts time out
+        let mut srv = Connector::default()
+            .attempt_delay(Millis::ZERO)
+            .attempt_timeout(Millis(50));
+        srv.cfg.connect = stalled_connect;
+        let err = srv
+            .connect(Connect::new("test").set_addrs(vec![stalled, stalled2]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConnectError::Io(e) if e.kind() == io::ErrorKind::TimedOut));
+        assert_eq!(attempts().len(), 2);
+
+        // overall timeout
+        let mut srv = Connector::default()
+            .attempt_delay(Millis(50))
+            .timeout(Millis(200));
+        srv.cfg.connect = stalled_connect;
+        let start = std::time::Instant::now();
+        let err = srv
+            .connect(Connect::new("test").set_addrs(vec![stalled, stalled2]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConnectError::Io(e) if e.kind() == io::ErrorKind::TimedOut));
+        assert!(start.elapsed() >= std::time::Duration::from_millis(190));
+        assert_eq!(attempts().len(), 2);
+        assert!(format!("{:?}", srv).contains("attempt_delay"));
+    }
+
+    #[ntex::test]
+    async fn test_custom_resolver() {
+        let server = ntex::server::test_server(|| {
+            ntex_service::fn_service(|_| async { Ok::<_, ()>(()) })
+        });
+
+        let resolver = crate::CachingResolver::new()
+            .host("www.rust-lang.org", "127.0.0.1".parse().unwrap());
+        let srv = Connector::default().resolver(resolver);
+        assert!(format!("{:?}", srv).contains("CachingResolver"));
+
+        let result = srv
+            .connect(format!("www.rust-lang.org:{}", server.addr().port()))
+            .await;
+        assert!(result.is_ok());
+
+        let srv = srv.clone().pipeline(()).await.unwrap();
+        let result = srv
+            .call(Connect::new("www.rust-lang.org").set_port(server.addr().port()))
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_interleave() {
+        let addrs: VecDeque<SocketAddr> = vec![
+            "[::1]:80".parse().unwrap(),
+            "[::2]:80".parse().unwrap(),
+            "[::3]:80".parse().unwrap(),
+            "127.0.0.1:80".parse().unwrap(),
+        ]
+        .into();
+        let addrs: Vec<_> = interleave(addrs).into_iter().collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "[::1]:80".parse::<SocketAddr>().unwrap(),
+                "127.0.0.1:80".parse().unwrap(),
+                "[::2]:80".parse().unwrap(),
+                "[::3]:80".parse().unwrap(),
+            ]
+        );
+
+        let addrs: VecDeque<SocketAddr> = vec![
+            "127.0.0.1:80".parse().unwrap(),
+            "127.0.0.2:80".parse().unwrap(),
+            "[::1]:80".parse().unwrap(),
+        ]
+        .into();
+        let addrs: Vec<_> = interleave(addrs).into_iter().collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "127.0.0.1:80".parse::<SocketAddr>().unwrap(),
+                "[::1]:80".parse().unwrap(),
+                "127.0.0.2:80".parse().unwrap(),
+            ]
+        );
+        assert!(interleave(VecDeque::new()).is_empty());
+    }
 }
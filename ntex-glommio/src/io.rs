@@ -33,6 +33,10 @@ impl Handle for TcpStream {
             if let Ok(addr) = self.0.borrow().peer_addr() {
                 return Some(Box::new(types::PeerAddr(addr)));
             }
+        } else if id == any::TypeId::of::<types::LocalAddr>() {
+            if let Ok(addr) = self.0.borrow().local_addr() {
+                return Some(Box::new(types::LocalAddr(addr)));
+            }
         }
         None
     }
@@ -15,6 +15,7 @@ mod filter;
 mod framed;
 mod io;
 mod ioref;
+mod proxy;
 mod seal;
 mod tasks;
 mod timer;
@@ -28,6 +29,7 @@ pub use self::dispatcher::{Dispatcher, DispatcherConfig};
 pub use self::filter::{Base, Filter, Layer};
 pub use self::framed::Framed;
 pub use self::io::{Io, IoRef, OnDisconnect};
+pub use self::proxy::{ProxyFilter, ProxyProtocol};
 pub use self::seal::{IoBoxed, Sealed};
 pub use self::tasks::{ReadContext, WriteContext};
 pub use self::timer::TimerHandle;
@@ -0,0 +1,618 @@
+//! PROXY protocol filter
+//!
+//! Parses v1 (text) and v2 (binary) headers prepended to the connection
+//! by load balancers, see https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt
+use std::{any, io, net::IpAddr, net::Ipv4Addr, net::Ipv6Addr, net::SocketAddr};
+
+use ntex_bytes::Bytes;
+use ntex_util::{future::BoxFuture, time, time::Millis};
+
+use crate::types::{LocalAddr, PeerAddr, ProxyInfo, ProxySsl};
+use crate::{Filter, FilterFactory, FilterLayer, Io, Layer, ReadBuf, WriteBuf};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LEN: usize = 107;
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LEN: usize = 16;
+
+const PP2_TYPE_ALPN: u8 = 0x01;
+const PP2_TYPE_AUTHORITY: u8 = 0x02;
+const PP2_TYPE_CRC32C: u8 = 0x03;
+const PP2_TYPE_NOOP: u8 = 0x04;
+const PP2_TYPE_UNIQUE_ID: u8 = 0x05;
+const PP2_TYPE_SSL: u8 = 0x20;
+const PP2_SUBTYPE_SSL_VERSION: u8 = 0x21;
+const PP2_SUBTYPE_SSL_CN: u8 = 0x22;
+const PP2_SUBTYPE_SSL_CIPHER: u8 = 0x23;
+const PP2_SUBTYPE_SSL_SIG_ALG: u8 = 0x24;
+const PP2_SUBTYPE_SSL_KEY_ALG: u8 = 0x25;
+
+const PP2_CLIENT_SSL: u8 = 0x01;
+const PP2_CLIENT_CERT_CONN: u8 = 0x02;
+const PP2_CLIENT_CERT_SESS: u8 = 0x04;
+
+/// PROXY protocol filter factory
+///
+/// Reads PROXY protocol header and adds [`ProxyFilter`] to the io stream.
+/// Addresses received from the proxy are available via `Io::query()`
+/// as [`PeerAddr`] and [`LocalAddr`], full header as [`ProxyInfo`].
+///
+/// Filter must be first in the pipeline, before tls filters.
+#[derive(Copy, Clone, Debug)]
+pub struct ProxyProtocol {
+    timeout: Millis,
+}
+
+impl Default for ProxyProtocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProxyProtocol {
+    /// Create PROXY protocol filter factory
+    pub fn new() -> Self {
+        ProxyProtocol {
+            timeout: Millis(5_000),
+        }
+    }
+
+    /// Set header read timeout.
+    ///
+    /// Zero value disables timeout. Default is set to 5 seconds.
+    pub fn timeout<U: Into<Millis>>(mut self, timeout: U) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+}
+
+impl<F: Filter> FilterFactory<F> for ProxyProtocol {
+    type Filter = ProxyFilter;
+
+    type Error = io::Error;
+    type Future = BoxFuture<'static, Result<Io<Layer<Self::Filter, F>>, io::Error>>;
+
+    fn create(self, io: Io<F>) -> Self::Future {
+        let timeout = self.timeout;
+
+        Box::pin(async move {
+            let info = time::timeout_checked(timeout, read_header(&io))
+                .await
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::TimedOut, "PROXY protocol header timeout")
+                })
+                .and_then(|item| item)?;
+            log::trace!("{}: PROXY protocol header {:?}", io.tag(), info);
+
+            Ok(io.add_filter(ProxyFilter { info }))
+        })
+    }
+}
+
+/// PROXY protocol filter
+///
+/// Overrides connection addresses with addresses received from the proxy.
+#[derive(Debug)]
+pub struct ProxyFilter {
+    info: ProxyInfo,
+}
+
+impl ProxyFilter {
+    /// Get PROXY protocol header information
+    pub fn info(&self) -> &ProxyInfo {
+        &self.info
+    }
+}
+
+impl FilterLayer for ProxyFilter {
+    const BUFFERS: bool = false;
+
+    fn query(&self, id: any::TypeId) -> Option<Box<dyn any::Any>> {
+        if id == any::TypeId::of::<PeerAddr>() {
+            if let Some(addr) = self.info.source {
+                return Some(Box::new(PeerAddr(addr)));
+            }
+        } else if id == any::TypeId::of::<LocalAddr>() {
+            if let Some(addr) = self.info.destination {
+                return Some(Box::new(LocalAddr(addr)));
+            }
+        } else if id == any::TypeId::of::<ProxyInfo>() {
+            return Some(Box::new(self.info.clone()));
+        }
+        None
+    }
+
+    #[inline]
+    fn process_read_buf(&self, buf: &ReadBuf<'_>) -> io::Result<usize> {
+        Ok(buf.nbytes())
+    }
+
+    #[inline]
+    fn process_write_buf(&self, _: &WriteBuf<'_>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+async fn read_header<F: Filter>(io: &Io<F>) -> io::Result<ProxyInfo> {
+    loop {
+        let result = io.with_read_buf(|buf| {
+            parse(buf).map(|item| {
+                item.map(|(size, info)| {
+                    let _ = buf.split_to(size);
+                    info
+                })
+            })
+        })?;
+
+        if let Some(info) = result {
+            return Ok(info);
+        }
+        if io.read_ready().await?.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Disconnected before PROXY protocol header",
+            ));
+        }
+    }
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Parse PROXY protocol header
+///
+/// Returns size of the header and parsed info, or `None` if more data is needed.
+fn parse(buf: &[u8]) -> io::Result<Option<(usize, ProxyInfo)>> {
+    if starts_with(buf, V2_SIGNATURE) {
+        parse_v2(buf)
+    } else if starts_with(buf, V1_PREFIX) {
+        parse_v1(buf)
+    } else {
+        Err(invalid("PROXY protocol header is expected"))
+    }
+}
+
+/// Check if buffer is a prefix of the signature or starts with it
+fn starts_with(buf: &[u8], sig: &[u8]) -> bool {
+    let len = buf.len().min(sig.len());
+    buf[..len] == sig[..len]
+}
+
+fn parse_v1(buf: &[u8]) -> io::Result<Option<(usize, ProxyInfo)>> {
+    let end = match buf
+        .windows(2)
+        .take(V1_MAX_LEN - 1)
+        .position(|w| w == b"\r\n")
+    {
+        Some(end) => end,
+        None if buf.len() >= V1_MAX_LEN => {
+            return Err(invalid("PROXY v1 header is too long"))
+        }
+        None => return Ok(None),
+    };
+    let line = std::str::from_utf8(&buf[V1_PREFIX.len()..end])
+        .map_err(|_| invalid("Invalid PROXY v1 header"))?;
+
+    let mut parts = line.split(' ');
+    let mut info = ProxyInfo {
+        version: 1,
+        ..Default::default()
+    };
+    match parts.next() {
+        // remaining part of the line must be ignored
+        Some("UNKNOWN") => (),
+        Some(proto @ ("TCP4" | "TCP6")) => {
+            let mut next = || {
+                parts
+                    .next()
+                    .ok_or_else(|| invalid("Invalid PROXY v1 header"))
+            };
+            let src: IpAddr = next()?
+                .parse()
+                .map_err(|_| invalid("Invalid PROXY v1 source address"))?;
+            let dst: IpAddr = next()?
+                .parse()
+                .map_err(|_| invalid("Invalid PROXY v1 destination address"))?;
+            let sport: u16 = next()?
+                .parse()
+                .map_err(|_| invalid("Invalid PROXY v1 source port"))?;
+            let dport: u16 = next()?
+                .parse()
+                .map_err(|_| invalid("Invalid PROXY v1 destination port"))?;
+            if parts.next().is_some() {
+                return Err(invalid("Invalid PROXY v1 header"));
+            }
+            if (proto == "TCP4") != (src.is_ipv4() && dst.is_ipv4())
+                || (proto == "TCP6") != (src.is_ipv6() && dst.is_ipv6())
+            {
+                return Err(invalid("PROXY v1 address family mismatch"));
+            }
+            info.source = Some(SocketAddr::new(src, sport));
+            info.destination = Some(SocketAddr::new(dst, dport));
+        }
+        _ => return Err(invalid("Unsupported PROXY v1 protocol")),
+    }
+    Ok(Some((end + 2, info)))
+}
+
+fn parse_v2(buf: &[u8]) -> io::Result<Option<(usize, ProxyInfo)>> {
+    if buf.len() < V2_HEADER_LEN {
+        return Ok(None);
+    }
+    if buf[12] >> 4 != 2 {
+        return Err(invalid("Unsupported PROXY protocol version"));
+    }
+    let size = V2_HEADER_LEN + u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    if buf.len() < size {
+        return Ok(None);
+    }
+    let header = &buf[..size];
+    let data = &header[V2_HEADER_LEN..];
+
+    let mut info = ProxyInfo {
+        version: 2,
+        ..Default::default()
+    };
+    match buf[12] & 0x0f {
+        // LOCAL, connection is established by the proxy itself,
+        // address block must be ignored
+        0x00 => return Ok(Some((size, info))),
+        // PROXY
+        0x01 => (),
+        _ => return Err(invalid("Unsupported PROXY v2 command")),
+    }
+
+    let addr_len = match buf[13] >> 4 {
+        // AF_UNSPEC
+        0x0 => 0,
+        // AF_INET
+        0x1 => {
+            if data.len() < 12 {
+                return Err(invalid("Truncated PROXY v2 address block"));
+            }
+            let src = Ipv4Addr::new(data[0], data[1], data[2], data[3]);
+            let dst = Ipv4Addr::new(data[4], data[5], data[6], data[7]);
+            let sport = u16::from_be_bytes([data[8], data[9]]);
+            let dport = u16::from_be_bytes([data[10], data[11]]);
+            info.source = Some(SocketAddr::new(src.into(), sport));
+            info.destination = Some(SocketAddr::new(dst.into(), dport));
+            12
+        }
+        // AF_INET6
+        0x2 => {
+            if data.len() < 36 {
+                return Err(invalid("Truncated PROXY v2 address block"));
+            }
+            let mut src = [0u8; 16];
+            let mut dst = [0u8; 16];
+            src.copy_from_slice(&data[..16]);
+            dst.copy_from_slice(&data[16..32]);
+            let sport = u16::from_be_bytes([data[32], data[33]]);
+            let dport = u16::from_be_bytes([data[34], data[35]]);
+            info.source = Some(SocketAddr::new(Ipv6Addr::from(src).into(), sport));
+            info.destination = Some(SocketAddr::new(Ipv6Addr::from(dst).into(), dport));
+            36
+        }
+        // AF_UNIX, paths cannot be represented as socket addresses
+        0x3 => {
+            if data.len() < 216 {
+                return Err(invalid("Truncated PROXY v2 address block"));
+            }
+            216
+        }
+        _ => return Err(invalid("Unsupported PROXY v2 address family")),
+    };
+
+    parse_tlvs(header, V2_HEADER_LEN + addr_len, &mut info)?;
+    Ok(Some((size, info)))
+}
+
+fn parse_tlvs(header: &[u8], mut pos: usize, info: &mut ProxyInfo) -> io::Result<()> {
+    while pos < header.len() {
+        let (tp, value) = read_tlv(header, pos)?;
+        match tp {
+            PP2_TYPE_ALPN => info.alpn = Some(Bytes::copy_from_slice(value)),
+            PP2_TYPE_AUTHORITY => info.authority = Some(to_string(value)),
+            PP2_TYPE_UNIQUE_ID => info.unique_id = Some(Bytes::copy_from_slice(value)),
+            PP2_TYPE_SSL => info.ssl = Some(parse_ssl(value)?),
+            PP2_TYPE_CRC32C => {
+                if value.len() != 4 {
+                    return Err(invalid("Invalid PROXY v2 checksum"));
+                }
+                let expected = u32::from_be_bytes([value[0], value[1], value[2], value[3]]);
+                // checksum is calculated with zeroed checksum field
+                let field = pos + 3..pos + 7;
+                let crc = crc32c(header.iter().enumerate().map(|(idx, b)| {
+                    if field.contains(&idx) {
+                        0
+                    } else {
+                        *b
+                    }
+                }));
+                if crc != expected {
+                    return Err(invalid("PROXY v2 checksum mismatch"));
+                }
+            }
+            PP2_TYPE_NOOP => (),
+            _ => info.tlvs.push((tp, Bytes::copy_from_slice(value))),
+        }
+        pos += 3 + value.len();
+    }
+    Ok(())
+}
+
+fn parse_ssl(value: &[u8]) -> io::Result<ProxySsl> {
+    if value.len() < 5 {
+        return Err(invalid("Invalid PROXY v2 SSL TLV"));
+    }
+    let client = value[0];
+    let verify = u32::from_be_bytes([value[1], value[2], value[3], value[4]]);
+    let mut ssl = ProxySsl {
+        client_ssl: client & PP2_CLIENT_SSL != 0,
+        client_cert_conn: client & PP2_CLIENT_CERT_CONN != 0,
+        client_cert_sess: client & PP2_CLIENT_CERT_SESS != 0,
+        verified: verify == 0,
+        ..Default::default()
+    };
+
+    let mut pos = 5;
+    while pos < value.len() {
+        let (tp, val) = read_tlv(value, pos)?;
+        match tp {
+            PP2_SUBTYPE_SSL_VERSION => ssl.version = Some(to_string(val)),
+            PP2_SUBTYPE_SSL_CN => ssl.cn = Some(to_string(val)),
+            PP2_SUBTYPE_SSL_CIPHER => ssl.cipher = Some(to_string(val)),
+            PP2_SUBTYPE_SSL_SIG_ALG => ssl.sig_alg = Some(to_string(val)),
+            PP2_SUBTYPE_SSL_KEY_ALG => ssl.key_alg = Some(to_string(val)),
+            _ => (),
+        }
+        pos += 3 + val.len();
+    }
+    Ok(ssl)
+}
+
+fn read_tlv(data: &[u8], pos: usize) -> io::Result<(u8, &[u8])> {
+    if data.len() < pos + 3 {
+        return Err(invalid("Truncated PROXY v2 TLV"));
+    }
+    let len = u16::from_be_bytes([data[pos + 1], data[pos + 2]]) as usize;
+    data.get(pos + 3..pos + 3 + len)
+        .map(|value| (data[pos], value))
+        .ok_or_else(|| invalid("Truncated PROXY v2 TLV"))
+}
+
+fn to_string(value: &[u8]) -> String {
+    String::from_utf8_lossy(value).into_owned()
+}
+
+/// CRC32c (Castagnoli) checksum
+fn crc32c<I: Iterator<Item = u8>>(data: I) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex_bytes::Bytes;
+    use ntex_codec::BytesCodec;
+
+    use super::*;
+    use crate::testing::IoTest;
+
+    fn v2_header(cmd: u8, fam: u8, body: &[u8]) -> Vec<u8> {
+        let mut buf = V2_SIGNATURE.to_vec();
+        buf.push(0x20 | cmd);
+        buf.push(fam);
+        buf.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    fn tlv(tp: u8, value: &[u8]) -> Vec<u8> {
+        let mut buf = vec![tp];
+        buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        buf.extend_from_slice(value);
+        buf
+    }
+
+    #[test]
+    fn test_parse_v1() {
+        let hdr = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET /";
+        assert!(parse(&hdr[..0]).unwrap().is_none());
+        assert!(parse(&hdr[..3]).unwrap().is_none());
+        assert!(parse(&hdr[..20]).unwrap().is_none());
+
+        let (size, info) = parse(hdr).unwrap().unwrap();
+        assert_eq!(&hdr[size..], b"GET /");
+        assert_eq!(info.version, 1);
+        assert_eq!(info.source, Some("192.168.0.1:56324".parse().unwrap()));
+        assert_eq!(info.destination, Some("192.168.0.11:443".parse().unwrap()));
+
+        let (_, info) = parse(b"PROXY TCP6 ::1 ::2 1000 80\r\n").unwrap().unwrap();
+        assert_eq!(info.source, Some("[::1]:1000".parse().unwrap()));
+        assert_eq!(info.destination, Some("[::2]:80".parse().unwrap()));
+
+        let (size, info) = parse(b"PROXY UNKNOWN ffff::1 ::2 1 2\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(size, 31);
+        assert_eq!(info.source, None);
+
+        assert!(parse(b"GET / HTTP/1.1\r\n").is_err());
+        assert!(parse(b"PROXY UDP4 1.1.1.1 1.1.1.2 1 2\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 ::1 ::2 1 2\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 1.1.1.1 1.1.1.2 1\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 1.1.1.1 1.1.1.2 1 99999\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 1.1.1.1 1.1.1.2 1 2 3\r\n").is_err());
+        assert!(parse(&[b'P', b'R', b'O', b'X', b'Y', b' ', b'T'].repeat(20)).is_err());
+    }
+
+    #[test]
+    fn test_parse_v2() {
+        let mut body = vec![127, 0, 0, 1, 10, 0, 0, 1, 0x1f, 0x90, 0, 80];
+        body.extend(tlv(PP2_TYPE_ALPN, b"h2"));
+        body.extend(tlv(PP2_TYPE_AUTHORITY, b"example.com"));
+        body.extend(tlv(PP2_TYPE_NOOP, b""));
+        body.extend(tlv(0xEA, b"\x01vpce-1"));
+        let mut ssl = vec![PP2_CLIENT_SSL | PP2_CLIENT_CERT_CONN, 0, 0, 0, 0];
+        ssl.extend(tlv(PP2_SUBTYPE_SSL_VERSION, b"TLSv1.3"));
+        ssl.extend(tlv(PP2_SUBTYPE_SSL_CN, b"client"));
+        body.extend(tlv(PP2_TYPE_SSL, &ssl));
+        let mut hdr = v2_header(0x01, 0x11, &body);
+        hdr.extend_from_slice(b"DATA");
+
+        assert!(parse(&hdr[..10]).unwrap().is_none());
+        assert!(parse(&hdr[..20]).unwrap().is_none());
+
+        let (size, info) = parse(&hdr).unwrap().unwrap();
+        assert_eq!(&hdr[size..], b"DATA");
+        assert_eq!(info.version, 2);
+        assert_eq!(info.source, Some("127.0.0.1:8080".parse().unwrap()));
+        assert_eq!(info.destination, Some("10.0.0.1:80".parse().unwrap()));
+        assert_eq!(info.alpn, Some(Bytes::from_static(b"h2")));
+        assert_eq!(info.authority.as_deref(), Some("example.com"));
+        assert_eq!(info.tlvs, vec![(0xEA, Bytes::from_static(b"\x01vpce-1"))]);
+        let ssl = info.ssl.unwrap();
+        assert!(ssl.client_ssl && ssl.client_cert_conn && !ssl.client_cert_sess);
+        assert!(ssl.verified);
+        assert_eq!(ssl.version.as_deref(), Some("TLSv1.3"));
+        assert_eq!(ssl.cn.as_deref(), Some("client"));
+
+        let mut body = vec![0; 32];
+        body[15] = 1;
+        body[31] = 2;
+        body.extend_from_slice(&[0, 1, 0, 2]);
+        let (_, info) = parse(&v2_header(0x01, 0x21, &body)).unwrap().unwrap();
+        assert_eq!(info.source, Some("[::1]:1".parse().unwrap()));
+        assert_eq!(info.destination, Some("[::2]:2".parse().unwrap()));
+
+        // LOCAL command ignores address block
+        let hdr = v2_header(0x00, 0x11, &[0; 12]);
+        let (size, info) = parse(&hdr).unwrap().unwrap();
+        assert_eq!(size, hdr.len());
+        assert_eq!(info.source, None);
+
+        let (_, info) = parse(&v2_header(0x01, 0x31, &[0; 216])).unwrap().unwrap();
+        assert_eq!(info.source, None);
+
+        assert!(parse(&v2_header(0x02, 0x11, &[0; 12])).is_err());
+        assert!(parse(&v2_header(0x01, 0x41, &[0; 12])).is_err());
+        assert!(parse(&v2_header(0x01, 0x11, &[0; 8])).is_err());
+        assert!(parse(&v2_header(0x01, 0x11, &[0; 14])).is_err());
+        let mut hdr = v2_header(0x01, 0x11, &[0; 12]);
+        hdr[12] = 0x11;
+        assert!(parse(&hdr).is_err());
+    }
+
+    #[test]
+    fn test_crc32c() {
+        assert_eq!(crc32c(b"123456789".iter().copied()), 0xE306_9283);
+
+        let mut body = vec![127, 0, 0, 1, 127, 0, 0, 2, 0, 1, 0, 2];
+        body.extend(tlv(PP2_TYPE_CRC32C, &[0; 4]));
+        let mut hdr = v2_header(0x01, 0x11, &body);
+        let crc = crc32c(hdr.iter().copied());
+        let len = hdr.len();
+        hdr[len - 4..].copy_from_slice(&crc.to_be_bytes());
+        assert!(parse(&hdr).unwrap().is_some());
+
+        hdr[len - 1] ^= 0xff;
+        assert!(parse(&hdr).is_err());
+    }
+
+    #[ntex::test]
+    async fn test_filter() {
+        let (client, server) = IoTest::create();
+        let server = server.set_peer_addr("127.0.0.1:1000".parse().unwrap());
+        client.remote_buffer_cap(1024);
+        client.write("PROXY TCP4 10.0.0.1 10.0.0.2 ");
+
+        let io = Io::new(server);
+        let fut = ntex::rt::spawn(ProxyProtocol::new().create(io));
+        ntex::time::sleep(Millis(50)).await;
+        client.write("1234 80\r\nREQ");
+
+        let io = fut.await.unwrap().unwrap();
+        assert_eq!(
+            io.query::<PeerAddr>().get(),
+            Some(PeerAddr("10.0.0.1:1234".parse().unwrap()))
+        );
+        assert_eq!(
+            io.query::<LocalAddr>().get(),
+            Some(LocalAddr("10.0.0.2:80".parse().unwrap()))
+        );
+        assert_eq!(io.query::<ProxyInfo>().as_ref().unwrap().version, 1);
+        assert_eq!(io.filter().info().version, 1);
+        assert_eq!(
+            io.recv(&BytesCodec).await.unwrap().unwrap(),
+            b"REQ".as_ref()
+        );
+
+        io.send(Bytes::from_static(b"RES"), &BytesCodec)
+            .await
+            .unwrap();
+        assert_eq!(client.read().await.unwrap(), b"RES".as_ref());
+    }
+
+    #[ntex::test]
+    async fn test_filter_unknown() {
+        let (client, server) = IoTest::create();
+        let server = server.set_peer_addr("127.0.0.1:1000".parse().unwrap());
+        client.remote_buffer_cap(1024);
+        client.write(v2_header(0x00, 0x00, &[]));
+
+        let io = ProxyProtocol::new().create(Io::new(server)).await.unwrap();
+        assert_eq!(
+            io.query::<PeerAddr>().get(),
+            Some(PeerAddr("127.0.0.1:1000".parse().unwrap()))
+        );
+        assert!(io.query::<LocalAddr>().get().is_none());
+        assert_eq!(io.query::<ProxyInfo>().as_ref().unwrap().version, 2);
+    }
+
+    #[ntex::test]
+    async fn test_filter_errors() {
+        let (client, server) = IoTest::create();
+        client.remote_buffer_cap(1024);
+        client.write("GET / HTTP/1.1\r\n");
+        let err = ProxyProtocol::new()
+            .create(Io::new(server))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let (client, server) = IoTest::create();
+        client.remote_buffer_cap(1024);
+        client.write("PROXY TCP4");
+        let err = ProxyProtocol::new()
+            .timeout(Millis(50))
+            .create(Io::new(server))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let (client, server) = IoTest::create();
+        client.remote_buffer_cap(1024);
+        client.write("PROXY TCP4");
+        client.close().await;
+        let err = ProxyProtocol::new()
+            .create(Io::new(server))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,6 +1,8 @@
 //! Query related types
 use std::{any, fmt, marker::PhantomData, net::SocketAddr};
 
+use ntex_bytes::Bytes;
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct PeerAddr(pub SocketAddr);
 
@@ -22,6 +24,72 @@ impl fmt::Debug for PeerAddr {
     }
 }
 
+/// Local address of the connection
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct LocalAddr(pub SocketAddr);
+
+impl LocalAddr {
+    pub fn into_inner(self) -> SocketAddr {
+        self.0
+    }
+}
+
+impl From<SocketAddr> for LocalAddr {
+    fn from(addr: SocketAddr) -> Self {
+        Self(addr)
+    }
+}
+
+impl fmt::Debug for LocalAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Information received with PROXY protocol header
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProxyInfo {
+    /// Protocol version, `1` or `2`
+    pub version: u8,
+    /// Address of the client, `None` for health checks and unknown protocols
+    pub source: Option<SocketAddr>,
+    /// Address the client connected to
+    pub destination: Option<SocketAddr>,
+    /// Application protocol negotiated by the proxy
+    pub alpn: Option<Bytes>,
+    /// Host name provided by the client (SNI)
+    pub authority: Option<String>,
+    /// Unique connection id
+    pub unique_id: Option<Bytes>,
+    /// TLS information, if client connected to the proxy over TLS
+    pub ssl: Option<ProxySsl>,
+    /// Other TLVs, including custom ones
+    pub tlvs: Vec<(u8, Bytes)>,
+}
+
+/// TLS information received with PROXY protocol v2 header
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProxySsl {
+    /// Client connected over SSL/TLS
+    pub client_ssl: bool,
+    /// Client provided a certificate over the current connection
+    pub client_cert_conn: bool,
+    /// Client provided a certificate at least once over the TLS session
+    pub client_cert_sess: bool,
+    /// Client certificate is verified
+    pub verified: bool,
+    /// Protocol version, i.e. "TLSv1.3"
+    pub version: Option<String>,
+    /// Common name of the client certificate
+    pub cn: Option<String>,
+    /// Cipher name, i.e. "ECDHE-RSA-AES128-GCM-SHA256"
+    pub cipher: Option<String>,
+    /// Signature algorithm of the certificate
+    pub sig_alg: Option<String>,
+    /// Key algorithm of the certificate
+    pub key_alg: Option<String>,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 /// Http protocol definition
 pub enum HttpProtocol {
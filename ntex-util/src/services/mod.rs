@@ -4,6 +4,7 @@ mod extensions;
 pub mod inflight;
 pub mod keepalive;
 pub mod onerequest;
+pub mod ratelimit;
 pub mod timeout;
 pub mod variant;
 
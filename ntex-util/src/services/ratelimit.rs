@@ -0,0 +1,470 @@
+//! Service that limits rate of requests per key.
+//!
+//! Limiter implements token bucket algorithm in form of generic cell rate
+//! algorithm (GCRA). Each key keeps only the theoretical arrival time of the
+//! next request, expired keys are removed lazily on access, so no timers are
+//! spawned per key.
+use std::sync::{Arc, Mutex};
+use std::{fmt, future::Future, hash::Hash, pin::Pin, rc::Rc, task::Context, task::Poll};
+use std::{time::Duration, time::Instant};
+
+use ntex_service::{IntoService, Middleware, Service, ServiceCall, ServiceCtx};
+
+use crate::future::{Either, Ready};
+use crate::time::{now, Millis};
+use crate::HashMap;
+
+/// Token bucket rate limiter.
+///
+/// Limiter allows `limit` requests per `period` for each key, requests are
+/// replenished evenly during the period. Limiter could be cloned, all clones
+/// share same state, so single limiter could be used by all server workers.
+pub struct RateLimiter<K> {
+    inner: Arc<Inner<K>>,
+}
+
+struct Inner<K> {
+    limit: u32,
+    period: Duration,
+    interval: Duration,
+    state: Mutex<State<K>>,
+}
+
+struct State<K> {
+    keys: HashMap<K, Instant>,
+    next_sweep: Option<Instant>,
+}
+
+/// Rate limit status of the key
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    limit: u32,
+    remaining: u32,
+    reset: Duration,
+    retry_after: Duration,
+}
+
+impl<K> RateLimiter<K> {
+    /// Create new rate limiter.
+    ///
+    /// Panics if `limit` or `period` is zero, or if `limit` is greater
+    /// than number of nanoseconds in `period`.
+    pub fn new<T: Into<Millis>>(limit: u32, period: T) -> Self {
+        let period: Duration = period.into().into();
+        assert!(limit > 0, "Rate limit must be greater than zero");
+        assert!(
+            !period.is_zero(),
+            "Rate limit period must be greater than zero"
+        );
+        let interval = period / limit;
+        assert!(
+            !interval.is_zero(),
+            "Rate limit is too large for the period"
+        );
+
+        RateLimiter {
+            inner: Arc::new(Inner {
+                limit,
+                period,
+                interval,
+                state: Mutex::new(State {
+                    keys: HashMap::default(),
+                    next_sweep: None,
+                }),
+            }),
+        }
+    }
+
+    /// Max number of requests per period
+    pub fn limit(&self) -> u32 {
+        self.inner.limit
+    }
+
+    /// Rate limit period
+    pub fn period(&self) -> Duration {
+        self.inner.period
+    }
+
+    /// Number of tracked keys
+    pub fn len(&self) -> usize {
+        self.inner.state.lock().unwrap().keys.len()
+    }
+
+    /// Returns `true` if limiter does not track any keys
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    /// Acquire one token for the key.
+    ///
+    /// Returns `Err` if rate limit for the key is exceeded.
+    pub fn check(&self, key: K) -> Result<RateLimitInfo, RateLimitInfo> {
+        let inner = &self.inner;
+        let now = now();
+        let mut state = inner.state.lock().unwrap();
+
+        // remove keys with full buckets
+        if state.next_sweep.map(|t| now >= t).unwrap_or(true) {
+            state.keys.retain(|_, tat| *tat > now);
+            state.next_sweep = Some(now + inner.period);
+        }
+
+        let tat = state
+            .keys
+            .get(&key)
+            .copied()
+            .filter(|tat| *tat > now)
+            .unwrap_or(now);
+        let reset = (tat + inner.interval) - now;
+
+        if reset > inner.period {
+            Err(RateLimitInfo {
+                limit: inner.limit,
+                remaining: 0,
+                reset: tat - now,
+                retry_after: reset - inner.period,
+            })
+        } else {
+            state.keys.insert(key, tat + inner.interval);
+            Ok(RateLimitInfo {
+                limit: inner.limit,
+                remaining: ((inner.period - reset).as_nanos() / inner.interval.as_nanos())
+                    as u32,
+                reset,
+                retry_after: Duration::ZERO,
+            })
+        }
+    }
+
+    /// Reset rate limit for the key
+    pub fn reset(&self, key: &K) {
+        self.inner.state.lock().unwrap().keys.remove(key);
+    }
+}
+
+impl<K> Clone for RateLimiter<K> {
+    fn clone(&self) -> Self {
+        RateLimiter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K> fmt::Debug for RateLimiter<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("limit", &self.inner.limit)
+            .field("period", &self.inner.period)
+            .finish()
+    }
+}
+
+impl RateLimitInfo {
+    /// Max number of requests per period
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Number of remaining requests
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Time until the limit is fully replenished
+    pub fn reset(&self) -> Duration {
+        self.reset
+    }
+
+    /// Time until next request is allowed, zero if request is allowed
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+}
+
+/// Rate limit error
+pub enum RateLimitError<E> {
+    /// Service error
+    Service(E),
+    /// Rate limit exceeded
+    Limited(RateLimitInfo),
+}
+
+impl<E> From<E> for RateLimitError<E> {
+    fn from(err: E) -> Self {
+        RateLimitError::Service(err)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for RateLimitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitError::Service(e) => write!(f, "RateLimitError::Service({:?})", e),
+            RateLimitError::Limited(info) => {
+                write!(f, "RateLimitError::Limited({:?})", info)
+            }
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for RateLimitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitError::Service(e) => e.fmt(f),
+            RateLimitError::Limited(_) => write!(f, "Rate limit exceeded"),
+        }
+    }
+}
+
+impl<E: fmt::Display + fmt::Debug> std::error::Error for RateLimitError<E> {}
+
+impl<E: PartialEq> PartialEq for RateLimitError<E> {
+    fn eq(&self, other: &RateLimitError<E>) -> bool {
+        match (self, other) {
+            (RateLimitError::Service(e1), RateLimitError::Service(e2)) => e1 == e2,
+            (RateLimitError::Limited(i1), RateLimitError::Limited(i2)) => i1 == i2,
+            _ => false,
+        }
+    }
+}
+
+/// RateLimit - service factory for service that limits rate of requests.
+///
+/// Key function selects rate limit key for the request, requests without
+/// key are not limited.
+pub struct RateLimit<K, F> {
+    limiter: RateLimiter<K>,
+    key: Rc<F>,
+}
+
+impl<K, F> RateLimit<K, F> {
+    pub fn new(limiter: RateLimiter<K>, key: F) -> Self {
+        Self {
+            limiter,
+            key: Rc::new(key),
+        }
+    }
+}
+
+impl<K, F> Clone for RateLimit<K, F> {
+    fn clone(&self) -> Self {
+        Self {
+            limiter: self.limiter.clone(),
+            key: self.key.clone(),
+        }
+    }
+}
+
+impl<K, F> fmt::Debug for RateLimit<K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("limiter", &self.limiter)
+            .finish()
+    }
+}
+
+impl<S, K, F> Middleware<S> for RateLimit<K, F> {
+    type Service = RateLimitService<S, K, F>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RateLimitService {
+            service,
+            limiter: self.limiter.clone(),
+            key: self.key.clone(),
+        }
+    }
+}
+
+/// Service that limits rate of requests.
+pub struct RateLimitService<S, K, F> {
+    service: S,
+    limiter: RateLimiter<K>,
+    key: Rc<F>,
+}
+
+impl<S, K, F> RateLimitService<S, K, F> {
+    pub fn new<U, R>(limiter: RateLimiter<K>, key: F, service: U) -> Self
+    where
+        S: Service<R>,
+        U: IntoService<S, R>,
+    {
+        Self {
+            limiter,
+            key: Rc::new(key),
+            service: service.into_service(),
+        }
+    }
+}
+
+impl<S: fmt::Debug, K, F> fmt::Debug for RateLimitService<S, K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitService")
+            .field("service", &self.service)
+            .field("limiter", &self.limiter)
+            .finish()
+    }
+}
+
+impl<S, K, F, R> Service<R> for RateLimitService<S, K, F>
+where
+    S: Service<R>,
+    K: Hash + Eq,
+    F: Fn(&R) -> Option<K>,
+{
+    type Response = S::Response;
+    type Error = RateLimitError<S::Error>;
+    type Future<'f> = Either<RateLimitServiceResponse<'f, S, R>, Ready<S::Response, RateLimitError<S::Error>>> where Self: 'f, R: 'f;
+
+    fn call<'a>(&'a self, req: R, ctx: ServiceCtx<'a, Self>) -> Self::Future<'a> {
+        if let Some(key) = (*self.key)(&req) {
+            if let Err(info) = self.limiter.check(key) {
+                log::trace!("Rate limit exceeded, retry after {:?}", info.retry_after);
+                return Either::Right(Ready::Err(RateLimitError::Limited(info)));
+            }
+        }
+        Either::Left(RateLimitServiceResponse {
+            fut: ctx.call(&self.service, req),
+        })
+    }
+
+    ntex_service::forward_poll_ready!(service, RateLimitError::Service);
+    ntex_service::forward_poll_shutdown!(service);
+}
+
+pin_project_lite::pin_project! {
+    /// `RateLimitService` response future
+    #[doc(hidden)]
+    #[must_use = "futures do nothing unless polled"]
+    pub struct RateLimitServiceResponse<'f, T: Service<R>, R>
+    where T: 'f, R: 'f,
+    {
+        #[pin]
+        fut: ServiceCall<'f, T, R>,
+    }
+}
+
+impl<'f, T, R> Future for RateLimitServiceResponse<'f, T, R>
+where
+    T: Service<R>,
+{
+    type Output = Result<T::Response, RateLimitError<T::Error>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().fut.poll(cx) {
+            Poll::Ready(Ok(v)) => Poll::Ready(Ok(v)),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(RateLimitError::Service(e))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex_service::{apply, fn_factory, fn_service, Pipeline, ServiceFactory};
+
+    use super::*;
+    use crate::{future::lazy, time::sleep};
+
+    #[ntex_macros::rt_test2]
+    async fn test_limiter() {
+        let limiter = RateLimiter::new(2, Millis(100));
+        assert_eq!(limiter.limit(), 2);
+        assert_eq!(limiter.period(), Duration::from_millis(100));
+        assert!(format!("{:?}", limiter).contains("RateLimiter"));
+        assert!(limiter.is_empty());
+
+        let info = limiter.check("a").unwrap();
+        assert_eq!(info.limit(), 2);
+        assert_eq!(info.remaining(), 1);
+        assert_eq!(info.retry_after(), Duration::ZERO);
+        assert!(info.reset() <= Duration::from_millis(50));
+
+        let info = limiter.check("a").unwrap();
+        assert_eq!(info.remaining(), 0);
+
+        let info = limiter.check("a").unwrap_err();
+        assert_eq!(info.remaining(), 0);
+        assert!(info.retry_after() > Duration::ZERO);
+        assert!(info.retry_after() <= Duration::from_millis(50));
+
+        // other keys are not affected
+        assert!(limiter.clone().check("b").is_ok());
+        assert_eq!(limiter.len(), 2);
+
+        limiter.reset(&"a");
+        assert_eq!(limiter.check("a").unwrap().remaining(), 1);
+
+        // tokens are replenished
+        sleep(Millis(150)).await;
+        assert_eq!(limiter.check("a").unwrap().remaining(), 1);
+        assert_eq!(limiter.len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_limiter_zero() {
+        let _ = RateLimiter::<()>::new(0, Millis(100));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_limiter_zero_interval() {
+        let _ = RateLimiter::<()>::new(u32::MAX, Millis(1));
+    }
+
+    #[ntex_macros::rt_test2]
+    async fn test_service() {
+        let limiter = RateLimiter::new(1, Millis::ONE_SEC);
+        let srv = Pipeline::new(RateLimitService::new(
+            limiter,
+            |req: &Option<u32>| *req,
+            fn_service(|_: Option<u32>| async { Ok::<_, ()>(()) }),
+        ));
+        assert!(lazy(|cx| srv.poll_ready(cx)).await.is_ready());
+        assert!(lazy(|cx| srv.poll_shutdown(cx)).await.is_ready());
+        assert!(format!("{:?}", srv).contains("RateLimitService"));
+
+        assert_eq!(srv.call(Some(1)).await, Ok(()));
+        assert!(matches!(
+            srv.call(Some(1)).await,
+            Err(RateLimitError::Limited(_))
+        ));
+        assert_eq!(srv.call(Some(2)).await, Ok(()));
+
+        // requests without key are not limited
+        assert_eq!(srv.call(None).await, Ok(()));
+        assert_eq!(srv.call(None).await, Ok(()));
+    }
+
+    #[ntex_macros::rt_test2]
+    async fn test_middleware() {
+        let factory = apply(
+            RateLimit::new(RateLimiter::new(1, Millis::ONE_SEC), |_: &()| Some(())).clone(),
+            fn_factory(|| async {
+                Ok::<_, ()>(fn_service(|_: ()| async { Err::<(), _>(()) }))
+            }),
+        );
+        let srv = factory.pipeline(&()).await.unwrap();
+
+        assert_eq!(srv.call(()).await, Err(RateLimitError::Service(())));
+        assert!(matches!(
+            srv.call(()).await,
+            Err(RateLimitError::Limited(_))
+        ));
+    }
+
+    #[ntex_macros::rt_test2]
+    async fn test_error() {
+        let limiter = RateLimiter::new(1, Millis::ONE_SEC);
+        let _ = limiter.check(());
+        let err1 = RateLimitError::<String>::Limited(limiter.check(()).unwrap_err());
+        assert!(format!("{:?}", err1).contains("RateLimitError::Limited"));
+        assert!(format!("{}", err1).contains("Rate limit exceeded"));
+
+        let err2: RateLimitError<_> = "SrvError".to_string().into();
+        assert!(format!("{:?}", err2).contains("RateLimitError::Service"));
+        assert!(format!("{}", err2).contains("SrvError"));
+        assert!(err1 != err2);
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     error::Error, fmt, marker::PhantomData, mem, pin::Pin, task::Context, task::Poll,
 };
 
+use crate::http::header::HeaderMap;
 use crate::util::{Bytes, BytesMut, Stream};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -27,6 +28,14 @@ pub trait MessageBody: 'static {
         &mut self,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>>;
+
+    /// Trailer headers of the body.
+    ///
+    /// Called once after `poll_next_chunk()` returns `None`. Trailers are sent
+    /// only with chunked http/1.1 bodies and http/2 streams.
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        None
+    }
 }
 
 impl MessageBody for () {
@@ -53,6 +62,10 @@ impl<T: MessageBody> MessageBody for Box<T> {
     ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
         self.as_mut().poll_next_chunk(cx)
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.as_mut().trailers()
+    }
 }
 
 #[derive(Debug)]
@@ -113,6 +126,13 @@ impl<B: MessageBody> MessageBody for ResponseBody<B> {
             ResponseBody::Other(ref mut body) => body.poll_next_chunk(cx),
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            ResponseBody::Body(ref mut body) => body.trailers(),
+            ResponseBody::Other(ref mut body) => body.trailers(),
+        }
+    }
 }
 
 impl<B: MessageBody + Unpin> Stream for ResponseBody<B> {
@@ -178,6 +198,13 @@ impl MessageBody for Body {
             Body::Message(ref mut body) => body.poll_next_chunk(cx),
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            Body::Message(ref mut body) => body.trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for Body {
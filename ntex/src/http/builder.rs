@@ -138,6 +138,29 @@ where
         self
     }
 
+    /// Set max size of request's payload.
+    ///
+    /// Requests with larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` response, streamed payloads are terminated
+    /// once they exceed the limit. To disable limit set value to 0.
+    ///
+    /// By default payload size is not limited.
+    pub fn payload_limit(mut self, limit: u64) -> Self {
+        self.config.payload_limit(limit);
+        self
+    }
+
+    /// Enable http/2 over cleartext connections (h2c).
+    ///
+    /// Cleartext connections are switched to http/2 if connection starts
+    /// with http/2 preface or first request is `Upgrade: h2c` request.
+    ///
+    /// By default h2c is disabled.
+    pub fn h2c(mut self, enabled: bool) -> Self {
+        self.config.h2c(enabled);
+        self
+    }
+
     #[doc(hidden)]
     /// Configure http2 connection settings
     pub fn configure_http2<O, R>(self, f: O) -> Self
@@ -4,19 +4,29 @@ use base64::{engine::general_purpose::STANDARD as base64, Engine};
 
 use crate::http::error::HttpError;
 use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
-use crate::{service::Service, time::Millis};
+use crate::service::{boxed, Middleware, Pipeline, Service};
+use crate::time::Millis;
 
-use super::connect::ConnectorWrapper;
-use super::error::ConnectError;
-use super::{Client, ClientConfig, Connect, Connection, Connector};
+use super::connect::{Connect as HttpConnect, ConnectorWrapper};
+use super::error::{ConnectError, SendRequestError};
+use super::middleware::{Redirect, Timeout};
+use super::service::{SendService, ServiceRequest};
+use super::{Client, ClientConfig, ClientResponse, ClientService};
+use super::{Connect, Connection, Connector, Proxy};
+
+type Wrapper = Box<dyn FnOnce(ClientService) -> ClientService>;
 
 /// An HTTP Client builder
 ///
 /// This type can be used to construct an instance of `Client` through a
 /// builder-like pattern.
-#[derive(Debug)]
 pub struct ClientBuilder {
-    config: ClientConfig,
+    headers: HeaderMap,
+    timeout: Millis,
+    connector: Box<dyn HttpConnect>,
+    middlewares: Vec<Wrapper>,
+    #[cfg(feature = "cookie")]
+    cookie_jar: Option<super::CookieJar>,
     default_headers: bool,
     allow_redirects: bool,
     max_redirects: usize,
@@ -34,11 +44,12 @@ impl ClientBuilder {
             default_headers: true,
             allow_redirects: true,
             max_redirects: 10,
-            config: ClientConfig {
-                headers: HeaderMap::new(),
-                timeout: Millis(5_000),
-                connector: Box::new(ConnectorWrapper(Connector::default().finish().into())),
-            },
+            headers: HeaderMap::new(),
+            timeout: Millis(5_000),
+            connector: Box::new(ConnectorWrapper(Connector::default().finish().into())),
+            middlewares: Vec::new(),
+            #[cfg(feature = "cookie")]
+            cookie_jar: None,
         }
     }
 
@@ -49,22 +60,29 @@ impl ClientBuilder {
             + fmt::Debug
             + 'static,
     {
-        self.config.connector = Box::new(ConnectorWrapper(connector.into()));
+        self.connector = Box::new(ConnectorWrapper(connector.into()));
         self
     }
 
+    /// Use default connector with proxy configuration.
+    ///
+    /// Replaces previously configured connector.
+    pub fn proxy(self, proxy: Proxy) -> Self {
+        self.connector(Connector::default().proxy(proxy).finish())
+    }
+
     /// Set request timeout.
     ///
     /// Request timeout is the total time before a response must be received.
     /// Default value is 5 seconds.
     pub fn timeout<T: Into<Millis>>(mut self, timeout: T) -> Self {
-        self.config.timeout = timeout.into();
+        self.timeout = timeout.into();
         self
     }
 
     /// Disable request timeout.
     pub fn disable_timeout(mut self) -> Self {
-        self.config.timeout = Millis::ZERO;
+        self.timeout = Millis::ZERO;
         self
     }
 
@@ -84,6 +102,16 @@ impl ClientBuilder {
         self
     }
 
+    #[cfg(feature = "cookie")]
+    /// Use cookie jar for storing and sending cookies.
+    ///
+    /// Cookies from `Set-Cookie` response headers get stored in the jar
+    /// and sent with subsequent requests. Jar is not set by default.
+    pub fn cookie_jar(mut self, jar: super::CookieJar) -> Self {
+        self.cookie_jar = Some(jar);
+        self
+    }
+
     /// Do not add default request headers.
     /// By default `Date` and `User-Agent` headers are set.
     pub fn no_default_headers(mut self) -> Self {
@@ -103,7 +131,7 @@ impl ClientBuilder {
         match HeaderName::try_from(key) {
             Ok(key) => match HeaderValue::try_from(value) {
                 Ok(value) => {
-                    self.config.headers.append(key, value);
+                    self.headers.append(key, value);
                 }
                 Err(e) => log::error!("Header value error: {:?}", e),
             },
@@ -135,9 +163,81 @@ impl ClientBuilder {
         self.header(header::AUTHORIZATION, format!("Bearer {}", token))
     }
 
+    /// Register client middleware.
+    ///
+    /// Middleware wraps request sending, it gets `ServiceRequest` with
+    /// request head and body and returns `ClientResponse`. Middlewares
+    /// registered later wrap previously registered ones. All middlewares
+    /// wrap built-in `Timeout`, `Redirect` and cookie jar middlewares.
+    ///
+    /// ```rust
+    /// use ntex::http::client::{Client, ClientService, ServiceRequest};
+    /// use ntex::service::{fn_service, boxed, Middleware, Pipeline};
+    ///
+    /// struct Logger;
+    ///
+    /// impl Middleware<ClientService> for Logger {
+    ///     type Service = ClientService;
+    ///
+    ///     fn create(&self, service: ClientService) -> ClientService {
+    ///         let service = Pipeline::new(service);
+    ///         boxed::service(fn_service(move |req: ServiceRequest| {
+    ///             let service = service.clone();
+    ///             async move {
+    ///                 println!("Request: {} {}", req.method(), req.uri());
+    ///                 service.call(req).await
+    ///             }
+    ///         }))
+    ///     }
+    /// }
+    ///
+    /// let client = Client::build().wrap(Logger).finish();
+    /// ```
+    pub fn wrap<M>(mut self, mw: M) -> Self
+    where
+        M: Middleware<ClientService> + 'static,
+        M::Service: Service<ServiceRequest, Response = ClientResponse, Error = SendRequestError>
+            + 'static,
+    {
+        self.middlewares
+            .push(Box::new(move |svc| boxed::service(mw.create(svc))));
+        self
+    }
+
     /// Finish build process and create `Client` instance.
     pub fn finish(self) -> Client {
-        Client(Rc::new(self.config))
+        let mut service = boxed::service(SendService(self.connector));
+        #[cfg(feature = "cookie")]
+        if let Some(jar) = self.cookie_jar {
+            service = boxed::service(jar.create(service));
+        }
+        if self.allow_redirects {
+            service = boxed::service(Redirect::new(self.max_redirects).create(service));
+        }
+        service = boxed::service(Timeout.create(service));
+        for wrapper in self.middlewares {
+            service = wrapper(service);
+        }
+
+        Client(Rc::new(ClientConfig {
+            service: Pipeline::new(service),
+            headers: self.headers,
+            timeout: self.timeout,
+        }))
+    }
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("headers", &self.headers)
+            .field("timeout", &self.timeout)
+            .field("connector", &self.connector)
+            .field("middlewares", &self.middlewares.len())
+            .field("default_headers", &self.default_headers)
+            .field("allow_redirects", &self.allow_redirects)
+            .field("max_redirects", &self.max_redirects)
+            .finish()
     }
 }
 
@@ -162,7 +262,6 @@ mod tests {
         let client = ClientBuilder::new().basic_auth("username", Some("password"));
         assert_eq!(
             client
-                .config
                 .headers
                 .get(header::AUTHORIZATION)
                 .unwrap()
@@ -174,7 +273,6 @@ mod tests {
         let client = ClientBuilder::new().basic_auth("username", None);
         assert_eq!(
             client
-                .config
                 .headers
                 .get(header::AUTHORIZATION)
                 .unwrap()
@@ -189,7 +287,6 @@ mod tests {
         let client = ClientBuilder::new().bearer_auth("someS3cr3tAutht0k3n");
         assert_eq!(
             client
-                .config
                 .headers
                 .get(header::AUTHORIZATION)
                 .unwrap()
@@ -51,10 +51,11 @@ where
             let connection = fut.await?;
 
             // send request
-            connection
-                .send_request(head, body, timeout)
-                .await
-                .map(|(head, payload)| ClientResponse::new(head, payload))
+            connection.send_request(head, body, timeout).await.map(
+                |(head, payload, trailers)| {
+                    ClientResponse::with_trailers(head, payload, trailers)
+                },
+            )
         })
     }
 }
@@ -6,6 +6,7 @@ use crate::http::payload::Payload;
 use crate::io::{types::HttpProtocol, IoBoxed};
 use crate::time::Millis;
 
+use super::response::ResponseTrailers;
 use super::{error::SendRequestError, h1proto, h2proto, pool::Acquired};
 
 pub(super) enum ConnectionType {
@@ -83,7 +84,7 @@ impl Connection {
         head: H,
         body: B,
         timeout: Millis,
-    ) -> Result<(ResponseHead, Payload), SendRequestError> {
+    ) -> Result<(ResponseHead, Payload, ResponseTrailers), SendRequestError> {
         match self.io.take().unwrap() {
             ConnectionType::H1(io) => {
                 h1proto::send_request(
@@ -1,13 +1,14 @@
-use std::{fmt, task::Context, task::Poll, time::Duration};
+use std::{fmt, rc::Rc, task::Context, task::Poll, time::Duration};
 
 use ntex_h2::{self as h2};
 
-use crate::connect::{Connect as TcpConnect, Connector as TcpConnector};
+use crate::connect::{Connect as TcpConnect, Connector as TcpConnector, Resolve};
 use crate::service::{apply_fn, boxed, Service, ServiceCall, ServiceCtx};
 use crate::time::{Millis, Seconds};
 use crate::util::{timeout::TimeoutError, timeout::TimeoutService, Either, Ready};
 use crate::{http::Uri, io::IoBoxed};
 
+use super::proxy::{Proxy, ProxyConnector, TlsConnector};
 use super::{connection::Connection, error::ConnectError, pool::ConnectionPool, Connect};
 
 #[cfg(feature = "openssl")]
@@ -17,6 +18,7 @@ use crate::connect::openssl::SslConnector;
 use crate::connect::rustls::ClientConfig;
 
 type BoxedConnector = boxed::BoxService<TcpConnect<Uri>, IoBoxed, ConnectError>;
+type DnsResolver = Rc<dyn Resolve>;
 
 #[derive(Debug)]
 /// Manages http client network connectivity.
@@ -39,8 +41,11 @@ pub struct Connector {
     disconnect_timeout: Seconds,
     limit: usize,
     h2config: h2::Config,
-    connector: BoxedConnector,
+    tcp: TcpConnector<Uri, DnsResolver>,
+    connector: Option<BoxedConnector>,
     ssl_connector: Option<BoxedConnector>,
+    tls: Option<TlsConnector>,
+    proxy: Option<Proxy>,
 }
 
 impl Default for Connector {
@@ -52,13 +57,11 @@ impl Default for Connector {
 impl Connector {
     pub fn new() -> Connector {
         let conn = Connector {
-            connector: boxed::service(
-                TcpConnector::new()
-                    .chain()
-                    .map(IoBoxed::from)
-                    .map_err(ConnectError::from),
-            ),
+            tcp: TcpConnector::new().resolver(default_resolver()),
+            connector: None,
             ssl_connector: None,
+            tls: None,
+            proxy: None,
             timeout: Millis(1_000),
             conn_lifetime: Duration::from_secs(75),
             conn_keep_alive: Duration::from_secs(15),
@@ -117,20 +120,59 @@ impl Connector {
         self
     }
 
+    /// Use custom dns resolver.
+    ///
+    /// Resolver is used by default tcp connector and by `openssl` and
+    /// `rustls` connectors, custom connectors use their own resolvers.
+    pub fn resolver<R: Resolve>(mut self, resolver: R) -> Self {
+        self.tcp = self.tcp.resolver(Rc::new(resolver) as DnsResolver);
+        self
+    }
+
+    /// Set connection attempt delay.
+    ///
+    /// Resolved addresses are tried concurrently, next connection attempt
+    /// starts if previous attempts are not completed within the delay.
+    /// Zero delay disables concurrent attempts. By default delay is 250 millis.
+    pub fn attempt_delay<T: Into<Millis>>(mut self, delay: T) -> Self {
+        self.tcp = self.tcp.attempt_delay(delay);
+        self
+    }
+
+    /// Set timeout for single connection attempt.
+    ///
+    /// Timed out attempt fails and next address is tried.
+    /// By default attempt timeout is not set.
+    pub fn attempt_timeout<T: Into<Millis>>(mut self, timeout: T) -> Self {
+        self.tcp = self.tcp.attempt_timeout(timeout);
+        self
+    }
+
     #[cfg(feature = "openssl")]
     /// Use openssl connector for secured connections.
-    pub fn openssl(self, connector: SslConnector) -> Self {
-        use crate::connect::openssl::Connector;
-
-        self.secure_connector(Connector::new(connector))
+    pub fn openssl(mut self, connector: SslConnector) -> Self {
+        self.ssl_connector = None;
+        self.tls = Some(TlsConnector::Openssl(connector));
+        self
     }
 
     #[cfg(feature = "rustls")]
     /// Use rustls connector for secured connections.
-    pub fn rustls(self, connector: ClientConfig) -> Self {
-        use crate::connect::rustls::Connector;
+    pub fn rustls(mut self, connector: ClientConfig) -> Self {
+        self.ssl_connector = None;
+        self.tls = Some(TlsConnector::Rustls(std::sync::Arc::new(connector)));
+        self
+    }
 
-        self.secure_connector(Connector::new(connector))
+    /// Use proxy for connections.
+    ///
+    /// Plain http requests are sent to the proxy server, https and websocket
+    /// connections are tunnelled with `CONNECT` method. Connections to the
+    /// proxy server are opened with default tcp connector, tunnelled secure
+    /// connections are supported only with `openssl` or `rustls` connectors.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
     }
 
     /// Set total number of simultaneous connections per type of scheme.
@@ -192,12 +234,12 @@ impl Connector {
         T: Service<TcpConnect<Uri>, Error = crate::connect::ConnectError> + 'static,
         IoBoxed: From<T::Response>,
     {
-        self.connector = boxed::service(
+        self.connector = Some(boxed::service(
             connector
                 .chain()
                 .map(IoBoxed::from)
                 .map_err(ConnectError::from),
-        );
+        ));
         self
     }
 
@@ -207,6 +249,7 @@ impl Connector {
         T: Service<TcpConnect<Uri>, Error = crate::connect::ConnectError> + 'static,
         IoBoxed: From<T::Response>,
     {
+        self.tls = None;
         self.ssl_connector = Some(boxed::service(
             connector
                 .chain()
@@ -223,10 +266,43 @@ impl Connector {
         self,
     ) -> impl Service<Connect, Response = Connection, Error = ConnectError> + fmt::Debug
     {
-        let tcp_service = connector(self.connector, self.timeout, self.disconnect_timeout);
+        let tcp_proxy = self
+            .proxy
+            .clone()
+            .map(|proxy| ProxyConnector::new(proxy, None));
+        let tcp_connector = self.connector.unwrap_or_else(|| {
+            boxed::service(
+                self.tcp
+                    .clone()
+                    .chain()
+                    .map(IoBoxed::from)
+                    .map_err(ConnectError::from),
+            )
+        });
+        let tcp_service = connector(
+            tcp_connector,
+            tcp_proxy,
+            self.timeout,
+            self.disconnect_timeout,
+        );
 
-        let ssl_pool = if let Some(ssl_connector) = self.ssl_connector {
-            let srv = connector(ssl_connector, self.timeout, self.disconnect_timeout);
+        let ssl_connector = self.ssl_connector.or_else(|| {
+            self.tls
+                .as_ref()
+                .map(|tls| tls_connector(tls, self.tcp.clone()))
+        });
+
+        let ssl_pool = if let Some(ssl_connector) = ssl_connector {
+            let ssl_proxy = self
+                .proxy
+                .clone()
+                .map(|proxy| ProxyConnector::new(proxy, self.tls));
+            let srv = connector(
+                ssl_connector,
+                ssl_proxy,
+                self.timeout,
+                self.disconnect_timeout,
+            );
             Some(ConnectionPool::new(
                 srv,
                 self.conn_lifetime,
@@ -234,6 +310,7 @@ impl Connector {
                 self.disconnect_timeout,
                 self.limit,
                 self.h2config.clone(),
+                self.proxy.clone(),
             ))
         } else {
             None
@@ -247,23 +324,64 @@ impl Connector {
                 self.disconnect_timeout,
                 self.limit,
                 self.h2config.clone(),
+                self.proxy,
             ),
             ssl_pool,
         }
     }
 }
 
+fn default_resolver() -> DnsResolver {
+    Rc::new(crate::connect::SystemResolver)
+}
+
+/// Secure connector that uses configured tcp connector
+#[cfg_attr(
+    not(any(feature = "openssl", feature = "rustls")),
+    allow(unused_variables)
+)]
+fn tls_connector(
+    tls: &TlsConnector,
+    tcp: TcpConnector<Uri, DnsResolver>,
+) -> BoxedConnector {
+    match *tls {
+        #[cfg(feature = "openssl")]
+        TlsConnector::Openssl(ref ssl) => boxed::service(
+            crate::connect::openssl::Connector::new(ssl.clone())
+                .connector(tcp)
+                .chain()
+                .map(IoBoxed::from)
+                .map_err(ConnectError::from),
+        ),
+        #[cfg(feature = "rustls")]
+        TlsConnector::Rustls(ref config) => boxed::service(
+            crate::connect::rustls::Connector::from(config.clone())
+                .connector(tcp)
+                .chain()
+                .map(IoBoxed::from)
+                .map_err(ConnectError::from),
+        ),
+    }
+}
+
 fn connector(
     connector: BoxedConnector,
+    proxy: Option<ProxyConnector>,
     timeout: Millis,
     disconnect_timeout: Seconds,
 ) -> impl Service<Connect, Response = IoBoxed, Error = ConnectError> + fmt::Debug {
     TimeoutService::new(
         timeout,
-        apply_fn(connector, |msg: Connect, srv| {
-            Box::pin(
-                async move { srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr)).await },
-            )
+        apply_fn(connector, move |msg: Connect, srv| {
+            let proxy = proxy.clone();
+            Box::pin(async move {
+                if let Some(ref proxy) = proxy {
+                    if let Some(server) = proxy.proxy().select(&msg.uri) {
+                        return proxy.connect(server, &msg.uri).await;
+                    }
+                }
+                srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr)).await
+            })
         })
         .chain()
         .map(move |io: IoBoxed| {
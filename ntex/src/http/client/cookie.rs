@@ -0,0 +1,569 @@
+use std::{cell::RefCell, fmt, fmt::Write, net, rc::Rc};
+
+use coo_kie::time::{Duration, OffsetDateTime};
+use coo_kie::Cookie;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::http::header::{HeaderMap, HeaderValue, COOKIE, SET_COOKIE};
+use crate::http::Uri;
+use crate::service::{Middleware, Service, ServiceCtx};
+use crate::util::BoxFuture;
+
+use super::error::SendRequestError;
+use super::{ClientResponse, ServiceRequest};
+
+/// Client cookie store.
+///
+/// Cookie jar stores cookies from `Set-Cookie` response headers and sends
+/// them back with subsequent requests. Domain, path, expiry and secure
+/// rules follow RFC 6265, public suffixes are not checked.
+///
+/// Jar is shared between clones, so it could be inspected after requests.
+/// Jar could be serialized with serde, expired cookies are skipped.
+///
+/// ```rust
+/// use ntex::http::client::{Client, CookieJar};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let jar = CookieJar::new();
+///     let client = Client::build().cookie_jar(jar.clone()).finish();
+///
+///     let _ = client.post("http://www.example.com/login").send().await;
+///
+///     for cookie in jar.iter() {
+///         println!("Cookie: {}", cookie);
+///     }
+///     let state = serde_json::to_string(&jar).unwrap();
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct CookieJar(Rc<RefCell<Vec<StoredCookie>>>);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredCookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    secure: bool,
+    http_only: bool,
+    /// Unix timestamp
+    expires: Option<i64>,
+}
+
+impl CookieJar {
+    /// Create empty cookie jar
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// Store cookies from response's `Set-Cookie` headers.
+    ///
+    /// Invalid cookies and cookies for foreign domains are ignored.
+    /// Cookie name and value are stored as received.
+    pub fn store(&self, uri: &Uri, headers: &HeaderMap) {
+        for hdr in headers.get_all(SET_COOKIE) {
+            if let Ok(Ok(cookie)) = hdr.to_str().map(Cookie::parse) {
+                self.add(uri, cookie);
+            }
+        }
+    }
+
+    /// Add cookie as it is received in response from the uri.
+    ///
+    /// Returns `false` if cookie is rejected.
+    pub fn add(&self, uri: &Uri, cookie: Cookie<'_>) -> bool {
+        let host = if let Some(host) = uri.host() {
+            host.to_ascii_lowercase()
+        } else {
+            return false;
+        };
+        let now = OffsetDateTime::now_utc();
+
+        // domain
+        let (domain, host_only) = match cookie.domain() {
+            Some(domain) if !domain.is_empty() => {
+                let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+                if !domain_match(&host, &domain) {
+                    log::trace!("Reject cookie {:?} for {:?}", cookie.name(), host);
+                    return false;
+                }
+                (domain, false)
+            }
+            _ => (host, true),
+        };
+
+        // path
+        let path = match cookie.path() {
+            Some(path) if path.starts_with('/') => path.to_string(),
+            _ => default_path(uri.path()),
+        };
+
+        // expiry, max-age takes precedence over expires
+        let expires = if let Some(max_age) = cookie.max_age() {
+            if max_age <= Duration::ZERO {
+                Some(i64::MIN)
+            } else {
+                Some(now.saturating_add(max_age).unix_timestamp())
+            }
+        } else {
+            cookie.expires_datetime().map(|dt| dt.unix_timestamp())
+        };
+
+        let item = StoredCookie {
+            path,
+            domain,
+            host_only,
+            expires,
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+        };
+
+        let mut cookies = self.0.borrow_mut();
+        let pos = cookies.iter().position(|c| {
+            c.name == item.name && c.domain == item.domain && c.path == item.path
+        });
+        if item.is_expired(now.unix_timestamp()) {
+            // expired cookie removes stored cookie
+            if let Some(pos) = pos {
+                cookies.remove(pos);
+            }
+        } else if let Some(pos) = pos {
+            cookies[pos] = item;
+        } else {
+            cookies.push(item);
+        }
+        true
+    }
+
+    /// Cookies that must be sent with request to the uri.
+    ///
+    /// Cookies with longer paths are listed first.
+    pub fn cookies(&self, uri: &Uri) -> Vec<Cookie<'static>> {
+        self.matches(uri)
+            .into_iter()
+            .map(|c| Cookie::new(c.name, c.value))
+            .collect()
+    }
+
+    /// All stored cookies with their attributes
+    pub fn iter(&self) -> impl Iterator<Item = Cookie<'static>> {
+        self.remove_expired();
+        self.0
+            .borrow()
+            .iter()
+            .map(|c| c.to_cookie())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Remove cookie
+    pub fn remove(&self, domain: &str, path: &str, name: &str) {
+        self.0
+            .borrow_mut()
+            .retain(|c| !(c.domain == domain && c.path == path && c.name == name));
+    }
+
+    /// Remove all cookies
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear()
+    }
+
+    /// Number of stored cookies
+    pub fn len(&self) -> usize {
+        self.remove_expired();
+        self.0.borrow().len()
+    }
+
+    /// Check if jar is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Value of `Cookie` header for request to the uri
+    ///
+    /// Names and values are sent unchanged, RFC 6265 section 5.4
+    pub(super) fn header(&self, uri: &Uri) -> Option<String> {
+        let mut value = String::new();
+        for c in self.matches(uri) {
+            let _ = write!(value, "; {}={}", c.name, c.value);
+        }
+        if value.is_empty() {
+            None
+        } else {
+            Some(value.split_off(2))
+        }
+    }
+
+    fn matches(&self, uri: &Uri) -> Vec<StoredCookie> {
+        let host = if let Some(host) = uri.host() {
+            host.to_ascii_lowercase()
+        } else {
+            return Vec::new();
+        };
+        let path = if uri.path().is_empty() {
+            "/"
+        } else {
+            uri.path()
+        };
+        let secure = matches!(uri.scheme_str(), Some("https") | Some("wss"));
+
+        self.remove_expired();
+        let mut cookies: Vec<_> = self
+            .0
+            .borrow()
+            .iter()
+            .filter(|c| {
+                (if c.host_only {
+                    host == c.domain
+                } else {
+                    domain_match(&host, &c.domain)
+                }) && path_match(path, &c.path)
+                    && (secure || !c.secure)
+            })
+            .cloned()
+            .collect();
+        // stable sort keeps creation order for equal paths
+        cookies.sort_by(|a, b| b.path.len().cmp(&a.path.len()));
+        cookies
+    }
+
+    fn remove_expired(&self) {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.0.borrow_mut().retain(|c| !c.is_expired(now));
+    }
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires.map(|exp| exp <= now).unwrap_or(false)
+    }
+
+    fn to_cookie(&self) -> Cookie<'static> {
+        let mut cookie = Cookie::build(self.name.clone(), self.value.clone())
+            .domain(self.domain.clone())
+            .path(self.path.clone())
+            .secure(self.secure)
+            .http_only(self.http_only)
+            .finish();
+        if let Some(dt) = self
+            .expires
+            .and_then(|exp| OffsetDateTime::from_unix_timestamp(exp).ok())
+        {
+            cookie.set_expires(dt);
+        }
+        cookie
+    }
+}
+
+impl fmt::Debug for CookieJar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CookieJar")
+            .field("cookies", &self.0.borrow())
+            .finish()
+    }
+}
+
+impl Serialize for CookieJar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.remove_expired();
+        self.0.borrow().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CookieJar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<StoredCookie>::deserialize(deserializer)
+            .map(|cookies| CookieJar(Rc::new(RefCell::new(cookies))))
+    }
+}
+
+/// Cookie jar is a client middleware.
+///
+/// Middleware adds jar's cookies to request's `Cookie` header and stores
+/// cookies from response. `ClientBuilder` applies it inside redirects
+/// handling middleware, so cookies are stored and sent on each redirect.
+impl<S> Middleware<S> for CookieJar {
+    type Service = CookieJarMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        CookieJarMiddleware {
+            service,
+            jar: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CookieJarMiddleware<S> {
+    service: S,
+    jar: CookieJar,
+}
+
+impl<S> Service<ServiceRequest> for CookieJarMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ClientResponse, Error = SendRequestError>,
+{
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future<'f> = BoxFuture<'f, Result<ClientResponse, SendRequestError>> where S: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        mut req: ServiceRequest,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        let uri = req.uri().clone();
+
+        if let Some(cookies) = self.jar.header(&uri) {
+            // merge with cookies set for the request
+            let value = match req.header(&COOKIE).and_then(|v| v.to_str().ok()) {
+                Some(val) => format!("{}; {}", val, cookies),
+                None => cookies,
+            };
+            match HeaderValue::try_from(value) {
+                Ok(value) => {
+                    req.head_mut().headers.insert(COOKIE, value);
+                }
+                Err(e) => log::error!("Cannot set COOKIE header {}", e),
+            }
+        }
+
+        Box::pin(async move {
+            let res = ctx.call(&self.service, req).await?;
+            self.jar.store(&uri, res.headers());
+            Ok(res)
+        })
+    }
+}
+
+/// Domain matching, RFC 6265 section 5.1.3
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.len() > domain.len()
+            && host.ends_with(domain)
+            && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+            && host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<net::IpAddr>()
+                .is_err())
+}
+
+/// Path matching, RFC 6265 section 5.1.4
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path.as_bytes()[cookie_path.len()] == b'/'))
+}
+
+/// Default path of the cookie, RFC 6265 section 5.1.4
+fn default_path(path: &str) -> String {
+    if !path.starts_with('/') {
+        return "/".to_string();
+    }
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(pos) => path[..pos].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(jar: &CookieJar, uri: &'static str, cookies: &[&'static str]) {
+        let mut headers = HeaderMap::new();
+        for c in cookies {
+            headers.append(SET_COOKIE, HeaderValue::from_static(c));
+        }
+        jar.store(&Uri::from_static(uri), &headers);
+    }
+
+    fn header(jar: &CookieJar, uri: &'static str) -> Option<String> {
+        jar.header(&Uri::from_static(uri))
+    }
+
+    #[test]
+    fn test_domain() {
+        let jar = CookieJar::new();
+        store(
+            &jar,
+            "http://www.example.com/",
+            &[
+                "host=1",
+                "domain=2; Domain=.example.com",
+                "foreign=3; Domain=other.com",
+                "sub=4; Domain=sub.www.example.com",
+            ],
+        );
+        assert_eq!(jar.len(), 2);
+        assert_eq!(
+            header(&jar, "http://www.example.com/").as_deref(),
+            Some("host=1; domain=2")
+        );
+        assert_eq!(
+            header(&jar, "http://example.com/").as_deref(),
+            Some("domain=2")
+        );
+        assert_eq!(
+            header(&jar, "http://a.www.example.com/").as_deref(),
+            Some("domain=2")
+        );
+        assert_eq!(header(&jar, "http://badexample.com/"), None);
+        assert_eq!(header(&jar, "http://other.com/"), None);
+
+        // ip address
+        let jar = CookieJar::new();
+        store(&jar, "http://127.0.0.1/", &["a=1; Domain=0.0.1", "b=2"]);
+        assert_eq!(header(&jar, "http://127.0.0.1/").as_deref(), Some("b=2"));
+    }
+
+    #[test]
+    fn test_path() {
+        let jar = CookieJar::new();
+        store(
+            &jar,
+            "http://example.com/docs/index.html",
+            &["default=1", "root=2; Path=/", "api=3; Path=/api/v1"],
+        );
+        let cookies = jar.iter().collect::<Vec<_>>();
+        assert_eq!(cookies[0].path(), Some("/docs"));
+        assert_eq!(
+            header(&jar, "http://example.com/docs/a").as_deref(),
+            Some("default=1; root=2")
+        );
+        assert_eq!(
+            header(&jar, "http://example.com/docs").as_deref(),
+            Some("default=1; root=2")
+        );
+        assert_eq!(
+            header(&jar, "http://example.com/docsx").as_deref(),
+            Some("root=2")
+        );
+        assert_eq!(
+            header(&jar, "http://example.com/api/v1/users").as_deref(),
+            Some("api=3; root=2")
+        );
+        assert_eq!(
+            header(&jar, "http://example.com").as_deref(),
+            Some("root=2")
+        );
+
+        assert_eq!(default_path(""), "/");
+        assert_eq!(default_path("/"), "/");
+        assert_eq!(default_path("/a"), "/");
+        assert_eq!(default_path("/a/b/c"), "/a/b");
+    }
+
+    #[test]
+    fn test_secure_and_replace() {
+        let jar = CookieJar::new();
+        store(
+            &jar,
+            "https://example.com/",
+            &["a=1; Secure; HttpOnly", "b=2"],
+        );
+        assert_eq!(header(&jar, "http://example.com/").as_deref(), Some("b=2"));
+        assert_eq!(
+            header(&jar, "https://example.com/").as_deref(),
+            Some("a=1; b=2")
+        );
+        assert_eq!(
+            header(&jar, "wss://example.com/").as_deref(),
+            Some("a=1; b=2")
+        );
+
+        // replace keeps position
+        store(&jar, "https://example.com/", &["a=3"]);
+        assert_eq!(
+            header(&jar, "http://example.com/").as_deref(),
+            Some("a=3; b=2")
+        );
+        assert_eq!(jar.len(), 2);
+
+        jar.remove("example.com", "/", "a");
+        assert_eq!(header(&jar, "http://example.com/").as_deref(), Some("b=2"));
+        jar.clear();
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let jar = CookieJar::new();
+        store(
+            &jar,
+            "http://example.com/",
+            &[
+                "a=1; Max-Age=100",
+                "b=2; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+                "c=3; Max-Age=100; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+                "d=4",
+            ],
+        );
+        assert_eq!(
+            header(&jar, "http://example.com/").as_deref(),
+            Some("a=1; c=3; d=4")
+        );
+        assert!(jar.iter().next().unwrap().expires_datetime().is_some());
+
+        // remove cookies
+        store(
+            &jar,
+            "http://example.com/",
+            &["a=; Max-Age=0", "d=; Expires=Wed, 21 Oct 2015 07:28:00 GMT"],
+        );
+        assert_eq!(header(&jar, "http://example.com/").as_deref(), Some("c=3"));
+    }
+
+    #[test]
+    fn test_raw_value() {
+        let jar = CookieJar::new();
+        store(
+            &jar,
+            "http://example.com/",
+            &["sid=YWJjZA==; Path=/", "raw=a%20b%zz/:@"],
+        );
+        assert_eq!(
+            header(&jar, "http://example.com/").as_deref(),
+            Some("sid=YWJjZA==; raw=a%20b%zz/:@")
+        );
+        let cookies = jar.cookies(&Uri::from_static("http://example.com/"));
+        assert_eq!(cookies[0].value(), "YWJjZA==");
+        assert_eq!(cookies[1].value(), "a%20b%zz/:@");
+    }
+
+    #[test]
+    fn test_serialize() {
+        let jar = CookieJar::new();
+        store(
+            &jar,
+            "https://example.com/",
+            &[
+                "a=1; Secure; Max-Age=100",
+                "b=2; Domain=example.com; Path=/p",
+            ],
+        );
+        assert!(format!("{:?}", jar).contains("CookieJar"));
+
+        let data = serde_json::to_string(&jar).unwrap();
+        let jar2: CookieJar = serde_json::from_str(&data).unwrap();
+        assert_eq!(*jar.0.borrow(), *jar2.0.borrow());
+        assert_eq!(
+            header(&jar2, "https://www.example.com/p").as_deref(),
+            Some("b=2")
+        );
+
+        // jar is shared between clones
+        let jar3 = jar.clone();
+        jar3.clear();
+        assert!(jar.is_empty());
+    }
+}
@@ -8,6 +8,7 @@ use thiserror::Error;
 use crate::connect::openssl::{HandshakeError, SslError};
 
 use crate::http::error::{HttpError, ParseError, PayloadError};
+use crate::http::StatusCode;
 use crate::util::Either;
 
 /// A set of errors that can occur during parsing json payloads
@@ -24,6 +25,20 @@ pub enum JsonPayloadError {
     Payload(#[from] PayloadError),
 }
 
+/// A set of errors that can occur during reading server-sent events
+#[derive(Error, Debug)]
+pub enum SseError {
+    /// Content type error
+    #[error("Content type error")]
+    ContentType,
+    /// Event is too large
+    #[error("Event size is too large")]
+    Overflow,
+    /// Payload error
+    #[error("Error that occur during reading payload: {0}")]
+    Payload(#[from] PayloadError),
+}
+
 /// A set of errors that can occur while connecting to an HTTP host
 #[derive(Error, Debug)]
 pub enum ConnectError {
@@ -60,6 +75,10 @@ pub enum ConnectError {
     /// Unresolved host name
     #[error("Connector received `Connect` method with unresolved host")]
     Unresolved,
+
+    /// Proxy server refused to open tunnel
+    #[error("Proxy tunnel request failed with status: {0}")]
+    ProxyTunnel(StatusCode),
 }
 
 impl Clone for ConnectError {
@@ -88,6 +107,7 @@ impl Clone for ConnectError {
                 }
             }
             ConnectError::Unresolved => ConnectError::Unresolved,
+            ConnectError::ProxyTunnel(status) => ConnectError::ProxyTunnel(*status),
         }
     }
 }
@@ -3,7 +3,7 @@ use std::{io, io::Write, pin::Pin, task::Context, task::Poll, time::Instant};
 use crate::http::body::{BodySize, MessageBody};
 use crate::http::error::PayloadError;
 use crate::http::h1;
-use crate::http::header::{HeaderMap, HeaderValue, HOST};
+use crate::http::header::{HeaderMap, HeaderValue, HOST, PROXY_AUTHORIZATION};
 use crate::http::message::{RequestHeadType, ResponseHead};
 use crate::http::payload::{Payload, PayloadStream};
 use crate::io::{IoBoxed, RecvError};
@@ -12,7 +12,8 @@ use crate::util::{poll_fn, ready, BufMut, Bytes, BytesMut, Stream};
 
 use super::connection::{Connection, ConnectionType};
 use super::error::{ConnectError, SendRequestError};
-use super::pool::Acquired;
+use super::response::ResponseTrailers;
+use super::{pool::Acquired, proxy::ProxyServer};
 
 pub(super) async fn send_request<B>(
     io: IoBoxed,
@@ -21,7 +22,7 @@ pub(super) async fn send_request<B>(
     created: Instant,
     timeout: Millis,
     pool: Option<Acquired>,
-) -> Result<(ResponseHead, Payload), SendRequestError>
+) -> Result<(ResponseHead, Payload, ResponseTrailers), SendRequestError>
 where
     B: MessageBody,
 {
@@ -52,6 +53,25 @@ where
         }
     }
 
+    // plain http request to proxy, use absolute-form
+    let proxy = pool
+        .as_ref()
+        .and_then(|pool| pool.proxy())
+        .filter(|_| !ProxyServer::is_tunnel(&head.as_ref().uri));
+    if let Some(auth) = proxy.and_then(|proxy| proxy.auth()) {
+        if !head.as_ref().headers.contains_key(PROXY_AUTHORIZATION) {
+            match head {
+                RequestHeadType::Owned(ref mut head) => {
+                    head.headers.insert(PROXY_AUTHORIZATION, auth.clone())
+                }
+                RequestHeadType::Rc(_, ref mut extra_headers) => {
+                    let headers = extra_headers.get_or_insert(HeaderMap::new());
+                    headers.insert(PROXY_AUTHORIZATION, auth.clone())
+                }
+            }
+        }
+    }
+
     log::trace!(
         "sending http1 request {:?} body size: {:?}",
         head,
@@ -59,7 +79,11 @@ where
     );
 
     // send request
-    let codec = h1::ClientCodec::default();
+    let codec = if proxy.is_some() {
+        h1::ClientCodec::default().absolute_form()
+    } else {
+        h1::ClientCodec::default()
+    };
     io.send((head, body.size()).into(), &codec).await?;
 
     log::trace!("http1 request has been sent");
@@ -96,11 +120,13 @@ where
     match codec.message_type() {
         h1::MessageType::None => {
             release_connection(io, !codec.keepalive(), created, pool);
-            Ok((head, Payload::None))
+            Ok((head, Payload::None, ResponseTrailers::default()))
         }
         _ => {
-            let pl: PayloadStream = Box::pin(PlStream::new(io, codec, created, pool));
-            Ok((head, pl.into()))
+            let trailers = ResponseTrailers::default();
+            let pl: PayloadStream =
+                Box::pin(PlStream::new(io, codec, created, pool, trailers.clone()));
+            Ok((head, pl.into(), trailers))
         }
     }
 }
@@ -121,7 +147,11 @@ where
                 io.flush(false).await?;
             }
             None => {
-                io.encode(h1::Message::Chunk(None), codec)?;
+                if let Some(trailers) = body.trailers() {
+                    io.encode(h1::Message::Trailers(trailers), codec)?;
+                } else {
+                    io.encode(h1::Message::Chunk(None), codec)?;
+                }
                 break;
             }
         }
@@ -136,6 +166,7 @@ pub(super) struct PlStream {
     codec: h1::ClientPayloadCodec,
     created: Instant,
     pool: Option<Acquired>,
+    trailers: ResponseTrailers,
 }
 
 impl PlStream {
@@ -144,12 +175,14 @@ impl PlStream {
         codec: h1::ClientCodec,
         created: Instant,
         pool: Option<Acquired>,
+        trailers: ResponseTrailers,
     ) -> Self {
         PlStream {
             io: Some(io),
             codec: codec.into_payload_codec(),
             created,
             pool,
+            trailers,
         }
     }
 }
@@ -169,6 +202,9 @@ impl Stream for PlStream {
                         if let Some(chunk) = chunk {
                             Ok(chunk)
                         } else {
+                            if let Some(trailers) = this.codec.take_trailers() {
+                                this.trailers.set(trailers);
+                            }
                             release_connection(
                                 this.io.take().unwrap(),
                                 !this.codec.keepalive(),
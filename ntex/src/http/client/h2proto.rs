@@ -11,13 +11,14 @@ use crate::time::{timeout_checked, Millis};
 use crate::util::{poll_fn, ByteString, Bytes};
 
 use super::error::{ConnectError, SendRequestError};
+use super::response::ResponseTrailers;
 
 pub(super) async fn send_request<B>(
     client: H2Client,
     head: RequestHeadType,
     body: B,
     timeout: Millis,
-) -> Result<(ResponseHead, Payload), SendRequestError>
+) -> Result<(ResponseHead, Payload, ResponseTrailers), SendRequestError>
 where
     B: MessageBody,
 {
@@ -96,7 +97,7 @@ where
 
 async fn get_response(
     rcv_stream: RecvStream,
-) -> Result<(ResponseHead, Payload), SendRequestError> {
+) -> Result<(ResponseHead, Payload, ResponseTrailers), SendRequestError> {
     let h2::Message { stream, kind } = rcv_stream
         .recv()
         .await
@@ -121,7 +122,9 @@ async fn get_response(
                     head.headers = headers;
                     head.version = Version::HTTP_2;
 
+                    let trailers = ResponseTrailers::default();
                     let payload = if !eof {
+                        let trailers = trailers.clone();
                         log::debug!("Creating local payload stream for {:?}", stream.id());
                         let (mut pl, payload) =
                             payload::Payload::create(stream.empty_capacity());
@@ -154,8 +157,9 @@ async fn get_response(
                                             h2::StreamEof::Data(data) => {
                                                 pl.feed_eof(data);
                                             }
-                                            h2::StreamEof::Trailers(_) => {
-                                                pl.feed_eof(Bytes::new());
+                                            h2::StreamEof::Trailers(hdrs) => {
+                                                trailers.set(hdrs.clone());
+                                                pl.feed_trailers(hdrs);
                                             }
                                             h2::StreamEof::Error(err) => {
                                                 pl.set_error(err.into())
@@ -186,7 +190,7 @@ async fn get_response(
                     } else {
                         Payload::None
                     };
-                    Ok((head, payload))
+                    Ok((head, payload, trailers))
                 }
                 None => Err(SendRequestError::H2(h2::OperationError::Connection(
                     h2::ConnectionError::MissingPseudo("Status"),
@@ -213,7 +217,11 @@ async fn send_body<B: MessageBody>(
             Some(Err(e)) => return Err(e.into()),
             None => {
                 log::debug!("{:?} eof of send stream ", stream.id());
-                stream.send_payload(Bytes::new(), true).await?;
+                if let Some(trailers) = body.trailers() {
+                    stream.send_trailers(trailers);
+                } else {
+                    stream.send_payload(Bytes::new(), true).await?;
+                }
                 return Ok(());
             }
         }
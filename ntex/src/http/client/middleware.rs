@@ -0,0 +1,917 @@
+//! Built-in client middlewares
+use std::time::SystemTime;
+
+use nanorand::{Rng, WyRand};
+
+use crate::http::body::Body;
+use crate::http::header::{self, HeaderMap};
+use crate::http::{Method, StatusCode, Uri};
+use crate::service::{Middleware, Service, ServiceCtx};
+use crate::time::{sleep, timeout_checked, Millis};
+use crate::util::BoxFuture;
+
+use super::error::SendRequestError;
+use super::{ClientResponse, ServiceRequest};
+
+/// Request timeout middleware.
+///
+/// Request fails with `SendRequestError::Timeout` error if response is not
+/// received within request's timeout. Timeout includes time spent on
+/// connecting and sending request body. Zero timeout disables timeout.
+///
+/// Middleware is always applied by `ClientBuilder`, timeout is set by
+/// [`ClientBuilder::timeout()`](super::ClientBuilder::timeout)
+/// and [`ClientRequest::timeout()`](super::ClientRequest::timeout).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Timeout;
+
+impl<S> Middleware<S> for Timeout {
+    type Service = TimeoutMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        TimeoutMiddleware { service }
+    }
+}
+
+#[derive(Debug)]
+pub struct TimeoutMiddleware<S> {
+    service: S,
+}
+
+impl<S> Service<ServiceRequest> for TimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ClientResponse, Error = SendRequestError>,
+{
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future<'f> = BoxFuture<'f, Result<ClientResponse, SendRequestError>> where S: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: ServiceRequest,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        let timeout = req.timeout;
+        Box::pin(async move {
+            timeout_checked(timeout, ctx.call(&self.service, req))
+                .await
+                .map_err(|_| SendRequestError::Timeout)
+                .and_then(|res| res)
+        })
+    }
+}
+
+/// Redirects handling middleware.
+///
+/// Middleware follows `301`, `302`, `303`, `307` and `308` redirects.
+/// `303` redirects and `POST` requests redirected with `301` and `302` are
+/// changed to `GET` requests without body. Requests with streaming body
+/// are not redirected with `307` and `308` statuses. `Authorization` and
+/// `Cookie` headers and request's peer address are removed if redirect
+/// points to different host.
+///
+/// Middleware is applied by `ClientBuilder` unless redirects are disabled
+/// with [`ClientBuilder::disable_redirects()`](super::ClientBuilder::disable_redirects).
+#[derive(Copy, Clone, Debug)]
+pub struct Redirect {
+    max_redirects: usize,
+}
+
+impl Default for Redirect {
+    fn default() -> Self {
+        Redirect { max_redirects: 10 }
+    }
+}
+
+impl Redirect {
+    /// Construct `Redirect` middleware with max number of redirects.
+    pub fn new(max_redirects: usize) -> Self {
+        Redirect { max_redirects }
+    }
+}
+
+impl<S> Middleware<S> for Redirect {
+    type Service = RedirectMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RedirectMiddleware {
+            service,
+            max_redirects: self.max_redirects,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RedirectMiddleware<S> {
+    service: S,
+    max_redirects: usize,
+}
+
+impl<S> Service<ServiceRequest> for RedirectMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ClientResponse, Error = SendRequestError>,
+{
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future<'f> = BoxFuture<'f, Result<ClientResponse, SendRequestError>> where S: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: ServiceRequest,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        Box::pin(async move {
+            let mut req = req;
+            let mut redirects = 0;
+            loop {
+                let prev = if redirects < self.max_redirects {
+                    Some(RedirectState::new(&req))
+                } else {
+                    None
+                };
+                let res = ctx.call(&self.service, req).await?;
+
+                match prev.and_then(|prev| prev.next(&res)) {
+                    Some(next) => {
+                        log::trace!("Redirect {:?} to {:?}", res.status(), next.head().uri);
+                        redirects += 1;
+                        req = next;
+                    }
+                    None => return Ok(res),
+                }
+            }
+        })
+    }
+}
+
+struct RedirectState {
+    req: ServiceRequest,
+    // request body could not be re-sent
+    stream: bool,
+}
+
+impl RedirectState {
+    fn new(req: &ServiceRequest) -> Self {
+        if let Some(req) = req.try_clone() {
+            RedirectState { req, stream: false }
+        } else {
+            let req = ServiceRequest {
+                head: req.copy_head(),
+                body: Body::None,
+                addr: req.addr,
+                timeout: req.timeout,
+            };
+            RedirectState { req, stream: true }
+        }
+    }
+
+    fn next(self, res: &ClientResponse) -> Option<ServiceRequest> {
+        let status = res.status();
+        let keep_method = match status {
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => {
+                *self.req.method() != Method::POST
+            }
+            StatusCode::SEE_OTHER => *self.req.method() == Method::HEAD,
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => true,
+            _ => return None,
+        };
+        if keep_method && self.stream {
+            return None;
+        }
+
+        let location = res.headers().get(header::LOCATION)?.to_str().ok()?;
+        let uri = resolve(&self.req.head().uri, location)?;
+        let mut req = self.req;
+
+        let head = req.head_mut();
+        if uri.scheme() != head.uri.scheme() || uri.authority() != head.uri.authority() {
+            remove_headers(
+                &mut head.headers,
+                &[header::AUTHORIZATION, header::COOKIE, header::HOST],
+            );
+            req.addr = None;
+        }
+        head.uri = uri;
+        if !keep_method {
+            head.method = Method::GET;
+            remove_headers(
+                &mut head.headers,
+                &[
+                    header::CONTENT_TYPE,
+                    header::CONTENT_LENGTH,
+                    header::CONTENT_ENCODING,
+                    header::TRANSFER_ENCODING,
+                ],
+            );
+            req.body = Body::None;
+        }
+        Some(req)
+    }
+}
+
+/// Retry policy middleware.
+///
+/// Failed requests are re-sent up to `max_attempts` times in total.
+/// Requests are retried:
+///
+/// * on connect errors for any method, request is not sent yet
+/// * on other send errors, including timeouts, for idempotent methods
+/// * on responses with selected status codes for idempotent methods
+///
+/// Non-idempotent methods could be allowed with
+/// [`Retry::idempotent_only()`]. Requests with streaming body are never
+/// retried, `Bytes` bodies and frozen requests are re-sent as is.
+///
+/// Delay between attempts grows exponentially, with random jitter applied
+/// delay is in range `[delay / 2, delay]`. `Retry-After` header of the
+/// response takes precedence, response is returned as is if server asks
+/// to wait longer than max backoff.
+///
+/// Middleware should be registered with `ClientBuilder::wrap()`, each
+/// attempt gets its own request timeout.
+///
+/// ```rust
+/// use ntex::http::{client::{middleware::Retry, Client}, StatusCode};
+///
+/// let client = Client::build()
+///     .wrap(Retry::new(3).status(StatusCode::SERVICE_UNAVAILABLE))
+///     .finish();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Retry {
+    max_attempts: usize,
+    backoff: Millis,
+    max_backoff: Millis,
+    jitter: bool,
+    connect_errors: bool,
+    idempotent_only: bool,
+    retry_after: bool,
+    statuses: Vec<StatusCode>,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry::new(3)
+    }
+}
+
+impl Retry {
+    /// Construct `Retry` middleware with max number of attempts.
+    pub fn new(max_attempts: usize) -> Self {
+        Retry {
+            max_attempts,
+            backoff: Millis(100),
+            max_backoff: Millis(10_000),
+            jitter: true,
+            connect_errors: true,
+            idempotent_only: true,
+            retry_after: true,
+            statuses: Vec::new(),
+        }
+    }
+
+    /// Set exponential backoff parameters.
+    ///
+    /// Delay starts from `base` and doubles on each attempt up to `max`.
+    /// By default delay starts from 100 millis, max delay is 10 seconds.
+    pub fn backoff<T: Into<Millis>, U: Into<Millis>>(mut self, base: T, max: U) -> Self {
+        self.backoff = base.into();
+        self.max_backoff = max.into();
+        self
+    }
+
+    /// Apply random jitter to delay, enabled by default.
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /// Retry on connect errors, enabled by default.
+    pub fn connect_errors(mut self, enabled: bool) -> Self {
+        self.connect_errors = enabled;
+        self
+    }
+
+    /// Retry idempotent requests only, enabled by default.
+    ///
+    /// `GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT` and `DELETE` are idempotent methods.
+    pub fn idempotent_only(mut self, enabled: bool) -> Self {
+        self.idempotent_only = enabled;
+        self
+    }
+
+    /// Honour `Retry-After` response header, enabled by default.
+    pub fn retry_after(mut self, enabled: bool) -> Self {
+        self.retry_after = enabled;
+        self
+    }
+
+    /// Retry on responses with status code.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    fn method_allowed(&self, method: &Method) -> bool {
+        !self.idempotent_only
+            || matches!(
+                *method,
+                Method::GET
+                    | Method::HEAD
+                    | Method::OPTIONS
+                    | Method::TRACE
+                    | Method::PUT
+                    | Method::DELETE
+            )
+    }
+
+    /// Delay before next attempt, `None` if request must not be retried
+    fn delay(
+        &self,
+        attempt: usize,
+        method: &Method,
+        res: &Result<ClientResponse, SendRequestError>,
+    ) -> Option<Millis> {
+        let retry_after = match res {
+            Err(SendRequestError::Connect(_)) if self.connect_errors => None,
+            Err(SendRequestError::Connect(_))
+            | Err(SendRequestError::Url(_))
+            | Err(SendRequestError::Http(_))
+            | Err(SendRequestError::TunnelNotSupported) => return None,
+            Err(_) if self.method_allowed(method) => None,
+            Ok(res)
+                if self.statuses.contains(&res.status()) && self.method_allowed(method) =>
+            {
+                if self.retry_after {
+                    res.headers()
+                        .get(header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(parse_retry_after)
+                } else {
+                    None
+                }
+            }
+            _ => return None,
+        };
+
+        if let Some(delay) = retry_after {
+            return if delay > self.max_backoff {
+                None
+            } else {
+                Some(delay)
+            };
+        }
+
+        let exp = (attempt - 1).min(31) as u32;
+        let delay = (self.backoff.0 as u64)
+            .saturating_mul(1 << exp)
+            .min(self.max_backoff.0 as u64);
+        let delay = if self.jitter && delay > 1 {
+            delay / 2 + WyRand::new().generate_range(0..=delay - delay / 2)
+        } else {
+            delay
+        };
+        Some(Millis(delay as u32))
+    }
+}
+
+impl<S> Middleware<S> for Retry {
+    type Service = RetryMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RetryMiddleware {
+            service,
+            policy: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RetryMiddleware<S> {
+    service: S,
+    policy: Retry,
+}
+
+impl<S> Service<ServiceRequest> for RetryMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ClientResponse, Error = SendRequestError>,
+{
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future<'f> = BoxFuture<'f, Result<ClientResponse, SendRequestError>> where S: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: ServiceRequest,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        Box::pin(async move {
+            let mut req = req;
+            let mut attempt = 1;
+            loop {
+                // streaming body could not be replayed
+                let next = if attempt < self.policy.max_attempts {
+                    req.try_clone()
+                } else {
+                    None
+                };
+                let res = ctx.call(&self.service, req).await;
+
+                let next = match next {
+                    Some(next) => next,
+                    None => return res,
+                };
+                match self.policy.delay(attempt, next.method(), &res) {
+                    Some(delay) => {
+                        log::trace!(
+                            "Retry request {:?} in {:?}, attempt {}",
+                            next.uri(),
+                            delay,
+                            attempt
+                        );
+                        drop(res);
+                        sleep(delay).await;
+                        attempt += 1;
+                        req = next;
+                    }
+                    None => return res,
+                }
+            }
+        })
+    }
+}
+
+/// Parse `Retry-After` header, delay in seconds or http date
+fn parse_retry_after(value: &str) -> Option<Millis> {
+    let secs = if let Ok(secs) = value.trim().parse::<u64>() {
+        secs
+    } else {
+        let date = httpdate::parse_http_date(value).ok()?;
+        date.duration_since(SystemTime::now())
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    };
+    Some(Millis(secs.saturating_mul(1000).min(u32::MAX as u64) as u32))
+}
+
+fn remove_headers(headers: &mut HeaderMap, names: &[header::HeaderName]) {
+    for name in names {
+        headers.remove(name);
+    }
+}
+
+/// Resolve redirect location relative to request uri (RFC 3986, Section 5.2)
+fn resolve(base: &Uri, location: &str) -> Option<Uri> {
+    let scheme = base.scheme_str().unwrap_or("http");
+    let authority = base.authority()?.as_str();
+    // fragment is not sent to the server
+    let location = location.split('#').next().unwrap_or_default();
+
+    let uri = if location.contains("://") {
+        location.to_string()
+    } else if location.starts_with("//") {
+        format!("{}:{}", scheme, location)
+    } else {
+        let (path, query) = match location.find('?') {
+            Some(pos) => (&location[..pos], &location[pos..]),
+            None => (location, ""),
+        };
+        let path = if path.is_empty() {
+            match base.path() {
+                "" => "/".to_string(),
+                path => path.to_string(),
+            }
+        } else if path.starts_with('/') {
+            remove_dot_segments(path)
+        } else {
+            let base = base.path();
+            let dir = &base[..base.rfind('/').map(|pos| pos + 1).unwrap_or(0)];
+            let dir = if dir.is_empty() { "/" } else { dir };
+            remove_dot_segments(&format!("{}{}", dir, path))
+        };
+        let query = match base.query() {
+            Some(q) if location.is_empty() => format!("?{}", q),
+            _ => query.to_string(),
+        };
+        format!("{}://{}{}{}", scheme, authority, path, query)
+    };
+    Uri::try_from(uri).ok()
+}
+
+/// Remove `.` and `..` segments from absolute path
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments = Vec::new();
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => (),
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+
+    let mut result = String::with_capacity(path.len());
+    for segment in segments {
+        result.push('/');
+        result.push_str(segment);
+    }
+    if result.is_empty() || path.ends_with("/.") || path.ends_with("/..") {
+        result.push('/');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::http::client::error::ConnectError;
+    use crate::http::{client::ClientService, RequestHead, ResponseHead};
+    use crate::service::{boxed, fn_service, Pipeline};
+    use crate::util::Bytes;
+
+    fn response(status: StatusCode, location: &'static str) -> ClientResponse {
+        let mut head = ResponseHead::new(status);
+        head.headers
+            .insert(header::LOCATION, header::HeaderValue::from_static(location));
+        ClientResponse::new(head, crate::http::Payload::None)
+    }
+
+    fn request(method: Method, uri: &'static str, body: Body) -> ServiceRequest {
+        let mut head = RequestHead::default();
+        head.method = method;
+        head.uri = Uri::from_static(uri);
+        head.headers.insert(
+            header::AUTHORIZATION,
+            header::HeaderValue::from_static("Basic dXNlcjpwYXNz"),
+        );
+        head.headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("text/plain"),
+        );
+        ServiceRequest::new(head, body)
+    }
+
+    fn redirect_service(
+        reqs: Rc<RefCell<Vec<(Method, Uri, bool, Option<Body>)>>>,
+    ) -> ClientService {
+        boxed::service(fn_service(move |mut req: ServiceRequest| {
+            let reqs = reqs.clone();
+            async move {
+                let path = req.uri().path().to_string();
+                let body = std::mem::replace(req.body_mut(), Body::None);
+                reqs.borrow_mut().push((
+                    req.method().clone(),
+                    req.uri().clone(),
+                    req.header(&header::AUTHORIZATION).is_some(),
+                    match body {
+                        Body::Message(_) => None,
+                        body => Some(body),
+                    },
+                ));
+                Ok::<_, SendRequestError>(match path.as_str() {
+                    "/301" => response(StatusCode::MOVED_PERMANENTLY, "/a/b"),
+                    "/a/b" => response(StatusCode::FOUND, "c?q=1"),
+                    "/a/c" => response(StatusCode::SEE_OTHER, "http://other/d"),
+                    "/307" => response(StatusCode::TEMPORARY_REDIRECT, "//other/308"),
+                    "/308" => response(StatusCode::PERMANENT_REDIRECT, "/loop"),
+                    "/loop" => response(StatusCode::FOUND, "/loop"),
+                    _ => response(StatusCode::OK, ""),
+                })
+            }
+        }))
+    }
+
+    #[crate::rt_test]
+    async fn test_redirect() {
+        let reqs = Rc::new(RefCell::new(Vec::new()));
+        let srv = Pipeline::new(Redirect::default().create(redirect_service(reqs.clone())));
+
+        let body = Body::Bytes(Bytes::from_static(b"data"));
+        let res = srv
+            .call(request(Method::POST, "http://localhost/301", body))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let reqs2 = reqs.borrow_mut().split_off(0);
+        assert_eq!(reqs2.len(), 4);
+        assert_eq!(reqs2[0].0, Method::POST);
+        assert_eq!(reqs2[1].0, Method::GET);
+        assert_eq!(reqs2[1].1, Uri::from_static("http://localhost/a/b"));
+        assert_eq!(reqs2[1].3, Some(Body::None));
+        assert_eq!(reqs2[2].1, Uri::from_static("http://localhost/a/c?q=1"));
+        assert!(reqs2[2].2);
+        assert_eq!(reqs2[3].1, Uri::from_static("http://other/d"));
+        assert!(!reqs2[3].2);
+
+        // 307 keeps method and body
+        let body = Body::Bytes(Bytes::from_static(b"data"));
+        let res = srv
+            .call(request(Method::PUT, "http://localhost/307", body))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        let reqs2 = reqs.borrow_mut().split_off(0);
+        assert_eq!(reqs2.len(), 11);
+        assert_eq!(reqs2[1].0, Method::PUT);
+        assert_eq!(reqs2[1].1, Uri::from_static("http://other/308"));
+        assert_eq!(reqs2[1].3, Some(Body::Bytes(Bytes::from_static(b"data"))));
+        assert_eq!(reqs2[2].1, Uri::from_static("http://other/loop"));
+
+        // streaming body
+        let res = srv
+            .call(request(
+                Method::PUT,
+                "http://localhost/307",
+                Body::from_message(Body::Empty),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(reqs.borrow_mut().split_off(0).len(), 1);
+
+        let res = srv
+            .call(request(
+                Method::POST,
+                "http://localhost/a/c",
+                Body::from_message(Body::Empty),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let reqs2 = reqs.borrow_mut().split_off(0);
+        assert_eq!(reqs2[1].0, Method::GET);
+
+        // disabled
+        let srv = Pipeline::new(Redirect::new(0).create(redirect_service(reqs.clone())));
+        let res = srv
+            .call(request(Method::GET, "http://localhost/301", Body::None))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    }
+
+    #[test]
+    fn test_redirect_other_host() {
+        let mut req = request(Method::GET, "http://localhost/a", Body::None);
+        req.addr = Some("127.0.0.1:8080".parse().unwrap());
+
+        let next = RedirectState::new(&req)
+            .next(&response(StatusCode::FOUND, "/b"))
+            .unwrap();
+        assert!(next.addr().is_some());
+        assert!(next.header(&header::AUTHORIZATION).is_some());
+
+        let next = RedirectState::new(&next)
+            .next(&response(StatusCode::FOUND, "http://other/b"))
+            .unwrap();
+        assert!(next.addr().is_none());
+        assert!(next.header(&header::AUTHORIZATION).is_none());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = Uri::from_static("https://localhost:8080/a/b?q=1");
+        assert_eq!(
+            resolve(&base, "http://other/c").unwrap(),
+            Uri::from_static("http://other/c")
+        );
+        assert_eq!(
+            resolve(&base, "//other/c").unwrap(),
+            Uri::from_static("https://other/c")
+        );
+        assert_eq!(
+            resolve(&base, "/c").unwrap(),
+            Uri::from_static("https://localhost:8080/c")
+        );
+        assert_eq!(
+            resolve(&base, "c?q=2").unwrap(),
+            Uri::from_static("https://localhost:8080/a/c?q=2")
+        );
+        assert_eq!(
+            resolve(&base, "?q=2").unwrap(),
+            Uri::from_static("https://localhost:8080/a/b?q=2")
+        );
+        assert_eq!(
+            resolve(&base, "").unwrap(),
+            Uri::from_static("https://localhost:8080/a/b?q=1")
+        );
+        assert_eq!(
+            resolve(&base, "#top").unwrap(),
+            Uri::from_static("https://localhost:8080/a/b?q=1")
+        );
+        assert_eq!(
+            resolve(&base, "../c").unwrap(),
+            Uri::from_static("https://localhost:8080/c")
+        );
+        assert_eq!(
+            resolve(&base, "../../c?q=2#top").unwrap(),
+            Uri::from_static("https://localhost:8080/c?q=2")
+        );
+        assert_eq!(
+            resolve(&base, "./c/.").unwrap(),
+            Uri::from_static("https://localhost:8080/a/c/")
+        );
+        assert_eq!(
+            resolve(&base, "/c/../d/./e").unwrap(),
+            Uri::from_static("https://localhost:8080/d/e")
+        );
+        assert_eq!(
+            resolve(&base, "..").unwrap(),
+            Uri::from_static("https://localhost:8080/")
+        );
+
+        let base = Uri::from_static("http://localhost");
+        assert_eq!(
+            resolve(&base, "c").unwrap(),
+            Uri::from_static("http://localhost/c")
+        );
+        assert_eq!(
+            resolve(&base, "?q=1").unwrap(),
+            Uri::from_static("http://localhost/?q=1")
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_timeout() {
+        let srv = Pipeline::new(Timeout.create(fn_service(|_: ServiceRequest| async {
+            sleep(Millis(50)).await;
+            Ok::<_, SendRequestError>(response(StatusCode::OK, "/"))
+        })));
+
+        let mut req = request(Method::GET, "http://localhost/", Body::None);
+        req.set_timeout(Millis(10));
+        assert!(matches!(
+            srv.call(req).await,
+            Err(SendRequestError::Timeout)
+        ));
+
+        let req = request(Method::GET, "http://localhost/", Body::None);
+        assert!(srv.call(req).await.is_ok());
+    }
+
+    #[crate::rt_test]
+    async fn test_retry() {
+        let attempts = Rc::new(RefCell::new(0));
+        let attempts2 = attempts.clone();
+        let srv = Pipeline::new(
+            Retry::new(3)
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .backoff(Millis(1), Millis(1000))
+                .create(fn_service(move |req: ServiceRequest| {
+                    let attempts = attempts2.clone();
+                    async move {
+                        *attempts.borrow_mut() += 1;
+                        match req.uri().path() {
+                            "/connect" => Err(SendRequestError::Connect(
+                                ConnectError::Disconnected(None),
+                            )),
+                            "/timeout" => Err(SendRequestError::Timeout),
+                            "/wait" => {
+                                let mut res = response(StatusCode::SERVICE_UNAVAILABLE, "");
+                                res.headers_mut().insert(
+                                    header::RETRY_AFTER,
+                                    header::HeaderValue::from_static("120"),
+                                );
+                                Ok(res)
+                            }
+                            "/ok" if *attempts.borrow() == 2 => {
+                                Ok(response(StatusCode::OK, ""))
+                            }
+                            _ => Ok(response(StatusCode::SERVICE_UNAVAILABLE, "")),
+                        }
+                    }
+                })),
+        );
+
+        let res = srv
+            .call(request(Method::GET, "http://localhost/ok", Body::None))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(*attempts.borrow(), 2);
+
+        *attempts.borrow_mut() = 0;
+        let res = srv
+            .call(request(Method::GET, "http://localhost/", Body::None))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(*attempts.borrow(), 3);
+
+        // non-idempotent method
+        *attempts.borrow_mut() = 0;
+        let body = Body::Bytes(Bytes::from_static(b"data"));
+        let res = srv
+            .call(request(Method::POST, "http://localhost/", body))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(*attempts.borrow(), 1);
+
+        *attempts.borrow_mut() = 0;
+        let res = srv
+            .call(request(
+                Method::POST,
+                "http://localhost/timeout",
+                Body::None,
+            ))
+            .await;
+        assert!(matches!(res, Err(SendRequestError::Timeout)));
+        assert_eq!(*attempts.borrow(), 1);
+
+        *attempts.borrow_mut() = 0;
+        let res = srv
+            .call(request(Method::GET, "http://localhost/timeout", Body::None))
+            .await;
+        assert!(matches!(res, Err(SendRequestError::Timeout)));
+        assert_eq!(*attempts.borrow(), 3);
+
+        // connect errors are retried for any method
+        *attempts.borrow_mut() = 0;
+        let res = srv
+            .call(request(
+                Method::POST,
+                "http://localhost/connect",
+                Body::None,
+            ))
+            .await;
+        assert!(matches!(res, Err(SendRequestError::Connect(_))));
+        assert_eq!(*attempts.borrow(), 3);
+
+        // streaming body
+        *attempts.borrow_mut() = 0;
+        let res = srv
+            .call(request(
+                Method::PUT,
+                "http://localhost/",
+                Body::from_message(Body::Empty),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(*attempts.borrow(), 1);
+
+        // retry-after is larger than max backoff
+        *attempts.borrow_mut() = 0;
+        let res = srv
+            .call(request(Method::GET, "http://localhost/wait", Body::None))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(*attempts.borrow(), 1);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = Retry::new(5)
+            .jitter(false)
+            .backoff(Millis(100), Millis(300))
+            .status(StatusCode::TOO_MANY_REQUESTS);
+        let res = Err(SendRequestError::Timeout);
+        assert_eq!(policy.delay(1, &Method::GET, &res), Some(Millis(100)));
+        assert_eq!(policy.delay(2, &Method::GET, &res), Some(Millis(200)));
+        assert_eq!(policy.delay(3, &Method::GET, &res), Some(Millis(300)));
+        assert_eq!(policy.delay(40, &Method::GET, &res), Some(Millis(300)));
+        assert_eq!(policy.delay(1, &Method::POST, &res), None);
+        let policy = policy.idempotent_only(false);
+        assert_eq!(policy.delay(1, &Method::POST, &res), Some(Millis(100)));
+
+        let res = Err(SendRequestError::Connect(ConnectError::Disconnected(None)));
+        let policy = policy.connect_errors(false).idempotent_only(true);
+        assert_eq!(policy.delay(1, &Method::POST, &res), None);
+
+        let mut res = response(StatusCode::TOO_MANY_REQUESTS, "");
+        res.headers_mut()
+            .insert(header::RETRY_AFTER, header::HeaderValue::from_static("0"));
+        let res = Ok(res);
+        assert_eq!(policy.delay(2, &Method::GET, &res), Some(Millis(0)));
+        let policy = policy.retry_after(false);
+        assert_eq!(policy.delay(2, &Method::GET, &res), Some(Millis(200)));
+
+        let policy = Retry::new(5).backoff(Millis(100), Millis(300));
+        let res = Err(SendRequestError::Timeout);
+        for _ in 0..10 {
+            let delay = policy.delay(2, &Method::GET, &res).unwrap();
+            assert!(delay >= Millis(100) && delay <= Millis(200));
+        }
+
+        assert_eq!(parse_retry_after("10"), Some(Millis(10_000)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Millis(0))
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -22,30 +22,41 @@ mod builder;
 mod connect;
 mod connection;
 mod connector;
+#[cfg(feature = "cookie")]
+mod cookie;
 pub mod error;
 mod frozen;
 mod h1proto;
 mod h2proto;
+pub mod middleware;
+mod multipart;
 mod pool;
+pub(crate) mod proxy;
 mod request;
 mod response;
 mod sender;
+mod service;
+mod sse;
 mod test;
 
 pub use self::builder::ClientBuilder;
 pub use self::connection::Connection;
 pub use self::connector::Connector;
+#[cfg(feature = "cookie")]
+pub use self::cookie::CookieJar;
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
+pub use self::multipart::{Multipart, Part};
+pub use self::proxy::Proxy;
 pub use self::request::ClientRequest;
 pub use self::response::{ClientResponse, JsonBody, MessageBody};
 pub use self::sender::SendClientRequest;
+pub use self::service::{ClientService, ServiceRequest};
+pub use self::sse::SseStream;
 pub use self::test::TestResponse;
 
 use crate::http::error::HttpError;
 use crate::http::{HeaderMap, Method, RequestHead, Uri};
-use crate::time::Millis;
-
-use self::connect::{Connect as HttpConnect, ConnectorWrapper};
+use crate::{service::Pipeline, time::Millis};
 
 #[derive(Debug, Clone)]
 pub struct Connect {
@@ -75,18 +86,14 @@ pub struct Client(Rc<ClientConfig>);
 
 #[derive(Debug)]
 struct ClientConfig {
-    pub(self) connector: Box<dyn HttpConnect>,
+    pub(self) service: Pipeline<ClientService>,
     pub(self) headers: HeaderMap,
     pub(self) timeout: Millis,
 }
 
 impl Default for Client {
     fn default() -> Self {
-        Client(Rc::new(ClientConfig {
-            connector: Box::new(ConnectorWrapper(Connector::default().finish().into())),
-            headers: HeaderMap::new(),
-            timeout: Millis(5_000),
-        }))
+        ClientBuilder::new().finish()
     }
 }
 
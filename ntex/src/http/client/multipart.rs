@@ -0,0 +1,343 @@
+use std::{collections::VecDeque, error::Error, fmt, task::Context, task::Poll};
+
+use mime::Mime;
+use nanorand::{Rng, WyRand};
+
+use crate::http::body::{Body, BodySize, MessageBody};
+use crate::util::{Bytes, BytesMut};
+
+/// Multipart form body builder (`multipart/form-data`)
+///
+/// Body consists of text parts, in-memory parts and streaming parts.
+/// `Content-Length` is known if all parts are sized, otherwise body is sent
+/// with chunked transfer encoding. Request's content type must be set to
+/// [`Multipart::content_type()`], it contains generated boundary.
+///
+/// ```rust
+/// use ntex::http::client::{Client, Multipart, Part};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let form = Multipart::new()
+///         .text("name", "ntex")
+///         .part(
+///             "file",
+///             Part::bytes("file content")
+///                 .file_name("file.txt")
+///                 .content_type(mime::TEXT_PLAIN),
+///         );
+///
+///     let res = Client::new()
+///         .post("http://www.rust-lang.org/upload")
+///         .content_type(form.content_type())
+///         .send_body(form)
+///         .await;
+/// }
+/// ```
+pub struct Multipart {
+    boundary: String,
+    parts: VecDeque<(String, Part)>,
+    current: Option<Box<dyn MessageBody>>,
+    eof: bool,
+}
+
+/// Part of the multipart form
+pub struct Part {
+    body: Body,
+    file_name: Option<String>,
+    content_type: Option<Mime>,
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Multipart::new()
+    }
+}
+
+impl Multipart {
+    /// Create multipart form with random boundary
+    pub fn new() -> Self {
+        let mut rng = WyRand::new();
+        let boundary = format!(
+            "{:016x}{:016x}",
+            rng.generate::<u64>(),
+            rng.generate::<u64>()
+        );
+        Multipart {
+            boundary,
+            parts: VecDeque::new(),
+            current: None,
+            eof: false,
+        }
+    }
+
+    /// Add text part
+    pub fn text<N, V>(self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.part(name, Part::text(value))
+    }
+
+    /// Add part
+    pub fn part<N: Into<String>>(mut self, name: N, part: Part) -> Self {
+        self.parts.push_back((name.into(), part));
+        self
+    }
+
+    /// Form boundary
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Value of `Content-Type` header for the form
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    fn part_header(&self, name: &str, part: &Part) -> BytesMut {
+        let mut buf = BytesMut::with_capacity(128);
+        buf.extend_from_slice(b"--");
+        buf.extend_from_slice(self.boundary.as_bytes());
+        buf.extend_from_slice(b"\r\ncontent-disposition: form-data; name=\"");
+        buf.extend_from_slice(escape(name).as_bytes());
+        buf.extend_from_slice(b"\"");
+        if let Some(ref file_name) = part.file_name {
+            buf.extend_from_slice(b"; filename=\"");
+            buf.extend_from_slice(escape(file_name).as_bytes());
+            buf.extend_from_slice(b"\"");
+        }
+        buf.extend_from_slice(b"\r\n");
+        if let Some(ref content_type) = part.content_type {
+            buf.extend_from_slice(b"content-type: ");
+            buf.extend_from_slice(content_type.as_ref().as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+        buf.extend_from_slice(b"\r\n");
+        buf
+    }
+
+    fn trailer_size(&self) -> u64 {
+        self.boundary.len() as u64 + 6
+    }
+}
+
+impl Part {
+    /// Create text part
+    pub fn text<V: Into<String>>(value: V) -> Self {
+        Part::new(Body::Bytes(Bytes::from(value.into())))
+    }
+
+    /// Create in-memory part
+    pub fn bytes<V: Into<Bytes>>(data: V) -> Self {
+        Part::new(Body::Bytes(data.into()))
+    }
+
+    /// Create streaming part.
+    ///
+    /// Part is sized if body's size is known, for example for
+    /// [`SizedStream`](crate::http::body::SizedStream).
+    pub fn stream<B: MessageBody>(body: B) -> Self {
+        Part::new(Body::from_message(body))
+    }
+
+    fn new(body: Body) -> Self {
+        Part {
+            body,
+            file_name: None,
+            content_type: None,
+        }
+    }
+
+    /// Set part's file name.
+    ///
+    /// Content type is guessed from the file name unless it is set.
+    pub fn file_name<T: Into<String>>(mut self, name: T) -> Self {
+        let name = name.into();
+        if self.content_type.is_none() {
+            self.content_type = Some(mime_guess::from_path(&name).first_or_octet_stream());
+        }
+        self.file_name = Some(name);
+        self
+    }
+
+    /// Set part's content type
+    pub fn content_type(mut self, content_type: Mime) -> Self {
+        self.content_type = Some(content_type);
+        self
+    }
+}
+
+impl MessageBody for Multipart {
+    fn size(&self) -> BodySize {
+        let mut size = self.trailer_size();
+        for (name, part) in &self.parts {
+            let body_size = match part.body.size() {
+                BodySize::None | BodySize::Empty => 0,
+                BodySize::Sized(size) => size,
+                BodySize::Stream => return BodySize::Stream,
+            };
+            size += self.part_header(name, part).len() as u64 + body_size + 2;
+        }
+        BodySize::Sized(size)
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        if let Some(ref mut body) = self.current {
+            return match body.poll_next_chunk(cx) {
+                Poll::Ready(None) => {
+                    self.current = None;
+                    Poll::Ready(Some(Ok(Bytes::from_static(b"\r\n"))))
+                }
+                res => res,
+            };
+        }
+
+        if let Some((name, part)) = self.parts.pop_front() {
+            let mut buf = self.part_header(&name, &part);
+            match part.body {
+                Body::None | Body::Empty => buf.extend_from_slice(b"\r\n"),
+                Body::Bytes(data) => {
+                    buf.extend_from_slice(&data);
+                    buf.extend_from_slice(b"\r\n");
+                }
+                Body::Message(body) => self.current = Some(body),
+            }
+            Poll::Ready(Some(Ok(buf.freeze())))
+        } else if !self.eof {
+            self.eof = true;
+            let mut buf = BytesMut::with_capacity(self.trailer_size() as usize);
+            buf.extend_from_slice(b"--");
+            buf.extend_from_slice(self.boundary.as_bytes());
+            buf.extend_from_slice(b"--\r\n");
+            Poll::Ready(Some(Ok(buf.freeze())))
+        } else {
+            Poll::Ready(None)
+        }
+    }
+}
+
+impl From<Multipart> for Body {
+    fn from(form: Multipart) -> Body {
+        Body::from_message(form)
+    }
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("boundary", &self.boundary)
+            .field("parts", &self.parts)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Part")
+            .field("body", &self.body)
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .finish()
+    }
+}
+
+/// Escape quoted string of the `Content-Disposition` header
+fn escape(s: &str) -> String {
+    s.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::body::{BodyStream, SizedStream};
+    use crate::util::poll_fn;
+
+    async fn read(mut form: Multipart) -> Bytes {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = poll_fn(|cx| form.poll_next_chunk(cx)).await {
+            buf.extend_from_slice(&chunk.unwrap());
+        }
+        buf.freeze()
+    }
+
+    #[crate::rt_test]
+    async fn test_sized() {
+        let mut form = Multipart::new()
+            .text("name", "value")
+            .part("file", Part::bytes("data").file_name("a\"b.txt"))
+            .part(
+                "empty",
+                Part::bytes(Bytes::new()).content_type(mime::TEXT_CSV),
+            );
+        form.boundary = "BOUNDARY".to_string();
+        assert_eq!(form.boundary(), "BOUNDARY");
+        assert_eq!(
+            form.content_type(),
+            "multipart/form-data; boundary=BOUNDARY"
+        );
+        assert!(format!("{:?}", form).contains("Multipart"));
+
+        let size = form.size();
+        let data = read(form).await;
+        assert_eq!(size, BodySize::Sized(data.len() as u64));
+        assert_eq!(
+            data,
+            Bytes::from_static(
+                b"--BOUNDARY\r\n\
+                  content-disposition: form-data; name=\"name\"\r\n\r\n\
+                  value\r\n\
+                  --BOUNDARY\r\n\
+                  content-disposition: form-data; name=\"file\"; filename=\"a%22b.txt\"\r\n\
+                  content-type: text/plain\r\n\r\n\
+                  data\r\n\
+                  --BOUNDARY\r\n\
+                  content-disposition: form-data; name=\"empty\"\r\n\
+                  content-type: text/csv\r\n\r\n\
+                  \r\n\
+                  --BOUNDARY--\r\n"
+            )
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_stream() {
+        let (tx, rx) = crate::channel::mpsc::channel::<Result<Bytes, Box<dyn Error>>>();
+        let _ = tx.send(Ok(Bytes::from_static(b"chunk1")));
+        let _ = tx.send(Ok(Bytes::from_static(b"chunk2")));
+        drop(tx);
+
+        let form = Multipart::new()
+            .part("sized", Part::stream(SizedStream::new(12, rx)))
+            .text("name", "value");
+        let size = form.size();
+        let data = read(form).await;
+        assert_eq!(size, BodySize::Sized(data.len() as u64));
+        assert!(data.windows(14).any(|w| w == b"chunk1chunk2\r\n"));
+
+        let (tx, rx) = crate::channel::mpsc::channel::<Result<Bytes, std::io::Error>>();
+        let _ = tx.send(Ok(Bytes::from_static(b"chunk")));
+        drop(tx);
+        let form = Multipart::new().part(
+            "stream",
+            Part::stream(BodyStream::new(rx)).file_name("f.bin"),
+        );
+        assert_eq!(form.size(), BodySize::Stream);
+
+        let mut body: Body = form.into();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = poll_fn(|cx| body.poll_next_chunk(cx)).await {
+            chunks.push(chunk.unwrap());
+        }
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks[0].ends_with(b"content-type: application/octet-stream\r\n\r\n"));
+        assert_eq!(chunks[1], Bytes::from_static(b"chunk"));
+        assert_eq!(chunks[2], Bytes::from_static(b"\r\n"));
+    }
+}
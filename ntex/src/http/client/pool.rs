@@ -12,16 +12,21 @@ use crate::util::{ready, BoxFuture, ByteString, HashMap, HashSet};
 use crate::{channel::pool, rt::spawn, task::LocalWaker};
 
 use super::connection::{Connection, ConnectionType};
+use super::proxy::{Proxy, ProxyServer};
 use super::{error::ConnectError, h2proto::H2Client, Connect};
 
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub(super) struct Key {
     authority: Authority,
+    proxy: Option<ProxyServer>,
 }
 
-impl From<Authority> for Key {
-    fn from(authority: Authority) -> Key {
-        Key { authority }
+impl Key {
+    fn new(uri: &Uri, proxy: Option<&Proxy>) -> Option<Key> {
+        uri.authority().map(|authority| Key {
+            authority: authority.clone(),
+            proxy: proxy.and_then(|proxy| proxy.select(uri)).cloned(),
+        })
     }
 }
 
@@ -45,6 +50,7 @@ struct AvailableConnection {
 #[derive(Debug)]
 pub(super) struct ConnectionPool<T> {
     connector: Pipeline<T>,
+    proxy: Option<Proxy>,
     inner: Rc<RefCell<Inner>>,
     waiters: Rc<RefCell<Waiters>>,
 }
@@ -60,6 +66,7 @@ where
         disconnect_timeout: Seconds,
         limit: usize,
         h2config: h2::Config,
+        proxy: Option<Proxy>,
     ) -> Self {
         let connector = Pipeline::new(connector);
         let waiters = Rc::new(RefCell::new(Waiters {
@@ -88,6 +95,7 @@ where
 
         ConnectionPool {
             connector,
+            proxy,
             inner,
             waiters,
         }
@@ -104,6 +112,7 @@ impl<T> Clone for ConnectionPool<T> {
     fn clone(&self) -> Self {
         ConnectionPool {
             connector: self.connector.clone(),
+            proxy: self.proxy.clone(),
             inner: self.inner.clone(),
             waiters: self.waiters.clone(),
         }
@@ -125,10 +134,11 @@ where
         trace!("Get connection for {:?}", req.uri);
         let inner = self.inner.clone();
         let waiters = self.waiters.clone();
+        let key = Key::new(&req.uri, self.proxy.as_ref());
 
         Box::pin(async move {
-            let key = if let Some(authority) = req.uri.authority() {
-                authority.clone().into()
+            let key = if let Some(key) = key {
+                key
             } else {
                 return Err(ConnectError::Unresolved);
             };
@@ -163,7 +173,7 @@ where
                         "Pool is full, waiting for available connections for {:?}",
                         req.uri
                     );
-                    let rx = waiters.borrow_mut().wait_for(req);
+                    let rx = waiters.borrow_mut().wait_for(key, req);
                     match rx.await {
                         Err(_) => Err(ConnectError::Disconnected(None)),
                         Ok(res) => res,
@@ -196,9 +206,8 @@ struct Waiters {
 
 impl Waiters {
     /// connection is not available, wait
-    fn wait_for(&mut self, connect: Connect) -> WaiterReceiver {
+    fn wait_for(&mut self, key: Key, connect: Connect) -> WaiterReceiver {
         let (tx, rx) = self.pool.channel();
-        let key: Key = connect.uri.authority().unwrap().clone().into();
         self.waiters
             .entry(key)
             .or_default()
@@ -562,6 +571,11 @@ impl Acquired {
         Acquired::new(self.0.clone(), self.1.as_ref().unwrap().clone())
     }
 
+    /// Proxy server of the connection
+    pub(super) fn proxy(&self) -> Option<&ProxyServer> {
+        self.0.proxy.as_ref()
+    }
+
     pub(super) fn release(&mut self, conn: Connection, close: bool) {
         if let Some(inner) = self.1.take() {
             let (io, created, _) = conn.into_inner();
@@ -632,6 +646,7 @@ mod tests {
                 Seconds::ZERO,
                 1,
                 h2::Config::client(),
+                None,
             )
             .clone(),
         );
@@ -723,4 +738,24 @@ mod tests {
         assert!(lazy(|cx| pool.poll_ready(cx)).await.is_ready());
         assert!(lazy(|cx| pool.poll_shutdown(cx)).await.is_ready());
     }
+
+    #[test]
+    fn test_proxy_key() {
+        let proxy = Proxy::http("http://proxy:3128").unwrap();
+        let proxy2 = Proxy::http("http://proxy2:3128").unwrap();
+        let uri = Uri::try_from("http://localhost/test").unwrap();
+
+        let key = Key::new(&uri, Some(&proxy)).unwrap();
+        assert!(key.proxy.is_some());
+        assert_eq!(key, Key::new(&uri, Some(&proxy.clone())).unwrap());
+        assert_ne!(key, Key::new(&uri, None).unwrap());
+        assert_ne!(key, Key::new(&uri, Some(&proxy2)).unwrap());
+
+        // no proxy for https
+        let uri = Uri::try_from("https://localhost/test").unwrap();
+        assert_eq!(Key::new(&uri, Some(&proxy)), Key::new(&uri, None));
+
+        let uri = Uri::try_from("/test").unwrap();
+        assert!(Key::new(&uri, Some(&proxy)).is_none());
+    }
 }
@@ -275,6 +275,15 @@ impl ClientRequest {
         self.header(header::AUTHORIZATION, format!("Bearer {}", token))
     }
 
+    /// Set `Last-Event-ID` header, used for reconnecting to the
+    /// server-sent events stream
+    pub fn last_event_id<T>(self, id: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        self.set_header("last-event-id", id.to_string())
+    }
+
     #[cfg(feature = "cookie")]
     /// Set a cookie
     ///
@@ -627,6 +636,12 @@ mod tests {
         );
     }
 
+    #[crate::rt_test]
+    async fn test_last_event_id() {
+        let req = Client::new().get("/").last_event_id(1).last_event_id("2");
+        assert_eq!(req.headers().get("last-event-id").unwrap(), "2");
+    }
+
     #[crate::rt_test]
     async fn test_client_header_override() {
         let req = Client::build()
@@ -1,6 +1,6 @@
-use std::cell::{Ref, RefMut};
+use std::cell::{Ref, RefCell, RefMut};
 use std::task::{Context, Poll};
-use std::{fmt, future::Future, marker::PhantomData, mem, pin::Pin};
+use std::{fmt, future::Future, marker::PhantomData, mem, pin::Pin, rc::Rc};
 
 use serde::de::DeserializeOwned;
 
@@ -13,12 +13,23 @@ use crate::http::{HeaderMap, HttpMessage, Payload, ResponseHead, StatusCode, Ver
 use crate::time::{Deadline, Millis};
 use crate::util::{Bytes, BytesMut, Extensions, Stream};
 
-use super::error::JsonPayloadError;
+use super::{error::JsonPayloadError, SseStream};
 
 /// Client Response
 pub struct ClientResponse {
     pub(crate) head: ResponseHead,
     pub(crate) payload: Payload,
+    trailers: ResponseTrailers,
+}
+
+/// Trailer headers of the response, set by protocol's payload stream
+#[derive(Clone, Debug, Default)]
+pub(super) struct ResponseTrailers(Rc<RefCell<Option<HeaderMap>>>);
+
+impl ResponseTrailers {
+    pub(super) fn set(&self, trailers: HeaderMap) {
+        *self.0.borrow_mut() = Some(trailers);
+    }
 }
 
 impl HttpMessage for ClientResponse {
@@ -59,7 +70,19 @@ impl HttpMessage for ClientResponse {
 impl ClientResponse {
     /// Create new client response instance
     pub(crate) fn new(head: ResponseHead, payload: Payload) -> Self {
-        ClientResponse { head, payload }
+        ClientResponse::with_trailers(head, payload, ResponseTrailers::default())
+    }
+
+    pub(super) fn with_trailers(
+        head: ResponseHead,
+        payload: Payload,
+        trailers: ResponseTrailers,
+    ) -> Self {
+        ClientResponse {
+            head,
+            payload,
+            trailers,
+        }
     }
 
     pub(crate) fn with_empty_payload(head: ResponseHead) -> Self {
@@ -116,6 +139,13 @@ impl ClientResponse {
         mem::take(&mut self.payload)
     }
 
+    /// Trailer headers of the response.
+    ///
+    /// Trailers are available after response's payload is fully read.
+    pub fn trailers(&self) -> Option<HeaderMap> {
+        self.trailers.0.borrow().clone()
+    }
+
     /// Request extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -145,6 +175,14 @@ impl ClientResponse {
     pub fn json<T: DeserializeOwned>(&mut self) -> JsonBody<T> {
         JsonBody::new(self)
     }
+
+    /// Decode `text/event-stream` body.
+    /// Return `SseStream` stream of server-sent events.
+    ///
+    /// Stream yields error if content type is not `text/event-stream`
+    pub fn sse(&mut self) -> SseStream {
+        SseStream::new(self)
+    }
 }
 
 impl Stream for ClientResponse {
@@ -433,6 +471,29 @@ mod tests {
         }
     }
 
+    #[crate::rt_test]
+    async fn test_trailers() {
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"test"))
+            .finish();
+        assert!(res.trailers().is_none());
+
+        let mut hdrs = HeaderMap::new();
+        hdrs.insert(
+            header::HeaderName::from_static("x-checksum"),
+            HeaderValue::from_static("1234"),
+        );
+        let trailers = ResponseTrailers::default();
+        let mut res = ClientResponse::with_trailers(
+            ResponseHead::new(StatusCode::OK),
+            res.take_payload(),
+            trailers.clone(),
+        );
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"test"));
+        trailers.set(hdrs.clone());
+        assert_eq!(res.trailers(), Some(hdrs));
+    }
+
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct MyObject {
         name: String,
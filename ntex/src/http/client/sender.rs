@@ -17,7 +17,7 @@ use crate::http::Payload;
 
 use super::error::{FreezeRequestError, InvalidUrl, SendRequestError};
 use super::response::ClientResponse;
-use super::ClientConfig;
+use super::{ClientConfig, ServiceRequest};
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum PrepForSendingError {
@@ -132,14 +132,13 @@ impl RequestHeadType {
         if timeout.is_zero() {
             timeout = config.timeout;
         }
-        let body = body.into();
-
-        let fut = Box::pin(async move {
-            config
-                .connector
-                .send_request(self, body, addr, timeout)
-                .await
-        });
+        let req = ServiceRequest {
+            addr,
+            body: body.into(),
+            timeout,
+            head: self,
+        };
+        let fut = Box::pin(async move { config.service.call(req).await });
 
         SendClientRequest::new(fut, response_decompress)
     }
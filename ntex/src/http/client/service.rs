@@ -0,0 +1,227 @@
+use std::{fmt, net};
+
+use crate::http::body::Body;
+use crate::http::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::http::{Method, RequestHead, RequestHeadType, Uri};
+use crate::service::{boxed::BoxService, Service, ServiceCtx};
+use crate::{time::Millis, util::BoxFuture};
+
+use super::connect::Connect as HttpConnect;
+use super::error::SendRequestError;
+use super::response::ClientResponse;
+
+/// Boxed client service.
+///
+/// Client middlewares wrap this service, see [`ClientBuilder::wrap()`](super::ClientBuilder::wrap).
+pub type ClientService = BoxService<ServiceRequest, ClientResponse, SendRequestError>;
+
+/// Request that passes through client middlewares.
+pub struct ServiceRequest {
+    pub(super) head: RequestHeadType,
+    pub(super) body: Body,
+    pub(super) addr: Option<net::SocketAddr>,
+    pub(super) timeout: Millis,
+}
+
+impl ServiceRequest {
+    /// Create service request from request head and body
+    pub fn new(head: RequestHead, body: Body) -> Self {
+        ServiceRequest {
+            body,
+            head: RequestHeadType::Owned(head),
+            addr: None,
+            timeout: Millis::ZERO,
+        }
+    }
+
+    #[inline]
+    /// Request head
+    pub fn head(&self) -> &RequestHead {
+        self.head.as_ref()
+    }
+
+    /// Mutable request head.
+    ///
+    /// Shared head of the frozen request gets copied.
+    pub fn head_mut(&mut self) -> &mut RequestHead {
+        if let RequestHeadType::Rc(ref head, ref mut extra) = self.head {
+            let mut new_head = copy_head(head);
+            if let Some(extra) = extra.take() {
+                for name in extra.keys() {
+                    new_head.headers.remove(name);
+                }
+                for (name, value) in extra.iter() {
+                    new_head.headers.append(name.clone(), value.clone());
+                }
+            }
+            self.head = RequestHeadType::Owned(new_head);
+        }
+        match self.head {
+            RequestHeadType::Owned(ref mut head) => head,
+            RequestHeadType::Rc(..) => unreachable!(),
+        }
+    }
+
+    #[inline]
+    /// Request url
+    pub fn uri(&self) -> &Uri {
+        &self.head().uri
+    }
+
+    #[inline]
+    /// Request method
+    pub fn method(&self) -> &Method {
+        &self.head().method
+    }
+
+    /// Get request header.
+    ///
+    /// Headers of the frozen request's `extra_headers()` take precedence.
+    pub fn header(&self, name: &HeaderName) -> Option<&HeaderValue> {
+        self.head
+            .extra_headers()
+            .and_then(|h| h.get(name))
+            .or_else(|| self.head().headers.get(name))
+    }
+
+    #[inline]
+    /// Request body
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    #[inline]
+    /// Mutable request body
+    pub fn body_mut(&mut self) -> &mut Body {
+        &mut self.body
+    }
+
+    #[inline]
+    /// Socket address of the server, if set
+    pub fn addr(&self) -> Option<net::SocketAddr> {
+        self.addr
+    }
+
+    #[inline]
+    /// Request timeout, zero means no timeout
+    pub fn timeout(&self) -> Millis {
+        self.timeout
+    }
+
+    #[inline]
+    /// Set request timeout
+    pub fn set_timeout(&mut self, timeout: Millis) {
+        self.timeout = timeout;
+    }
+
+    /// Copy request, returns `None` if request body is a stream
+    pub fn try_clone(&self) -> Option<ServiceRequest> {
+        let body = match self.body {
+            Body::None => Body::None,
+            Body::Empty => Body::Empty,
+            Body::Bytes(ref b) => Body::Bytes(b.clone()),
+            Body::Message(_) => return None,
+        };
+        Some(ServiceRequest {
+            body,
+            head: self.copy_head(),
+            addr: self.addr,
+            timeout: self.timeout,
+        })
+    }
+
+    pub(super) fn copy_head(&self) -> RequestHeadType {
+        match self.head {
+            RequestHeadType::Owned(ref head) => RequestHeadType::Owned(copy_head(head)),
+            RequestHeadType::Rc(ref head, ref extra) => {
+                RequestHeadType::Rc(head.clone(), extra.clone())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ServiceRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServiceRequest")
+            .field("head", &self.head)
+            .field("body", &self.body)
+            .field("addr", &self.addr)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Copy request head, extensions are not copied
+fn copy_head(head: &RequestHead) -> RequestHead {
+    RequestHead {
+        uri: head.uri.clone(),
+        method: head.method.clone(),
+        version: head.version,
+        headers: head.headers.clone(),
+        flags: head.flags,
+        ..Default::default()
+    }
+}
+
+/// Innermost client service, sends request to the connector
+pub(super) struct SendService(pub(super) Box<dyn HttpConnect>);
+
+impl Service<ServiceRequest> for SendService {
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future<'f> = BoxFuture<'f, Result<ClientResponse, SendRequestError>>;
+
+    fn call<'a>(
+        &'a self,
+        req: ServiceRequest,
+        _: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        // request timeout is handled by `Timeout` middleware
+        self.0
+            .send_request(req.head, req.body, req.addr, Millis::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::http::header;
+    use crate::util::Bytes;
+
+    #[test]
+    fn test_service_request() {
+        let mut head = RequestHead::default();
+        head.uri = Uri::from_static("http://localhost/test");
+        head.headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+
+        let mut extra = HeaderMap::new();
+        extra.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        let mut req = ServiceRequest {
+            head: RequestHeadType::Rc(Rc::new(head), Some(extra)),
+            body: Body::Bytes(Bytes::from_static(b"test")),
+            addr: None,
+            timeout: Millis(100),
+        };
+        assert_eq!(req.uri(), &Uri::from_static("http://localhost/test"));
+        assert_eq!(req.method(), &Method::GET);
+        assert_eq!(req.timeout(), Millis(100));
+        assert_eq!(req.header(&header::CONTENT_TYPE).unwrap(), "text/html");
+        assert!(format!("{:?}", req).contains("ServiceRequest"));
+
+        let req2 = req.try_clone().unwrap();
+        assert_eq!(req2.header(&header::CONTENT_TYPE).unwrap(), "text/html");
+        assert_eq!(req2.body(), &Body::Bytes(Bytes::from_static(b"test")));
+
+        req.head_mut().method = Method::POST;
+        assert!(matches!(req.head, RequestHeadType::Owned(_)));
+        assert_eq!(req.method(), &Method::POST);
+        assert_eq!(req.header(&header::CONTENT_TYPE).unwrap(), "text/html");
+        assert_eq!(req.head().headers.get_all(header::CONTENT_TYPE).count(), 1);
+
+        *req.body_mut() = Body::from_message(Body::Empty);
+        assert!(req.try_clone().is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use std::{pin::Pin, task::Context, task::Poll, time::Duration};
+
+use crate::http::sse::{Event, EventDecoder};
+use crate::http::{HttpMessage, Payload};
+use crate::util::{BytesMut, Stream};
+
+use super::{error::SseError, ClientResponse};
+
+/// Stream of server-sent events.
+///
+/// Stream tracks last event id and reconnection time, on reconnect last
+/// event id should be sent to the server with `Last-Event-ID` header.
+///
+/// ```rust
+/// use ntex::http::client::Client;
+/// use ntex::util::stream_recv;
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::new();
+///     let mut last_event_id = None;
+///
+///     loop {
+///         let res = client
+///             .get("http://www.example.com/events")
+///             .if_some(last_event_id.take(), |id: String, req| req.last_event_id(id))
+///             .send()
+///             .await;
+///
+///         if let Ok(mut res) = res {
+///             let mut events = res.sse();
+///             while let Some(Ok(event)) = stream_recv(&mut events).await {
+///                 println!("Event: {:?}", event);
+///             }
+///             last_event_id = events.last_event_id().map(|id| id.to_string());
+///         }
+///         # break;
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SseStream {
+    payload: Payload,
+    buf: BytesMut,
+    decoder: EventDecoder,
+    limit: usize,
+    err: Option<SseError>,
+}
+
+impl SseStream {
+    /// Create `SseStream` for the response.
+    pub fn new(res: &mut ClientResponse) -> Self {
+        let err = match res.mime_type() {
+            Ok(Some(mime)) if mime.essence_str() == "text/event-stream" => None,
+            _ => Some(SseError::ContentType),
+        };
+
+        SseStream {
+            err,
+            payload: res.take_payload(),
+            buf: BytesMut::new(),
+            decoder: EventDecoder::new(),
+            limit: 262_144,
+        }
+    }
+
+    /// Change max size of the event. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Id of the last received event
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.decoder.last_event_id()
+    }
+
+    /// Reconnection time requested by the server
+    pub fn retry(&self) -> Option<Duration> {
+        self.decoder.retry()
+    }
+}
+
+impl Stream for SseStream {
+    type Item = Result<Event, SseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(err) = this.err.take() {
+            this.payload = Payload::None;
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        loop {
+            if let Some(event) = this.decoder.decode(&mut this.buf) {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if this.buf.len() > this.limit {
+                this.payload = Payload::None;
+                this.buf.clear();
+                return Poll::Ready(Some(Err(SseError::Overflow)));
+            }
+
+            return match Pin::new(&mut this.payload).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.buf.extend_from_slice(&chunk);
+                    continue;
+                }
+                Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e.into()))),
+                Poll::Ready(None) => Poll::Ready(None),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{client::test::TestResponse, h1, header};
+    use crate::util::{stream_recv, Bytes};
+
+    #[crate::rt_test]
+    async fn test_sse_stream() {
+        let mut res = TestResponse::with_header(header::CONTENT_TYPE, "text/html").finish();
+        let mut events = res.sse();
+        assert!(matches!(
+            stream_recv(&mut events).await,
+            Some(Err(SseError::ContentType))
+        ));
+        assert!(stream_recv(&mut events).await.is_none());
+
+        let (mut tx, pl) = h1::Payload::create(false);
+        let mut res =
+            TestResponse::with_header(header::CONTENT_TYPE, "text/event-stream").finish();
+        res.set_payload(pl.into());
+        let mut events = res.sse();
+
+        tx.feed_data(Bytes::from_static(b"retry: 100\nid: 1\ndata: a\n"));
+        tx.feed_data(Bytes::from_static(b"\ndata: b\n\nevent: e\ndata: c\n\n"));
+        tx.feed_eof();
+
+        let ev = stream_recv(&mut events).await.unwrap().unwrap();
+        assert_eq!(ev.data, "a");
+        assert_eq!(ev.id.as_deref(), Some("1"));
+        assert_eq!(events.retry(), Some(Duration::from_millis(100)));
+        assert_eq!(stream_recv(&mut events).await.unwrap().unwrap().data, "b");
+        let ev = stream_recv(&mut events).await.unwrap().unwrap();
+        assert_eq!(ev.event.as_deref(), Some("e"));
+        assert!(stream_recv(&mut events).await.is_none());
+        assert_eq!(events.last_event_id(), Some("1"));
+    }
+
+    #[crate::rt_test]
+    async fn test_sse_overflow() {
+        let mut res = TestResponse::with_header(header::CONTENT_TYPE, "text/event-stream")
+            .set_payload(Bytes::from_static(b"data: 0123456789"))
+            .finish();
+        let mut events = res.sse().limit(10);
+        assert!(matches!(
+            stream_recv(&mut events).await,
+            Some(Err(SseError::Overflow))
+        ));
+        assert!(stream_recv(&mut events).await.is_none());
+    }
+}
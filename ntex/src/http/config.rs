@@ -2,7 +2,7 @@ use std::{cell::Cell, ptr::copy_nonoverlapping, rc::Rc, time};
 
 use ntex_h2::{self as h2};
 
-use crate::http::{Request, Response};
+use crate::http::{payload, HeaderMap, Request, Response};
 use crate::service::{boxed::BoxService, Pipeline};
 use crate::time::{sleep, Millis, Seconds};
 use crate::{io::IoRef, util::BytesMut};
@@ -50,6 +50,8 @@ pub struct ServiceConfig {
     pub(super) h2config: h2::Config,
     pub(super) headers_read_rate: Option<ReadRate>,
     pub(super) payload_read_rate: Option<ReadRate>,
+    pub(super) payload_limit: Option<u64>,
+    pub(super) h2c: bool,
     pub(super) timer: DateService,
 }
 
@@ -115,6 +117,8 @@ impl ServiceConfig {
                 max_timeout: client_timeout + Seconds(3),
             }),
             payload_read_rate: None,
+            payload_limit: None,
+            h2c: false,
         }
     }
 
@@ -234,6 +238,30 @@ impl ServiceConfig {
         }
         self
     }
+
+    /// Set max size of request's payload.
+    ///
+    /// Requests with larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` response, streamed payloads are terminated
+    /// with `PayloadError::Overflow` error once they exceed the limit.
+    /// To disable limit set value to 0.
+    ///
+    /// By default payload size is not limited.
+    pub fn payload_limit(&mut self, limit: u64) -> &mut Self {
+        self.payload_limit = if limit == 0 { None } else { Some(limit) };
+        self
+    }
+
+    /// Enable http/2 over cleartext connections (h2c).
+    ///
+    /// Cleartext connections are switched to http/2 if connection starts
+    /// with http/2 preface or first request is `Upgrade: h2c` request.
+    ///
+    /// By default h2c is disabled.
+    pub fn h2c(&mut self, enabled: bool) -> &mut Self {
+        self.h2c = enabled;
+        self
+    }
 }
 
 pub(super) type OnRequest = BoxService<(Request, IoRef), Request, Response>;
@@ -248,6 +276,8 @@ pub(super) struct DispatcherConfig<S, X, U> {
     pub(super) ka_enabled: bool,
     pub(super) headers_read_rate: Option<ReadRate>,
     pub(super) payload_read_rate: Option<ReadRate>,
+    pub(super) payload_limit: Option<u64>,
+    pub(super) h2c: bool,
     pub(super) timer: DateService,
     pub(super) on_request: Option<Pipeline<OnRequest>>,
 }
@@ -270,6 +300,8 @@ impl<S, X, U> DispatcherConfig<S, X, U> {
             ka_enabled: cfg.ka_enabled,
             headers_read_rate: cfg.headers_read_rate,
             payload_read_rate: cfg.payload_read_rate,
+            payload_limit: cfg.payload_limit,
+            h2c: cfg.h2c,
             h2config: cfg.h2config.clone(),
             timer: cfg.timer.clone(),
         }
@@ -283,6 +315,13 @@ impl<S, X, U> DispatcherConfig<S, X, U> {
     pub(super) fn headers_read_rate(&self) -> Option<&ReadRate> {
         self.headers_read_rate.as_ref()
     }
+
+    /// Check if request's `Content-Length` exceeds payload limit
+    pub(super) fn payload_too_large(&self, headers: &HeaderMap) -> bool {
+        self.payload_limit
+            .map(|limit| payload::content_length_exceeds(headers, limit))
+            .unwrap_or(false)
+    }
 }
 
 const DATE_VALUE_LENGTH_HDR: usize = 39;
@@ -5,7 +5,7 @@ use brotli2::write::BrotliEncoder;
 use flate2::write::{GzEncoder, ZlibEncoder};
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
-use crate::http::header::{ContentEncoding, HeaderValue, CONTENT_ENCODING};
+use crate::http::header::{ContentEncoding, HeaderMap, HeaderValue, CONTENT_ENCODING};
 use crate::http::{ResponseHead, StatusCode};
 use crate::rt::{spawn_blocking, JoinHandle};
 use crate::util::Bytes;
@@ -178,6 +178,18 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
             }
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        // trailers follow the last encoded chunk
+        if self.encoder.is_some() || self.fut.is_some() {
+            return None;
+        }
+        match self.body {
+            EncoderBody::Bytes(_) => None,
+            EncoderBody::Stream(ref mut b) => b.trailers(),
+            EncoderBody::BoxedStream(ref mut b) => b.trailers(),
+        }
+    }
 }
 
 fn update_head(encoding: ContentEncoding, head: &mut ResponseHead) {
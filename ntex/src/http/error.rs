@@ -191,6 +191,10 @@ pub enum DispatchError {
     #[error("Malformed request")]
     MalformedRequest,
 
+    /// Request's payload is too large
+    #[error("Request payload is too large")]
+    PayloadTooLarge,
+
     /// Response body processing error
     #[error("Response body processing error: {0}")]
     ResponsePayload(Box<dyn std::error::Error>),
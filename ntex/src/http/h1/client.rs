@@ -6,6 +6,7 @@ use crate::codec::{Decoder, Encoder};
 use crate::http::body::BodySize;
 use crate::http::config::DateService;
 use crate::http::error::{ParseError, PayloadError};
+use crate::http::header::HeaderMap;
 use crate::http::message::{ConnectionType, RequestHeadType, ResponseHead};
 use crate::http::{Method, Version};
 use crate::util::{Bytes, BytesMut};
@@ -39,6 +40,7 @@ struct ClientCodecInner {
     timer: DateService,
     decoder: decoder::MessageDecoder<ResponseHead>,
     payload: RefCell<Option<PayloadDecoder>>,
+    trailers: RefCell<Option<HeaderMap>>,
     version: Cell<Version>,
     ctype: Cell<ConnectionType>,
 
@@ -68,6 +70,7 @@ impl ClientCodec {
                 timer,
                 decoder: decoder::MessageDecoder::default(),
                 payload: RefCell::new(None),
+                trailers: RefCell::new(None),
                 version: Cell::new(Version::HTTP_11),
                 ctype: Cell::new(ConnectionType::Close),
                 flags: Cell::new(flags),
@@ -76,6 +79,14 @@ impl ClientCodec {
         }
     }
 
+    /// Use absolute-form request target.
+    ///
+    /// Absolute-form is required for requests sent to a proxy.
+    pub fn absolute_form(mut self) -> Self {
+        self.inner.encoder.absolute_form = true;
+        self
+    }
+
     /// Check if request is upgrade
     pub fn upgrade(&self) -> bool {
         self.inner.ctype.get() == ConnectionType::Upgrade
@@ -109,6 +120,11 @@ impl ClientPayloadCodec {
         self.inner.ctype.get() == ConnectionType::KeepAlive
     }
 
+    /// Take trailer headers of the last response's payload
+    pub fn take_trailers(&self) -> Option<HeaderMap> {
+        self.inner.trailers.borrow_mut().take()
+    }
+
     /// Transform payload codec to a message codec
     pub fn into_message_codec(self) -> ClientCodec {
         ClientCodec { inner: self.inner }
@@ -180,6 +196,10 @@ impl Decoder for ClientPayloadCodec {
                 reserve_readbuf(src);
                 Some(Some(chunk))
             }
+            Some(PayloadItem::Trailers(trailers)) => {
+                *self.inner.trailers.borrow_mut() = Some(trailers);
+                return self.decode(src);
+            }
             Some(PayloadItem::Eof) => {
                 self.inner.payload.borrow_mut().take();
                 Some(None)
@@ -232,6 +252,9 @@ impl Encoder for ClientCodec {
             Message::Chunk(None) => {
                 self.inner.encoder.encode_eof(dst)?;
             }
+            Message::Trailers(trailers) => {
+                self.inner.encoder.encode_trailers(&trailers, dst)?;
+            }
         }
         Ok(())
     }
@@ -189,6 +189,9 @@ impl Encoder for Codec {
             Message::Chunk(None) => {
                 self.encoder.encode_eof(dst)?;
             }
+            Message::Trailers(trailers) => {
+                self.encoder.encode_trailers(&trailers, dst)?;
+            }
         }
         Ok(())
     }
@@ -13,6 +13,7 @@ use crate::util::{Buf, Bytes, BytesMut};
 use super::MAX_BUFFER_SIZE;
 
 const MAX_HEADERS: usize = 96;
+const MAX_TRAILERS_SIZE: usize = 8192;
 
 #[derive(Debug)]
 /// Incoming messagd decoder
@@ -442,6 +443,8 @@ impl HeaderIndex {
 /// Http payload item
 pub enum PayloadItem {
     Chunk(Bytes),
+    /// Trailer headers of chunked payload, followed by `Eof`
+    Trailers(HeaderMap),
     Eof,
 }
 
@@ -509,6 +512,7 @@ enum ChunkedState {
     BodyLf,
     EndCr,
     EndLf,
+    Trailers,
     End,
 }
 
@@ -556,6 +560,18 @@ impl Decoder for PayloadDecoder {
                         break Ok(Some(PayloadItem::Eof));
                     }
 
+                    if *state == ChunkedState::Trailers {
+                        match read_trailers(src) {
+                            Ok(Some(trailers)) => {
+                                log::trace!("Chunked stream trailers: {:?}", trailers);
+                                *state = ChunkedState::End;
+                                break Ok(Some(PayloadItem::Trailers(trailers)));
+                            }
+                            Ok(None) => break Ok(None),
+                            Err(e) => break Err(e),
+                        }
+                    }
+
                     if let Some(buf) = buf {
                         break Ok(Some(PayloadItem::Chunk(buf)));
                     }
@@ -607,6 +623,7 @@ impl ChunkedState {
             BodyLf => ChunkedState::read_body_lf(body),
             EndCr => ChunkedState::read_end_cr(body),
             EndLf => ChunkedState::read_end_lf(body),
+            Trailers => Poll::Ready(Ok(ChunkedState::Trailers)),
             End => Poll::Ready(Ok(ChunkedState::End)),
         }
     }
@@ -719,9 +736,14 @@ impl ChunkedState {
         }
     }
     fn read_end_cr(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, ParseError>> {
-        match byte!(rdr) {
-            b'\r' => Poll::Ready(Ok(ChunkedState::EndLf)),
-            _ => Poll::Ready(Err(ParseError::InvalidInput("Invalid chunk end CR"))),
+        if rdr.is_empty() {
+            Poll::Pending
+        } else if rdr[0] == b'\r' {
+            rdr.advance(1);
+            Poll::Ready(Ok(ChunkedState::EndLf))
+        } else {
+            // trailer section
+            Poll::Ready(Ok(ChunkedState::Trailers))
         }
     }
     fn read_end_lf(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, ParseError>> {
@@ -732,6 +754,37 @@ impl ChunkedState {
     }
 }
 
+/// Parse trailer section of chunked payload
+fn read_trailers(src: &mut BytesMut) -> Result<Option<HeaderMap>, ParseError> {
+    let len = if let Some(pos) = src.windows(4).position(|w| w == b"\r\n\r\n") {
+        pos + 4
+    } else if src.len() > MAX_TRAILERS_SIZE {
+        return Err(ParseError::TooLarge);
+    } else {
+        return Ok(None);
+    };
+    if len > MAX_TRAILERS_SIZE {
+        return Err(ParseError::TooLarge);
+    }
+
+    let data = src.split_to(len);
+    let mut parsed = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    match httparse::parse_headers(&data, &mut parsed)? {
+        httparse::Status::Complete((_, headers)) => {
+            let mut trailers = HeaderMap::with_capacity(headers.len());
+            for h in headers {
+                let name = HeaderName::from_bytes(h.name.as_bytes())
+                    .map_err(|_| ParseError::Header)?;
+                let value =
+                    HeaderValue::from_bytes(h.value).map_err(|_| ParseError::Header)?;
+                trailers.append(name, value);
+            }
+            Ok(Some(trailers))
+        }
+        httparse::Status::Partial => Err(ParseError::Header),
+    }
+}
+
 fn uninit_array<T, const LEN: usize>() -> [mem::MaybeUninit<T>; LEN] {
     // SAFETY: An uninitialized `[mem::MaybeUninit<_>; LEN]` is valid.
     unsafe { mem::MaybeUninit::uninit().assume_init() }
@@ -1260,6 +1313,54 @@ mod tests {
         assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
     }
 
+    #[test]
+    fn test_http_request_chunked_payload_trailers() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+        );
+        let reader = MessageDecoder::<Request>::default();
+        let (_, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let pl = pl.unwrap();
+
+        buf.extend(b"4\r\ndata\r\n0\r\nx-checksum: 1234\r\n");
+        assert_eq!(
+            pl.decode(&mut buf).unwrap().unwrap().chunk().as_ref(),
+            b"data"
+        );
+        assert!(pl.decode(&mut buf).unwrap().is_none());
+
+        buf.extend(b"x-status: ok\r\n\r\nGET /test2 HTTP/1.1\r\n\r\n");
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("x-checksum"),
+            HeaderValue::from_static("1234"),
+        );
+        trailers.insert(
+            HeaderName::from_static("x-status"),
+            HeaderValue::from_static("ok"),
+        );
+        assert_eq!(
+            pl.decode(&mut buf).unwrap().unwrap(),
+            PayloadItem::Trailers(trailers)
+        );
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+
+        let req = parse_ready!(&mut buf);
+        assert_eq!(req.path(), "/test2");
+
+        // invalid trailer
+        let (_, pl) = reader
+            .decode(&mut BytesMut::from(
+                "GET /test HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n",
+            ))
+            .unwrap()
+            .unwrap();
+        let pl = pl.unwrap();
+        let mut buf = BytesMut::from("0\r\nx test\r\n\r\n");
+        assert!(pl.decode(&mut buf).is_err());
+    }
+
     #[test]
     fn test_http_request_chunked_payload_and_next_message() {
         let mut buf = BytesMut::from(
@@ -1,16 +1,17 @@
 //! Framed transport dispatcher
-use std::task::{Context, Poll};
 use std::{cell::RefCell, error::Error, future::Future, io, marker, pin::Pin, rc::Rc};
+use std::{task::Context, task::Poll, time::Instant};
 
 use crate::io::{Decoded, Filter, Io, IoBoxed, IoRef, IoStatusUpdate, RecvError};
 use crate::service::{Pipeline, PipelineCall, Service};
 use crate::time::Seconds;
-use crate::util::{ready, Bytes};
+use crate::util::{ready, BoxFuture, Bytes};
 
 use crate::http;
 use crate::http::body::{BodySize, MessageBody, ResponseBody};
 use crate::http::config::{DispatcherConfig, OnRequest};
 use crate::http::error::{DispatchError, ParseError, PayloadError, ResponseError};
+use crate::http::h2::{self, h2c};
 use crate::http::message::{ConnectionType, CurrentIo};
 use crate::http::request::Request;
 use crate::http::response::Response;
@@ -28,6 +29,8 @@ bitflags::bitflags! {
         const UPGRADE_HND          = 0b0000_0010;
         /// Stop after sending payload
         const SENDPAYLOAD_AND_STOP = 0b0000_0100;
+        /// Detect http/2 cleartext connection
+        const H2C                  = 0b0000_1000;
 
         /// Keep-alive is enabled
         const READ_KA_TIMEOUT    = 0b0001_0000;
@@ -47,6 +50,7 @@ pin_project_lite::pin_project! {
         call: CallState<S, X>,
         st: State<B>,
         inner: DispatcherInner<F, S, B, X, U>,
+        h2: Option<BoxFuture<'static, Result<(), DispatchError>>>,
     }
 }
 
@@ -67,6 +71,8 @@ enum State<B> {
     },
     #[error("State::Upgrade")]
     Upgrade(Option<Request>),
+    #[error("State::H2c")]
+    H2c(Option<h2c::Upgrade>),
     #[error("State::StopIo")]
     StopIo(Box<(IoBoxed, Codec)>),
     #[error("State::Stop")]
@@ -96,6 +102,8 @@ struct DispatcherInner<F, S, B, X, U> {
     read_remains: u32,
     read_consumed: u32,
     read_max_timeout: Seconds,
+    // start time of current request
+    started: Option<Instant>,
     _t: marker::PhantomData<(S, B)>,
 }
 
@@ -136,10 +144,21 @@ where
                 read_remains: 0,
                 read_consumed: 0,
                 read_max_timeout: max_timeout,
+                started: None,
                 _t: marker::PhantomData,
             },
+            h2: None,
         }
     }
+
+    /// Enable http/2 cleartext (h2c) connections.
+    ///
+    /// Dispatcher switches to http/2 if connection starts with http/2
+    /// preface or first request is `Upgrade: h2c` request.
+    pub(in crate::http) fn h2c(mut self) -> Self {
+        self.inner.flags.insert(Flags::H2C);
+        self
+    }
 }
 
 impl<F, S, B, X, U> Future for Dispatcher<F, S, B, X, U>
@@ -158,6 +177,11 @@ where
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut this = self.as_mut().project();
 
+        // connection is switched to http/2
+        if let Some(fut) = this.h2 {
+            return fut.as_mut().poll(cx);
+        }
+
         loop {
             match this.st {
                 State::Call => {
@@ -329,7 +353,7 @@ where
                         loop {
                             let _ = ready!(this.inner.io.poll_flush(cx, false));
                             let item = ready!(body.poll_next_chunk(cx));
-                            if let Some(st) = this.inner.send_payload(item) {
+                            if let Some(st) = this.inner.send_payload(item, body) {
                                 *this.st = st;
                                 break;
                             }
@@ -367,8 +391,12 @@ where
                                 }
                                 None => {
                                     trace!("response payload eof {:?}", this.inner.flags);
-                                    if let Err(e) = io.0.encode(Message::Chunk(None), &io.1)
-                                    {
+                                    let msg = if let Some(trailers) = body.trailers() {
+                                        Message::Trailers(trailers)
+                                    } else {
+                                        Message::Chunk(None)
+                                    };
+                                    if let Err(e) = io.0.encode(msg, &io.1) {
                                         trace!("Cannot encode payload eof: {:?}", e);
                                     }
                                 }
@@ -406,6 +434,40 @@ where
                     });
                     return Poll::Ready(Ok(()));
                 }
+                // switch to http/2
+                State::H2c(ref upgrade) => {
+                    if let Some(upgrade) = upgrade {
+                        // wait for client's connection preface and insert
+                        // upgrade request as stream 1
+                        if !this
+                            .inner
+                            .io
+                            .with_read_buf(|buf| h2c::insert_stream(buf, upgrade))
+                        {
+                            if this.inner.poll_io_closed(cx) {
+                                *this.st = State::Stop;
+                                continue;
+                            }
+                            match ready!(this.inner.io.poll_read_ready(cx)) {
+                                Ok(Some(_)) => continue,
+                                Ok(None) => *this.st = State::Stop,
+                                Err(err) => {
+                                    this.inner.error =
+                                        Some(DispatchError::PeerGone(Some(err)));
+                                    *this.st = State::Stop;
+                                }
+                            }
+                            continue;
+                        }
+                    }
+
+                    log::trace!("{}: Switching to http/2", this.inner.io.tag());
+                    let io = this.inner.io.take();
+                    io.stop_timer();
+
+                    let fut = h2::handle(io.into(), this.inner.config.clone());
+                    return this.h2.insert(Box::pin(fut)).as_mut().poll(cx);
+                }
                 // prepare to shutdown
                 State::Stop => {
                     this.inner.io.stop_timer();
@@ -504,6 +566,27 @@ where
         log::trace!("{}: Trying to read http message", self.io.tag());
 
         loop {
+            // http/2 with prior knowledge
+            if self.flags.contains(Flags::H2C) {
+                match self.io.with_read_buf(|buf| h2c::check_preface(buf)) {
+                    Some(true) => {
+                        log::trace!("{}: Http/2 connection preface", self.io.tag());
+                        return Poll::Ready(State::H2c(None));
+                    }
+                    Some(false) => (),
+                    None => {
+                        return match ready!(self.io.poll_read_ready(cx)) {
+                            Ok(Some(_)) => continue,
+                            Ok(None) => Poll::Ready(State::Stop),
+                            Err(err) => {
+                                self.error = Some(DispatchError::PeerGone(Some(err)));
+                                Poll::Ready(State::Stop)
+                            }
+                        };
+                    }
+                }
+            }
+
             let result = match self.io.poll_recv_decode(&self.codec, cx) {
                 Ok(decoded) => {
                     if let Some(st) = self.update_hdrs_timer(&decoded) {
@@ -528,19 +611,49 @@ where
                         req,
                         pl
                     );
+                    self.started = Some(Instant::now());
+
+                    // check request's payload size
+                    if self.config.payload_too_large(&req.head().headers) {
+                        log::trace!("{}: Request payload is too large", self.io.tag());
+                        let (res, body) = Response::PayloadTooLarge().finish().into_parts();
+                        self.error = Some(DispatchError::PayloadTooLarge);
+                        return Poll::Ready(self.send_response(res, body.into_body()));
+                    }
+
+                    // http/2 upgrade, only first request could be upgraded
+                    if self.flags.contains(Flags::H2C) {
+                        self.flags.remove(Flags::H2C);
+
+                        if let (PayloadType::None, Some(upgrade)) =
+                            (&pl, h2c::upgrade(&req))
+                        {
+                            log::trace!("{}: Http/2 upgrade request", self.io.tag());
+                            let result = self.io.with_write_buf(|buf| {
+                                buf.extend_from_slice(h2c::SWITCHING_PROTOCOLS)
+                            });
+                            return Poll::Ready(match result {
+                                Ok(_) => State::H2c(Some(upgrade)),
+                                Err(err) => {
+                                    self.error = Some(DispatchError::PeerGone(Some(err)));
+                                    State::Stop
+                                }
+                            });
+                        }
+                    }
 
                     // configure request payload
                     let upgrade = match pl {
                         PayloadType::None => false,
                         PayloadType::Payload(decoder) => {
-                            let (ps, pl) = Payload::create(false);
+                            let (ps, pl) = self.create_payload();
                             req.replace_payload(http::Payload::H1(pl));
                             self.payload = Some((decoder, ps));
                             false
                         }
                         PayloadType::Stream(decoder) => {
                             if self.config.upgrade.is_none() {
-                                let (ps, pl) = Payload::create(false);
+                                let (ps, pl) = self.create_payload();
                                 req.replace_payload(http::Payload::H1(pl));
                                 self.payload = Some((decoder, ps));
                                 false
@@ -621,6 +734,14 @@ where
         }
     }
 
+    fn create_payload(&self) -> (PayloadSender, Payload) {
+        let (ps, pl) = Payload::create(false);
+        if let Some(limit) = self.config.payload_limit {
+            pl.set_limit(limit);
+        }
+        (ps, pl)
+    }
+
     fn send_response(&mut self, msg: Response<()>, body: ResponseBody<B>) -> State<B> {
         trace!(
             "{}: Sending response: {:?} body: {:?}",
@@ -628,6 +749,10 @@ where
             msg,
             body.size()
         );
+        // responses without request, i.e. malformed request, have zero duration
+        let elapsed = self.started.take().map(|t| t.elapsed()).unwrap_or_default();
+        crate::server::http_response(msg.status(), elapsed);
+
         // we dont need to process responses if socket is disconnected
         // but we still want to handle requests with app service
         // so we skip response processing for droppped connection
@@ -666,6 +791,7 @@ where
     fn send_payload(
         &mut self,
         item: Option<Result<Bytes, Box<dyn Error>>>,
+        body: &mut ResponseBody<B>,
     ) -> Option<State<B>> {
         match item {
             Some(Ok(item)) => {
@@ -680,7 +806,12 @@ where
             }
             None => {
                 trace!("{}: Response payload eof {:?}", self.io.tag(), self.flags);
-                if let Err(err) = self.io.encode(Message::Chunk(None), &self.codec) {
+                let msg = if let Some(trailers) = body.trailers() {
+                    Message::Trailers(trailers)
+                } else {
+                    Message::Chunk(None)
+                };
+                if let Err(err) = self.io.encode(msg, &self.codec) {
                     self.error = Some(DispatchError::Encode(err));
                     Some(State::Stop)
                 } else if self.flags.contains(Flags::SENDPAYLOAD_AND_STOP) {
@@ -760,6 +891,9 @@ where
                         Ok(PayloadItem::Chunk(chunk)) => {
                             self.payload.as_mut().unwrap().1.feed_data(chunk);
                         }
+                        Ok(PayloadItem::Trailers(trailers)) => {
+                            self.payload.as_mut().unwrap().1.feed_trailers(trailers);
+                        }
                         Ok(PayloadItem::Eof) => {
                             self.payload.as_mut().unwrap().1.feed_eof();
                             self.payload = None;
@@ -1427,6 +1561,49 @@ mod tests {
         assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_ready());
     }
 
+    #[crate::rt_test]
+    async fn test_trailers() {
+        struct Body(Option<Bytes>, Option<http::HeaderMap>);
+
+        impl body::MessageBody for Body {
+            fn size(&self) -> body::BodySize {
+                body::BodySize::Stream
+            }
+            fn poll_next_chunk(
+                &mut self,
+                _: &mut Context<'_>,
+            ) -> Poll<Option<Result<Bytes, Box<dyn std::error::Error>>>> {
+                Poll::Ready(self.0.take().map(Ok))
+            }
+            fn trailers(&mut self) -> Option<http::HeaderMap> {
+                self.1.take()
+            }
+        }
+
+        let (client, server) = Io::create();
+        client.remote_buffer_cap(4096);
+        spawn_h1(server, |mut req: Request| async move {
+            let mut pl = req.take_payload();
+            let mut data = BytesMut::new();
+            while let Some(item) = stream_recv(&mut pl).await {
+                data.extend_from_slice(&item.unwrap());
+            }
+            let trailers = pl.trailers();
+            Ok::<_, io::Error>(
+                Response::Ok().message_body(Body(Some(data.freeze()), trailers)),
+            )
+        });
+
+        client.write(
+            "POST /test HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n\
+             4\r\ndata\r\n0\r\nx-checksum: 1234\r\n\r\n",
+        );
+        let data = client.read().await.unwrap();
+        assert!(data.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(data.ends_with(b"\r\n\r\n4\r\ndata\r\n0\r\nx-checksum: 1234\r\n\r\n"));
+        assert!(!client.is_server_dropped());
+    }
+
     #[crate::rt_test]
     async fn test_service_error() {
         let (client, server) = Io::create();
@@ -1503,4 +1680,103 @@ mod tests {
         }
         assert!(mark.load(Ordering::Relaxed) == 1536);
     }
+
+    /// Read http/2 frames until response for stream 1 is complete
+    async fn read_h2_response(client: &Io, mut buf: BytesMut) -> (Vec<u8>, Bytes) {
+        let mut types = Vec::new();
+        let mut body = BytesMut::new();
+        loop {
+            while buf.len() >= 9 {
+                let len = u32::from_be_bytes([0, buf[0], buf[1], buf[2]]) as usize;
+                if buf.len() < 9 + len {
+                    break;
+                }
+                let hdr = buf.split_to(9);
+                let payload = buf.split_to(len);
+                let stream = u32::from_be_bytes([hdr[5], hdr[6], hdr[7], hdr[8]]);
+                types.push(hdr[3]);
+
+                // DATA frame
+                if hdr[3] == 0x0 && stream == 1 {
+                    body.extend_from_slice(&payload);
+                    if hdr[4] & 0x1 != 0 {
+                        return (types, body.freeze());
+                    }
+                }
+            }
+            buf.extend_from_slice(&client.read().await.unwrap());
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_h2c_prior_knowledge() {
+        let (client, server) = Io::create();
+        client.remote_buffer_cap(4096);
+        crate::rt::spawn(
+            h1(server, |req: Request| async move {
+                Ok::<_, io::Error>(Response::Ok().body(req.path().to_string()))
+            })
+            .h2c(),
+        );
+
+        client.write(h2c::PREFACE);
+        // SETTINGS
+        client.write(b"\0\0\0\x04\0\0\0\0\0");
+        // HEADERS, GET http://localhost/
+        client.write(b"\0\0\x03\x01\x05\0\0\0\x01\x82\x86\x84");
+
+        let (types, body) = read_h2_response(&client, BytesMut::new()).await;
+        // server starts with SETTINGS frame
+        assert_eq!(types[0], 0x4);
+        assert!(types.contains(&0x1));
+        assert_eq!(body, Bytes::from_static(b"/"));
+        assert!(!client.is_server_dropped());
+
+        client.close().await;
+    }
+
+    #[crate::rt_test]
+    async fn test_h2c_upgrade() {
+        let (client, server) = Io::create();
+        client.remote_buffer_cap(4096);
+        crate::rt::spawn(
+            h1(server, |req: Request| async move {
+                Ok::<_, io::Error>(Response::Ok().body(req.path().to_string()))
+            })
+            .h2c(),
+        );
+
+        client.write(
+            "GET /test HTTP/1.1\r\nHost: localhost\r\n\
+             Connection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\n\
+             HTTP2-Settings: AAMAAABkAAQAAP__\r\n\r\n",
+        );
+
+        let mut buf = BytesMut::from(&client.read().await.unwrap()[..]);
+        assert!(buf.starts_with(b"HTTP/1.1 101 Switching Protocols\r\n"));
+        let pos = buf.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let _ = buf.split_to(pos + 4);
+
+        client.write(h2c::PREFACE);
+        client.write(b"\0\0\0\x04\0\0\0\0\0");
+
+        let (types, body) = read_h2_response(&client, buf).await;
+        assert_eq!(types[0], 0x4);
+        assert_eq!(body, Bytes::from_static(b"/test"));
+
+        // h2c is not enabled
+        let (client, server) = Io::create();
+        client.remote_buffer_cap(4096);
+        spawn_h1(server, |_| async {
+            Ok::<_, io::Error>(Response::Ok().finish())
+        });
+
+        client.write(
+            "GET /test HTTP/1.1\r\nConnection: Upgrade, HTTP2-Settings\r\n\
+             Upgrade: h2c\r\nHTTP2-Settings: AAMAAABkAAQAAP__\r\n\r\n",
+        );
+        let mut buf = BytesMut::from(&client.read().await.unwrap()[..]);
+        let mut decoder = ClientCodec::default();
+        assert!(load(&mut decoder, &mut buf).status.is_success());
+    }
 }
@@ -16,6 +16,7 @@ const AVERAGE_HEADER_SIZE: usize = 30;
 pub(super) struct MessageEncoder<T: MessageType> {
     pub(super) length: BodySize,
     pub(super) te: Cell<TransferEncoding>,
+    pub(super) absolute_form: bool,
     _t: PhantomData<T>,
 }
 
@@ -24,6 +25,7 @@ impl<T: MessageType> Default for MessageEncoder<T> {
         MessageEncoder {
             length: BodySize::None,
             te: Cell::new(TransferEncoding::empty()),
+            absolute_form: false,
             _t: PhantomData,
         }
     }
@@ -34,6 +36,7 @@ impl<T: MessageType> Clone for MessageEncoder<T> {
         MessageEncoder {
             length: self.length,
             te: self.te.clone(),
+            absolute_form: self.absolute_form,
             _t: PhantomData,
         }
     }
@@ -50,6 +53,11 @@ pub(super) trait MessageType: Sized {
 
     fn encode_status(&self, dst: &mut BytesMut) -> io::Result<()>;
 
+    /// Encode status line with absolute-form request target
+    fn encode_absolute_status(&self, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode_status(dst)
+    }
+
     fn encode_headers(
         &self,
         dst: &mut BytesMut,
@@ -246,12 +254,38 @@ impl MessageType for RequestHeadType {
 
     fn encode_status(&self, dst: &mut BytesMut) -> io::Result<()> {
         let head = self.as_ref();
-        dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE);
+        self.encode_request_line(
+            dst,
+            head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
+        )
+    }
+
+    fn encode_absolute_status(&self, dst: &mut BytesMut) -> io::Result<()> {
+        let head = self.as_ref();
+        match (head.uri.scheme(), head.uri.authority()) {
+            (Some(scheme), Some(authority)) => self.encode_request_line(
+                dst,
+                &format!(
+                    "{}://{}{}",
+                    scheme,
+                    authority,
+                    head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/")
+                ),
+            ),
+            _ => self.encode_status(dst),
+        }
+    }
+}
+
+impl RequestHeadType {
+    fn encode_request_line(&self, dst: &mut BytesMut, target: &str) -> io::Result<()> {
+        let head = self.as_ref();
+        dst.reserve(256 + target.len() + head.headers.len() * AVERAGE_HEADER_SIZE);
         write!(
             helpers::Writer(dst),
             "{} {} {}",
             head.method,
-            head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
+            target,
             // only HTTP-0.9/1.1
             match head.version {
                 Version::HTTP_09 => "HTTP/0.9",
@@ -282,6 +316,18 @@ impl<T: MessageType> MessageEncoder<T> {
         result
     }
 
+    /// Encode eof with trailers
+    pub(super) fn encode_trailers(
+        &self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        let mut te = self.te.get();
+        let result = te.encode_trailers(trailers, buf);
+        self.te.set(te);
+        result
+    }
+
     pub(super) fn encode(
         &self,
         dst: &mut BytesMut,
@@ -311,7 +357,11 @@ impl<T: MessageType> MessageEncoder<T> {
             self.te.set(TransferEncoding::empty());
         }
 
-        message.encode_status(dst)?;
+        if self.absolute_form {
+            message.encode_absolute_status(dst)?;
+        } else {
+            message.encode_status(dst)?;
+        }
         message.encode_headers(dst, version, length, ctype, timer)
     }
 }
@@ -434,6 +484,36 @@ impl TransferEncoding {
             }
         }
     }
+
+    /// Encode eof with trailers.
+    ///
+    /// Trailers could be sent only with chunked encoding,
+    /// for other encodings trailers are dropped.
+    #[inline]
+    pub(super) fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        match self.kind {
+            TransferEncodingKind::Chunked(false) => {
+                buf.extend_from_slice(b"0\r\n");
+                for (key, value) in trailers {
+                    buf.extend_from_slice(key.as_str().as_bytes());
+                    buf.extend_from_slice(b": ");
+                    buf.extend_from_slice(value.as_ref());
+                    buf.extend_from_slice(b"\r\n");
+                }
+                buf.extend_from_slice(b"\r\n");
+                self.kind = TransferEncodingKind::Chunked(true);
+                Ok(())
+            }
+            _ => {
+                log::trace!("Trailers are not supported, drop trailers");
+                self.encode_eof(buf)
+            }
+        }
+    }
 }
 
 const DEC_DIGITS_LUT: &[u8] = b"0001020304050607080910111213141516171819\
@@ -584,7 +664,7 @@ mod tests {
     use std::rc::Rc;
 
     use super::*;
-    use crate::http::header::{HeaderValue, AUTHORIZATION};
+    use crate::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
     use crate::http::RequestHead;
     use crate::util::Bytes;
 
@@ -599,6 +679,30 @@ mod tests {
         assert_eq!(bytes.split(), Bytes::from_static(b"4\r\ntest\r\n0\r\n\r\n"));
     }
 
+    #[test]
+    fn test_chunked_te_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("x-checksum"),
+            HeaderValue::from_static("1234"),
+        );
+
+        let mut bytes = BytesMut::new();
+        let mut enc = TransferEncoding::chunked();
+        assert!(!enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert!(enc.encode(b"", &mut bytes).unwrap());
+        assert_eq!(
+            bytes.split(),
+            Bytes::from_static(b"4\r\ntest\r\n0\r\nx-checksum: 1234\r\n\r\n")
+        );
+
+        let mut enc = TransferEncoding::length(4);
+        assert!(enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(bytes.split(), Bytes::from_static(b"test"));
+    }
+
     #[test]
     fn test_extra_headers() {
         let mut bytes = BytesMut::with_capacity(2048);
@@ -632,6 +736,47 @@ mod tests {
         assert!(data.contains("date: date\r\n"));
     }
 
+    #[test]
+    fn test_absolute_form() {
+        let mut head = RequestHead::default();
+        head.uri = crate::http::Uri::from_static("http://example.com:8080/test?q=1");
+        let mut head = RequestHeadType::Owned(head);
+
+        let mut bytes = BytesMut::new();
+        let enc = MessageEncoder::<RequestHeadType>::default();
+        enc.encode(
+            &mut bytes,
+            &mut head,
+            false,
+            false,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::Close,
+            &DateService::default(),
+        )
+        .unwrap();
+        assert!(bytes.split().starts_with(b"GET /test?q=1 HTTP/1.1\r\n"));
+
+        let enc = MessageEncoder::<RequestHeadType> {
+            absolute_form: true,
+            ..Default::default()
+        };
+        enc.encode(
+            &mut bytes,
+            &mut head,
+            false,
+            false,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::Close,
+            &DateService::default(),
+        )
+        .unwrap();
+        assert!(bytes
+            .split()
+            .starts_with(b"GET http://example.com:8080/test?q=1 HTTP/1.1\r\n"));
+    }
+
     #[test]
     fn test_write_content_length() {
         let mut bytes = BytesMut::new();
@@ -1,4 +1,5 @@
 //! HTTP/1 implementation
+use crate::http::header::HeaderMap;
 use crate::util::{Bytes, BytesMut};
 
 mod client;
@@ -30,6 +31,8 @@ pub enum Message<T> {
     Item(T),
     /// Payload chunk
     Chunk(Option<Bytes>),
+    /// Payload trailers, ends payload
+    Trailers(HeaderMap),
 }
 
 impl<T> From<T> for Message<T> {
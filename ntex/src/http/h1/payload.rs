@@ -3,7 +3,7 @@ use std::rc::{Rc, Weak};
 use std::task::{Context, Poll};
 use std::{cell::RefCell, collections::VecDeque, pin::Pin};
 
-use crate::http::error::PayloadError;
+use crate::http::{error::PayloadError, header::HeaderMap};
 use crate::{task::LocalWaker, util::Bytes, util::Stream};
 
 /// max buffer size 32k
@@ -56,12 +56,30 @@ impl Payload {
         }
     }
 
+    /// Set max size of the payload.
+    ///
+    /// If payload exceeds limit, buffered data is dropped and stream
+    /// yields `PayloadError::Overflow` error. Limit could only be decreased,
+    /// smallest limit applies.
+    #[inline]
+    pub fn set_limit(&self, limit: u64) {
+        self.inner.borrow_mut().set_limit(limit);
+    }
+
     /// Put unused data back to payload
     #[inline]
     pub fn unread_data(&mut self, data: Bytes) {
         self.inner.borrow_mut().unread_data(data);
     }
 
+    /// Trailer headers of the payload.
+    ///
+    /// Trailers are available after payload is fully read.
+    #[inline]
+    pub fn trailers(&self) -> Option<HeaderMap> {
+        self.inner.borrow().trailers.clone()
+    }
+
     #[inline]
     pub fn readany(
         &mut self,
@@ -115,6 +133,14 @@ impl PayloadSender {
         }
     }
 
+    /// Feed trailer headers and eof
+    pub fn feed_trailers(&mut self, trailers: HeaderMap) {
+        if let Some(shared) = self.inner.upgrade() {
+            shared.borrow_mut().trailers = Some(trailers);
+        }
+        self.feed_eof();
+    }
+
     pub(super) fn poll_data_required(&self, cx: &mut Context<'_>) -> PayloadStatus {
         // we check only if Payload (other side) is alive,
         // otherwise always return true (consume payload)
@@ -135,9 +161,13 @@ impl PayloadSender {
 struct Inner {
     len: usize,
     eof: bool,
+    limit: u64,
+    total: u64,
+    overflow: bool,
     err: Option<PayloadError>,
     need_read: bool,
     items: VecDeque<Bytes>,
+    trailers: Option<HeaderMap>,
     task: LocalWaker,
     io_task: LocalWaker,
 }
@@ -147,8 +177,12 @@ impl Inner {
         Inner {
             eof,
             len: 0,
+            limit: u64::MAX,
+            total: 0,
+            overflow: false,
             err: None,
             items: VecDeque::new(),
+            trailers: None,
             need_read: true,
             task: LocalWaker::new(),
             io_task: LocalWaker::new(),
@@ -156,8 +190,10 @@ impl Inner {
     }
 
     fn set_error(&mut self, err: PayloadError) {
-        self.err = Some(err);
-        self.task.wake()
+        if !self.overflow {
+            self.err = Some(err);
+            self.task.wake()
+        }
     }
 
     fn feed_eof(&mut self) {
@@ -165,7 +201,35 @@ impl Inner {
         self.task.wake()
     }
 
+    fn set_limit(&mut self, limit: u64) {
+        if limit < self.limit {
+            self.limit = limit;
+            if self.total > limit {
+                self.set_overflow();
+            }
+        }
+    }
+
+    fn set_overflow(&mut self) {
+        // stop reading, dispatcher closes connection after response
+        self.overflow = true;
+        self.eof = true;
+        self.need_read = false;
+        self.len = 0;
+        self.items.clear();
+        self.err = Some(PayloadError::Overflow);
+        self.task.wake()
+    }
+
     fn feed_data(&mut self, data: Bytes) {
+        if self.overflow {
+            return;
+        }
+        self.total += data.len() as u64;
+        if self.total > self.limit {
+            self.set_overflow();
+            return;
+        }
         self.len += data.len();
         self.items.push_back(data);
         self.need_read = self.len < MAX_BUFFER_SIZE;
@@ -206,7 +270,8 @@ impl Inner {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::util::poll_fn;
+    use crate::http::header::{HeaderName, HeaderValue};
+    use crate::util::{lazy, poll_fn};
 
     #[crate::rt_test]
     async fn test_unread_data() {
@@ -220,4 +285,60 @@ mod tests {
             poll_fn(|cx| payload.readany(cx)).await.unwrap().unwrap()
         );
     }
+
+    #[crate::rt_test]
+    async fn test_limit() {
+        let (mut sender, mut payload) = Payload::create(false);
+        payload.set_limit(10);
+        payload.set_limit(20);
+
+        sender.feed_data(Bytes::from("0123456789"));
+        assert_eq!(
+            lazy(|cx| sender.poll_data_required(cx)).await,
+            PayloadStatus::Read
+        );
+        assert_eq!(
+            Bytes::from("0123456789"),
+            poll_fn(|cx| payload.readany(cx)).await.unwrap().unwrap()
+        );
+
+        sender.feed_data(Bytes::from("a"));
+        sender.feed_data(Bytes::from("b"));
+        assert!(matches!(
+            poll_fn(|cx| payload.readany(cx)).await,
+            Some(Err(PayloadError::Overflow))
+        ));
+        assert!(poll_fn(|cx| payload.readany(cx)).await.is_none());
+        assert!(!payload.inner.borrow().need_read);
+        drop(sender);
+        assert!(poll_fn(|cx| payload.readany(cx)).await.is_none());
+
+        // limit is set after data is received
+        let (mut sender, payload) = Payload::create(false);
+        sender.feed_data(Bytes::from("0123456789"));
+        payload.set_limit(5);
+        assert!(payload.inner.borrow().items.is_empty());
+        assert!(payload.inner.borrow().overflow);
+    }
+
+    #[crate::rt_test]
+    async fn test_trailers() {
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from("data"));
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("x-checksum"),
+            HeaderValue::from_static("1234"),
+        );
+        sender.feed_trailers(trailers.clone());
+        assert!(payload.trailers().is_some());
+
+        assert_eq!(
+            Bytes::from("data"),
+            poll_fn(|cx| payload.readany(cx)).await.unwrap().unwrap()
+        );
+        assert!(poll_fn(|cx| payload.readany(cx)).await.is_none());
+        assert_eq!(payload.trailers(), Some(trailers));
+    }
 }
@@ -83,7 +83,7 @@ mod openssl {
             Acceptor::new(acceptor)
                 .timeout(self.cfg.ssl_handshake_timeout)
                 .chain()
-                .map_err(SslError::Ssl)
+                .map_err(SslError::handshake)
                 .map_init_err(|_| panic!())
                 .and_then(self.chain().map_err(SslError::Service))
         }
@@ -129,7 +129,7 @@ mod rustls {
             Acceptor::from(config)
                 .timeout(self.cfg.ssl_handshake_timeout)
                 .chain()
-                .map_err(|e| SslError::Ssl(Box::new(e)))
+                .map_err(SslError::handshake)
                 .map_init_err(|_| panic!())
                 .and_then(self.chain().map_err(SslError::Service))
         }
@@ -0,0 +1,354 @@
+//! HTTP/2 over cleartext TCP (h2c)
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as base64, Engine};
+
+use crate::http::header::{self, HeaderMap, HeaderValue};
+use crate::http::{Request, Version};
+use crate::util::{Bytes, BytesMut, BytesVec};
+
+/// HTTP/2 client connection preface
+pub(in crate::http) const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Response for `Upgrade: h2c` request
+pub(in crate::http) const SWITCHING_PROTOCOLS: &[u8] =
+    b"HTTP/1.1 101 Switching Protocols\r\nconnection: upgrade\r\nupgrade: h2c\r\n\r\n";
+
+/// Length of the preface request line, `PRI * HTTP/2.0\r\n`
+const PREFACE_LINE: usize = 16;
+
+/// Default max frame size
+const MAX_FRAME_SIZE: usize = 16_384;
+
+const FRAME_HEADER_SIZE: usize = 9;
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_SETTINGS: u8 = 0x4;
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+
+const SETTING_SIZE: usize = 6;
+const SETTINGS_ENABLE_PUSH: u16 = 0x2;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+
+/// Upgrade request encoded as http/2 frames
+#[derive(Debug)]
+pub(in crate::http) struct Upgrade {
+    /// Settings from `HTTP2-Settings` header, SETTINGS frame payload
+    settings: Bytes,
+    /// HEADERS frame for stream 1
+    headers: Bytes,
+}
+
+/// Check if buffer starts with http/2 connection preface.
+///
+/// Returns `None` if buffer contains incomplete preface that could be
+/// parsed as http/1 request.
+pub(in crate::http) fn check_preface(buf: &[u8]) -> Option<bool> {
+    if buf.len() >= PREFACE.len() {
+        Some(buf.starts_with(PREFACE))
+    } else if buf.len() >= PREFACE_LINE && PREFACE.starts_with(buf) {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Check if request is `Upgrade: h2c` request and encode it as
+/// http/2 HEADERS frame for stream 1.
+///
+/// Requests with payload and requests with missing or malformed
+/// `HTTP2-Settings` header are not upgraded.
+pub(in crate::http) fn upgrade(req: &Request) -> Option<Upgrade> {
+    let head = req.head();
+    if head.version != Version::HTTP_11 || !is_upgrade(&head.headers) {
+        return None;
+    }
+    let settings = decode_settings(head.headers.get("http2-settings")?)?;
+
+    let mut block = BytesMut::new();
+    encode_header(&mut block, b":method", head.method.as_str().as_bytes());
+    encode_header(&mut block, b":scheme", b"http");
+    encode_header(
+        &mut block,
+        b":path",
+        head.uri
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/")
+            .as_bytes(),
+    );
+    if let Some(host) = head.headers.get(&header::HOST) {
+        encode_header(&mut block, b":authority", host.as_bytes());
+    }
+    for (name, value) in head.headers.iter() {
+        // connection-specific headers are not allowed in http/2
+        if !matches!(
+            name.as_str(),
+            "host"
+                | "connection"
+                | "upgrade"
+                | "http2-settings"
+                | "keep-alive"
+                | "proxy-connection"
+                | "transfer-encoding"
+                | "te"
+        ) {
+            encode_header(&mut block, name.as_str().as_bytes(), value.as_bytes());
+        }
+    }
+    if block.len() > MAX_FRAME_SIZE {
+        return None;
+    }
+
+    let mut frame = BytesMut::with_capacity(FRAME_HEADER_SIZE + block.len());
+    frame.extend_from_slice(&(block.len() as u32).to_be_bytes()[1..]);
+    frame.extend_from_slice(&[FRAME_HEADERS, FLAG_END_HEADERS | FLAG_END_STREAM]);
+    frame.extend_from_slice(&1u32.to_be_bytes());
+    frame.extend_from_slice(&block);
+    Some(Upgrade {
+        settings,
+        headers: frame.freeze(),
+    })
+}
+
+/// Decode `HTTP2-Settings` header value (RFC 7540, Section 3.2.1)
+fn decode_settings(value: &HeaderValue) -> Option<Bytes> {
+    let value = value.as_bytes();
+    let len = value
+        .iter()
+        .rposition(|b| *b != b'=')
+        .map(|p| p + 1)
+        .unwrap_or(0);
+    let settings = base64.decode(&value[..len]).ok()?;
+    if settings.len() % SETTING_SIZE != 0 || settings.len() > MAX_FRAME_SIZE {
+        return None;
+    }
+
+    for item in settings.chunks(SETTING_SIZE) {
+        let id = u16::from_be_bytes([item[0], item[1]]);
+        let val = u32::from_be_bytes([item[2], item[3], item[4], item[5]]);
+        let valid = match id {
+            SETTINGS_ENABLE_PUSH => val <= 1,
+            SETTINGS_INITIAL_WINDOW_SIZE => val <= i32::MAX as u32,
+            SETTINGS_MAX_FRAME_SIZE => (MAX_FRAME_SIZE as u32..1 << 24).contains(&val),
+            _ => true,
+        };
+        if !valid {
+            return None;
+        }
+    }
+    Some(Bytes::from(settings))
+}
+
+/// Apply upgrade request's settings to client's first SETTINGS frame and
+/// insert request's HEADERS frame after it.
+///
+/// Returns `false` if buffer does not contain preface and SETTINGS frame yet.
+pub(in crate::http) fn insert_stream(buf: &mut BytesVec, upgrade: &Upgrade) -> bool {
+    if buf.len() < PREFACE.len() {
+        return false;
+    } else if !buf.starts_with(PREFACE) {
+        // not http/2 client, let h2 dispatcher handle error
+        return true;
+    } else if buf.len() < PREFACE.len() + FRAME_HEADER_SIZE {
+        return false;
+    }
+
+    let hdr = &buf[PREFACE.len()..PREFACE.len() + FRAME_HEADER_SIZE];
+    if hdr[3] != FRAME_SETTINGS {
+        // protocol error, let h2 dispatcher handle it
+        let data = buf.split();
+        buf.extend_from_slice(&data[..PREFACE.len()]);
+        buf.extend_from_slice(&upgrade.headers);
+        buf.extend_from_slice(&data[PREFACE.len()..]);
+        return true;
+    }
+
+    let len = u32::from_be_bytes([0, hdr[0], hdr[1], hdr[2]]) as usize;
+    let pos = PREFACE.len() + FRAME_HEADER_SIZE + len;
+    if buf.len() < pos {
+        return false;
+    }
+
+    // settings from header are applied first, client's SETTINGS frame
+    // could override them. peer expects single SETTINGS ack
+    let data = buf.split();
+    let hdr = &data[PREFACE.len()..PREFACE.len() + FRAME_HEADER_SIZE];
+    let len = (len + upgrade.settings.len()) as u32;
+    buf.extend_from_slice(PREFACE);
+    buf.extend_from_slice(&len.to_be_bytes()[1..]);
+    buf.extend_from_slice(&hdr[3..]);
+    buf.extend_from_slice(&upgrade.settings);
+    buf.extend_from_slice(&data[PREFACE.len() + FRAME_HEADER_SIZE..pos]);
+    buf.extend_from_slice(&upgrade.headers);
+    buf.extend_from_slice(&data[pos..]);
+    true
+}
+
+fn is_upgrade(headers: &HeaderMap) -> bool {
+    let has_token = |name, token: &str| {
+        headers.get_all(name).any(|v| {
+            v.to_str()
+                .map(|s| s.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)))
+                .unwrap_or(false)
+        })
+    };
+
+    has_token(&header::UPGRADE, "h2c")
+        && has_token(&header::CONNECTION, "upgrade")
+        && has_token(&header::CONNECTION, "http2-settings")
+        && headers.get_all("http2-settings").count() == 1
+}
+
+/// Encode header as hpack literal field without indexing
+fn encode_header(dst: &mut BytesMut, name: &[u8], value: &[u8]) {
+    dst.extend_from_slice(&[0]);
+    encode_str(dst, name);
+    encode_str(dst, value);
+}
+
+/// Encode hpack string without huffman encoding
+fn encode_str(dst: &mut BytesMut, s: &[u8]) {
+    const MAX: usize = 0x7f;
+
+    let mut len = s.len();
+    if len < MAX {
+        dst.extend_from_slice(&[len as u8]);
+    } else {
+        dst.extend_from_slice(&[MAX as u8]);
+        len -= MAX;
+        while len >= 0x80 {
+            dst.extend_from_slice(&[(len & 0x7f) as u8 | 0x80]);
+            len >>= 7;
+        }
+        dst.extend_from_slice(&[len as u8]);
+    }
+    dst.extend_from_slice(s);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::test::TestRequest;
+
+    #[test]
+    fn test_check_preface() {
+        assert_eq!(check_preface(PREFACE), Some(true));
+        assert_eq!(
+            check_preface(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0"),
+            Some(true)
+        );
+        assert_eq!(check_preface(b"PRI * HTTP/2.0\r\n\r\n"), None);
+        assert_eq!(check_preface(b"PRI"), Some(false));
+        assert_eq!(check_preface(b"GET / HTTP/1.1\r\n\r\n"), Some(false));
+        assert_eq!(
+            check_preface(b"PRI * HTTP/2.0\r\n\r\nXX\r\n\r\n"),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let req = TestRequest::with_uri("/test?q=1")
+            .header(header::HOST, "localhost")
+            .header(header::CONNECTION, "Upgrade, HTTP2-Settings")
+            .header(header::UPGRADE, "h2c")
+            .header("http2-settings", "AAMAAABkAAQAAP__")
+            .header("x-test", "1")
+            .finish();
+        let upgrade = upgrade(&req).unwrap();
+        assert_eq!(&upgrade.settings[..], b"\0\x03\0\0\0\x64\0\x04\0\0\xff\xff");
+        let frame = upgrade.headers;
+        assert_eq!(&frame[3..9], &[FRAME_HEADERS, 0x5, 0, 0, 0, 1]);
+        assert_eq!(
+            &frame[9..],
+            &b"\0\x07:method\x03GET\0\x07:scheme\x04http\0\x05:path\x09/test?q=1\
+               \0\x0a:authority\x09localhost\0\x06x-test\x011"[..]
+        );
+        assert_eq!(
+            u32::from_be_bytes([0, frame[0], frame[1], frame[2]]) as usize,
+            frame.len() - 9
+        );
+
+        // missing settings
+        let req = TestRequest::default()
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "h2c")
+            .finish();
+        assert!(upgrade(&req).is_none());
+
+        // malformed settings
+        for settings in ["AAMAAABk!", "AAMAAAB", "AAIAAAAC", "AAUAAAAB"] {
+            let req = TestRequest::default()
+                .header(header::CONNECTION, "upgrade, http2-settings")
+                .header(header::UPGRADE, "h2c")
+                .header("http2-settings", settings)
+                .finish();
+            assert!(upgrade(&req).is_none(), "{}", settings);
+        }
+
+        // empty settings
+        let req = TestRequest::default()
+            .header(header::CONNECTION, "upgrade, http2-settings")
+            .header(header::UPGRADE, "h2c")
+            .header("http2-settings", "")
+            .finish();
+        assert!(upgrade(&req).unwrap().settings.is_empty());
+
+        // websocket
+        let req = TestRequest::default()
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .finish();
+        assert!(upgrade(&req).is_none());
+    }
+
+    #[test]
+    fn test_encode_str() {
+        let mut buf = BytesMut::new();
+        encode_str(&mut buf, &[b'a'; 100]);
+        assert_eq!(buf[0], 100);
+        assert_eq!(buf.len(), 101);
+
+        let mut buf = BytesMut::new();
+        encode_str(&mut buf, &[b'a'; 300]);
+        assert_eq!(&buf[..3], &[0x7f, 0xad, 0x01]);
+        assert_eq!(buf.len(), 303);
+    }
+
+    #[test]
+    fn test_insert_stream() {
+        let upgrade = Upgrade {
+            settings: Bytes::from_static(b"\0\x04\0\0\xff\xff"),
+            headers: Bytes::from_static(b"frame"),
+        };
+        let settings = b"\0\0\x06\x04\0\0\0\0\0\0\x03\0\0\0\x64";
+        let mut buf = BytesVec::new();
+        buf.extend_from_slice(PREFACE);
+        assert!(!insert_stream(&mut buf, &upgrade));
+        buf.extend_from_slice(&settings[..10]);
+        assert!(!insert_stream(&mut buf, &upgrade));
+        buf.extend_from_slice(&settings[10..]);
+        buf.extend_from_slice(b"rest");
+        assert!(insert_stream(&mut buf, &upgrade));
+
+        let mut expected = PREFACE.to_vec();
+        expected.extend_from_slice(b"\0\0\x0c\x04\0\0\0\0\0");
+        expected.extend_from_slice(b"\0\x04\0\0\xff\xff\0\x03\0\0\0\x64");
+        expected.extend_from_slice(b"framerest");
+        assert_eq!(&buf[..], &expected[..]);
+
+        // first frame is not SETTINGS
+        let mut buf = BytesVec::new();
+        buf.extend_from_slice(PREFACE);
+        buf.extend_from_slice(b"\0\0\0\x08\0\0\0\0\0");
+        assert!(insert_stream(&mut buf, &upgrade));
+        let mut expected = PREFACE.to_vec();
+        expected.extend_from_slice(b"frame\0\0\0\x08\0\0\0\0\0");
+        assert_eq!(&buf[..], &expected[..]);
+
+        let mut buf = BytesVec::new();
+        buf.extend_from_slice(b"GET / HTTP/1.1\r\n\r\n\r\n\r\n");
+        assert!(insert_stream(&mut buf, &upgrade));
+        assert_eq!(&buf[..], b"GET / HTTP/1.1\r\n\r\n\r\n\r\n");
+    }
+}
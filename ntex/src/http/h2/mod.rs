@@ -1,4 +1,5 @@
 //! HTTP/2 implementation
+pub(super) mod h2c;
 pub(super) mod payload;
 mod service;
 
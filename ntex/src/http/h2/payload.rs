@@ -4,8 +4,9 @@ use std::{cell::RefCell, collections::VecDeque, pin::Pin, rc::Rc, rc::Weak};
 
 use ntex_h2::{self as h2};
 
+use crate::http::{error::PayloadError, header::HeaderMap};
+use crate::task::LocalWaker;
 use crate::util::{poll_fn, Bytes, Stream};
-use crate::{http::error::PayloadError, task::LocalWaker};
 
 /// Buffered stream of byte chunks
 ///
@@ -39,6 +40,24 @@ impl Payload {
         )
     }
 
+    /// Set max size of the payload.
+    ///
+    /// If payload exceeds limit, buffered data is dropped, http/2 stream
+    /// gets reset and payload yields `PayloadError::Overflow` error.
+    /// Limit could only be decreased, smallest limit applies.
+    #[inline]
+    pub fn set_limit(&self, limit: u64) {
+        self.inner.borrow_mut().set_limit(limit);
+    }
+
+    /// Trailer headers of the payload.
+    ///
+    /// Trailers are available after payload is fully read.
+    #[inline]
+    pub fn trailers(&self) -> Option<HeaderMap> {
+        self.inner.borrow().trailers.clone()
+    }
+
     #[inline]
     pub async fn read(&self) -> Option<Result<Bytes, PayloadError>> {
         poll_fn(|cx| self.poll_read(cx)).await
@@ -91,6 +110,14 @@ impl PayloadSender {
         }
     }
 
+    /// Feed trailer headers and eof
+    pub fn feed_trailers(&mut self, trailers: HeaderMap) {
+        if let Some(shared) = self.inner.upgrade() {
+            shared.borrow_mut().trailers = Some(trailers);
+        }
+        self.feed_eof(Bytes::new());
+    }
+
     pub fn feed_data(&mut self, data: Bytes, cap: h2::Capacity) {
         if let Some(shared) = self.inner.upgrade() {
             shared.borrow_mut().feed_data(data, cap)
@@ -107,9 +134,13 @@ impl PayloadSender {
 #[derive(Debug)]
 struct Inner {
     eof: bool,
+    limit: u64,
+    total: u64,
+    overflow: bool,
     cap: h2::Capacity,
     err: Option<PayloadError>,
     items: VecDeque<Bytes>,
+    trailers: Option<HeaderMap>,
     task: LocalWaker,
     io_task: LocalWaker,
     stream: Option<h2::Stream>,
@@ -120,20 +151,61 @@ impl Inner {
         Inner {
             cap,
             eof: false,
+            limit: u64::MAX,
+            total: 0,
+            overflow: false,
             err: None,
             stream: None,
             items: VecDeque::new(),
+            trailers: None,
             task: LocalWaker::new(),
             io_task: LocalWaker::new(),
         }
     }
 
     fn set_error(&mut self, err: PayloadError) {
-        self.err = Some(err);
+        if !self.overflow {
+            self.err = Some(err);
+            self.task.wake()
+        }
+    }
+
+    fn set_limit(&mut self, limit: u64) {
+        if limit < self.limit {
+            self.limit = limit;
+            if self.total > limit {
+                self.set_overflow();
+            }
+        }
+    }
+
+    fn set_overflow(&mut self) {
+        self.overflow = true;
+        self.eof = true;
+        self.err = Some(PayloadError::Overflow);
+
+        // reset stream so peer stops sending data,
+        // otherwise release capacity of dropped data
+        if let Some(ref stream) = self.stream {
+            stream.reset(h2::frame::Reason::CANCEL);
+            self.items.clear();
+        } else {
+            while let Some(data) = self.items.pop_front() {
+                self.cap.consume(data.len() as u32);
+            }
+        }
         self.task.wake()
     }
 
     fn feed_eof(&mut self, data: Bytes) {
+        if self.overflow {
+            return;
+        }
+        self.total += data.len() as u64;
+        if self.total > self.limit {
+            self.set_overflow();
+            return;
+        }
         self.eof = true;
         if !data.is_empty() {
             self.items.push_back(data);
@@ -143,8 +215,19 @@ impl Inner {
 
     fn feed_data(&mut self, data: Bytes, cap: h2::Capacity) {
         self.cap += cap;
+        if self.overflow {
+            if self.stream.is_none() {
+                self.cap.consume(data.len() as u32);
+            }
+            return;
+        }
+        self.total += data.len() as u64;
         self.items.push_back(data);
-        self.task.wake();
+        if self.total > self.limit {
+            self.set_overflow();
+        } else {
+            self.task.wake();
+        }
     }
 
     fn readany(
@@ -1,5 +1,5 @@
 use std::{cell::RefCell, io, task::Context, task::Poll};
-use std::{marker::PhantomData, mem, rc::Rc};
+use std::{marker::PhantomData, mem, rc::Rc, time::Instant};
 
 use ntex_h2::{self as h2, frame::StreamId, server};
 
@@ -72,7 +72,7 @@ mod openssl {
             Acceptor::new(acceptor)
                 .timeout(self.cfg.ssl_handshake_timeout)
                 .chain()
-                .map_err(SslError::Ssl)
+                .map_err(SslError::handshake)
                 .map_init_err(|_| panic!())
                 .and_then(self.chain().map_err(SslError::Service))
         }
@@ -111,7 +111,7 @@ mod rustls {
             Acceptor::from(config)
                 .timeout(self.cfg.ssl_handshake_timeout)
                 .chain()
-                .map_err(|e| SslError::Ssl(Box::new(e)))
+                .map_err(SslError::handshake)
                 .map_init_err(|_| panic!())
                 .and_then(self.chain().map_err(SslError::Service))
         }
@@ -289,6 +289,7 @@ where
                 let pl = if !eof {
                     log::debug!("Creating local payload stream for {:?}", stream.id());
                     let (sender, payload) = Payload::create(stream.empty_capacity());
+                    sender.set_stream(Some(stream.clone()));
                     self.streams.borrow_mut().insert(stream.id(), sender);
                     Some(payload)
                 } else {
@@ -312,8 +313,8 @@ where
                         h2::StreamEof::Data(data) => {
                             sender.feed_eof(data);
                         }
-                        h2::StreamEof::Trailers(_) => {
-                            sender.feed_eof(Bytes::new());
+                        h2::StreamEof::Trailers(trailers) => {
+                            sender.feed_trailers(trailers);
                         }
                         h2::StreamEof::Error(err) => sender.set_error(err.into()),
                     }
@@ -332,6 +333,7 @@ where
         let cfg = self.config.clone();
 
         Either::Left(Box::pin(async move {
+            let started = Instant::now();
             log::trace!(
                 "{:?} got request (eof: {}): {:#?}\nheaders: {:#?}",
                 stream.id(),
@@ -339,7 +341,11 @@ where
                 pseudo,
                 headers
             );
+            let too_large = payload.is_some() && cfg.payload_too_large(&headers);
             let mut req = if let Some(pl) = payload {
+                if let Some(limit) = cfg.payload_limit {
+                    pl.set_limit(limit);
+                }
                 Request::with_payload(crate::http::Payload::H2(pl))
             } else {
                 Request::new()
@@ -361,17 +367,24 @@ where
             head.headers = headers;
             head.io = CurrentIo::Ref(io);
 
-            let (mut res, mut body) = match cfg.service.call(req).await {
-                Ok(res) => res.into().into_parts(),
-                Err(err) => {
-                    let (res, body) = Response::from(&err).into_parts();
-                    (res, body.into_body())
+            let (mut res, mut body) = if too_large {
+                log::trace!("{:?} request payload is too large", stream.id());
+                let (res, body) = Response::PayloadTooLarge().finish().into_parts();
+                (res, body.into_body())
+            } else {
+                match cfg.service.call(req).await {
+                    Ok(res) => res.into().into_parts(),
+                    Err(err) => {
+                        let (res, body) = Response::from(&err).into_parts();
+                        (res, body.into_body())
+                    }
                 }
             };
 
             let head = res.head_mut();
             let mut size = body.size();
             prepare_response(&cfg.timer, head, &mut size);
+            crate::server::http_response(head.status, started.elapsed());
 
             log::debug!("Received service response: {:?} payload: {:?}", head, size);
 
@@ -385,7 +398,11 @@ where
                     match poll_fn(|cx| body.poll_next_chunk(cx)).await {
                         None => {
                             log::debug!("{:?} closing payload stream", stream.id());
-                            stream.send_payload(Bytes::new(), true).await?;
+                            if let Some(trailers) = body.trailers() {
+                                stream.send_trailers(trailers);
+                            } else {
+                                stream.send_payload(Bytes::new(), true).await?;
+                            }
                             break;
                         }
                         Some(Ok(chunk)) => {
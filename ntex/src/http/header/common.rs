@@ -0,0 +1,804 @@
+//! Typed representations of the common RFC 9110 headers
+use std::{fmt, time::Duration, time::SystemTime};
+
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use mime::Mime;
+
+use super::typed::{encode_date, encode_list, list, parse_date, single};
+use super::typed::{ByteRangeSpec, EntityTag, QualityItem, TypedHeader};
+use super::{self as header, HeaderName, HeaderValue, InvalidHeaderValue};
+use crate::http::Method;
+
+/// Header with single value that implements `FromStr` and `Display`
+macro_rules! single_header {
+    ($(#[$doc:meta])* $name:ident, $header:ident, $ty:ty) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name(pub $ty);
+
+        impl TypedHeader for $name {
+            fn name() -> HeaderName {
+                header::$header
+            }
+
+            fn decode<'a, I>(values: I) -> Option<Self>
+            where
+                I: Iterator<Item = &'a HeaderValue>,
+            {
+                single(values).and_then(|val| val.parse().ok()).map($name)
+            }
+
+            fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+                HeaderValue::try_from(self.0.to_string())
+            }
+        }
+    };
+}
+
+/// Comma separated list header
+macro_rules! list_header {
+    ($(#[$doc:meta])* $name:ident, $header:ident, $ty:ty) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name(pub Vec<$ty>);
+
+        impl TypedHeader for $name {
+            fn name() -> HeaderName {
+                header::$header
+            }
+
+            fn decode<'a, I>(values: I) -> Option<Self>
+            where
+                I: Iterator<Item = &'a HeaderValue>,
+            {
+                list(values).map($name)
+            }
+
+            fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+                encode_list(&self.0)
+            }
+        }
+    };
+}
+
+/// Http-date header
+macro_rules! date_header {
+    ($(#[$doc:meta])* $name:ident, $header:ident) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub struct $name(pub SystemTime);
+
+        impl TypedHeader for $name {
+            fn name() -> HeaderName {
+                header::$header
+            }
+
+            fn decode<'a, I>(values: I) -> Option<Self>
+            where
+                I: Iterator<Item = &'a HeaderValue>,
+            {
+                single(values).and_then(parse_date).map($name)
+            }
+
+            fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+                Ok(encode_date(&self.0))
+            }
+        }
+    };
+}
+
+/// Entity tags list header, `*` or list of tags
+macro_rules! etag_list_header {
+    ($(#[$doc:meta])* $name:ident, $header:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub enum $name {
+            /// Any entity, `*`
+            Any,
+            /// List of entity tags
+            Tags(Vec<EntityTag>),
+        }
+
+        impl TypedHeader for $name {
+            fn name() -> HeaderName {
+                header::$header
+            }
+
+            fn decode<'a, I>(values: I) -> Option<Self>
+            where
+                I: Iterator<Item = &'a HeaderValue>,
+            {
+                let mut values = values.peekable();
+                if values.peek().map(|v| v.as_bytes() == b"*").unwrap_or(false) {
+                    values.next();
+                    if values.next().is_none() {
+                        Some($name::Any)
+                    } else {
+                        None
+                    }
+                } else {
+                    list(values).map($name::Tags)
+                }
+            }
+
+            fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+                match self {
+                    $name::Any => Ok(HeaderValue::from_static("*")),
+                    $name::Tags(tags) => encode_list(tags),
+                }
+            }
+        }
+    };
+}
+
+list_header!(
+    /// `Accept` header, list of acceptable media types
+    Accept,
+    ACCEPT,
+    QualityItem<Mime>
+);
+
+list_header!(
+    /// `Accept-Charset` header, list of acceptable charsets
+    AcceptCharset,
+    ACCEPT_CHARSET,
+    QualityItem<String>
+);
+
+list_header!(
+    /// `Accept-Encoding` header, list of acceptable content codings
+    AcceptEncoding,
+    ACCEPT_ENCODING,
+    QualityItem<String>
+);
+
+list_header!(
+    /// `Accept-Language` header, list of preferred languages
+    AcceptLanguage,
+    ACCEPT_LANGUAGE,
+    QualityItem<String>
+);
+
+list_header!(
+    /// `Accept-Ranges` header, list of supported range units
+    AcceptRanges,
+    ACCEPT_RANGES,
+    String
+);
+
+list_header!(
+    /// `Allow` header, list of supported methods
+    Allow,
+    ALLOW,
+    Method
+);
+
+list_header!(
+    /// `Content-Language` header
+    ContentLanguage,
+    CONTENT_LANGUAGE,
+    String
+);
+
+list_header!(
+    /// `Vary` header, list of header names or `*`
+    Vary,
+    VARY,
+    HeaderName
+);
+
+single_header!(
+    /// `Age` header, age of the response in seconds
+    Age,
+    AGE,
+    u64
+);
+
+single_header!(
+    /// `Content-Length` header
+    ContentLength,
+    CONTENT_LENGTH,
+    u64
+);
+
+single_header!(
+    /// `Content-Type` header
+    ContentType,
+    CONTENT_TYPE,
+    Mime
+);
+
+single_header!(
+    /// `ETag` header
+    ETag,
+    ETAG,
+    EntityTag
+);
+
+single_header!(
+    /// `Host` header
+    Host,
+    HOST,
+    String
+);
+
+single_header!(
+    /// `Location` header
+    Location,
+    LOCATION,
+    String
+);
+
+single_header!(
+    /// `Referer` header
+    Referer,
+    REFERER,
+    String
+);
+
+single_header!(
+    /// `Server` header
+    Server,
+    SERVER,
+    String
+);
+
+single_header!(
+    /// `User-Agent` header
+    UserAgent,
+    USER_AGENT,
+    String
+);
+
+date_header!(
+    /// `Date` header
+    Date,
+    DATE
+);
+
+date_header!(
+    /// `Expires` header
+    Expires,
+    EXPIRES
+);
+
+date_header!(
+    /// `If-Modified-Since` header
+    IfModifiedSince,
+    IF_MODIFIED_SINCE
+);
+
+date_header!(
+    /// `If-Unmodified-Since` header
+    IfUnmodifiedSince,
+    IF_UNMODIFIED_SINCE
+);
+
+date_header!(
+    /// `Last-Modified` header
+    LastModified,
+    LAST_MODIFIED
+);
+
+etag_list_header!(
+    /// `If-Match` header
+    IfMatch,
+    IF_MATCH
+);
+
+etag_list_header!(
+    /// `If-None-Match` header
+    IfNoneMatch,
+    IF_NONE_MATCH
+);
+
+impl ContentType {
+    /// `application/json` content type
+    pub fn json() -> Self {
+        ContentType(mime::APPLICATION_JSON)
+    }
+
+    /// `text/html; charset=utf-8` content type
+    pub fn html() -> Self {
+        ContentType(mime::TEXT_HTML_UTF_8)
+    }
+
+    /// `text/plain; charset=utf-8` content type
+    pub fn text() -> Self {
+        ContentType(mime::TEXT_PLAIN_UTF_8)
+    }
+
+    /// `application/octet-stream` content type
+    pub fn octet_stream() -> Self {
+        ContentType(mime::APPLICATION_OCTET_STREAM)
+    }
+}
+
+impl IfMatch {
+    /// Check if header matches entity tag, uses strong comparison
+    pub fn matches(&self, tag: &EntityTag) -> bool {
+        match self {
+            IfMatch::Any => true,
+            IfMatch::Tags(tags) => tags.iter().any(|t| t.strong_eq(tag)),
+        }
+    }
+}
+
+impl IfNoneMatch {
+    /// Check if header matches entity tag, uses weak comparison
+    pub fn matches(&self, tag: &EntityTag) -> bool {
+        match self {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Tags(tags) => tags.iter().any(|t| t.weak_eq(tag)),
+        }
+    }
+}
+
+/// `Authorization` header
+#[derive(Clone, PartialEq, Eq)]
+pub enum Authorization {
+    /// Basic authentication scheme
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    /// Bearer token
+    Bearer(String),
+    /// Other authentication scheme, scheme name and credentials
+    Other(String, String),
+}
+
+impl Authorization {
+    /// Create basic authorization
+    pub fn basic<U: Into<String>>(username: U, password: Option<&str>) -> Self {
+        Authorization::Basic {
+            username: username.into(),
+            password: password.map(|p| p.to_string()),
+        }
+    }
+
+    /// Create bearer authorization
+    pub fn bearer<T: Into<String>>(token: T) -> Self {
+        Authorization::Bearer(token.into())
+    }
+}
+
+impl fmt::Debug for Authorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // do not leak credentials to logs
+        match self {
+            Authorization::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Authorization::Bearer(_) => f.write_str("Bearer(..)"),
+            Authorization::Other(scheme, _) => write!(f, "Other({:?}, ..)", scheme),
+        }
+    }
+}
+
+impl TypedHeader for Authorization {
+    fn name() -> HeaderName {
+        header::AUTHORIZATION
+    }
+
+    fn decode<'a, I>(values: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a HeaderValue>,
+    {
+        let val = single(values)?;
+        let (scheme, credentials) = val.split_once(' ')?;
+        let credentials = credentials.trim();
+        if credentials.is_empty() {
+            return None;
+        }
+
+        if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = String::from_utf8(base64.decode(credentials).ok()?).ok()?;
+            Some(match decoded.split_once(':') {
+                Some((username, password)) => {
+                    Authorization::basic(username, Some(password))
+                }
+                None => Authorization::basic(decoded, None),
+            })
+        } else if scheme.eq_ignore_ascii_case("bearer") {
+            Some(Authorization::bearer(credentials))
+        } else {
+            Some(Authorization::Other(
+                scheme.to_string(),
+                credentials.to_string(),
+            ))
+        }
+    }
+
+    fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        let s = match self {
+            Authorization::Basic { username, password } => {
+                let credentials = if let Some(password) = password {
+                    format!("{}:{}", username, password)
+                } else {
+                    username.clone()
+                };
+                format!("Basic {}", base64.encode(credentials))
+            }
+            Authorization::Bearer(token) => format!("Bearer {}", token),
+            Authorization::Other(scheme, credentials) => {
+                format!("{} {}", scheme, credentials)
+            }
+        };
+        let mut val = HeaderValue::try_from(s)?;
+        val.set_sensitive(true);
+        Ok(val)
+    }
+}
+
+/// `If-Range` header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IfRange {
+    /// Entity tag of the representation
+    ETag(EntityTag),
+    /// Last modification date of the representation
+    Date(SystemTime),
+}
+
+impl TypedHeader for IfRange {
+    fn name() -> HeaderName {
+        header::IF_RANGE
+    }
+
+    fn decode<'a, I>(values: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a HeaderValue>,
+    {
+        let val = single(values)?;
+        if let Ok(tag) = val.parse() {
+            Some(IfRange::ETag(tag))
+        } else {
+            parse_date(val).map(IfRange::Date)
+        }
+    }
+
+    fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        match self {
+            IfRange::ETag(tag) => HeaderValue::try_from(tag.to_string()),
+            IfRange::Date(date) => Ok(encode_date(date)),
+        }
+    }
+}
+
+/// `Retry-After` header
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RetryAfter {
+    /// Delay in seconds
+    Delay(Duration),
+    /// Date after which request could be retried
+    Date(SystemTime),
+}
+
+impl TypedHeader for RetryAfter {
+    fn name() -> HeaderName {
+        header::RETRY_AFTER
+    }
+
+    fn decode<'a, I>(values: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a HeaderValue>,
+    {
+        let val = single(values)?;
+        if let Ok(secs) = val.parse() {
+            Some(RetryAfter::Delay(Duration::from_secs(secs)))
+        } else {
+            parse_date(val).map(RetryAfter::Date)
+        }
+    }
+
+    fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        match self {
+            RetryAfter::Delay(delay) => Ok(HeaderValue::from(delay.as_secs())),
+            RetryAfter::Date(date) => Ok(encode_date(date)),
+        }
+    }
+}
+
+/// `Range` header, only `bytes` ranges are supported
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Range(pub Vec<ByteRangeSpec>);
+
+impl TypedHeader for Range {
+    fn name() -> HeaderName {
+        header::RANGE
+    }
+
+    fn decode<'a, I>(values: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a HeaderValue>,
+    {
+        let val = single(values)?;
+        if val.len() < 6 || !val[..6].eq_ignore_ascii_case("bytes=") {
+            return None;
+        }
+        let ranges = val[6..]
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().ok())
+            .collect::<Option<Vec<_>>>()?;
+        if ranges.is_empty() {
+            None
+        } else {
+            Some(Range(ranges))
+        }
+    }
+
+    fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        let ranges = self
+            .0
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        HeaderValue::try_from(format!("bytes={}", ranges))
+    }
+}
+
+/// `Content-Range` header, only `bytes` unit is supported
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ContentRange {
+    /// First and last byte positions, inclusive. `None` for unsatisfied range
+    pub range: Option<(u64, u64)>,
+    /// Complete length of the representation, if known
+    pub complete_length: Option<u64>,
+}
+
+impl TypedHeader for ContentRange {
+    fn name() -> HeaderName {
+        header::CONTENT_RANGE
+    }
+
+    fn decode<'a, I>(values: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a HeaderValue>,
+    {
+        let val = single(values)?;
+        let (unit, rest) = val.split_once(' ')?;
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+        let (range, length) = rest.trim().split_once('/')?;
+        let complete_length = match length {
+            "*" => None,
+            len => Some(len.parse().ok()?),
+        };
+        let range = match range {
+            "*" => None,
+            range => {
+                let (start, end) = range.split_once('-')?;
+                let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                if start > end || complete_length.map(|len| end >= len).unwrap_or(false) {
+                    return None;
+                }
+                Some((start, end))
+            }
+        };
+        if range.is_none() && complete_length.is_none() {
+            None
+        } else {
+            Some(ContentRange {
+                range,
+                complete_length,
+            })
+        }
+    }
+
+    fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        let range = self
+            .range
+            .map(|(start, end)| format!("{}-{}", start, end))
+            .unwrap_or_else(|| "*".to_string());
+        let length = self
+            .complete_length
+            .map(|len| len.to_string())
+            .unwrap_or_else(|| "*".to_string());
+        HeaderValue::try_from(format!("bytes {}/{}", range, length))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::{HeaderMap, TypedHeaderError};
+
+    fn headers(name: HeaderName, values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for val in values {
+            headers.append(name.clone(), HeaderValue::from_static(val));
+        }
+        headers
+    }
+
+    fn decode<T: TypedHeader>(values: &[&'static str]) -> Option<T> {
+        T::from_headers(&headers(T::name(), values)).ok().flatten()
+    }
+
+    fn encode<T: TypedHeader>(hdr: T) -> String {
+        hdr.encode().unwrap().to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let hdrs = HeaderMap::new();
+        assert_eq!(ContentLength::from_headers(&hdrs), Ok(None));
+
+        let hdrs = headers(header::CONTENT_LENGTH, &["abc"]);
+        assert_eq!(
+            ContentLength::from_headers(&hdrs),
+            Err(TypedHeaderError::Invalid(header::CONTENT_LENGTH))
+        );
+        let hdrs = headers(header::CONTENT_LENGTH, &["10", "11"]);
+        assert!(ContentLength::from_headers(&hdrs).is_err());
+    }
+
+    #[test]
+    fn test_list_headers() {
+        let accept =
+            decode::<Accept>(&["text/html, application/json;q=0.9", "*/*;q=0.1"]).unwrap();
+        assert_eq!(accept.0.len(), 3);
+        assert_eq!(accept.0[0], QualityItem::max(mime::TEXT_HTML));
+        assert_eq!(accept.0[1], QualityItem::new(mime::APPLICATION_JSON, 900));
+        assert_eq!(accept.0[2].quality, 100);
+        assert_eq!(
+            encode(accept),
+            "text/html, application/json; q=0.9, */*; q=0.1"
+        );
+        assert!(decode::<Accept>(&["text"]).is_none());
+
+        let enc = decode::<AcceptEncoding>(&["gzip, br;q=0.5"]).unwrap();
+        assert_eq!(enc.0[1], QualityItem::new("br".to_string(), 500));
+
+        let allow = decode::<Allow>(&["GET, HEAD"]).unwrap();
+        assert_eq!(allow, Allow(vec![Method::GET, Method::HEAD]));
+        assert_eq!(encode(allow), "GET, HEAD");
+
+        let vary = decode::<Vary>(&["Origin, Accept-Encoding"]).unwrap();
+        assert_eq!(vary.0, vec![header::ORIGIN, header::ACCEPT_ENCODING]);
+        assert_eq!(encode(vary), "origin, accept-encoding");
+        assert_eq!(decode::<AcceptLanguage>(&[""]).unwrap().0, vec![]);
+    }
+
+    #[test]
+    fn test_single_headers() {
+        assert_eq!(decode::<ContentLength>(&["10"]), Some(ContentLength(10)));
+        assert_eq!(encode(ContentLength(10)), "10");
+        assert_eq!(decode::<Age>(&["-1"]), None);
+
+        assert_eq!(
+            decode::<ContentType>(&["application/json"]),
+            Some(ContentType::json())
+        );
+        assert_eq!(encode(ContentType::html()), "text/html; charset=utf-8");
+        assert_eq!(encode(ContentType::text()), "text/plain; charset=utf-8");
+        assert_eq!(
+            encode(ContentType::octet_stream()),
+            "application/octet-stream"
+        );
+
+        assert_eq!(
+            decode::<ETag>(&["W/\"abc\""]),
+            Some(ETag(EntityTag::weak("abc")))
+        );
+        assert_eq!(encode(ETag(EntityTag::strong("abc"))), "\"abc\"");
+        assert_eq!(
+            decode::<UserAgent>(&["ntex"]),
+            Some(UserAgent("ntex".to_string()))
+        );
+        assert!(UserAgent("a\nb".to_string()).encode().is_err());
+    }
+
+    #[test]
+    fn test_date_headers() {
+        let s = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let date = decode::<LastModified>(&[s]).unwrap();
+        assert_eq!(encode(date), s);
+        assert_eq!(encode(IfModifiedSince(date.0)), s);
+        assert!(decode::<Date>(&["yesterday"]).is_none());
+    }
+
+    #[test]
+    fn test_etag_list_headers() {
+        assert_eq!(decode::<IfMatch>(&["*"]), Some(IfMatch::Any));
+        assert!(decode::<IfMatch>(&["*", "\"a\""]).is_none());
+        assert_eq!(encode(IfNoneMatch::Any), "*");
+
+        let tag = EntityTag::strong("a");
+        let hdr = decode::<IfNoneMatch>(&["\"b\", W/\"a\""]).unwrap();
+        assert!(hdr.matches(&tag));
+        assert_eq!(encode(hdr), "\"b\", W/\"a\"");
+
+        let hdr = decode::<IfMatch>(&["\"b\", W/\"a\""]).unwrap();
+        assert!(!hdr.matches(&tag));
+        assert!(decode::<IfMatch>(&["\"a\""]).unwrap().matches(&tag));
+        assert!(IfMatch::Any.matches(&tag));
+    }
+
+    #[test]
+    fn test_authorization() {
+        let auth = decode::<Authorization>(&["Basic dXNlcjpwYXNz"]).unwrap();
+        assert_eq!(auth, Authorization::basic("user", Some("pass")));
+        assert!(!format!("{:?}", auth).contains("pass"));
+        assert_eq!(encode(auth), "Basic dXNlcjpwYXNz");
+        assert!(Authorization::bearer("t").encode().unwrap().is_sensitive());
+
+        let auth = decode::<Authorization>(&["Basic dXNlcg=="]).unwrap();
+        assert_eq!(auth, Authorization::basic("user", None));
+        assert_eq!(encode(auth), "Basic dXNlcg==");
+
+        let auth = decode::<Authorization>(&["bearer token"]).unwrap();
+        assert_eq!(auth, Authorization::bearer("token"));
+        assert_eq!(encode(auth), "Bearer token");
+        assert_eq!(format!("{:?}", Authorization::bearer("t")), "Bearer(..)");
+
+        let auth = decode::<Authorization>(&["Digest abc"]).unwrap();
+        assert_eq!(auth, Authorization::Other("Digest".into(), "abc".into()));
+        assert_eq!(encode(auth), "Digest abc");
+
+        assert!(decode::<Authorization>(&["Basic"]).is_none());
+        assert!(decode::<Authorization>(&["Basic !!!"]).is_none());
+    }
+
+    #[test]
+    fn test_if_range() {
+        assert_eq!(
+            decode::<IfRange>(&["\"a\""]),
+            Some(IfRange::ETag(EntityTag::strong("a")))
+        );
+        let s = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let hdr = decode::<IfRange>(&[s]).unwrap();
+        assert!(matches!(hdr, IfRange::Date(_)));
+        assert_eq!(encode(hdr), s);
+        assert!(decode::<IfRange>(&["abc"]).is_none());
+    }
+
+    #[test]
+    fn test_retry_after() {
+        assert_eq!(
+            decode::<RetryAfter>(&["120"]),
+            Some(RetryAfter::Delay(Duration::from_secs(120)))
+        );
+        assert_eq!(encode(RetryAfter::Delay(Duration::from_secs(120))), "120");
+        let s = "Sun, 06 Nov 1994 08:49:37 GMT";
+        assert_eq!(encode(decode::<RetryAfter>(&[s]).unwrap()), s);
+    }
+
+    #[test]
+    fn test_range() {
+        let range = decode::<Range>(&["bytes=0-499, -500"]).unwrap();
+        assert_eq!(
+            range.0,
+            vec![ByteRangeSpec::FromTo(0, 499), ByteRangeSpec::Last(500)]
+        );
+        assert_eq!(encode(range), "bytes=0-499,-500");
+        assert!(decode::<Range>(&["items=0-1"]).is_none());
+        assert!(decode::<Range>(&["bytes="]).is_none());
+        assert!(decode::<Range>(&["bytes=5-1"]).is_none());
+    }
+
+    #[test]
+    fn test_content_range() {
+        let hdr = decode::<ContentRange>(&["bytes 0-499/1234"]).unwrap();
+        assert_eq!(hdr.range, Some((0, 499)));
+        assert_eq!(hdr.complete_length, Some(1234));
+        assert_eq!(encode(hdr), "bytes 0-499/1234");
+
+        let hdr = decode::<ContentRange>(&["bytes */1234"]).unwrap();
+        assert_eq!(hdr.range, None);
+        assert_eq!(encode(hdr), "bytes */1234");
+
+        let hdr = decode::<ContentRange>(&["bytes 0-1/*"]).unwrap();
+        assert_eq!(hdr.complete_length, None);
+        assert_eq!(encode(hdr), "bytes 0-1/*");
+
+        assert!(decode::<ContentRange>(&["bytes */*"]).is_none());
+        assert!(decode::<ContentRange>(&["bytes 0-10/5"]).is_none());
+        assert!(decode::<ContentRange>(&["items 0-1/5"]).is_none());
+    }
+}
@@ -7,6 +7,19 @@ pub use ntex_http::header::*;
 pub use ntex_http::header::{AsName, GetAll, Value};
 pub use ntex_http::HeaderMap;
 
+mod common;
+mod typed;
+
+pub use self::common::{
+    Accept, AcceptCharset, AcceptEncoding, AcceptLanguage, AcceptRanges, Age, Allow,
+    Authorization, ContentLanguage, ContentLength, ContentRange, ContentType, Date, ETag,
+    Expires, Host, IfMatch, IfModifiedSince, IfNoneMatch, IfRange, IfUnmodifiedSince,
+    LastModified, Location, Range, Referer, RetryAfter, Server, UserAgent, Vary,
+};
+pub use self::typed::{
+    ByteRangeSpec, EntityTag, QualityItem, TypedHeader, TypedHeaderError,
+};
+
 /// Represents supported types of content encodings
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum ContentEncoding {
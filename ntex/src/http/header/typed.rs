@@ -0,0 +1,421 @@
+use std::{fmt, str::FromStr, time::SystemTime};
+
+use super::{HeaderMap, HeaderName, HeaderValue, InvalidHeaderValue};
+
+/// Typed representation of the http header.
+///
+/// ```rust
+/// use ntex::http::header::{ContentLength, HeaderMap, TypedHeader};
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert(ContentLength::name(), ContentLength(10).encode().unwrap());
+/// assert_eq!(ContentLength::from_headers(&headers), Ok(Some(ContentLength(10))));
+/// ```
+pub trait TypedHeader: Sized {
+    /// Header name
+    fn name() -> HeaderName;
+
+    /// Parse header from all its values.
+    ///
+    /// Returns `None` if header values are malformed.
+    fn decode<'a, I>(values: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a HeaderValue>;
+
+    /// Encode header to the header value
+    fn encode(&self) -> Result<HeaderValue, InvalidHeaderValue>;
+
+    /// Get header from the header map.
+    ///
+    /// Returns `Ok(None)` if header is missing.
+    fn from_headers(headers: &HeaderMap) -> Result<Option<Self>, TypedHeaderError> {
+        let name = Self::name();
+        let mut values = headers.get_all(&name).peekable();
+        if values.peek().is_none() {
+            Ok(None)
+        } else {
+            Self::decode(values)
+                .map(Some)
+                .ok_or(TypedHeaderError::Invalid(name))
+        }
+    }
+}
+
+/// Errors which can occur during typed header extraction
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TypedHeaderError {
+    /// Header is missing
+    #[error("Header {0} is missing")]
+    Missing(HeaderName),
+    /// Header value is malformed
+    #[error("Header {0} is malformed")]
+    Invalid(HeaderName),
+}
+
+/// Value with quality parameter, i.e. `text/html;q=0.8`.
+///
+/// Quality is stored in thousandths, `1000` is the default quality.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QualityItem<T> {
+    /// Item value
+    pub item: T,
+    /// Item quality, from 0 to 1000
+    pub quality: u16,
+}
+
+impl<T> QualityItem<T> {
+    /// Create item with the specified quality
+    pub fn new(item: T, quality: u16) -> Self {
+        QualityItem {
+            item,
+            quality: quality.min(1000),
+        }
+    }
+
+    /// Create item with default quality
+    pub fn max(item: T) -> Self {
+        QualityItem {
+            item,
+            quality: 1000,
+        }
+    }
+}
+
+impl<T: FromStr> FromStr for QualityItem<T> {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut quality = 1000;
+        let mut item = String::with_capacity(s.len());
+        for (idx, part) in s.split(';').enumerate() {
+            let part = part.trim();
+            if idx > 0 {
+                if let Some((name, val)) = part.split_once('=') {
+                    if name.trim().eq_ignore_ascii_case("q") {
+                        quality = parse_quality(val.trim()).ok_or(())?;
+                        continue;
+                    }
+                }
+                item.push(';');
+            }
+            item.push_str(part);
+        }
+        let item = item.parse().map_err(|_| ())?;
+        Ok(QualityItem { item, quality })
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for QualityItem<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.item.fmt(f)?;
+        match self.quality {
+            1000 => Ok(()),
+            0 => f.write_str("; q=0"),
+            q => {
+                let s = format!("{:03}", q);
+                write!(f, "; q=0.{}", s.trim_end_matches('0'))
+            }
+        }
+    }
+}
+
+/// Parse quality value, `0`, `0.5`, `1.000` etc
+fn parse_quality(s: &str) -> Option<u16> {
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let frac = format!("{:0<3}", frac).parse::<u16>().ok()?;
+    match int {
+        "0" => Some(frac),
+        "1" if frac == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+/// Entity tag, value of `ETag` header.
+///
+/// Strong comparison requires both tags to be strong and tag values
+/// to be equal, weak comparison only compares tag values.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EntityTag {
+    weak: bool,
+    tag: String,
+}
+
+impl EntityTag {
+    /// Create new entity tag.
+    ///
+    /// Panics if tag contains invalid characters.
+    pub fn new<T: Into<String>>(weak: bool, tag: T) -> Self {
+        let tag = tag.into();
+        assert!(is_etag(&tag), "Invalid entity tag");
+        EntityTag { weak, tag }
+    }
+
+    /// Create new strong entity tag
+    pub fn strong<T: Into<String>>(tag: T) -> Self {
+        EntityTag::new(false, tag)
+    }
+
+    /// Create new weak entity tag
+    pub fn weak<T: Into<String>>(tag: T) -> Self {
+        EntityTag::new(true, tag)
+    }
+
+    /// Tag value, without quotes
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Check if tag is weak
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Strong comparison
+    pub fn strong_eq(&self, other: &EntityTag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison
+    pub fn weak_eq(&self, other: &EntityTag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+fn is_etag(s: &str) -> bool {
+    s.bytes()
+        .all(|b| b == 0x21 || (0x23..0x7f).contains(&b) || b >= 0x80)
+}
+
+impl FromStr for EntityTag {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (weak, s) = if let Some(s) = s.strip_prefix("W/") {
+            (true, s)
+        } else {
+            (false, s)
+        };
+        match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            Some(tag) if is_etag(tag) => Ok(EntityTag {
+                weak,
+                tag: tag.to_string(),
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for EntityTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            write!(f, "W/\"{}\"", self.tag)
+        } else {
+            write!(f, "\"{}\"", self.tag)
+        }
+    }
+}
+
+/// Byte range specification of the `Range` header
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteRangeSpec {
+    /// Range from first to last byte, inclusive
+    FromTo(u64, u64),
+    /// Range from the byte to the end of the resource
+    From(u64),
+    /// Last N bytes of the resource
+    Last(u64),
+}
+
+impl ByteRangeSpec {
+    /// Resolve range for resource of the `size`.
+    ///
+    /// Returns first and last byte positions, inclusive, or `None` if
+    /// range is not satisfiable.
+    pub fn to_satisfiable_range(&self, size: u64) -> Option<(u64, u64)> {
+        match *self {
+            ByteRangeSpec::FromTo(start, end) if start < size && start <= end => {
+                Some((start, end.min(size - 1)))
+            }
+            ByteRangeSpec::From(start) if start < size => Some((start, size - 1)),
+            ByteRangeSpec::Last(len) if len > 0 && size > 0 => {
+                Some((size - len.min(size), size - 1))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ByteRangeSpec {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.trim().split_once('-').ok_or(())?;
+        let (start, end) = (start.trim(), end.trim());
+        if start.is_empty() {
+            end.parse().map(ByteRangeSpec::Last).map_err(|_| ())
+        } else {
+            let start = start.parse().map_err(|_| ())?;
+            if end.is_empty() {
+                Ok(ByteRangeSpec::From(start))
+            } else {
+                let end = end.parse().map_err(|_| ())?;
+                if start <= end {
+                    Ok(ByteRangeSpec::FromTo(start, end))
+                } else {
+                    Err(())
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ByteRangeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ByteRangeSpec::FromTo(start, end) => write!(f, "{}-{}", start, end),
+            ByteRangeSpec::From(start) => write!(f, "{}-", start),
+            ByteRangeSpec::Last(len) => write!(f, "-{}", len),
+        }
+    }
+}
+
+/// Single header value as str
+pub(super) fn single<'a, I>(mut values: I) -> Option<&'a str>
+where
+    I: Iterator<Item = &'a HeaderValue>,
+{
+    let val = values.next()?.to_str().ok()?.trim();
+    if values.next().is_none() {
+        Some(val)
+    } else {
+        None
+    }
+}
+
+/// Parse comma separated list of items, header could be split to multiple values
+pub(super) fn list<'a, I, T>(values: I) -> Option<Vec<T>>
+where
+    I: Iterator<Item = &'a HeaderValue>,
+    T: FromStr,
+{
+    let mut items = Vec::new();
+    for val in values {
+        for item in val.to_str().ok()?.split(',') {
+            let item = item.trim();
+            if !item.is_empty() {
+                items.push(item.parse().ok()?);
+            }
+        }
+    }
+    Some(items)
+}
+
+/// Encode list of items as comma separated value
+pub(super) fn encode_list<T: fmt::Display>(
+    items: &[T],
+) -> Result<HeaderValue, InvalidHeaderValue> {
+    let s = items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::try_from(s)
+}
+
+pub(super) fn parse_date(s: &str) -> Option<SystemTime> {
+    httpdate::parse_http_date(s).ok()
+}
+
+pub(super) fn encode_date(t: &SystemTime) -> HeaderValue {
+    HeaderValue::try_from(httpdate::fmt_http_date(*t)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quality_item() {
+        let item: QualityItem<String> = "gzip;q=0.5".parse().unwrap();
+        assert_eq!(item, QualityItem::new("gzip".to_string(), 500));
+        assert_eq!(item.to_string(), "gzip; q=0.5");
+
+        let item: QualityItem<String> = "text/html; level=1; q=0.25".parse().unwrap();
+        assert_eq!(item.item, "text/html;level=1");
+        assert_eq!(item.quality, 250);
+        assert_eq!(item.to_string(), "text/html;level=1; q=0.25");
+
+        let item: QualityItem<String> = "br".parse().unwrap();
+        assert_eq!(item, QualityItem::max("br".to_string()));
+        assert_eq!(item.to_string(), "br");
+        assert_eq!(QualityItem::new("a", 0).to_string(), "a; q=0");
+        assert_eq!(QualityItem::new("a", 2000).quality, 1000);
+
+        assert_eq!(parse_quality("1.000"), Some(1000));
+        assert_eq!(parse_quality("0.001"), Some(1));
+        assert_eq!(parse_quality("1.5"), None);
+        assert_eq!(parse_quality("0.1234"), None);
+        assert_eq!(parse_quality("2"), None);
+        assert!("gzip;q=x".parse::<QualityItem<String>>().is_err());
+    }
+
+    #[test]
+    fn test_entity_tag() {
+        let tag: EntityTag = "\"xyzzy\"".parse().unwrap();
+        assert_eq!(tag, EntityTag::strong("xyzzy"));
+        assert_eq!(tag.tag(), "xyzzy");
+        assert!(!tag.is_weak());
+        assert_eq!(tag.to_string(), "\"xyzzy\"");
+
+        let weak: EntityTag = "W/\"xyzzy\"".parse().unwrap();
+        assert!(weak.is_weak());
+        assert_eq!(weak.to_string(), "W/\"xyzzy\"");
+        assert!(tag.strong_eq(&tag));
+        assert!(!tag.strong_eq(&weak));
+        assert!(tag.weak_eq(&weak));
+        assert!(!tag.weak_eq(&EntityTag::weak("other")));
+
+        assert!("xyzzy".parse::<EntityTag>().is_err());
+        assert!("\"xy\"zzy\"".parse::<EntityTag>().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_entity_tag_invalid() {
+        let _ = EntityTag::strong("a\"b");
+    }
+
+    #[test]
+    fn test_byte_range_spec() {
+        assert_eq!("0-499".parse(), Ok(ByteRangeSpec::FromTo(0, 499)));
+        assert_eq!("500-".parse(), Ok(ByteRangeSpec::From(500)));
+        assert_eq!("-500".parse(), Ok(ByteRangeSpec::Last(500)));
+        assert!("500-1".parse::<ByteRangeSpec>().is_err());
+        assert!("a-b".parse::<ByteRangeSpec>().is_err());
+        assert!("".parse::<ByteRangeSpec>().is_err());
+
+        assert_eq!(
+            ByteRangeSpec::FromTo(0, 499).to_satisfiable_range(100),
+            Some((0, 99))
+        );
+        assert_eq!(
+            ByteRangeSpec::From(10).to_satisfiable_range(100),
+            Some((10, 99))
+        );
+        assert_eq!(ByteRangeSpec::From(100).to_satisfiable_range(100), None);
+        assert_eq!(
+            ByteRangeSpec::Last(10).to_satisfiable_range(100),
+            Some((90, 99))
+        );
+        assert_eq!(
+            ByteRangeSpec::Last(200).to_satisfiable_range(100),
+            Some((0, 99))
+        );
+        assert_eq!(ByteRangeSpec::Last(0).to_satisfiable_range(100), None);
+        assert_eq!(ByteRangeSpec::Last(10).to_string(), "-10");
+    }
+}
@@ -9,10 +9,11 @@ pub(crate) mod helpers;
 mod httpcodes;
 mod httpmessage;
 mod message;
-mod payload;
+pub(crate) mod payload;
 mod request;
 mod response;
 mod service;
+pub mod sse;
 
 pub mod error;
 pub mod h1;
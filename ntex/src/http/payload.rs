@@ -1,7 +1,8 @@
 use std::{fmt, mem, pin::Pin, task::Context, task::Poll};
 
+use super::header::{ContentLength, HeaderMap, TypedHeader};
 use super::{error::PayloadError, h1, h2};
-use crate::util::{poll_fn, Bytes, Stream};
+use crate::util::{poll_fn, ready, Bytes, Stream};
 
 /// Type represent boxed payload
 pub type PayloadStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>;
@@ -59,6 +60,40 @@ impl Payload {
         Payload::Stream(Box::pin(stream))
     }
 
+    /// Set max size of the payload.
+    ///
+    /// If payload exceeds limit, payload yields `PayloadError::Overflow` error.
+    /// Limit could only be decreased, smallest limit applies.
+    pub fn set_limit(&mut self, limit: u64) {
+        match self {
+            Payload::None => (),
+            Payload::H1(ref pl) => pl.set_limit(limit),
+            Payload::H2(ref pl) => pl.set_limit(limit),
+            Payload::Stream(_) => {
+                if let Payload::Stream(stream) = self.take() {
+                    *self = Payload::Stream(Box::pin(Limited {
+                        stream,
+                        limit,
+                        total: 0,
+                        overflow: false,
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Trailer headers of the payload.
+    ///
+    /// Trailers are available after payload is fully read. Payload streams
+    /// do not support trailers.
+    pub fn trailers(&self) -> Option<HeaderMap> {
+        match self {
+            Payload::H1(ref pl) => pl.trailers(),
+            Payload::H2(ref pl) => pl.trailers(),
+            Payload::None | Payload::Stream(_) => None,
+        }
+    }
+
     #[inline]
     /// Attempt to pull out the next value of this payload.
     pub async fn recv(&mut self) -> Option<Result<Bytes, PayloadError>> {
@@ -91,10 +126,66 @@ impl Stream for Payload {
     }
 }
 
+/// Check if request's `Content-Length` exceeds the limit.
+///
+/// Missing or malformed header is not checked, such payloads are limited
+/// while they are streamed.
+pub(crate) fn content_length_exceeds(headers: &HeaderMap, limit: u64) -> bool {
+    matches!(ContentLength::from_headers(headers), Ok(Some(ContentLength(len))) if len > limit)
+}
+
+/// Payload stream with size limit
+struct Limited {
+    stream: PayloadStream,
+    limit: u64,
+    total: u64,
+    overflow: bool,
+}
+
+impl Stream for Limited {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.overflow {
+            return Poll::Ready(None);
+        }
+
+        match ready!(self.stream.as_mut().poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                self.total += chunk.len() as u64;
+                if self.total > self.limit {
+                    self.overflow = true;
+                    Poll::Ready(Some(Err(PayloadError::Overflow)))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            item => Poll::Ready(item),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn content_length() {
+        use crate::http::header::{self, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        assert!(!content_length_exceeds(&headers, 10));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("10"));
+        assert!(!content_length_exceeds(&headers, 10));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("11"));
+        assert!(content_length_exceeds(&headers, 10));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("abc"));
+        assert!(!content_length_exceeds(&headers, 10));
+    }
+
     #[test]
     fn payload_debug() {
         assert!(format!("{:?}", Payload::None).contains("Payload::None"));
@@ -106,4 +197,27 @@ mod tests {
         )
         .contains("Payload::Stream"));
     }
+
+    #[crate::rt_test]
+    async fn payload_limit() {
+        let mut pl = Payload::None;
+        pl.set_limit(10);
+        assert!(pl.recv().await.is_none());
+
+        let (mut sender, h1_pl) = h1::Payload::create(false);
+        let mut pl = Payload::H1(h1_pl);
+        pl.set_limit(4);
+        sender.feed_data(Bytes::from("12345"));
+        assert!(matches!(pl.recv().await, Some(Err(PayloadError::Overflow))));
+
+        let (mut sender, h1_pl) = h1::Payload::create(false);
+        let mut pl = Payload::from_stream(h1_pl);
+        pl.set_limit(8);
+        pl.set_limit(4);
+        sender.feed_data(Bytes::from("1234"));
+        assert_eq!(pl.recv().await.unwrap().unwrap(), Bytes::from("1234"));
+        sender.feed_data(Bytes::from("5"));
+        assert!(matches!(pl.recv().await, Some(Err(PayloadError::Overflow))));
+        assert!(pl.recv().await.is_none());
+    }
 }
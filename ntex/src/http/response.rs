@@ -360,6 +360,29 @@ impl ResponseBuilder {
         self
     }
 
+    /// Set a typed header.
+    ///
+    /// ```rust
+    /// use ntex::http::{header::ContentType, Request, Response};
+    ///
+    /// fn index(req: Request) -> Response {
+    ///     Response::Ok()
+    ///         .typed_header(ContentType::json())
+    ///         .finish()
+    /// }
+    /// ```
+    pub fn typed_header<H: header::TypedHeader>(&mut self, hdr: H) -> &mut Self {
+        if let Some(parts) = parts(&mut self.head, &self.err) {
+            match hdr.encode() {
+                Ok(value) => {
+                    parts.headers.insert(H::name(), value);
+                }
+                Err(e) => self.err = Some(log_error(e)),
+            };
+        }
+        self
+    }
+
     /// Set the custom reason for the response.
     #[inline]
     pub fn reason(&mut self, reason: &'static str) -> &mut Self {
@@ -914,6 +937,22 @@ mod tests {
         assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/plain")
     }
 
+    #[test]
+    fn test_typed_header() {
+        let resp = Response::build(StatusCode::OK)
+            .typed_header(header::ContentType::json())
+            .finish();
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let resp = Response::build(StatusCode::OK)
+            .typed_header(header::Location("/\n".to_string()))
+            .finish();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     #[test]
     fn test_json() {
         let resp = Response::build(StatusCode::OK).json(&vec!["v1", "v2", "v3"]);
@@ -176,7 +176,7 @@ mod openssl {
             Acceptor::new(acceptor)
                 .timeout(self.cfg.ssl_handshake_timeout)
                 .chain()
-                .map_err(SslError::Ssl)
+                .map_err(SslError::handshake)
                 .map_init_err(|_| panic!())
                 .and_then(self.chain().map_err(SslError::Service))
         }
@@ -223,7 +223,7 @@ mod rustls {
             Acceptor::from(config)
                 .timeout(self.cfg.ssl_handshake_timeout)
                 .chain()
-                .map_err(|e| SslError::Ssl(Box::new(e)))
+                .map_err(SslError::handshake)
                 .map_init_err(|_| panic!())
                 .and_then(self.chain().map_err(SslError::Service))
         }
@@ -371,18 +371,23 @@ where
             io.query::<types::PeerAddr>().get()
         );
 
-        if io.query::<types::HttpProtocol>().get() == Some(types::HttpProtocol::Http2) {
-            HttpServiceHandlerResponse {
+        match io.query::<types::HttpProtocol>().get() {
+            Some(types::HttpProtocol::Http2) => HttpServiceHandlerResponse {
                 state: ResponseState::H2 {
                     fut: Box::pin(h2::handle(io.into(), self.config.clone())),
                 },
-            }
-        } else {
-            HttpServiceHandlerResponse {
+            },
+            // cleartext connection, http/2 could be negotiated with h2c
+            None if self.config.h2c => HttpServiceHandlerResponse {
+                state: ResponseState::H1 {
+                    fut: h1::Dispatcher::new(io, self.config.clone()).h2c(),
+                },
+            },
+            _ => HttpServiceHandlerResponse {
                 state: ResponseState::H1 {
                     fut: h1::Dispatcher::new(io, self.config.clone()),
                 },
-            }
+            },
         }
     }
 }
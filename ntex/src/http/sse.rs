@@ -0,0 +1,297 @@
+//! Server-sent events
+//!
+//! [`Event`] is encoded to the `text/event-stream` format by `web::Sse`
+//! responder and decoded by [`EventDecoder`] on the client side.
+use std::{fmt::Write, time::Duration};
+
+use serde::Serialize;
+
+use crate::util::BytesMut;
+
+/// Utf-8 byte order mark
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Server-sent event
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Event {
+    /// Event id
+    pub id: Option<String>,
+    /// Event type
+    pub event: Option<String>,
+    /// Event data
+    pub data: String,
+    /// Reconnection time
+    pub retry: Option<Duration>,
+}
+
+impl Event {
+    /// Create new event
+    pub fn new<T: Into<String>>(data: T) -> Self {
+        Event {
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create new event with json encoded data
+    pub fn json<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        Ok(Event::new(serde_json::to_string(value)?))
+    }
+
+    /// Set event id
+    pub fn id<T: Into<String>>(mut self, id: T) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set event type
+    pub fn event<T: Into<String>>(mut self, event: T) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set client's reconnection time
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Encode event to the `text/event-stream` format
+    pub fn encode(&self, dst: &mut BytesMut) {
+        if let Some(ref event) = self.event {
+            encode_field(dst, "event", event);
+        }
+        if let Some(ref id) = self.id {
+            encode_field(dst, "id", &id.replace('\0', ""));
+        }
+        if let Some(ref retry) = self.retry {
+            let _ = writeln!(dst, "retry: {}", retry.as_millis());
+        }
+        for line in self.data.split('\n') {
+            encode_field(dst, "data", line.strip_suffix('\r').unwrap_or(line));
+        }
+        dst.extend_from_slice(b"\n");
+    }
+}
+
+/// Encode single line field, line breaks are removed
+fn encode_field(dst: &mut BytesMut, name: &str, value: &str) {
+    dst.extend_from_slice(name.as_bytes());
+    dst.extend_from_slice(b": ");
+    for part in value.split(|c| c == '\r' || c == '\n') {
+        dst.extend_from_slice(part.as_bytes());
+    }
+    dst.extend_from_slice(b"\n");
+}
+
+/// Encode comment, comments are ignored by clients
+pub(crate) fn encode_comment(dst: &mut BytesMut, comment: &str) {
+    for line in comment.split('\n') {
+        dst.extend_from_slice(b": ");
+        dst.extend_from_slice(line.strip_suffix('\r').unwrap_or(line).as_bytes());
+        dst.extend_from_slice(b"\n");
+    }
+    dst.extend_from_slice(b"\n");
+}
+
+/// Incremental `text/event-stream` decoder.
+///
+/// Decoder tracks last event id and reconnection time, these values should
+/// be used for reconnecting to the event source.
+#[derive(Debug, Default)]
+pub struct EventDecoder {
+    event: Option<String>,
+    data: Option<String>,
+    last_id: Option<String>,
+    retry: Option<Duration>,
+    started: bool,
+}
+
+impl EventDecoder {
+    /// Create new decoder
+    pub fn new() -> Self {
+        EventDecoder::default()
+    }
+
+    /// Id of the last event
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_id.as_deref()
+    }
+
+    /// Reconnection time requested by the server
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Decode next event from the buffer.
+    ///
+    /// Processed bytes are removed from the buffer, returns `None` if
+    /// buffer does not contain complete event.
+    pub fn decode(&mut self, src: &mut BytesMut) -> Option<Event> {
+        if !self.started {
+            if src.len() < BOM.len() && BOM.starts_with(&src[..]) {
+                return None;
+            }
+            if src.starts_with(BOM) {
+                let _ = src.split_to(BOM.len());
+            }
+            self.started = true;
+        }
+
+        loop {
+            let pos = src.iter().position(|b| *b == b'\n' || *b == b'\r')?;
+            let size = if src[pos] == b'\r' {
+                match src.get(pos + 1) {
+                    Some(b'\n') => 2,
+                    Some(_) => 1,
+                    // wait for next byte, it could be `\n`
+                    None => return None,
+                }
+            } else {
+                1
+            };
+            let line = src.split_to(pos);
+            let _ = src.split_to(size);
+
+            if line.is_empty() {
+                if let Some(ev) = self.dispatch() {
+                    return Some(ev);
+                }
+            } else {
+                self.process_line(&String::from_utf8_lossy(&line));
+            }
+        }
+    }
+
+    fn process_line(&mut self, line: &str) {
+        let (name, value) = match line.split_once(':') {
+            // comment
+            Some(("", _)) => return,
+            Some((name, value)) => (name, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match name {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match self.data {
+                Some(ref mut data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "id" if !value.contains('\0') => {
+                self.last_id = if value.is_empty() {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+            }
+            "retry" => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = self.event.take();
+        self.data.take().map(|data| Event {
+            data,
+            event,
+            id: self.last_id.clone(),
+            retry: self.retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(ev: &Event) -> String {
+        let mut buf = BytesMut::new();
+        ev.encode(&mut buf);
+        String::from_utf8(buf.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(&Event::new("hello")), "data: hello\n\n");
+        assert_eq!(
+            encode(
+                &Event::new("line1\r\nline2\nline3")
+                    .id("1")
+                    .event("update")
+                    .retry(Duration::from_secs(3))
+            ),
+            "event: update\nid: 1\nretry: 3000\ndata: line1\ndata: line2\ndata: line3\n\n"
+        );
+        assert_eq!(
+            encode(&Event::new("").event("a\nb").id("1\0\r2")),
+            "event: ab\nid: 12\ndata: \n\n"
+        );
+        assert_eq!(
+            encode(&Event::json(&vec![1, 2]).unwrap()),
+            "data: [1,2]\n\n"
+        );
+
+        let mut buf = BytesMut::new();
+        encode_comment(&mut buf, "ping\npong");
+        assert_eq!(&buf[..], b": ping\n: pong\n\n");
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut dec = EventDecoder::new();
+        let mut buf = BytesMut::from(
+            &b"\xEF\xBB\xBF: comment\nevent: update\nid: 1\nretry: 3000\ndata: line1\ndata:line2\n\n"[..],
+        );
+        let ev = dec.decode(&mut buf).unwrap();
+        assert_eq!(
+            ev,
+            Event::new("line1\nline2")
+                .id("1")
+                .event("update")
+                .retry(Duration::from_secs(3))
+        );
+        assert!(buf.is_empty());
+        assert_eq!(dec.last_event_id(), Some("1"));
+        assert_eq!(dec.retry(), Some(Duration::from_secs(3)));
+
+        // partial event
+        buf.extend_from_slice(b"data: a\r\n");
+        assert!(dec.decode(&mut buf).is_none());
+        buf.extend_from_slice(b"\r");
+        assert!(dec.decode(&mut buf).is_none());
+        buf.extend_from_slice(b"\ndata\n\n");
+        let ev = dec.decode(&mut buf).unwrap();
+        assert_eq!(ev.data, "a");
+        assert_eq!(ev.event, None);
+        assert_eq!(ev.id.as_deref(), Some("1"));
+        let ev = dec.decode(&mut buf).unwrap();
+        assert_eq!(ev.data, "");
+        assert!(dec.decode(&mut buf).is_none());
+
+        // event without data and id reset
+        buf.extend_from_slice(b"event: x\nid\n\ndata: b\n\n");
+        let ev = dec.decode(&mut buf).unwrap();
+        assert_eq!(ev, Event::new("b").retry(Duration::from_secs(3)));
+        assert_eq!(dec.last_event_id(), None);
+
+        // invalid retry and unknown fields
+        buf.extend_from_slice(b"retry: abc\nfoo: bar\ndata: c\n\n");
+        let ev = dec.decode(&mut buf).unwrap();
+        assert_eq!(ev.retry, Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let ev = Event::new("a\n\nb").id("5").event("e");
+        let mut buf = BytesMut::new();
+        ev.encode(&mut buf);
+        assert_eq!(EventDecoder::new().decode(&mut buf), Some(ev));
+    }
+}
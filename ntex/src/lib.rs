@@ -6,6 +6,7 @@
 //! * `rustls` - enables ssl support via `rustls` crate
 //! * `compress` - enables compression support in http and web modules
 //! * `cookie` - enables cookie support in http and web modules
+//! * `tracing` - enables `tracing` spans support in web middlewares
 #![warn(
     rust_2018_idioms,
     unreachable_pub,
@@ -5,6 +5,8 @@ use polling::{Event, Events, Poller};
 
 use crate::{rt::System, time::sleep, time::Millis, util::Either};
 
+use super::limits::Limiter;
+use super::metrics::{ListenerMetrics, Metrics};
 use super::socket::{Listener, SocketAddr};
 use super::worker::{Connection, WorkerClient};
 use super::{Server, ServerStatus, Token};
@@ -21,6 +23,9 @@ pub(super) enum Command {
     Worker(WorkerClient),
     Timer,
     WorkerAvailable,
+    #[cfg(unix)]
+    /// Get copies of all listeners
+    Listeners(oneshot::Sender<Vec<(Token, Listener)>>),
 }
 
 #[derive(Debug)]
@@ -30,6 +35,8 @@ struct ServerSocketInfo {
     sock: Listener,
     registered: Cell<bool>,
     timeout: Cell<Option<Instant>>,
+    metrics: Option<Arc<ListenerMetrics>>,
+    limiter: Option<Arc<Limiter>>,
 }
 
 #[derive(Debug, Clone)]
@@ -86,10 +93,27 @@ impl AcceptLoop {
         self.status_handler = Some(Box::new(f));
     }
 
+    #[cfg(unix)]
+    /// Add status handler, existing handler is called first
+    pub(super) fn add_status_handler<F>(&mut self, mut f: F)
+    where
+        F: FnMut(ServerStatus) + Send + 'static,
+    {
+        if let Some(mut hnd) = self.status_handler.take() {
+            self.status_handler = Some(Box::new(move |st| {
+                hnd(st);
+                f(st)
+            }));
+        } else {
+            self.status_handler = Some(Box::new(f));
+        }
+    }
+
     pub(super) fn start(
         &mut self,
-        socks: Vec<(Token, Listener)>,
+        socks: Vec<(Token, Listener, Option<Arc<Limiter>>)>,
         workers: Vec<WorkerClient>,
+        metrics: Metrics,
     ) {
         let (rx, poll, srv) = self
             .inner
@@ -105,6 +129,7 @@ impl AcceptLoop {
             workers,
             self.notify.clone(),
             status_handler,
+            metrics,
         );
     }
 }
@@ -129,17 +154,19 @@ struct Accept {
     next: usize,
     backpressure: bool,
     status_handler: Option<Box<dyn FnMut(ServerStatus) + Send>>,
+    metrics: Metrics,
 }
 
 impl Accept {
     fn start(
         rx: mpsc::Receiver<Command>,
         poller: Arc<Poller>,
-        socks: Vec<(Token, Listener)>,
+        socks: Vec<(Token, Listener, Option<Arc<Limiter>>)>,
         srv: Server,
         workers: Vec<WorkerClient>,
         notify: AcceptNotify,
         status_handler: Option<Box<dyn FnMut(ServerStatus) + Send>>,
+        metrics: Metrics,
     ) {
         let sys = System::current();
 
@@ -148,27 +175,40 @@ impl Accept {
             .name("ntex-server accept loop".to_owned())
             .spawn(move || {
                 System::set_current(sys);
-                Accept::new(rx, poller, socks, workers, srv, notify, status_handler).poll()
+                Accept::new(
+                    rx,
+                    poller,
+                    socks,
+                    workers,
+                    srv,
+                    notify,
+                    status_handler,
+                    metrics,
+                )
+                .poll()
             });
     }
 
     fn new(
         rx: mpsc::Receiver<Command>,
         poller: Arc<Poller>,
-        socks: Vec<(Token, Listener)>,
+        socks: Vec<(Token, Listener, Option<Arc<Limiter>>)>,
         workers: Vec<WorkerClient>,
         srv: Server,
         notify: AcceptNotify,
         status_handler: Option<Box<dyn FnMut(ServerStatus) + Send>>,
+        metrics: Metrics,
     ) -> Accept {
         let mut sockets = Vec::new();
-        for (hnd_token, lst) in socks.into_iter() {
+        for (hnd_token, lst, limiter) in socks.into_iter() {
             sockets.push(ServerSocketInfo {
                 addr: lst.local_addr(),
                 sock: lst,
                 token: hnd_token,
                 registered: Cell::new(false),
                 timeout: Cell::new(None),
+                metrics: metrics.listener(hnd_token),
+                limiter,
             });
         }
 
@@ -180,6 +220,7 @@ impl Accept {
             notify,
             srv,
             status_handler,
+            metrics,
             next: 0,
             backpressure: false,
         }
@@ -341,6 +382,23 @@ impl Accept {
                         log::trace!("Worker is available");
                         self.backpressure(false);
                     }
+                    #[cfg(unix)]
+                    Command::Listeners(tx) => {
+                        let mut listeners = Vec::new();
+                        for info in &self.sockets {
+                            match info.sock.try_clone() {
+                                Ok(lst) => listeners.push((info.token, lst)),
+                                Err(e) => {
+                                    log::error!(
+                                        "Cannot clone listener {}: {}",
+                                        info.addr,
+                                        e
+                                    )
+                                }
+                            }
+                        }
+                        let _ = tx.send(listeners);
+                    }
                 },
                 Err(err) => {
                     break match err {
@@ -360,6 +418,7 @@ impl Accept {
     }
 
     fn backpressure(&mut self, on: bool) {
+        self.metrics.set_backpressure(on);
         self.update_status(if on {
             ServerStatus::NotReady
         } else {
@@ -463,10 +522,31 @@ impl Accept {
         loop {
             let msg = if let Some(info) = self.sockets.get_mut(token) {
                 match info.sock.accept() {
-                    Ok(Some(io)) => Connection {
-                        io,
-                        token: info.token,
-                    },
+                    Ok(Some(io)) => {
+                        if let Some(ref metrics) = info.metrics {
+                            metrics.accepted();
+                        }
+                        let limit = match info.limiter.as_ref().map(|l| l.admit(&io)) {
+                            Some(Ok(guard)) => Some(guard),
+                            Some(Err(reason)) => {
+                                log::trace!(
+                                    "Connection {:?} is rejected: {}",
+                                    io,
+                                    reason.as_str()
+                                );
+                                if let Some(ref metrics) = info.metrics {
+                                    metrics.rejected(reason);
+                                }
+                                continue;
+                            }
+                            None => None,
+                        };
+                        Connection {
+                            io,
+                            limit,
+                            token: info.token,
+                        }
+                    }
                     Ok(None) => return true,
                     Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return true,
                     Err(ref e) if connection_error(e) => continue,
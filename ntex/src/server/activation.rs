@@ -0,0 +1,294 @@
+//! Listeners inherited from the parent process
+//!
+//! Systemd socket activation protocol, `LISTEN_PID`, `LISTEN_FDS` and
+//! `LISTEN_FDNAMES` environment variables describe file descriptors passed
+//! to the process, descriptors start at 3.
+use std::{fmt, io, net};
+
+use super::socket::Listener;
+
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// Inherited listeners
+#[derive(Default)]
+pub(super) struct Inherited(Vec<(Option<String>, Listener)>);
+
+impl Inherited {
+    #[cfg(unix)]
+    /// Load listeners passed in with `LISTEN_FDS` environment variable.
+    ///
+    /// Environment variables get removed, so child processes do not
+    /// inherit them.
+    pub(super) fn from_env() -> io::Result<Self> {
+        let fds = parse_env(
+            std::env::var("LISTEN_PID").ok().as_deref(),
+            std::env::var("LISTEN_FDS").ok().as_deref(),
+            std::env::var("LISTEN_FDNAMES").ok().as_deref(),
+            std::process::id(),
+        )?;
+        std::env::remove_var("LISTEN_PID");
+        std::env::remove_var("LISTEN_FDS");
+        std::env::remove_var("LISTEN_FDNAMES");
+
+        // Safety: descriptors are passed to the process by the parent
+        // and are not owned by anything else
+        unsafe { Inherited::from_fds(fds) }
+    }
+
+    #[cfg(not(unix))]
+    /// Socket activation is not supported on this platform
+    pub(super) fn from_env() -> io::Result<Self> {
+        Ok(Inherited::default())
+    }
+
+    #[cfg(unix)]
+    /// Adopt listening sockets
+    ///
+    /// Descriptors that are not listening stream sockets are skipped.
+    ///
+    /// # Safety
+    ///
+    /// Descriptors must be open and not owned by anything else.
+    pub(super) unsafe fn from_fds(
+        fds: Vec<(std::os::unix::io::RawFd, Option<String>)>,
+    ) -> io::Result<Self> {
+        use socket2::{Domain, Socket};
+        use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+        let mut listeners = Vec::new();
+        for (fd, name) in fds {
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            let sock = Socket::from_raw_fd(fd);
+            let lst = match listener_domain(&sock) {
+                Ok(Domain::UNIX) => Listener::from_uds(sock.into()),
+                Ok(_) => Listener::from_tcp(sock.into()),
+                Err(e) => {
+                    log::warn!("Skip inherited descriptor {} ({:?}): {}", fd, name, e);
+                    // descriptor is not ours to close
+                    let _ = sock.into_raw_fd();
+                    continue;
+                }
+            };
+            log::info!("Inherited listener {} ({:?})", lst, name);
+            listeners.push((name, lst));
+        }
+        Ok(Inherited(listeners))
+    }
+
+    /// Set names of listeners
+    pub(super) fn with_names<I>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        for (item, name) in self.0.iter_mut().zip(names) {
+            item.0 = if name.is_empty() { None } else { Some(name) };
+        }
+        self
+    }
+
+    /// Merge inherited listeners
+    pub(super) fn extend(&mut self, other: Inherited) {
+        self.0.extend(other.0)
+    }
+
+    /// Take all listeners with the name
+    pub(super) fn take_named(&mut self, name: &str) -> Vec<Listener> {
+        let mut result = Vec::new();
+        let mut idx = 0;
+        while idx < self.0.len() {
+            if self.0[idx].0.as_deref() == Some(name) {
+                result.push(self.0.remove(idx).1);
+            } else {
+                idx += 1;
+            }
+        }
+        result
+    }
+
+    /// Take tcp listener bound to the address
+    pub(super) fn take_tcp(&mut self, addr: &net::SocketAddr) -> Option<net::TcpListener> {
+        let idx = self.0.iter().position(|(_, lst)| match lst {
+            Listener::Tcp(lst) => lst.local_addr().ok().as_ref() == Some(addr),
+            #[cfg(unix)]
+            _ => false,
+        })?;
+        match self.0.remove(idx).1 {
+            Listener::Tcp(lst) => Some(lst),
+            #[cfg(unix)]
+            _ => None,
+        }
+    }
+
+    #[cfg(unix)]
+    /// Take unix domain listener bound to the path
+    pub(super) fn take_uds(
+        &mut self,
+        path: &std::path::Path,
+    ) -> Option<std::os::unix::net::UnixListener> {
+        let idx = self.0.iter().position(|(_, lst)| match lst {
+            Listener::Uds(lst) => lst
+                .local_addr()
+                .ok()
+                .and_then(|addr| addr.as_pathname().map(|p| p == path))
+                .unwrap_or(false),
+            _ => false,
+        })?;
+        match self.0.remove(idx).1 {
+            Listener::Uds(lst) => Some(lst),
+            _ => None,
+        }
+    }
+
+    /// Take all remaining listeners
+    pub(super) fn take_all(&mut self) -> Vec<(Option<String>, Listener)> {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl fmt::Debug for Inherited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+#[cfg(unix)]
+/// Domain of listening stream socket
+fn listener_domain(sock: &socket2::Socket) -> io::Result<socket2::Domain> {
+    use std::os::unix::io::AsRawFd;
+
+    if sock.r#type()? != socket2::Type::STREAM {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Not a stream socket",
+        ));
+    }
+
+    let mut val: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // Safety: option value points to valid c_int
+    let res = unsafe {
+        libc::getsockopt(
+            sock.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_ACCEPTCONN,
+            &mut val as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    } else if val == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Socket is not listening",
+        ));
+    }
+
+    Ok(sock.local_addr()?.domain())
+}
+
+#[cfg(unix)]
+/// Parse socket activation environment variables
+fn parse_env(
+    pid: Option<&str>,
+    fds: Option<&str>,
+    names: Option<&str>,
+    own_pid: u32,
+) -> io::Result<Vec<(std::os::unix::io::RawFd, Option<String>)>> {
+    let fds = match fds {
+        Some(fds) => fds,
+        None => return Ok(Vec::new()),
+    };
+    if let Some(pid) = pid {
+        // descriptors are passed to different process
+        if pid.trim().parse::<u32>().ok() != Some(own_pid) {
+            return Ok(Vec::new());
+        }
+    }
+
+    let num = fds.trim().parse::<i32>().map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "Cannot parse LISTEN_FDS")
+    })?;
+    let names: Vec<_> = names.map(|n| n.split(':').collect()).unwrap_or_default();
+
+    Ok((0..num.max(0))
+        .map(|idx| {
+            let name = names
+                .get(idx as usize)
+                .filter(|name| !name.is_empty() && **name != "unknown")
+                .map(|name| name.to_string());
+            (LISTEN_FDS_START + idx, name)
+        })
+        .collect())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::io::IntoRawFd;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_env() {
+        assert!(parse_env(None, None, None, 10).unwrap().is_empty());
+        assert!(parse_env(Some("11"), Some("2"), None, 10)
+            .unwrap()
+            .is_empty());
+        assert!(parse_env(Some("10"), Some("x"), None, 10).is_err());
+
+        let fds = parse_env(Some("10"), Some("2"), None, 10).unwrap();
+        assert_eq!(fds, vec![(3, None), (4, None)]);
+
+        let fds = parse_env(None, Some("3"), Some("http:unknown"), 10).unwrap();
+        assert_eq!(
+            fds,
+            vec![(3, Some("http".to_string())), (4, None), (5, None)]
+        );
+    }
+
+    #[test]
+    fn test_inherited() {
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp_addr = tcp.local_addr().unwrap();
+        let tcp2 = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp2_addr = tcp2.local_addr().unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("ntex-inherit-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let uds = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+        // not listening stream sockets are skipped
+        let udp = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let conn = net::TcpStream::connect(tcp_addr).unwrap();
+        let file = std::fs::File::open("/dev/null").unwrap();
+
+        let mut inherited = unsafe {
+            Inherited::from_fds(vec![
+                (tcp.into_raw_fd(), Some("http".to_string())),
+                (udp.into_raw_fd(), Some("http".to_string())),
+                (conn.into_raw_fd(), None),
+                (file.into_raw_fd(), None),
+                (tcp2.into_raw_fd(), None),
+                (uds.into_raw_fd(), None),
+            ])
+            .unwrap()
+        };
+        assert!(format!("{:?}", inherited).contains("http"));
+
+        assert!(inherited.take_named("other").is_empty());
+        let lst = inherited.take_named("http");
+        assert_eq!(lst.len(), 1);
+        assert_eq!(format!("{}", lst[0]), tcp_addr.to_string());
+
+        assert!(inherited.take_tcp(&tcp_addr).is_none());
+        assert!(inherited.take_tcp(&tcp2_addr).is_some());
+        assert!(inherited
+            .take_uds(std::path::Path::new("/tmp/none"))
+            .is_none());
+        assert!(inherited.take_uds(&path).is_some());
+        assert!(inherited.take_all().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,7 +1,7 @@
 use std::{fmt, future::Future, io, marker, mem, net, pin::Pin, task::Context, task::Poll};
 
 use async_channel::unbounded;
-use log::{error, info};
+use log::{error, info, warn};
 use socket2::{Domain, SockAddr, Socket, Type};
 
 use crate::rt::{spawn, Signal, System};
@@ -9,9 +9,12 @@ use crate::time::{sleep, Millis};
 use crate::{io::Io, service::ServiceFactory, util::join_all, util::Stream};
 
 use super::accept::{AcceptLoop, AcceptNotify, Command};
+use super::activation::Inherited;
 use super::config::{
     Config, ConfigWrapper, ConfiguredService, ServiceConfig, ServiceRuntime,
 };
+use super::limits::{ConnectionLimits, Limiter};
+use super::metrics::Metrics;
 use super::service::{Factory, InternalServiceFactory};
 use super::worker::{self, Worker, WorkerAvailability, WorkerClient};
 use super::{socket::Listener, Server, ServerCommand, ServerStatus, Token};
@@ -28,7 +31,16 @@ pub struct ServerBuilder {
     workers: Vec<(usize, WorkerClient)>,
     services: Vec<Box<dyn InternalServiceFactory>>,
     sockets: Vec<(Token, String, Listener)>,
+    limits: Vec<(String, ConnectionLimits)>,
+    inherited: Inherited,
+    #[cfg(unix)]
+    names: Vec<(Token, String)>,
+    #[cfg(unix)]
+    handover: Option<std::os::unix::net::UnixStream>,
+    #[cfg(unix)]
+    handover_timeout: Millis,
     accept: AcceptLoop,
+    metrics: Metrics,
     exit: bool,
     shutdown_timeout: Millis,
     no_signals: bool,
@@ -56,7 +68,16 @@ impl ServerBuilder {
             workers: Vec::new(),
             services: Vec::new(),
             sockets: Vec::new(),
+            limits: Vec::new(),
+            inherited: Inherited::default(),
+            #[cfg(unix)]
+            names: Vec::new(),
+            #[cfg(unix)]
+            handover: None,
+            #[cfg(unix)]
+            handover_timeout: Millis::from_secs(30),
             accept: AcceptLoop::new(server.clone()),
+            metrics: Metrics::default(),
             backlog: 2048,
             exit: false,
             shutdown_timeout: Millis::from_secs(30),
@@ -130,6 +151,19 @@ impl ServerBuilder {
         self
     }
 
+    #[cfg(unix)]
+    /// Timeout for listeners hand-off to a new process.
+    ///
+    /// Child process started by [`Server::handover()`] must report readiness
+    /// within this time, otherwise it gets killed and server continues
+    /// accepting connections. To disable timeout set value to 0.
+    ///
+    /// By default handover timeout is set to 30 seconds.
+    pub fn handover_timeout<T: Into<Millis>>(mut self, timeout: T) -> Self {
+        self.handover_timeout = timeout.into();
+        self
+    }
+
     /// Set server status handler.
     ///
     /// Server calls this handler on every inner status update.
@@ -141,6 +175,31 @@ impl ServerBuilder {
         self
     }
 
+    /// Adopt listeners inherited from the parent process.
+    ///
+    /// Listeners passed in by systemd socket activation or by a supervising
+    /// process (`LISTEN_FDS`, `LISTEN_FDNAMES` environment variables) are used
+    /// by `bind()` and `bind_uds()` methods. Listener is matched to a service
+    /// by its name (`FileDescriptorName=` systemd option), otherwise by
+    /// listener's address. If there is no matching listener, new socket
+    /// is bound.
+    ///
+    /// Listeners handed over by parent process with [`Server::handover()`]
+    /// are adopted as well, child process reports readiness to the parent
+    /// once server is ready.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn from_env_listeners(mut self) -> io::Result<Self> {
+        self.inherited.extend(Inherited::from_env()?);
+
+        #[cfg(unix)]
+        if let Some((sock, inherited)) = super::handover::receive()? {
+            self.inherited.extend(inherited);
+            self.handover = Some(sock);
+        }
+        Ok(self)
+    }
+
     /// Execute external configuration as part of the server building
     /// process.
     ///
@@ -161,6 +220,10 @@ impl ServerBuilder {
             srv.stream(token, name.clone(), lst.local_addr()?, tag);
             self.sockets.push((token, name, Listener::from_tcp(lst)));
         }
+        for (name, limits) in mem::take(&mut cfg.limits) {
+            self.limits.retain(|(n, _)| n != &name);
+            self.limits.push((name, limits));
+        }
         self.services.push(Box::new(srv));
         self.threads = cfg.threads;
 
@@ -189,6 +252,10 @@ impl ServerBuilder {
             srv.stream(token, name.clone(), lst.local_addr()?, tag);
             self.sockets.push((token, name, Listener::from_tcp(lst)));
         }
+        for (name, limits) in mem::take(&mut cfg.limits) {
+            self.limits.retain(|(n, _)| n != &name);
+            self.limits.push((name, limits));
+        }
         self.services.push(Box::new(srv));
         self.threads = cfg.threads;
 
@@ -225,26 +292,36 @@ impl ServerBuilder {
         F: Fn(Config) -> R + Send + Clone + 'static,
         R: ServiceFactory<Io>,
     {
-        let sockets = bind_addr(addr, self.backlog)?;
+        let inherited = self.inherited.take_named(name.as_ref());
+        if !inherited.is_empty() {
+            for lst in inherited {
+                self.add_listener(name.as_ref(), lst, factory.clone())?;
+            }
+            return Ok(self);
+        }
+
+        let mut sockets = Vec::new();
+        let mut addrs = Vec::new();
+        for addr in addr.to_socket_addrs()? {
+            if let Some(lst) = self.inherited.take_tcp(&addr) {
+                sockets.push(lst);
+            } else {
+                addrs.push(addr);
+            }
+        }
+        if !addrs.is_empty() || sockets.is_empty() {
+            sockets.extend(bind_addr(&addrs[..], self.backlog)?);
+        }
 
         for lst in sockets {
-            let token = self.token.next();
-            self.services.push(Factory::create(
-                name.as_ref().to_string(),
-                token,
-                factory.clone(),
-                lst.local_addr()?,
-                "",
-            ));
-            self.sockets
-                .push((token, name.as_ref().to_string(), Listener::from_tcp(lst)));
+            self.add_listener(name.as_ref(), Listener::from_tcp(lst), factory.clone())?;
         }
         Ok(self)
     }
 
     #[cfg(unix)]
     /// Add new unix domain service to the server.
-    pub fn bind_uds<F, U, N, R>(self, name: N, addr: U, factory: F) -> io::Result<Self>
+    pub fn bind_uds<F, U, N, R>(mut self, name: N, addr: U, factory: F) -> io::Result<Self>
     where
         N: AsRef<str>,
         U: AsRef<std::path::Path>,
@@ -253,6 +330,17 @@ impl ServerBuilder {
     {
         use std::os::unix::net::UnixListener;
 
+        let inherited = self.inherited.take_named(name.as_ref());
+        if !inherited.is_empty() {
+            for lst in inherited {
+                self.add_listener(name.as_ref(), lst, factory.clone())?;
+            }
+            return Ok(self);
+        }
+        if let Some(lst) = self.inherited.take_uds(addr.as_ref()) {
+            return self.listen_uds(name, lst, factory);
+        }
+
         // The path must not exist when we try to bind.
         // Try to remove it to avoid bind error.
         if let Err(e) = std::fs::remove_file(addr.as_ref()) {
@@ -280,18 +368,7 @@ impl ServerBuilder {
         F: Fn(Config) -> R + Send + Clone + 'static,
         R: ServiceFactory<Io>,
     {
-        use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-        let token = self.token.next();
-        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
-        self.services.push(Factory::create(
-            name.as_ref().to_string(),
-            token,
-            factory,
-            addr,
-            "",
-        ));
-        self.sockets
-            .push((token, name.as_ref().to_string(), Listener::from_uds(lst)));
+        self.add_listener(name.as_ref(), Listener::from_uds(lst), factory)?;
         Ok(self)
     }
 
@@ -306,19 +383,43 @@ impl ServerBuilder {
         F: Fn(Config) -> R + Send + Clone + 'static,
         R: ServiceFactory<Io>,
     {
-        let token = self.token.next();
-        self.services.push(Factory::create(
-            name.as_ref().to_string(),
-            token,
-            factory,
-            lst.local_addr()?,
-            "",
-        ));
-        self.sockets
-            .push((token, name.as_ref().to_string(), Listener::from_tcp(lst)));
+        self.add_listener(name.as_ref(), Listener::from_tcp(lst), factory)?;
         Ok(self)
     }
 
+    /// Take inherited tcp listener bound to the address
+    pub(crate) fn take_inherited(
+        &mut self,
+        addr: &net::SocketAddr,
+    ) -> Option<net::TcpListener> {
+        self.inherited.take_tcp(addr)
+    }
+
+    fn add_listener<F, R>(
+        &mut self,
+        name: &str,
+        lst: Listener,
+        factory: F,
+    ) -> io::Result<()>
+    where
+        F: Fn(Config) -> R + Send + Clone + 'static,
+        R: ServiceFactory<Io>,
+    {
+        let addr = match lst {
+            Listener::Tcp(ref lst) => lst.local_addr()?,
+            #[cfg(unix)]
+            Listener::Uds(_) => net::SocketAddr::new(
+                net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)),
+                8080,
+            ),
+        };
+        let token = self.token.next();
+        self.services
+            .push(Factory::create(name.to_string(), token, factory, addr, ""));
+        self.sockets.push((token, name.to_string(), lst));
+        Ok(())
+    }
+
     /// Add new service to the server.
     pub fn set_tag<N: AsRef<str>>(mut self, name: N, tag: &'static str) -> Self {
         let mut token = None;
@@ -342,6 +443,22 @@ impl ServerBuilder {
         self
     }
 
+    /// Set connection limits for the service.
+    ///
+    /// Limits are shared by all listeners of the service and are enforced
+    /// by accept loop, connections over the limits get closed.
+    /// Per-worker limit set with `maxconn()` is still in effect.
+    ///
+    /// Panics if service with `name` is not bound.
+    pub fn set_limits<N: AsRef<str>>(mut self, name: N, limits: ConnectionLimits) -> Self {
+        if !self.sockets.iter().any(|sock| sock.1 == name.as_ref()) {
+            panic!("Cannot find service by name {:?}", name.as_ref());
+        }
+        self.limits.retain(|(n, _)| n != name.as_ref());
+        self.limits.push((name.as_ref().to_string(), limits));
+        self
+    }
+
     /// Starts processing incoming connections and return server controller.
     pub fn run(mut self) -> Server {
         if self.sockets.is_empty() {
@@ -349,6 +466,15 @@ impl ServerBuilder {
         } else {
             info!("Starting {} workers", self.threads);
 
+            // unused inherited listeners get closed
+            for (name, lst) in self.inherited.take_all() {
+                warn!("Inherited listener {} ({:?}) is not used", lst, name);
+            }
+
+            for (token, name, lst) in &self.sockets {
+                self.metrics.add_listener(*token, name, lst.to_string());
+            }
+
             // start workers
             let mut workers = Vec::new();
             for idx in 0..self.threads {
@@ -357,16 +483,42 @@ impl ServerBuilder {
                 self.workers.push((idx, worker));
             }
 
+            // report readiness to parent process
+            #[cfg(unix)]
+            if let Some(sock) = self.handover.take() {
+                self.accept
+                    .add_status_handler(super::handover::ready_notifier(sock));
+            }
+
             // start accept thread
             for sock in &self.sockets {
                 info!("Starting \"{}\" service on {}", sock.1, sock.2);
             }
+            #[cfg(unix)]
+            {
+                self.names = self
+                    .sockets
+                    .iter()
+                    .map(|(token, name, _)| (*token, name.clone()))
+                    .collect();
+            }
+            let limiters: Vec<_> = mem::take(&mut self.limits)
+                .into_iter()
+                .map(|(name, limits)| (name, Limiter::new(limits)))
+                .collect();
             self.accept.start(
                 mem::take(&mut self.sockets)
                     .into_iter()
-                    .map(|t| (t.0, t.2))
+                    .map(|(token, name, lst)| {
+                        let limiter = limiters
+                            .iter()
+                            .find(|(n, _)| n == &name)
+                            .map(|(_, l)| l.clone());
+                        (token, lst, limiter)
+                    })
                     .collect(),
                 workers,
+                self.metrics.clone(),
             );
 
             // handle signals
@@ -386,7 +538,13 @@ impl ServerBuilder {
         let services: Vec<Box<dyn InternalServiceFactory>> =
             self.services.iter().map(|v| v.clone_factory()).collect();
 
-        Worker::start(idx, services, avail, self.shutdown_timeout)
+        Worker::start(
+            idx,
+            services,
+            avail,
+            self.shutdown_timeout,
+            self.metrics.clone(),
+        )
     }
 
     fn handle_cmd(&mut self, item: ServerCommand) {
@@ -433,6 +591,56 @@ impl ServerBuilder {
             ServerCommand::Notify(tx) => {
                 self.notify.push(tx);
             }
+            ServerCommand::Metrics(tx) => {
+                let _ = tx.send(self.metrics.clone());
+            }
+            #[cfg(unix)]
+            ServerCommand::Handover(cmd, tx) => {
+                // get copies of listeners from accept thread
+                let (ltx, lrx) = oneshot::channel();
+                self.accept.send(Command::Listeners(ltx));
+
+                let names = self.names.clone();
+                let srv = self.server.clone();
+                let timeout = self.handover_timeout;
+                spawn(async move {
+                    let listeners: Vec<_> = lrx
+                        .await
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|(token, lst)| {
+                            names
+                                .iter()
+                                .find(|(t, _)| *t == token)
+                                .map(|(_, name)| (name.clone(), lst))
+                        })
+                        .collect();
+
+                    let res = match crate::rt::spawn_blocking(move || {
+                        super::handover::handover(cmd, listeners, timeout)
+                    })
+                    .await
+                    {
+                        Ok(res) => res,
+                        Err(_) => Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            "Handover task is canceled",
+                        )),
+                    };
+
+                    match res {
+                        Ok(_) => {
+                            info!("Listeners are handed over, stopping");
+                            srv.stop(true).await;
+                            let _ = tx.send(Ok(()));
+                        }
+                        Err(e) => {
+                            error!("Cannot hand listeners over: {}", e);
+                            let _ = tx.send(Err(e));
+                        }
+                    }
+                });
+            }
             ServerCommand::Stop {
                 graceful,
                 completion,
@@ -499,6 +707,7 @@ impl ServerBuilder {
 
                 if found {
                     error!("Worker has died {:?}, restarting", idx);
+                    self.metrics.remove_worker(idx);
 
                     let mut new_idx = self.workers.len();
                     'found: loop {
@@ -609,4 +818,47 @@ mod tests {
         let addrs: Vec<net::SocketAddr> = Vec::new();
         assert!(bind_addr(&addrs[..], 10).is_err());
     }
+
+    #[cfg(unix)]
+    #[crate::rt_test]
+    async fn test_inherited() {
+        use std::os::unix::io::IntoRawFd;
+
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = lst.local_addr().unwrap();
+        let lst2 = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr2 = lst2.local_addr().unwrap();
+        let lst3 = net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let mut builder = ServerBuilder::new().from_env_listeners().unwrap();
+        builder.inherited = unsafe {
+            Inherited::from_fds(vec![
+                (lst.into_raw_fd(), Some("named".to_string())),
+                (lst2.into_raw_fd(), None),
+                (lst3.into_raw_fd(), None),
+            ])
+            .unwrap()
+        };
+
+        let factory = |_| crate::service::fn_service(|_: Io| async { Ok::<_, ()>(()) });
+        let mut builder = builder
+            .bind("named", "127.0.0.1:0", factory)
+            .unwrap()
+            .bind("by-addr", addr2, factory)
+            .unwrap()
+            .bind("new", "127.0.0.1:0", factory)
+            .unwrap();
+
+        let sockets: Vec<_> = builder
+            .sockets
+            .iter()
+            .map(|(_, name, lst)| (name.as_str(), lst.to_string()))
+            .collect();
+        assert_eq!(sockets.len(), 3);
+        assert_eq!(sockets[0], ("named", addr.to_string()));
+        assert_eq!(sockets[1], ("by-addr", addr2.to_string()));
+        assert_eq!(sockets[2].0, "new");
+        assert_ne!(sockets[2].1, addr.to_string());
+        assert_eq!(builder.inherited.take_all().len(), 1);
+    }
 }
@@ -9,7 +9,7 @@ use crate::util::{BoxFuture, HashMap, PoolId, Ready};
 use super::service::{
     BoxedServerService, InternalServiceFactory, ServerMessage, StreamService,
 };
-use super::{builder::bind_addr, counter::CounterGuard, Token};
+use super::{builder::bind_addr, counter::CounterGuard, ConnectionLimits, Token};
 
 #[derive(Clone, Debug)]
 pub struct Config(Rc<InnerServiceConfig>);
@@ -47,6 +47,7 @@ pub struct ServiceConfig(pub(super) Rc<RefCell<ServiceConfigInner>>);
 #[derive(Debug)]
 pub(super) struct ServiceConfigInner {
     pub(super) services: Vec<(String, net::TcpListener, &'static str)>,
+    pub(super) limits: Vec<(String, ConnectionLimits)>,
     pub(super) apply: Option<Box<dyn ServiceRuntimeConfiguration + Send>>,
     pub(super) threads: usize,
     pub(super) backlog: i32,
@@ -59,6 +60,7 @@ impl ServiceConfig {
             threads,
             backlog,
             services: Vec::new(),
+            limits: Vec::new(),
             applied: false,
             apply: Some(Box::new(ConfigWrapper {
                 f: |_| {
@@ -113,6 +115,21 @@ impl ServiceConfig {
         self
     }
 
+    /// Set connection limits for configured service.
+    ///
+    /// Limits are shared by all listeners of the service.
+    ///
+    /// Panics if service with `name` is not configured.
+    pub fn set_limits<N: AsRef<str>>(&self, name: N, limits: ConnectionLimits) -> &Self {
+        let mut inner = self.0.borrow_mut();
+        if !inner.services.iter().any(|svc| svc.0 == name.as_ref()) {
+            panic!("Cannot find service by name {:?}", name.as_ref());
+        }
+        inner.limits.retain(|(n, _)| n != name.as_ref());
+        inner.limits.push((name.as_ref().to_string(), limits));
+        self
+    }
+
     /// Register async service configuration function.
     ///
     /// This function get called during worker runtime configuration stage.
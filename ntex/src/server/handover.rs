@@ -0,0 +1,304 @@
+//! Listeners hand-off to a new process
+//!
+//! Parent process spawns child process with one end of unix socket pair,
+//! descriptor number is passed with `NTEX_HANDOVER_FD` environment variable.
+//! Listeners are sent over the socket with `SCM_RIGHTS` message, child
+//! process reports readiness by writing single byte to the socket.
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::{net::UnixStream, process::CommandExt};
+use std::{mem, process::Command, ptr};
+
+use super::{activation::Inherited, socket::Listener, ServerStatus};
+use crate::time::Millis;
+
+const HANDOVER_FD: &str = "NTEX_HANDOVER_FD";
+const READY: u8 = b'R';
+// SCM_MAX_FD
+const MAX_FDS: usize = 253;
+
+/// Spawn child process and send listeners to it, then wait for child readiness.
+///
+/// Child process is killed if it does not become ready within `timeout`.
+/// This function blocks.
+pub(super) fn handover(
+    mut cmd: Command,
+    listeners: Vec<(String, Listener)>,
+    timeout: Millis,
+) -> io::Result<()> {
+    if listeners.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::Other, "No listeners"));
+    }
+    if listeners.len() > MAX_FDS {
+        return Err(io::Error::new(io::ErrorKind::Other, "Too many listeners"));
+    }
+
+    let (mut sock, child_sock) = UnixStream::pair()?;
+    sock.set_read_timeout(timeout.map(|t| t.into()))?;
+    sock.set_write_timeout(timeout.map(|t| t.into()))?;
+    let fd = child_sock.as_raw_fd();
+    cmd.env(HANDOVER_FD, fd.to_string());
+    // Safety: fcntl is async-signal-safe
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::fcntl(fd, libc::F_SETFD, 0) < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        });
+    }
+    let mut child = cmd.spawn()?;
+    drop(child_sock);
+    log::info!("Handing listeners over to process {}", child.id());
+
+    let res = send_listeners(&mut sock, &listeners)
+        .and_then(|_| {
+            drop(listeners);
+
+            let mut buf = [0u8; 1];
+            match sock.read(&mut buf)? {
+                1 if buf[0] == READY => {
+                    log::info!("Process {} is ready", child.id());
+                    Ok(())
+                }
+                _ => Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Child process exited before it became ready",
+                )),
+            }
+        })
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Child process did not become ready in time",
+            ),
+            _ => e,
+        });
+    if res.is_err() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    res
+}
+
+fn send_listeners(
+    sock: &mut UnixStream,
+    listeners: &[(String, Listener)],
+) -> io::Result<()> {
+    let names: Vec<_> = listeners
+        .iter()
+        .map(|(name, _)| name.replace('\n', " "))
+        .collect();
+    let names = names.join("\n");
+    let fds: Vec<_> = listeners.iter().map(|(_, lst)| lst.as_raw_fd()).collect();
+
+    let mut data = Vec::with_capacity(names.len() + 4);
+    data.extend_from_slice(&(names.len() as u32).to_le_bytes());
+    data.extend_from_slice(names.as_bytes());
+    let sent = send_fds(sock, &data, &fds)?;
+    sock.write_all(&data[sent..])
+}
+
+/// Receive listeners from the parent process, if `NTEX_HANDOVER_FD` is set.
+pub(super) fn receive() -> io::Result<Option<(UnixStream, Inherited)>> {
+    let fd: RawFd = match std::env::var(HANDOVER_FD) {
+        Ok(fd) => fd.trim().parse().map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Cannot parse NTEX_HANDOVER_FD")
+        })?,
+        Err(_) => return Ok(None),
+    };
+    std::env::remove_var(HANDOVER_FD);
+
+    // Safety: descriptor is passed to the process by the parent
+    let mut sock = unsafe {
+        libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        UnixStream::from_raw_fd(fd)
+    };
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut fds = Vec::new();
+    let mut size = recv_fds(&sock, &mut buf, &mut fds)?;
+
+    // Safety: received descriptors are owned by this process
+    let inherited =
+        unsafe { Inherited::from_fds(fds.iter().map(|fd| (*fd, None)).collect())? };
+
+    while size < 4 {
+        size += read_some(&mut sock, &mut buf[size..])?;
+    }
+    let len = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
+    if len + 4 > buf.len() {
+        buf.resize(len + 4, 0);
+    }
+    while size < len + 4 {
+        size += read_some(&mut sock, &mut buf[size..])?;
+    }
+
+    let names = String::from_utf8_lossy(&buf[4..len + 4]);
+    let names: Vec<_> = names.split('\n').collect();
+    if names.len() != fds.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Number of listeners does not match",
+        ));
+    }
+    let inherited = inherited.with_names(names.into_iter().map(|n| n.to_string()));
+
+    Ok(Some((sock, inherited)))
+}
+
+/// Status handler that reports readiness to the parent process
+pub(super) fn ready_notifier(sock: UnixStream) -> impl FnMut(ServerStatus) + Send {
+    let mut sock = Some(sock);
+    move |status| {
+        if status == ServerStatus::Ready {
+            if let Some(mut sock) = sock.take() {
+                if let Err(e) = sock.write_all(&[READY]) {
+                    log::error!("Cannot notify parent process: {}", e);
+                }
+            }
+        }
+    }
+}
+
+fn read_some(sock: &mut UnixStream, buf: &mut [u8]) -> io::Result<usize> {
+    match sock.read(buf)? {
+        0 => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Handover socket is closed",
+        )),
+        n => Ok(n),
+    }
+}
+
+fn send_fds(sock: &UnixStream, data: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+    let fds_len = mem::size_of_val(fds);
+    unsafe {
+        let space = libc::CMSG_SPACE(fds_len as u32) as usize;
+        let mut control = vec![0u64; (space + 7) / 8];
+
+        let mut iov = libc::iovec {
+            iov_base: data.as_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        };
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = space as _;
+
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len as u32) as _;
+        ptr::copy_nonoverlapping(
+            fds.as_ptr(),
+            libc::CMSG_DATA(cmsg) as *mut RawFd,
+            fds.len(),
+        );
+
+        let res = libc::sendmsg(sock.as_raw_fd(), &msg, 0);
+        if res < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(res as usize)
+        }
+    }
+}
+
+fn recv_fds(sock: &UnixStream, buf: &mut [u8], fds: &mut Vec<RawFd>) -> io::Result<usize> {
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    let flags = libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    let flags = 0;
+
+    unsafe {
+        let space = libc::CMSG_SPACE(mem::size_of::<[RawFd; MAX_FDS]>() as u32) as usize;
+        let mut control = vec![0u64; (space + 7) / 8];
+
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = space as _;
+
+        let res = libc::recvmsg(sock.as_raw_fd(), &mut msg, flags);
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        } else if res == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Handover socket is closed",
+            ));
+        }
+
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET
+                && (*cmsg).cmsg_type == libc::SCM_RIGHTS
+            {
+                let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+                let len = ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize)
+                    / mem::size_of::<RawFd>();
+                for idx in 0..len {
+                    fds.push(ptr::read_unaligned(data.add(idx)));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+
+        if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Control message is truncated",
+            ));
+        }
+        Ok(res as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net;
+
+    #[test]
+    fn test_send_recv_fds() {
+        let (sock1, sock2) = UnixStream::pair().unwrap();
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = lst.local_addr().unwrap();
+
+        let sent = send_fds(&sock1, b"data", &[lst.as_raw_fd()]).unwrap();
+        assert_eq!(sent, 4);
+        drop(lst);
+
+        let mut buf = [0u8; 16];
+        let mut fds = Vec::new();
+        let size = recv_fds(&sock2, &mut buf, &mut fds).unwrap();
+        assert_eq!(&buf[..size], b"data");
+        assert_eq!(fds.len(), 1);
+
+        // received descriptor refers to the same socket
+        let lst = unsafe { net::TcpListener::from_raw_fd(fds[0]) };
+        assert_eq!(lst.local_addr().unwrap(), addr);
+        assert!(net::TcpStream::connect(addr).is_ok());
+    }
+
+    #[test]
+    fn test_ready_notifier() {
+        let (sock1, mut sock2) = UnixStream::pair().unwrap();
+        let mut notify = ready_notifier(sock1);
+        notify(ServerStatus::NotReady);
+        notify(ServerStatus::Ready);
+        notify(ServerStatus::Ready);
+
+        let mut buf = Vec::new();
+        sock2.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, vec![READY]);
+    }
+}
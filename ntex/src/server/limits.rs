@@ -0,0 +1,282 @@
+//! Per-service connection limits
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{fmt, net::IpAddr, time::Instant};
+
+use crate::util::HashMap;
+
+use super::socket::Stream;
+
+/// Connection limits of the service
+///
+/// Limits are enforced by accept loop before connection is passed to
+/// a worker, connections over the limit get closed. All listeners of
+/// the service share the same limits.
+///
+/// ```rust
+/// use ntex::server::{ConnectionLimits, Server};
+/// use ntex::service::fn_service;
+///
+/// fn main() -> std::io::Result<()> {
+///     let _srv = Server::build()
+///         .bind("public", "127.0.0.1:0", |_| {
+///             fn_service(|_| async { Ok::<_, ()>(()) })
+///         })?
+///         .set_limits(
+///             "public",
+///             ConnectionLimits::new()
+///                 .maxconn(10_000)
+///                 .maxconn_per_ip(64)
+///                 .rate(1_000),
+///         );
+///     Ok(())
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ConnectionLimits {
+    maxconn: Option<usize>,
+    maxconn_per_ip: Option<usize>,
+    rate: Option<u32>,
+}
+
+impl ConnectionLimits {
+    /// Create limits, no limits are set by default
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of concurrent connections of the service.
+    pub fn maxconn(mut self, num: usize) -> Self {
+        self.maxconn = Some(num);
+        self
+    }
+
+    /// Set the maximum number of concurrent connections from a single peer
+    /// ip address.
+    ///
+    /// Unix domain socket connections are not limited.
+    pub fn maxconn_per_ip(mut self, num: usize) -> Self {
+        self.maxconn_per_ip = Some(num);
+        self
+    }
+
+    /// Set the maximum number of accepted connections per second.
+    ///
+    /// Bursts of up to `num` connections are allowed.
+    ///
+    /// Panics if `num` is 0.
+    pub fn rate(mut self, num: u32) -> Self {
+        assert!(num > 0, "Connection rate must be greater than 0");
+        self.rate = Some(num);
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Connection rejection reason
+pub(super) enum Reject {
+    MaxConn,
+    PerIp,
+    Rate,
+}
+
+impl Reject {
+    pub(super) const ALL: [Reject; 3] = [Reject::MaxConn, Reject::PerIp, Reject::Rate];
+
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            Reject::MaxConn => "maxconn",
+            Reject::PerIp => "maxconn_per_ip",
+            Reject::Rate => "rate",
+        }
+    }
+}
+
+/// Limits state shared by service listeners
+pub(super) struct Limiter {
+    limits: ConnectionLimits,
+    conns: AtomicUsize,
+    peers: Mutex<HashMap<IpAddr, usize>>,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl Limiter {
+    pub(super) fn new(limits: ConnectionLimits) -> Arc<Self> {
+        Arc::new(Limiter {
+            limits,
+            conns: AtomicUsize::new(0),
+            peers: Mutex::new(HashMap::default()),
+            bucket: Mutex::new((limits.rate.unwrap_or(0) as f64, Instant::now())),
+        })
+    }
+
+    /// Check limits for accepted connection.
+    ///
+    /// Returned guard must be held for the connection's lifetime.
+    pub(super) fn admit(self: &Arc<Self>, io: &Stream) -> Result<LimitGuard, Reject> {
+        if let Some(max) = self.limits.maxconn {
+            if self.conns.load(Ordering::Acquire) >= max {
+                return Err(Reject::MaxConn);
+            }
+        }
+
+        // peers lock is held until rate token is taken, so rejected
+        // connection does not occupy per-ip slot
+        let mut peers = None;
+        if let Some(max) = self.limits.maxconn_per_ip {
+            if let Some(ip) = io.peer_ip() {
+                let guard = self.peers.lock().unwrap();
+                if guard.get(&ip).copied().unwrap_or(0) >= max {
+                    return Err(Reject::PerIp);
+                }
+                peers = Some((ip, guard));
+            }
+        }
+
+        if let Some(rate) = self.limits.rate {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.1).as_secs_f64();
+            bucket.0 = (bucket.0 + elapsed * rate as f64).min(rate as f64);
+            bucket.1 = now;
+            if bucket.0 < 1.0 {
+                return Err(Reject::Rate);
+            }
+            bucket.0 -= 1.0;
+        }
+
+        let peer = peers.map(|(ip, mut guard)| {
+            *guard.entry(ip).or_insert(0) += 1;
+            ip
+        });
+
+        self.conns.fetch_add(1, Ordering::AcqRel);
+        Ok(LimitGuard {
+            peer,
+            limiter: self.clone(),
+        })
+    }
+}
+
+/// Connection slot, released on drop
+pub(super) struct LimitGuard {
+    limiter: Arc<Limiter>,
+    peer: Option<IpAddr>,
+}
+
+impl Drop for LimitGuard {
+    fn drop(&mut self) {
+        self.limiter.conns.fetch_sub(1, Ordering::AcqRel);
+
+        if let Some(ip) = self.peer {
+            let mut peers = self.limiter.peers.lock().unwrap();
+            if let Some(num) = peers.get_mut(&ip) {
+                *num -= 1;
+                if *num == 0 {
+                    peers.remove(&ip);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LimitGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LimitGuard")
+            .field("limits", &self.limiter.limits)
+            .field("peer", &self.peer)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net;
+
+    use super::*;
+
+    fn stream(lst: &net::TcpListener) -> (Stream, net::TcpStream) {
+        let client = net::TcpStream::connect(lst.local_addr().unwrap()).unwrap();
+        (Stream::Tcp(lst.accept().unwrap().0), client)
+    }
+
+    #[test]
+    fn test_maxconn() {
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let limiter = Limiter::new(ConnectionLimits::new().maxconn(2));
+
+        let (io1, _c1) = stream(&lst);
+        let (io2, _c2) = stream(&lst);
+        let g1 = limiter.admit(&io1).unwrap();
+        let _g2 = limiter.admit(&io2).unwrap();
+        assert_eq!(limiter.admit(&io1).unwrap_err(), Reject::MaxConn);
+
+        drop(g1);
+        let _g3 = limiter.admit(&io1).unwrap();
+        assert!(format!("{:?}", limiter.admit(&io1)).contains("MaxConn"));
+    }
+
+    #[test]
+    fn test_maxconn_per_ip() {
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let limiter = Limiter::new(ConnectionLimits::new().maxconn_per_ip(1));
+
+        let (io, _c) = stream(&lst);
+        let g = limiter.admit(&io).unwrap();
+        assert!(format!("{:?}", g).contains("127.0.0.1"));
+        assert_eq!(limiter.admit(&io).unwrap_err(), Reject::PerIp);
+        assert_eq!(limiter.conns.load(Ordering::Relaxed), 1);
+
+        drop(g);
+        assert!(limiter.peers.lock().unwrap().is_empty());
+        assert_eq!(limiter.conns.load(Ordering::Relaxed), 0);
+        assert!(limiter.admit(&io).is_ok());
+    }
+
+    #[test]
+    fn test_rate() {
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let limiter = Limiter::new(ConnectionLimits::new().rate(2));
+
+        let (io, _c) = stream(&lst);
+        assert!(limiter.admit(&io).is_ok());
+        assert!(limiter.admit(&io).is_ok());
+        assert_eq!(limiter.admit(&io).unwrap_err(), Reject::Rate);
+
+        std::thread::sleep(std::time::Duration::from_millis(600));
+        assert!(limiter.admit(&io).is_ok());
+        assert_eq!(limiter.admit(&io).unwrap_err(), Reject::Rate);
+        assert_eq!(Reject::Rate.as_str(), "rate");
+    }
+
+    #[test]
+    fn test_rate_after_limits() {
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let limiter =
+            Limiter::new(ConnectionLimits::new().maxconn(1).maxconn_per_ip(1).rate(2));
+
+        // rejected by maxconn, rate token is not taken
+        let (io, _c) = stream(&lst);
+        let g = limiter.admit(&io).unwrap();
+        assert_eq!(limiter.admit(&io).unwrap_err(), Reject::MaxConn);
+        assert_eq!(limiter.admit(&io).unwrap_err(), Reject::MaxConn);
+        drop(g);
+        assert!(limiter.admit(&io).is_ok());
+
+        // rejected by rate, per-ip slot is not taken
+        let limiter = Limiter::new(ConnectionLimits::new().maxconn_per_ip(2).rate(1));
+        let _g = limiter.admit(&io).unwrap();
+        assert_eq!(limiter.admit(&io).unwrap_err(), Reject::Rate);
+        assert_eq!(
+            limiter.peers.lock().unwrap().get(&io.peer_ip().unwrap()),
+            Some(&1)
+        );
+        assert_eq!(limiter.conns.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rate_zero() {
+        let _ = ConnectionLimits::new().rate(0);
+    }
+}
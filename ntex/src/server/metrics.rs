@@ -0,0 +1,558 @@
+//! Server metrics registry
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{cell::RefCell, fmt, fmt::Write, time::Duration};
+
+use crate::http::StatusCode;
+use crate::time::{sleep, Millis};
+use crate::util::PoolId;
+
+use super::{limits::Reject, Token};
+
+/// Worker metrics sampling interval
+const SAMPLE_INTERVAL: Millis = Millis::ONE_SEC;
+
+/// Latency histogram buckets, in microseconds
+const BUCKETS: [u64; 11] = [
+    5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000, 2_500_000,
+    5_000_000, 10_000_000,
+];
+
+const POOLS: [(PoolId, &str); 16] = [
+    (PoolId::P0, "P0"),
+    (PoolId::P1, "P1"),
+    (PoolId::P2, "P2"),
+    (PoolId::P3, "P3"),
+    (PoolId::P4, "P4"),
+    (PoolId::P5, "P5"),
+    (PoolId::P6, "P6"),
+    (PoolId::P7, "P7"),
+    (PoolId::P8, "P8"),
+    (PoolId::P9, "P9"),
+    (PoolId::P10, "P10"),
+    (PoolId::P11, "P11"),
+    (PoolId::P12, "P12"),
+    (PoolId::P13, "P13"),
+    (PoolId::P14, "P14"),
+    (PoolId::DEFAULT, "DEFAULT"),
+];
+
+/// Status codes are in 100..=999 range
+const MAX_STATUS: usize = 1000;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Metrics>> = RefCell::new(None);
+}
+
+/// Server metrics registry
+///
+/// Accept loop, workers and http services of the server report into
+/// the registry. Registry could be obtained with `Server::metrics()` method
+/// or with [`Metrics::current()`] from within worker thread.
+///
+/// Worker's connection counts and memory pools usage are sampled
+/// once a second.
+#[derive(Clone, Default)]
+pub struct Metrics(Arc<Inner>);
+
+struct Inner {
+    listeners: Mutex<Vec<Arc<ListenerMetrics>>>,
+    workers: Mutex<Vec<Arc<WorkerMetrics>>>,
+    backpressure: AtomicBool,
+    backpressure_total: AtomicU64,
+    tls_failures: AtomicU64,
+    responses: Box<[AtomicU64]>,
+    latency: Histogram,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Inner {
+            listeners: Mutex::new(Vec::new()),
+            workers: Mutex::new(Vec::new()),
+            backpressure: AtomicBool::new(false),
+            backpressure_total: AtomicU64::new(0),
+            tls_failures: AtomicU64::new(0),
+            responses: (0..MAX_STATUS).map(|_| AtomicU64::new(0)).collect(),
+            latency: Histogram::default(),
+        }
+    }
+}
+
+pub(super) struct ListenerMetrics {
+    token: Token,
+    name: String,
+    addr: String,
+    accepted: AtomicU64,
+    rejected: [AtomicU64; Reject::ALL.len()],
+}
+
+pub(super) struct WorkerMetrics {
+    idx: usize,
+    connections: AtomicUsize,
+    pools: [AtomicUsize; 16],
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum: AtomicU64,
+}
+
+impl Metrics {
+    /// Get metrics registry of the server that runs current worker.
+    ///
+    /// Returns `None` if called outside of server's worker thread.
+    pub fn current() -> Option<Metrics> {
+        CURRENT.with(|m| m.borrow().clone())
+    }
+
+    /// Number of connections accepted by listeners with the name
+    pub fn accepted(&self, name: &str) -> u64 {
+        self.0
+            .listeners
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|lst| lst.name == name)
+            .map(|lst| lst.accepted.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Number of connections rejected by limits of listeners with the name
+    pub fn rejected(&self, name: &str) -> u64 {
+        self.0
+            .listeners
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|lst| lst.name == name)
+            .flat_map(|lst| lst.rejected.iter())
+            .map(|num| num.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Number of active connections across all workers
+    pub fn connections(&self) -> usize {
+        self.0
+            .workers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|wrk| wrk.connections.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Number of times accept loop has been paused because of back-pressure
+    pub fn backpressure_pauses(&self) -> u64 {
+        self.0.backpressure_total.load(Ordering::Relaxed)
+    }
+
+    /// Number of failed tls handshakes
+    pub fn tls_handshake_failures(&self) -> u64 {
+        self.0.tls_failures.load(Ordering::Relaxed)
+    }
+
+    /// Number of http responses with the status code
+    pub fn http_responses(&self, status: StatusCode) -> u64 {
+        self.0
+            .responses
+            .get(status.as_u16() as usize)
+            .map(|num| num.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Render metrics in prometheus text format
+    pub fn render(&self) -> String {
+        let mut buf = String::new();
+        let _ = self.write(&mut buf);
+        buf
+    }
+
+    fn write(&self, buf: &mut String) -> fmt::Result {
+        let inner = &self.0;
+
+        header(
+            buf,
+            "ntex_connections_accepted_total",
+            "counter",
+            "Number of accepted connections",
+        )?;
+        for lst in inner.listeners.lock().unwrap().iter() {
+            writeln!(
+                buf,
+                "ntex_connections_accepted_total{{listener=\"{}\",addr=\"{}\"}} {}",
+                escape(&lst.name),
+                escape(&lst.addr),
+                lst.accepted.load(Ordering::Relaxed)
+            )?;
+        }
+
+        header(
+            buf,
+            "ntex_connections_rejected_total",
+            "counter",
+            "Number of connections rejected by limits",
+        )?;
+        for lst in inner.listeners.lock().unwrap().iter() {
+            for reason in Reject::ALL {
+                writeln!(
+                    buf,
+                    "ntex_connections_rejected_total{{listener=\"{}\",addr=\"{}\",reason=\"{}\"}} {}",
+                    escape(&lst.name),
+                    escape(&lst.addr),
+                    reason.as_str(),
+                    lst.rejected[reason as usize].load(Ordering::Relaxed)
+                )?;
+            }
+        }
+
+        let workers = inner.workers.lock().unwrap().clone();
+        header(
+            buf,
+            "ntex_worker_connections",
+            "gauge",
+            "Number of active connections",
+        )?;
+        for wrk in &workers {
+            writeln!(
+                buf,
+                "ntex_worker_connections{{worker=\"{}\"}} {}",
+                wrk.idx,
+                wrk.connections.load(Ordering::Relaxed)
+            )?;
+        }
+        header(
+            buf,
+            "ntex_memory_pool_allocated_bytes",
+            "gauge",
+            "Number of bytes allocated by memory pool",
+        )?;
+        for wrk in &workers {
+            for (idx, (_, name)) in POOLS.iter().enumerate() {
+                let size = wrk.pools[idx].load(Ordering::Relaxed);
+                if size != 0 {
+                    writeln!(
+                        buf,
+                        "ntex_memory_pool_allocated_bytes{{worker=\"{}\",pool=\"{}\"}} {}",
+                        wrk.idx, name, size
+                    )?;
+                }
+            }
+        }
+
+        header(
+            buf,
+            "ntex_accept_backpressure",
+            "gauge",
+            "Accept loop is paused, all workers are busy",
+        )?;
+        writeln!(
+            buf,
+            "ntex_accept_backpressure {}",
+            inner.backpressure.load(Ordering::Relaxed) as u8
+        )?;
+        header(
+            buf,
+            "ntex_accept_backpressure_total",
+            "counter",
+            "Number of accept loop back-pressure pauses",
+        )?;
+        writeln!(
+            buf,
+            "ntex_accept_backpressure_total {}",
+            self.backpressure_pauses()
+        )?;
+
+        header(
+            buf,
+            "ntex_tls_handshake_failures_total",
+            "counter",
+            "Number of failed tls handshakes",
+        )?;
+        writeln!(
+            buf,
+            "ntex_tls_handshake_failures_total {}",
+            self.tls_handshake_failures()
+        )?;
+
+        header(
+            buf,
+            "ntex_http_responses_total",
+            "counter",
+            "Number of http responses by status code",
+        )?;
+        for (code, num) in inner.responses.iter().enumerate() {
+            let num = num.load(Ordering::Relaxed);
+            if num != 0 {
+                writeln!(
+                    buf,
+                    "ntex_http_responses_total{{status=\"{}\"}} {}",
+                    code, num
+                )?;
+            }
+        }
+
+        header(
+            buf,
+            "ntex_http_request_duration_seconds",
+            "histogram",
+            "Time spent processing http requests",
+        )?;
+        let mut count = 0;
+        for (idx, le) in BUCKETS.iter().enumerate() {
+            count += inner.latency.buckets[idx].load(Ordering::Relaxed);
+            writeln!(
+                buf,
+                "ntex_http_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                *le as f64 / 1_000_000.0,
+                count
+            )?;
+        }
+        let count = inner.latency.count.load(Ordering::Relaxed);
+        writeln!(
+            buf,
+            "ntex_http_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            count
+        )?;
+        writeln!(
+            buf,
+            "ntex_http_request_duration_seconds_sum {}",
+            inner.latency.sum.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        )?;
+        writeln!(buf, "ntex_http_request_duration_seconds_count {}", count)
+    }
+
+    pub(super) fn add_listener(&self, token: Token, name: &str, addr: String) {
+        self.0
+            .listeners
+            .lock()
+            .unwrap()
+            .push(Arc::new(ListenerMetrics {
+                token,
+                addr,
+                name: name.to_string(),
+                accepted: AtomicU64::new(0),
+                rejected: Default::default(),
+            }));
+    }
+
+    pub(super) fn listener(&self, token: Token) -> Option<Arc<ListenerMetrics>> {
+        self.0
+            .listeners
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|lst| lst.token == token)
+            .cloned()
+    }
+
+    pub(super) fn add_worker(&self, idx: usize) -> Arc<WorkerMetrics> {
+        let wrk = Arc::new(WorkerMetrics {
+            idx,
+            connections: AtomicUsize::new(0),
+            pools: Default::default(),
+        });
+        let mut workers = self.0.workers.lock().unwrap();
+        workers.retain(|w| w.idx != idx);
+        workers.push(wrk.clone());
+        wrk
+    }
+
+    pub(super) fn remove_worker(&self, idx: usize) {
+        self.0.workers.lock().unwrap().retain(|w| w.idx != idx);
+    }
+
+    pub(super) fn set_backpressure(&self, on: bool) {
+        let prev = self.0.backpressure.swap(on, Ordering::Relaxed);
+        if on && !prev {
+            self.0.backpressure_total.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Set registry for current worker thread
+    pub(super) fn set_current(&self) {
+        CURRENT.with(|m| *m.borrow_mut() = Some(self.clone()));
+    }
+
+    fn response(&self, status: StatusCode, elapsed: Duration) {
+        if let Some(num) = self.0.responses.get(status.as_u16() as usize) {
+            num.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let micros = elapsed.as_micros() as u64;
+        let latency = &self.0.latency;
+        if let Some(idx) = BUCKETS.iter().position(|le| micros <= *le) {
+            latency.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        }
+        latency.count.fetch_add(1, Ordering::Relaxed);
+        latency.sum.fetch_add(micros, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("connections", &self.connections())
+            .field("backpressure_pauses", &self.backpressure_pauses())
+            .field("tls_handshake_failures", &self.tls_handshake_failures())
+            .finish()
+    }
+}
+
+impl ListenerMetrics {
+    pub(super) fn accepted(&self) {
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn rejected(&self, reason: Reject) {
+        self.rejected[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl WorkerMetrics {
+    /// Periodically sample worker's connections and memory pools.
+    ///
+    /// Must run on worker's thread.
+    pub(super) async fn sample(self: Arc<Self>) {
+        loop {
+            self.connections
+                .store(super::worker::num_connections(), Ordering::Relaxed);
+            for (idx, (id, _)) in POOLS.iter().enumerate() {
+                self.pools[idx].store(id.pool_ref().allocated(), Ordering::Relaxed);
+            }
+            sleep(SAMPLE_INTERVAL).await;
+        }
+    }
+}
+
+/// Report http response to current worker's registry
+pub(crate) fn http_response(status: StatusCode, elapsed: Duration) {
+    CURRENT.with(|m| {
+        if let Some(ref m) = *m.borrow() {
+            m.response(status, elapsed)
+        }
+    })
+}
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+/// Report failed tls handshake to current worker's registry
+pub(crate) fn tls_handshake_failed() {
+    CURRENT.with(|m| {
+        if let Some(ref m) = *m.borrow() {
+            m.0.tls_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    })
+}
+
+fn header(buf: &mut String, name: &str, tp: &str, help: &str) -> fmt::Result {
+    writeln!(buf, "# HELP {} {}", name, help)?;
+    writeln!(buf, "# TYPE {} {}", name, tp)
+}
+
+/// Escape label value
+fn escape(val: &str) -> String {
+    val.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let m = Metrics::default();
+        m.add_listener(Token(0), "web", "127.0.0.1:8080".to_string());
+        m.add_listener(Token(1), "web\"2", "127.0.0.1:8081".to_string());
+        m.listener(Token(0)).unwrap().accepted();
+        m.listener(Token(0)).unwrap().accepted();
+        assert!(m.listener(Token(2)).is_none());
+        assert_eq!(m.accepted("web"), 2);
+        assert_eq!(m.accepted("other"), 0);
+        m.listener(Token(0)).unwrap().rejected(Reject::Rate);
+        m.listener(Token(1)).unwrap().rejected(Reject::PerIp);
+        assert_eq!(m.rejected("web"), 1);
+        assert_eq!(m.rejected("other"), 0);
+
+        let wrk = m.add_worker(0);
+        wrk.connections.store(3, Ordering::Relaxed);
+        wrk.pools[15].store(1024, Ordering::Relaxed);
+        m.add_worker(1).connections.store(2, Ordering::Relaxed);
+        assert_eq!(m.connections(), 5);
+        m.remove_worker(1);
+        assert_eq!(m.connections(), 3);
+
+        m.set_backpressure(true);
+        m.set_backpressure(true);
+        m.set_backpressure(false);
+        m.set_backpressure(true);
+        assert_eq!(m.backpressure_pauses(), 2);
+
+        m.response(StatusCode::OK, Duration::from_millis(3));
+        m.response(StatusCode::OK, Duration::from_millis(70));
+        m.response(StatusCode::NOT_FOUND, Duration::from_secs(20));
+        assert_eq!(m.http_responses(StatusCode::OK), 2);
+        assert_eq!(m.http_responses(StatusCode::NOT_FOUND), 1);
+        assert_eq!(m.http_responses(StatusCode::BAD_REQUEST), 0);
+
+        let text = m.render();
+        assert!(text.contains(
+            "ntex_connections_accepted_total{listener=\"web\",addr=\"127.0.0.1:8080\"} 2\n"
+        ));
+        assert!(text.contains("listener=\"web\\\"2\""));
+        assert!(text.contains(
+            "ntex_connections_rejected_total{listener=\"web\",addr=\"127.0.0.1:8080\",reason=\"rate\"} 1\n"
+        ));
+        assert!(text.contains(
+            "ntex_connections_rejected_total{listener=\"web\",addr=\"127.0.0.1:8080\",reason=\"maxconn\"} 0\n"
+        ));
+        assert!(text.contains("ntex_worker_connections{worker=\"0\"} 3\n"));
+        assert!(!text.contains("ntex_worker_connections{worker=\"1\"}"));
+        assert!(text.contains(
+            "ntex_memory_pool_allocated_bytes{worker=\"0\",pool=\"DEFAULT\"} 1024\n"
+        ));
+        assert!(text.contains("ntex_accept_backpressure 1\n"));
+        assert!(text.contains("ntex_accept_backpressure_total 2\n"));
+        assert!(text.contains("ntex_tls_handshake_failures_total 0\n"));
+        assert!(text.contains("ntex_http_responses_total{status=\"200\"} 2\n"));
+        assert!(text.contains("ntex_http_responses_total{status=\"404\"} 1\n"));
+        assert!(
+            text.contains("ntex_http_request_duration_seconds_bucket{le=\"0.005\"} 1\n")
+        );
+        assert!(text.contains("ntex_http_request_duration_seconds_bucket{le=\"0.1\"} 2\n"));
+        assert!(text.contains("ntex_http_request_duration_seconds_bucket{le=\"10\"} 2\n"));
+        assert!(text.contains("ntex_http_request_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("ntex_http_request_duration_seconds_count 3\n"));
+        assert!(format!("{:?}", m).contains("Metrics"));
+    }
+
+    #[test]
+    fn test_current() {
+        assert!(Metrics::current().is_none());
+        http_response(StatusCode::OK, Duration::from_millis(1));
+
+        let m = Metrics::default();
+        m.set_current();
+        http_response(StatusCode::OK, Duration::from_millis(1));
+        assert_eq!(m.http_responses(StatusCode::OK), 1);
+        assert_eq!(
+            Metrics::current().unwrap().http_responses(StatusCode::OK),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "rustls"))]
+    fn test_tls_handshake_failed() {
+        tls_handshake_failed();
+
+        let m = Metrics::default();
+        m.set_current();
+        tls_handshake_failed();
+        assert_eq!(m.tls_handshake_failures(), 1);
+        assert_eq!(Metrics::current().unwrap().tls_handshake_failures(), 1);
+    }
+}
@@ -4,9 +4,14 @@ use std::{future::Future, io, pin::Pin, task::Context, task::Poll};
 use async_channel::Sender;
 
 mod accept;
+mod activation;
 mod builder;
 mod config;
 mod counter;
+#[cfg(unix)]
+mod handover;
+mod limits;
+mod metrics;
 mod service;
 mod socket;
 mod test;
@@ -23,6 +28,11 @@ pub use ntex_tls::max_concurrent_ssl_accept;
 pub(crate) use self::builder::create_tcp_listener;
 pub use self::builder::ServerBuilder;
 pub use self::config::{Config, ServiceConfig, ServiceRuntime};
+pub use self::limits::ConnectionLimits;
+pub(crate) use self::metrics::http_response;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+pub(crate) use self::metrics::tls_handshake_failed;
+pub use self::metrics::Metrics;
 pub use self::test::{build_test_server, test_server, TestServer};
 
 #[non_exhaustive]
@@ -58,6 +68,15 @@ pub enum SslError<E> {
     Service(E),
 }
 
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+impl<E> SslError<E> {
+    /// Tls handshake error, failure is reported to server metrics
+    pub(crate) fn handshake<T: Into<Box<dyn std::error::Error>>>(err: T) -> Self {
+        tls_handshake_failed();
+        SslError::Ssl(err.into())
+    }
+}
+
 #[derive(Debug)]
 enum ServerCommand {
     WorkerFaulted(usize),
@@ -71,6 +90,11 @@ enum ServerCommand {
     },
     /// Notify of server stop
     Notify(oneshot::Sender<()>),
+    /// Get server metrics registry
+    Metrics(oneshot::Sender<Metrics>),
+    #[cfg(unix)]
+    /// Hand listeners over to a new process
+    Handover(std::process::Command, oneshot::Sender<io::Result<()>>),
 }
 
 /// Server controller
@@ -129,6 +153,49 @@ impl Server {
             let _ = rx.await;
         }
     }
+
+    /// Get server metrics registry
+    ///
+    /// Returns `None` if server is stopped.
+    pub fn metrics(&self) -> impl Future<Output = Option<Metrics>> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.0.try_send(ServerCommand::Metrics(tx));
+        async move { rx.await.ok() }
+    }
+}
+
+#[cfg(unix)]
+impl Server {
+    /// Hand listeners over to a new process and stop gracefully.
+    ///
+    /// Spawns child process with provided command and passes all listeners
+    /// to it over unix socket. Child process must adopt listeners with
+    /// [`ServerBuilder::from_env_listeners()`] method and bind services with
+    /// the same names. Once child process reports `ServerStatus::Ready`,
+    /// server stops accepting new connections and stops gracefully,
+    /// same as `stop(true)`. If child process fails or does not become ready
+    /// within [`ServerBuilder::handover_timeout()`], child process is killed,
+    /// server continues accepting connections and error is returned.
+    ///
+    /// ```rust,no_run
+    /// # async fn upgrade(srv: ntex::server::Server) -> std::io::Result<()> {
+    /// let exe = std::env::current_exe()?;
+    /// srv.handover(std::process::Command::new(exe)).await
+    /// # }
+    /// ```
+    pub fn handover(
+        &self,
+        cmd: std::process::Command,
+    ) -> impl Future<Output = io::Result<()>> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.0.try_send(ServerCommand::Handover(cmd, tx));
+        async move {
+            match rx.await {
+                Ok(res) => res,
+                Err(_) => Err(io::Error::new(io::ErrorKind::Other, "Server is stopped")),
+            }
+        }
+    }
 }
 
 impl Clone for Server {
@@ -68,6 +68,15 @@ impl Listener {
         Listener::Uds(lst)
     }
 
+    #[cfg(unix)]
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Listener::Tcp(lst) => lst.try_clone().map(Listener::Tcp),
+            #[cfg(unix)]
+            Listener::Uds(lst) => lst.try_clone().map(Listener::Uds),
+        }
+    }
+
     pub(crate) fn local_addr(&self) -> SocketAddr {
         match self {
             Listener::Tcp(lst) => SocketAddr::Tcp(lst.local_addr().unwrap()),
@@ -158,6 +167,17 @@ pub enum Stream {
     Uds(std::os::unix::net::UnixStream),
 }
 
+impl Stream {
+    /// Peer ip address of tcp stream
+    pub(super) fn peer_ip(&self) -> Option<net::IpAddr> {
+        match self {
+            Stream::Tcp(stream) => stream.peer_addr().ok().map(|addr| addr.ip()),
+            #[cfg(unix)]
+            Stream::Uds(_) => None,
+        }
+    }
+}
+
 impl TryFrom<Stream> for Io {
     type Error = io::Error;
 
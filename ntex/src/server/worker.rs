@@ -11,6 +11,8 @@ use crate::util::{
 };
 
 use super::accept::{AcceptNotify, Command};
+use super::limits::LimitGuard;
+use super::metrics::Metrics;
 use super::service::{BoxedServerService, InternalServiceFactory, ServerMessage};
 use super::{counter::Counter, socket::Stream, Token};
 
@@ -32,6 +34,7 @@ pub(super) struct StopCommand {
 pub(super) struct Connection {
     pub(super) io: Stream,
     pub(super) token: Token,
+    pub(super) limit: Option<LimitGuard>,
 }
 
 const STOP_TIMEOUT: Millis = Millis::ONE_SEC;
@@ -166,12 +169,16 @@ impl Worker {
         factories: Vec<Box<dyn InternalServiceFactory>>,
         availability: WorkerAvailability,
         shutdown_timeout: Millis,
+        metrics: Metrics,
     ) -> WorkerClient {
         let (tx1, rx1) = unbounded();
         let (tx2, rx2) = unbounded();
         let avail = availability.clone();
+        let wrk_metrics = metrics.add_worker(idx);
 
         Arbiter::default().exec_fn(move || {
+            metrics.set_current();
+            spawn(wrk_metrics.sample());
             spawn(async move {
                 match Worker::create(rx1, rx2, factories, availability, shutdown_timeout)
                     .await
@@ -492,11 +499,13 @@ impl Future for Worker {
                                 self.factories[srv.factory].name(msg.token)
                             );
                         }
+                        let limit = msg.limit;
                         let fut = srv
                             .service
                             .call_static((Some(guard), ServerMessage::Connect(msg.io)));
                         spawn(async move {
                             let _ = fut.await;
+                            drop(limit);
                         });
                     } else {
                         return Poll::Ready(());
@@ -11,6 +11,7 @@ use crate::util::{BoxFuture, Extensions, Ready};
 
 use super::app_service::{AppFactory, AppService};
 use super::config::{AppConfig, ServiceConfig};
+use super::middleware::BodyLimit;
 use super::request::WebRequest;
 use super::resource::Resource;
 use super::response::WebResponse;
@@ -34,6 +35,7 @@ pub struct App<M, F, Err: ErrorRenderer = DefaultError> {
     state_factories: Vec<FnStateFactory>,
     error_renderer: Err,
     case_insensitive: bool,
+    body_limit: BodyLimit,
 }
 
 impl App<Identity, Filter<DefaultError>, DefaultError> {
@@ -49,6 +51,7 @@ impl App<Identity, Filter<DefaultError>, DefaultError> {
             extensions: Extensions::new(),
             error_renderer: DefaultError,
             case_insensitive: false,
+            body_limit: BodyLimit::default(),
         }
     }
 }
@@ -66,6 +69,7 @@ impl<Err: ErrorRenderer> App<Identity, Filter<Err>, Err> {
             extensions: Extensions::new(),
             error_renderer: err,
             case_insensitive: false,
+            body_limit: BodyLimit::default(),
         }
     }
 }
@@ -366,6 +370,7 @@ where
             extensions: self.extensions,
             error_renderer: self.error_renderer,
             case_insensitive: self.case_insensitive,
+            body_limit: self.body_limit,
         }
     }
 
@@ -406,6 +411,7 @@ where
             extensions: self.extensions,
             error_renderer: self.error_renderer,
             case_insensitive: self.case_insensitive,
+            body_limit: self.body_limit,
         }
     }
 
@@ -416,6 +422,21 @@ where
         self.case_insensitive = true;
         self
     }
+
+    /// Set max size of request's payload for all application's services.
+    ///
+    /// Requests with larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` response before any middleware or extractor
+    /// runs, streamed payloads fail with `PayloadError::Overflow` error
+    /// once they exceed the limit. Scopes and resources could set
+    /// smaller limit, the smallest limit applies.
+    /// To disable limit set value to 0.
+    ///
+    /// By default payload size is not limited.
+    pub fn body_limit(mut self, limit: u64) -> Self {
+        self.body_limit = BodyLimit::new(limit);
+        self
+    }
 }
 
 impl<M, F, Err> App<M, F, Err>
@@ -498,6 +519,7 @@ where
             default: self.default,
             extensions: RefCell::new(Some(self.extensions)),
             case_insensitive: self.case_insensitive,
+            body_limit: self.body_limit,
         };
         map_config(app, move |_| cfg.clone())
     }
@@ -526,6 +548,7 @@ where
             default: self.default,
             extensions: RefCell::new(Some(self.extensions)),
             case_insensitive: self.case_insensitive,
+            body_limit: self.body_limit,
         }
     }
 }
@@ -552,6 +575,7 @@ where
             default: self.default,
             extensions: RefCell::new(Some(self.extensions)),
             case_insensitive: self.case_insensitive,
+            body_limit: self.body_limit,
         }
     }
 }
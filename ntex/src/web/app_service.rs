@@ -14,6 +14,7 @@ use super::config::AppConfig;
 use super::error::ErrorRenderer;
 use super::guard::Guard;
 use super::httprequest::{HttpRequest, HttpRequestPool};
+use super::middleware::{BodyLimit, BodyLimitMiddleware};
 use super::request::WebRequest;
 use super::response::WebResponse;
 use super::rmap::ResourceMap;
@@ -48,6 +49,7 @@ where
     pub(super) default: Option<Rc<HttpNewService<Err>>>,
     pub(super) external: RefCell<Vec<ResourceDef>>,
     pub(super) case_insensitive: bool,
+    pub(super) body_limit: BodyLimit,
 }
 
 impl<T, F, Err> ServiceFactory<Request> for AppFactory<T, F, Err>
@@ -65,7 +67,7 @@ where
     type Response = WebResponse;
     type Error = Err::Container;
     type InitError = ();
-    type Service = AppFactoryService<T::Service, Err>;
+    type Service = AppFactoryService<BodyLimitMiddleware<T::Service>, Err>;
     type Future<'f> = BoxFuture<'f, Result<Self::Service, Self::InitError>> where Self: 'f;
 
     fn create(&self, _: ()) -> Self::Future<'_> {
@@ -88,7 +90,7 @@ where
     type Response = WebResponse;
     type Error = Err::Container;
     type InitError = ();
-    type Service = AppFactoryService<T::Service, Err>;
+    type Service = AppFactoryService<BodyLimitMiddleware<T::Service>, Err>;
     type Future<'f> = BoxFuture<'f, Result<Self::Service, Self::InitError>> where Self: 'f;
 
     fn create(&self, config: AppConfig) -> Self::Future<'_> {
@@ -107,6 +109,7 @@ where
         let state_factories = self.state_factories.clone();
         let mut extensions = self.extensions.borrow_mut().take().unwrap_or_default();
         let middleware = self.middleware.clone();
+        let body_limit = self.body_limit;
         let external = std::mem::take(&mut *self.external.borrow_mut());
 
         let mut router = Router::build();
@@ -169,7 +172,7 @@ where
             Ok(AppFactoryService {
                 rmap,
                 state,
-                service: middleware.create(service),
+                service: body_limit.create(middleware.create(service)),
                 pool: HttpRequestPool::create(),
                 _t: PhantomData,
             })
@@ -2,6 +2,7 @@ use std::{net::SocketAddr, rc::Rc};
 
 use crate::{router::ResourceDef, util::Extensions};
 
+use super::info::TrustedProxies;
 use super::resource::Resource;
 use super::route::Route;
 use super::service::{AppServiceFactory, ServiceFactoryWrapper, WebServiceFactory};
@@ -11,17 +12,32 @@ use super::{DefaultError, ErrorRenderer};
 #[derive(Debug, Clone)]
 pub struct AppConfig(Rc<AppConfigInner>);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct AppConfigInner {
     secure: bool,
     host: String,
     addr: SocketAddr,
+    proxies: Option<TrustedProxies>,
 }
 
 impl AppConfig {
     /// Create an AppConfig instance.
     pub fn new(secure: bool, addr: SocketAddr, host: String) -> Self {
-        AppConfig(Rc::new(AppConfigInner { secure, host, addr }))
+        AppConfig(Rc::new(AppConfigInner {
+            secure,
+            host,
+            addr,
+            proxies: None,
+        }))
+    }
+
+    /// Set trusted proxies.
+    ///
+    /// Check [TrustedProxies](./struct.TrustedProxies.html) documentation
+    /// for more information.
+    pub fn with_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        Rc::make_mut(&mut self.0).proxies = Some(proxies);
+        self
     }
 
     /// Server host name.
@@ -44,6 +60,14 @@ impl AppConfig {
     pub fn local_addr(&self) -> SocketAddr {
         self.0.addr
     }
+
+    /// Trusted proxies, if set.
+    ///
+    /// If trusted proxies are not set, forwarded headers of any request
+    /// are honoured.
+    pub fn trusted_proxies(&self) -> Option<&TrustedProxies> {
+        self.0.proxies.as_ref()
+    }
 }
 
 impl Default for AppConfig {
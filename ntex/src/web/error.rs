@@ -161,6 +161,90 @@ pub enum QueryPayloadError {
     Deserialize(#[from] serde::de::value::Error),
 }
 
+/// A set of errors that can occur during parsing multipart payloads
+#[derive(Error, Debug)]
+pub enum MultipartError {
+    /// Content type error
+    #[error("Content type error")]
+    ContentType,
+    /// Multipart boundary is missing or malformed
+    #[error("Multipart boundary error")]
+    Boundary,
+    /// Field headers are malformed or too large
+    #[error("Multipart field headers error")]
+    Headers,
+    /// Field's `Content-Disposition` header is missing or malformed
+    #[error("Multipart field Content-Disposition error")]
+    ContentDisposition,
+    /// Multipart stream ended unexpectedly
+    #[error("Multipart stream is incomplete")]
+    Incomplete,
+    /// Multipart payload size is bigger than allowed. (default: 8MB)
+    #[error("Multipart payload size is bigger than allowed (limit: {limit} bytes)")]
+    Overflow { limit: usize },
+    /// Field size is bigger than allowed. (default: 2MB)
+    #[error("Multipart field size is bigger than allowed (limit: {limit} bytes)")]
+    FieldOverflow { limit: usize },
+    /// Payload error
+    #[error("Error that occur during reading payload: {0}")]
+    Payload(#[from] error::PayloadError),
+}
+
+/// A set of errors that can occur during processing CORS requests
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CorsError {
+    /// Request's origin is not allowed
+    #[error("Origin is not allowed to make this request")]
+    OriginNotAllowed,
+    /// Requested method is not allowed
+    #[error("Requested method is not allowed")]
+    MethodNotAllowed,
+    /// One or more requested headers are not allowed
+    #[error("One or more requested headers are not allowed")]
+    HeadersNotAllowed,
+    /// `Access-Control-Request-Method` header is missing or malformed
+    #[error("Access-Control-Request-Method header is missing or malformed")]
+    BadRequestMethod,
+    /// `Access-Control-Request-Headers` header is malformed
+    #[error("Access-Control-Request-Headers header is malformed")]
+    BadRequestHeaders,
+}
+
+/// A set of errors that can occur during serving static files
+#[derive(Error, Debug)]
+pub enum FilesError {
+    /// Path is not a directory
+    #[error("Path is not a directory. Unable to serve static files")]
+    IsNotDirectory,
+    /// Cannot render directory
+    #[error("Unable to render directory without index file")]
+    IsDirectory,
+    /// Request path contains forbidden segments
+    #[error("The path contains forbidden segments")]
+    InvalidPath,
+    /// Io error
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(feature = "cookie")]
+/// A set of errors that can occur during session processing
+#[derive(Error, Debug)]
+pub enum SessionError {
+    /// Cannot serialize session value
+    #[error("Cannot serialize session value: {0}")]
+    Serialize(serde_json::Error),
+    /// Cannot deserialize session value
+    #[error("Cannot deserialize session value: {0}")]
+    Deserialize(serde_json::Error),
+    /// Session state is too large for the store
+    #[error("Session state is too large")]
+    Overflow,
+    /// Session store error
+    #[error("Session store error: {0}")]
+    Store(Box<dyn std::error::Error>),
+}
+
 #[derive(Error, Debug)]
 pub enum PayloadError {
     /// Http error.
@@ -837,6 +921,66 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn test_multipart_error() {
+        let req = TestRequest::default().to_http_request();
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &MultipartError::Overflow { limit: 0 },
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &MultipartError::FieldOverflow { limit: 0 },
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &MultipartError::Payload(error::PayloadError::Overflow),
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &MultipartError::Boundary,
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_cors_error() {
+        let req = TestRequest::default().to_http_request();
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &CorsError::OriginNotAllowed,
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &CorsError::BadRequestMethod,
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_files_error() {
+        let req = TestRequest::default().to_http_request();
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &FilesError::IsDirectory,
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &FilesError::InvalidPath,
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &FilesError::Io(io::Error::new(io::ErrorKind::PermissionDenied, "denied")),
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
     #[test]
     fn test_query_payload_error() {
         let req = TestRequest::default().to_http_request();
@@ -854,6 +998,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_typed_header_error() {
+        let req = TestRequest::default().to_http_request();
+        let err =
+            crate::http::header::TypedHeaderError::Missing(crate::http::header::USER_AGENT);
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn test_path_error() {
         let req = TestRequest::default().to_http_request();
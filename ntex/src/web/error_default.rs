@@ -173,6 +173,54 @@ impl WebResponseError<DefaultError> for error::JsonPayloadError {
     }
 }
 
+/// Response renderer for `MultipartError`
+impl WebResponseError<DefaultError> for error::MultipartError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::MultipartError::Overflow { .. }
+            | error::MultipartError::FieldOverflow { .. }
+            | error::MultipartError::Payload(http::error::PayloadError::Overflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Response renderer for `CorsError`
+impl WebResponseError<DefaultError> for error::CorsError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::CorsError::BadRequestMethod | error::CorsError::BadRequestHeaders => {
+                StatusCode::BAD_REQUEST
+            }
+            _ => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// Response renderer for `FilesError`
+impl WebResponseError<DefaultError> for error::FilesError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::FilesError::IsNotDirectory | error::FilesError::IsDirectory => {
+                StatusCode::NOT_FOUND
+            }
+            error::FilesError::InvalidPath => StatusCode::BAD_REQUEST,
+            error::FilesError::Io(ref e) => {
+                WebResponseError::<DefaultError>::status_code(e)
+            }
+        }
+    }
+}
+
+/// Error renderer for `TypedHeaderError`
+impl WebResponseError<DefaultError> for header::TypedHeaderError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
 /// Error renderer for `PathError`
 impl WebResponseError<DefaultError> for error::PathError {
     fn status_code(&self) -> StatusCode {
@@ -214,6 +262,10 @@ impl WebResponseError<DefaultError> for coo_kie::ParseError {
     }
 }
 
+#[cfg(feature = "cookie")]
+/// Return `InternalServerError` for `SessionError`
+impl WebResponseError<DefaultError> for error::SessionError {}
+
 /// Return `BadRequest` for `ContentTypeError`
 impl WebResponseError<DefaultError> for http::error::ContentTypeError {
     fn status_code(&self) -> StatusCode {
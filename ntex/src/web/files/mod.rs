@@ -0,0 +1,638 @@
+//! Static files support
+use std::path::{Path, PathBuf};
+use std::{fmt, fmt::Write, fs, io, marker::PhantomData, rc::Rc};
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::http::{header, Method, Response};
+use crate::router::ResourceDef;
+use crate::service::{Service, ServiceCtx, ServiceFactory};
+use crate::util::{BoxFuture, Ready};
+use crate::web::dev::{WebServiceConfig, WebServiceFactory};
+use crate::web::error::{BlockingError, ErrorRenderer, FilesError};
+use crate::web::{block, HttpRequest, WebRequest, WebResponse};
+
+mod named;
+
+pub use self::named::NamedFile;
+
+use self::named::Flags;
+
+type DirectoryRenderer =
+    dyn Fn(&Directory, &HttpRequest) -> Result<Response, io::Error> + 'static;
+
+/// Static files handling service
+///
+/// `Files` service must be registered with `App::service()` or
+/// `Scope::service()` method. All file reads are performed on a thread pool.
+///
+/// ```rust
+/// use ntex::web::{self, files};
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         files::Files::new("/static", ".")
+///             .show_files_listing()
+///             .index_file("index.html")
+///     );
+/// }
+/// ```
+pub struct Files<Err> {
+    path: String,
+    directory: PathBuf,
+    index: Option<String>,
+    show_index: bool,
+    redirect_to_slash: bool,
+    renderer: Rc<DirectoryRenderer>,
+    file_flags: Flags,
+    _t: PhantomData<Err>,
+}
+
+impl<Err> fmt::Debug for Files<Err> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Files")
+            .field("path", &self.path)
+            .field("directory", &self.directory)
+            .field("index", &self.index)
+            .field("show_index", &self.show_index)
+            .finish()
+    }
+}
+
+impl<Err> Clone for Files<Err> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            directory: self.directory.clone(),
+            index: self.index.clone(),
+            show_index: self.show_index,
+            redirect_to_slash: self.redirect_to_slash,
+            renderer: self.renderer.clone(),
+            file_flags: self.file_flags,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<Err: ErrorRenderer> Files<Err> {
+    /// Create new `Files` instance for specified base directory.
+    ///
+    /// `Files` uses thread pool for blocking filesystem operations.
+    pub fn new<T: Into<PathBuf>>(path: &str, dir: T) -> Files<Err> {
+        let orig = dir.into();
+        let directory = match orig.canonicalize() {
+            Ok(dir) if dir.is_dir() => dir,
+            _ => {
+                log::error!("Specified path is not a directory: {:?}", orig);
+                PathBuf::new()
+            }
+        };
+
+        Files {
+            path: path.to_string(),
+            directory,
+            index: None,
+            show_index: false,
+            redirect_to_slash: false,
+            renderer: Rc::new(directory_listing),
+            file_flags: Flags::default(),
+            _t: PhantomData,
+        }
+    }
+
+    /// Show files listing for directories.
+    ///
+    /// By default show files listing is disabled.
+    pub fn show_files_listing(mut self) -> Self {
+        self.show_index = true;
+        self
+    }
+
+    /// Redirects to a slash-ended path when browsing a directory.
+    ///
+    /// By default never redirect.
+    pub fn redirect_to_slash_directory(mut self) -> Self {
+        self.redirect_to_slash = true;
+        self
+    }
+
+    /// Set custom directory renderer
+    pub fn files_listing_renderer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Directory, &HttpRequest) -> Result<Response, io::Error> + 'static,
+    {
+        self.renderer = Rc::new(f);
+        self
+    }
+
+    /// Set index file
+    ///
+    /// Shows specific index file for directory "/" instead of
+    /// showing files listing.
+    pub fn index_file<T: Into<String>>(mut self, index: T) -> Self {
+        self.index = Some(index.into());
+        self
+    }
+
+    /// Specifies whether to use ETag or not.
+    ///
+    /// Default is true.
+    pub fn use_etag(mut self, value: bool) -> Self {
+        self.file_flags.set(Flags::ETAG, value);
+        self
+    }
+
+    /// Specifies whether to use Last-Modified or not.
+    ///
+    /// Default is true.
+    pub fn use_last_modified(mut self, value: bool) -> Self {
+        self.file_flags.set(Flags::LAST_MODIFIED, value);
+        self
+    }
+
+    /// Specifies whether to send `Content-Disposition` header or not.
+    ///
+    /// Default is true.
+    pub fn use_content_disposition(mut self, value: bool) -> Self {
+        self.file_flags.set(Flags::CONTENT_DISPOSITION, value);
+        self
+    }
+}
+
+impl<Err> WebServiceFactory<Err> for Files<Err>
+where
+    Err: ErrorRenderer,
+    Err::Container: From<FilesError>,
+{
+    fn register(self, config: &mut WebServiceConfig<Err>) {
+        let rdef = if config.is_root() || !self.path.is_empty() {
+            ResourceDef::root_prefix(self.path.as_str())
+        } else {
+            ResourceDef::prefix(self.path.as_str())
+        };
+        config.register_service(rdef, None, self, None)
+    }
+}
+
+impl<Err> ServiceFactory<WebRequest<Err>> for Files<Err>
+where
+    Err: ErrorRenderer,
+    Err::Container: From<FilesError>,
+{
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Service = FilesService<Err>;
+    type InitError = ();
+    type Future<'f> = Ready<Self::Service, Self::InitError>;
+
+    fn create(&self, _: ()) -> Self::Future<'_> {
+        Ready::Ok(FilesService {
+            inner: Rc::new(self.clone()),
+        })
+    }
+}
+
+/// Static files service
+pub struct FilesService<Err> {
+    inner: Rc<Files<Err>>,
+}
+
+impl<Err> Service<WebRequest<Err>> for FilesService<Err>
+where
+    Err: ErrorRenderer,
+    Err::Container: From<FilesError>,
+{
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Future<'f> = BoxFuture<'f, Result<WebResponse, Err::Container>>;
+
+    fn call<'a>(
+        &'a self,
+        req: WebRequest<Err>,
+        _: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        let (req, _) = req.into_parts();
+        let inner = self.inner.clone();
+
+        Box::pin(async move {
+            match inner.handle(&req).await {
+                Ok(res) => Ok(WebResponse::new(res, req)),
+                Err(e) => Ok(WebResponse::from_err::<Err, _>(e, req)),
+            }
+        })
+    }
+}
+
+impl<Err> Files<Err> {
+    async fn handle(&self, req: &HttpRequest) -> Result<Response, FilesError> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return Ok(Response::MethodNotAllowed()
+                .header(header::ALLOW, "GET, HEAD")
+                .finish());
+        }
+        if self.directory.as_os_str().is_empty() {
+            return Err(FilesError::IsNotDirectory);
+        }
+
+        let path = self
+            .directory
+            .join(path_from_tail(req.match_info().unprocessed())?);
+        let base = self.directory.clone();
+        let (path, is_dir) = block(move || {
+            let path = resolve(&base, &path)?;
+            let is_dir = path.is_dir();
+            Ok::<_, io::Error>((path, is_dir))
+        })
+        .await
+        .map_err(blocking_err)?;
+
+        if !is_dir {
+            let file = NamedFile::open_async(path).await?;
+            return Ok(file.set_flags(self.file_flags).into_response(req));
+        }
+
+        if self.redirect_to_slash
+            && !req.path().ends_with('/')
+            && (self.index.is_some() || self.show_index)
+        {
+            let mut location = format!("{}/", req.path());
+            if !req.query_string().is_empty() {
+                let _ = write!(&mut location, "?{}", req.query_string());
+            }
+            return Ok(Response::Found()
+                .header(header::LOCATION, location)
+                .finish());
+        }
+
+        if let Some(ref index) = self.index {
+            let base = self.directory.clone();
+            let index = path.join(index);
+            let file = block(move || NamedFile::open(resolve(&base, &index)?))
+                .await
+                .map_err(blocking_err);
+            match file {
+                Ok(file) => return Ok(file.set_flags(self.file_flags).into_response(req)),
+                Err(FilesError::Io(e))
+                    if e.kind() == io::ErrorKind::NotFound && self.show_index => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if self.show_index {
+            let base = self.directory.clone();
+            let dir = block(move || Directory::read(base, path))
+                .await
+                .map_err(blocking_err)?;
+            Ok((*self.renderer)(&dir, req)?)
+        } else {
+            Err(FilesError::IsDirectory)
+        }
+    }
+}
+
+fn blocking_err(err: BlockingError<io::Error>) -> FilesError {
+    match err {
+        BlockingError::Error(e) => FilesError::Io(e),
+        BlockingError::Canceled => FilesError::Io(io::Error::new(
+            io::ErrorKind::Other,
+            "Blocking operation is canceled",
+        )),
+    }
+}
+
+/// Canonicalize path and make sure it does not escape base directory
+///
+/// Symlinks inside of base directory could point anywhere.
+fn resolve(base: &Path, path: &Path) -> io::Result<PathBuf> {
+    let path = path.canonicalize()?;
+    if path.starts_with(base) {
+        Ok(path)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Path is outside of base directory",
+        ))
+    }
+}
+
+/// Convert unprocessed part of the request path to a relative file path
+fn path_from_tail(tail: &str) -> Result<PathBuf, FilesError> {
+    let mut buf = PathBuf::new();
+
+    for segment in tail.split('/') {
+        let segment = percent_decode_str(segment)
+            .decode_utf8()
+            .map_err(|_| FilesError::InvalidPath)?;
+
+        if segment.is_empty() || segment == "." {
+            continue;
+        } else if segment.starts_with('.')
+            || segment.starts_with('*')
+            || segment.contains(['/', '\\', '\0', ':', '<', '>', '|'])
+        {
+            return Err(FilesError::InvalidPath);
+        }
+        buf.push(segment.as_ref());
+    }
+    Ok(buf)
+}
+
+/// A directory to render with files listing
+#[derive(Debug)]
+pub struct Directory {
+    /// Base directory
+    pub base: PathBuf,
+    /// Path of subdirectory to generate listing for
+    pub path: PathBuf,
+    entries: Vec<fs::DirEntry>,
+}
+
+impl Directory {
+    fn read(base: PathBuf, path: PathBuf) -> io::Result<Directory> {
+        let mut entries = fs::read_dir(&path)?
+            .filter_map(|entry| entry.ok())
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        Ok(Directory {
+            base,
+            path,
+            entries,
+        })
+    }
+
+    /// Directory entries, sorted by name
+    pub fn entries(&self) -> &[fs::DirEntry] {
+        &self.entries
+    }
+
+    /// Is this entry visible from this directory?
+    pub fn is_visible(&self, entry: &fs::DirEntry) -> bool {
+        !entry.file_name().to_string_lossy().starts_with('.')
+    }
+}
+
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/');
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Default files listing renderer
+fn directory_listing(dir: &Directory, req: &HttpRequest) -> Result<Response, io::Error> {
+    let title = match percent_decode_str(req.path()).decode_utf8() {
+        Ok(path) => escape_html(&path),
+        Err(_) => escape_html(req.path()),
+    };
+    let base = req.path().trim_end_matches('/');
+
+    let mut body = String::new();
+    for entry in dir.entries().iter().filter(|entry| dir.is_visible(entry)) {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let is_dir = entry.metadata().map(|m| m.is_dir()).unwrap_or(false);
+        let slash = if is_dir { "/" } else { "" };
+
+        let _ = writeln!(
+            body,
+            "<li><a href=\"{}/{}{}\">{}{}</a></li>",
+            base,
+            utf8_percent_encode(&name, PATH_SEGMENT),
+            slash,
+            escape_html(&name),
+            slash,
+        );
+    }
+
+    let html = format!(
+        "<html>\
+         <head><meta charset=\"utf-8\"><title>Index of {}</title></head>\
+         <body><h1>Index of {}</h1>\
+         <ul>\n{}</ul></body>\n</html>",
+        title, title, body
+    );
+    Ok(Response::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App};
+
+    #[crate::rt_test]
+    async fn test_files() {
+        let srv = init_service(App::new().service(Files::new("/", "."))).await;
+
+        let req = TestRequest::with_uri("/Cargo.toml").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key(header::ETAG));
+        let body = read_body(resp).await;
+        assert_eq!(body, std::fs::read("Cargo.toml").unwrap());
+
+        let req = TestRequest::with_uri("/tests/test.png").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+
+        let req = TestRequest::with_uri("/missing.file").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/../Cargo.toml").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/%2e%2e/Cargo.toml").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/tests").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/Cargo.toml")
+            .method(Method::POST)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[crate::rt_test]
+    async fn test_files_scope() {
+        let srv = init_service(
+            App::new().service(
+                web::scope("/assets").service(
+                    Files::new("/static", ".")
+                        .use_etag(false)
+                        .use_last_modified(false)
+                        .use_content_disposition(false),
+                ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/assets/static/Cargo.toml").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(header::ETAG));
+        assert!(!resp.headers().contains_key(header::LAST_MODIFIED));
+        assert!(!resp.headers().contains_key(header::CONTENT_DISPOSITION));
+
+        let req = TestRequest::with_uri("/assets/Cargo.toml").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[crate::rt_test]
+    async fn test_files_index() {
+        let srv = init_service(
+            App::new().service(
+                Files::new("/", ".")
+                    .index_file("Cargo.toml")
+                    .redirect_to_slash_directory(),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        assert_eq!(body, std::fs::read("Cargo.toml").unwrap());
+
+        let req = TestRequest::with_uri("/src?q=1").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        assert_eq!(resp.headers().get(header::LOCATION).unwrap(), "/src/?q=1");
+
+        let req = TestRequest::with_uri("/src/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[crate::rt_test]
+    async fn test_files_listing() {
+        let srv =
+            init_service(App::new().service(Files::new("/", ".").show_files_listing()))
+                .await;
+
+        let req = TestRequest::with_uri("/tests/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<title>Index of /tests/</title>"));
+        assert!(body.contains("<a href=\"/tests/test.png\">test.png</a>"));
+
+        let srv = init_service(
+            App::new().service(
+                Files::new("/", ".")
+                    .show_files_listing()
+                    .files_listing_renderer(|dir, _| {
+                        Ok(Response::Ok().body(format!("{}", dir.entries().len())))
+                    }),
+            ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/tests/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let count = std::fs::read_dir("tests").unwrap().count();
+        assert_eq!(read_body(resp).await, count.to_string());
+    }
+
+    #[cfg(unix)]
+    #[crate::rt_test]
+    async fn test_files_symlink_outside_root() {
+        let tmp = std::env::temp_dir().join(format!("ntex-files-{}", std::process::id()));
+        let root = tmp.join("root");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("inside.txt"), "inside").unwrap();
+        std::fs::write(tmp.join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(tmp.join("secret.txt"), root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(root.join("inside.txt"), root.join("link")).unwrap();
+
+        let srv = init_service(
+            App::new()
+                .service(Files::new("/files", &root))
+                .service(Files::new("/index", &root).index_file("escape")),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/files/inside.txt").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // symlink within root directory
+        let req = TestRequest::with_uri("/files/link").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, "inside");
+
+        let req = TestRequest::with_uri("/files/escape").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/index/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_path_from_tail() {
+        assert_eq!(
+            path_from_tail("/a/b%20c/./d.txt").unwrap(),
+            PathBuf::from("a/b c/d.txt")
+        );
+        assert!(path_from_tail("/a/../b").is_err());
+        assert!(path_from_tail("/.hidden").is_err());
+        assert!(path_from_tail("/a%2fb").is_err());
+        assert!(path_from_tail("/a%5cb").is_err());
+        assert!(path_from_tail("/%ff").is_err());
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,591 @@
+use std::{error::Error, fmt, fs, future::Future, io, io::Read, io::Seek, pin::Pin};
+use std::{path::Path, path::PathBuf, task::Context, task::Poll, time::SystemTime};
+
+use bitflags::bitflags;
+use mime::Mime;
+
+use crate::http::body::{Body, BodySize, MessageBody};
+use crate::http::header::{self, EntityTag, HeaderValue, Range};
+use crate::http::{Method, Response, StatusCode};
+use crate::rt::{spawn_blocking, JoinHandle};
+use crate::util::Bytes;
+use crate::web::error::ErrorRenderer;
+use crate::web::middleware::{if_range, preconditions, typed};
+use crate::web::responder::{Ready, Responder};
+use crate::web::HttpRequest;
+
+const CHUNK_SIZE: u64 = 65_536;
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub(super) struct Flags: u8 {
+        const ETAG                = 0b0000_0001;
+        const LAST_MODIFIED       = 0b0000_0010;
+        const CONTENT_DISPOSITION = 0b0000_0100;
+    }
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Flags::all()
+    }
+}
+
+/// A file with an associated name.
+///
+/// `NamedFile` could be used as a handler's response. Response's
+/// `Content-Type` is derived from the file extension, conditional
+/// (`If-Match`, `If-None-Match`, `If-Modified-Since`, `If-Unmodified-Since`)
+/// and range (`Range`, `If-Range`) requests are supported.
+///
+/// ```rust,no_run
+/// use ntex::web::{self, files::NamedFile};
+///
+/// async fn index() -> std::io::Result<NamedFile> {
+///     NamedFile::open_async("static/index.html").await
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(web::resource("/").to(index));
+/// }
+/// ```
+pub struct NamedFile {
+    path: PathBuf,
+    file: fs::File,
+    md: fs::Metadata,
+    modified: Option<SystemTime>,
+    content_type: Mime,
+    content_disposition: Option<HeaderValue>,
+    status_code: StatusCode,
+    flags: Flags,
+}
+
+impl fmt::Debug for NamedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamedFile")
+            .field("path", &self.path)
+            .field("content_type", &self.content_type)
+            .field("status_code", &self.status_code)
+            .finish()
+    }
+}
+
+impl NamedFile {
+    /// Creates an instance from a previously opened file.
+    ///
+    /// The given `path` need not exist and is only used to determine the
+    /// `Content-Type` and `Content-Disposition` headers.
+    pub fn from_file<P: AsRef<Path>>(file: fs::File, path: P) -> io::Result<NamedFile> {
+        let path = path.as_ref().to_path_buf();
+
+        let filename = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Provided path has no filename",
+                ))
+            }
+        };
+        let content_type = mime_guess::from_path(&path).first_or_octet_stream();
+        let content_disposition = content_disposition(&content_type, &filename);
+
+        let md = file.metadata()?;
+        let modified = md.modified().ok();
+
+        Ok(NamedFile {
+            path,
+            file,
+            md,
+            modified,
+            content_type,
+            content_disposition,
+            status_code: StatusCode::OK,
+            flags: Flags::default(),
+        })
+    }
+
+    /// Attempts to open a file in read-only mode.
+    ///
+    /// This method blocks current thread, use `open_async()` from
+    /// async contexts.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<NamedFile> {
+        Self::from_file(fs::File::open(&path)?, path)
+    }
+
+    /// Attempts to open a file in read-only mode on a thread pool.
+    pub async fn open_async<P: AsRef<Path>>(path: P) -> io::Result<NamedFile> {
+        let path = path.as_ref().to_path_buf();
+        match spawn_blocking(move || NamedFile::open(path)).await {
+            Ok(res) => res,
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Blocking operation is canceled",
+            )),
+        }
+    }
+
+    #[inline]
+    /// Returns reference to the underlying `File` object.
+    pub fn file(&self) -> &fs::File {
+        &self.file
+    }
+
+    #[inline]
+    /// Retrieve the path of this file.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    #[inline]
+    /// Retrieve content type of this file.
+    pub fn content_type(&self) -> &Mime {
+        &self.content_type
+    }
+
+    /// Set response **Status Code**
+    pub fn set_status_code(mut self, status: StatusCode) -> Self {
+        self.status_code = status;
+        self
+    }
+
+    /// Set the MIME Content-Type for serving this file. By default
+    /// the Content-Type is inferred from the filename extension.
+    pub fn set_content_type(mut self, mime_type: Mime) -> Self {
+        self.content_type = mime_type;
+        self
+    }
+
+    /// Set the Content-Disposition for serving this file.
+    ///
+    /// By default `inline` is used for text, image and video files,
+    /// and `attachment` for others.
+    pub fn set_content_disposition(mut self, value: HeaderValue) -> Self {
+        self.content_disposition = Some(value);
+        self.flags.insert(Flags::CONTENT_DISPOSITION);
+        self
+    }
+
+    /// Disable `Content-Disposition` header.
+    ///
+    /// By default Content-Disposition` header is enabled.
+    pub fn disable_content_disposition(mut self) -> Self {
+        self.flags.remove(Flags::CONTENT_DISPOSITION);
+        self
+    }
+
+    /// Specifies whether to use ETag or not.
+    ///
+    /// Default is true.
+    pub fn use_etag(mut self, value: bool) -> Self {
+        self.flags.set(Flags::ETAG, value);
+        self
+    }
+
+    /// Specifies whether to use Last-Modified or not.
+    ///
+    /// Default is true.
+    pub fn use_last_modified(mut self, value: bool) -> Self {
+        self.flags.set(Flags::LAST_MODIFIED, value);
+        self
+    }
+
+    pub(super) fn set_flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Strong entity tag of the file
+    pub fn etag(&self) -> Option<EntityTag> {
+        let dur = self
+            .modified
+            .and_then(|mtime| mtime.duration_since(std::time::UNIX_EPOCH).ok());
+
+        dur.map(|dur| {
+            #[cfg(unix)]
+            let ino = std::os::unix::fs::MetadataExt::ino(&self.md);
+            #[cfg(not(unix))]
+            let ino = 0;
+
+            EntityTag::strong(format!(
+                "{:x}-{:x}-{:x}-{:x}",
+                ino,
+                self.md.len(),
+                dur.as_secs(),
+                dur.subsec_nanos()
+            ))
+        })
+    }
+
+    /// Last modification time of the file
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Creates a `Response` with file as a streaming body.
+    pub fn into_response(self, req: &HttpRequest) -> Response {
+        let etag = if self.flags.contains(Flags::ETAG) {
+            self.etag()
+        } else {
+            None
+        };
+        let last_modified = if self.flags.contains(Flags::LAST_MODIFIED) {
+            self.modified.map(httpdate::HttpDate::from)
+        } else {
+            None
+        };
+        // http dates have seconds precision
+        let modified = last_modified.map(SystemTime::from);
+
+        let mut resp = Response::build(self.status_code);
+        if let Some(ref etag) = etag {
+            resp.header(header::ETAG, etag.to_string());
+        }
+        if let Some(lm) = last_modified {
+            resp.header(header::LAST_MODIFIED, lm.to_string());
+        }
+
+        // check preconditions
+        if let Some(status) = preconditions(req.head(), etag.as_ref(), modified) {
+            return resp.status(status).finish();
+        }
+
+        resp.header(header::CONTENT_TYPE, self.content_type.as_ref());
+        if self.flags.contains(Flags::CONTENT_DISPOSITION) {
+            if let Some(ref cd) = self.content_disposition {
+                resp.header(header::CONTENT_DISPOSITION, cd.clone());
+            }
+        }
+        resp.header(header::ACCEPT_RANGES, "bytes");
+
+        let size = self.md.len();
+        let mut offset = 0;
+        let mut length = size;
+
+        // check range, multiple ranges are not supported, full content is returned
+        if let Some(range) = typed::<Range>(req.headers()) {
+            if if_range(req.head(), etag.as_ref(), modified) {
+                let ranges: Vec<_> = range
+                    .0
+                    .iter()
+                    .filter_map(|spec| spec.to_satisfiable_range(size))
+                    .collect();
+
+                match ranges.len() {
+                    0 => {
+                        return resp
+                            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                            .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+                            .finish();
+                    }
+                    1 => {
+                        let (start, end) = ranges[0];
+                        offset = start;
+                        length = end - start + 1;
+                        resp.status(StatusCode::PARTIAL_CONTENT).header(
+                            header::CONTENT_RANGE,
+                            format!("bytes {}-{}/{}", start, end, size),
+                        );
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let file = if req.method() == Method::HEAD {
+            None
+        } else {
+            Some(self.file)
+        };
+        resp.body(Body::from_message(ChunkedReadFile {
+            file,
+            offset,
+            size: length,
+            counter: 0,
+            fut: None,
+        }))
+    }
+}
+
+impl<Err: ErrorRenderer> Responder<Err> for NamedFile {
+    type Future = Ready<Response>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        self.into_response(req).into()
+    }
+}
+
+fn content_disposition(ct: &Mime, filename: &str) -> Option<HeaderValue> {
+    let kind = match ct.type_() {
+        mime::IMAGE | mime::TEXT | mime::VIDEO => "inline",
+        _ => "attachment",
+    };
+    let escaped = filename.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let value = if filename.is_ascii() {
+        format!("{}; filename=\"{}\"", kind, escaped)
+    } else {
+        let ascii: String = escaped
+            .chars()
+            .map(|c| if c.is_ascii() { c } else { '_' })
+            .collect();
+        format!(
+            "{}; filename=\"{}\"; filename*=UTF-8''{}",
+            kind,
+            ascii,
+            percent_encoding::utf8_percent_encode(
+                filename,
+                percent_encoding::NON_ALPHANUMERIC
+            )
+        )
+    };
+    HeaderValue::from_str(&value).ok()
+}
+
+/// Body that reads file in chunks on a thread pool
+struct ChunkedReadFile {
+    size: u64,
+    offset: u64,
+    file: Option<fs::File>,
+    fut: Option<JoinHandle<io::Result<(fs::File, Bytes)>>>,
+    counter: u64,
+}
+
+impl MessageBody for ChunkedReadFile {
+    fn size(&self) -> BodySize {
+        BodySize::Sized(self.size)
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        if let Some(ref mut fut) = self.fut {
+            return match Pin::new(fut).poll(cx) {
+                Poll::Ready(Ok(Ok((file, chunk)))) => {
+                    self.fut.take();
+                    self.file = Some(file);
+                    self.offset += chunk.len() as u64;
+                    self.counter += chunk.len() as u64;
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+                Poll::Ready(Ok(Err(e))) => Poll::Ready(Some(Err(e.into()))),
+                Poll::Ready(Err(_)) => Poll::Ready(Some(Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Blocking operation is canceled",
+                ))))),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+
+        if self.counter >= self.size {
+            return Poll::Ready(None);
+        }
+
+        if let Some(mut file) = self.file.take() {
+            let offset = self.offset;
+            let max_bytes = std::cmp::min(self.size - self.counter, CHUNK_SIZE);
+
+            self.fut = Some(spawn_blocking(move || {
+                let mut buf = Vec::with_capacity(max_bytes as usize);
+                file.seek(io::SeekFrom::Start(offset))?;
+                let n = file.by_ref().take(max_bytes).read_to_end(&mut buf)?;
+                if n == 0 {
+                    Err(io::ErrorKind::UnexpectedEof.into())
+                } else {
+                    Ok((file, Bytes::from(buf)))
+                }
+            }));
+            self.poll_next_chunk(cx)
+        } else {
+            // HEAD request
+            Poll::Ready(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::HeaderValue;
+    use crate::web::test::{read_body, respond_to, TestRequest};
+    use crate::web::{DefaultError, WebResponse};
+
+    async fn body(req: HttpRequest, resp: Response) -> Bytes {
+        read_body(WebResponse::new(resp, req)).await
+    }
+
+    #[crate::rt_test]
+    async fn test_named_file() {
+        let file = NamedFile::open("tests/test.png").unwrap();
+        assert_eq!(file.path(), Path::new("tests/test.png"));
+        assert_eq!(file.content_type(), &mime::IMAGE_PNG);
+        assert!(file.file().metadata().is_ok());
+        assert!(format!("{:?}", file).contains("test.png"));
+        let etag = file.etag().unwrap().to_string();
+
+        let req = TestRequest::default().to_http_request();
+        let resp = respond_to(file, &req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"test.png\""
+        );
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), etag.as_str());
+        assert!(resp.headers().contains_key(header::LAST_MODIFIED));
+        assert_eq!(resp.headers().get(header::ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(
+            body(req, resp).await,
+            Bytes::from(std::fs::read("tests/test.png").unwrap())
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_named_file_settings() {
+        let file = NamedFile::open_async("Cargo.toml")
+            .await
+            .unwrap()
+            .set_status_code(StatusCode::NOT_FOUND)
+            .set_content_type(mime::APPLICATION_OCTET_STREAM)
+            .set_content_disposition(HeaderValue::from_static("attachment"))
+            .use_etag(false)
+            .use_last_modified(false);
+
+        let req = TestRequest::default().to_http_request();
+        let resp = file.into_response(&req);
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment"
+        );
+        assert!(!resp.headers().contains_key(header::ETAG));
+        assert!(!resp.headers().contains_key(header::LAST_MODIFIED));
+
+        let file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .disable_content_disposition();
+        let resp = file.into_response(&req);
+        assert!(!resp.headers().contains_key(header::CONTENT_DISPOSITION));
+
+        assert!(NamedFile::open("missing.file").is_err());
+        assert!(NamedFile::open_async("missing.file").await.is_err());
+    }
+
+    #[crate::rt_test]
+    async fn test_named_file_conditional() {
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let etag = file.etag().unwrap().to_string();
+        let lm = httpdate::HttpDate::from(file.last_modified().unwrap()).to_string();
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, etag.as_str())
+            .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "\"other\", W/\"x\"")
+            .header(header::IF_MODIFIED_SINCE, lm.as_str())
+            .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_MODIFIED_SINCE, lm.as_str())
+            .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_header(header::IF_MATCH, "\"other\"").to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        let req =
+            TestRequest::with_header(header::IF_MATCH, etag.as_str()).to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_header(
+            header::IF_UNMODIFIED_SINCE,
+            "Mon, 01 Jan 2001 00:00:00 GMT",
+        )
+        .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[crate::rt_test]
+    async fn test_named_file_ranges() {
+        let content = std::fs::read("Cargo.toml").unwrap();
+        let size = content.len();
+        let etag = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .etag()
+            .unwrap()
+            .to_string();
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=10-20").to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            format!("bytes 10-20/{}", size).as_str()
+        );
+        assert_eq!(body(req, resp).await, &content[10..21]);
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=-5")
+            .header(header::IF_RANGE, etag.as_str())
+            .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(body(req, resp).await, &content[size - 5..]);
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=-5")
+            .header(header::IF_RANGE, "\"other\"")
+            .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body(req, resp).await, &content[..]);
+
+        let req = TestRequest::with_header(header::RANGE, format!("bytes={}-", size))
+            .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            format!("bytes */{}", size).as_str()
+        );
+
+        let req =
+            TestRequest::with_header(header::RANGE, "bytes=1-2,5-6").to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-10")
+            .method(Method::HEAD)
+            .to_http_request();
+        let resp = NamedFile::open("Cargo.toml").unwrap().into_response(&req);
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(resp.body().size(), BodySize::Sized(11));
+        assert!(body(req, resp).await.is_empty());
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        let cd = content_disposition(&mime::IMAGE_PNG, "test.png").unwrap();
+        assert_eq!(cd, "inline; filename=\"test.png\"");
+        let cd = content_disposition(&mime::APPLICATION_OCTET_STREAM, "a\"b.bin").unwrap();
+        assert_eq!(cd, "attachment; filename=\"a\\\"b.bin\"");
+        let cd = content_disposition(&mime::TEXT_PLAIN, "тест.txt").unwrap();
+        assert_eq!(
+            cd,
+            "inline; filename=\"____.txt\"; \
+             filename*=UTF-8''%D1%82%D0%B5%D1%81%D1%82%2Etxt"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::cell::Ref;
+use std::{cell::Ref, net::IpAddr};
 
 use crate::http::header::{self, HeaderName};
 use crate::http::RequestHead;
@@ -8,6 +8,147 @@ const X_FORWARDED_FOR: &[u8] = b"x-forwarded-for";
 const X_FORWARDED_HOST: &[u8] = b"x-forwarded-host";
 const X_FORWARDED_PROTO: &[u8] = b"x-forwarded-proto";
 
+/// Trusted reverse proxies
+///
+/// Forwarded headers (`Forwarded`, `X-Forwarded-For`, `X-Forwarded-Host`
+/// and `X-Forwarded-Proto`) are honoured only if request's peer address
+/// is trusted. Forwarded chain is walked from right to left, right-most
+/// untrusted hop is selected as the client. Hop is trusted if it matches
+/// one of the configured networks or if it is within configured number
+/// of hops, the peer is the first hop.
+///
+/// ```rust,no_run
+/// use ntex::web::{self, App, HttpResponse, HttpServer, TrustedProxies};
+///
+/// fn main() {
+///     let proxies = TrustedProxies::new()
+///         .cidr("10.0.0.0/8")
+///         .cidr("127.0.0.1");
+///
+///     HttpServer::new(
+///         || App::new()
+///             .service(web::resource("/").to(|| async { HttpResponse::Ok() })))
+///         .trusted_proxies(proxies);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    nets: Vec<(IpAddr, u8)>,
+    hops: usize,
+}
+
+impl TrustedProxies {
+    /// Create empty trusted proxies set, forwarded headers are ignored
+    pub fn new() -> Self {
+        TrustedProxies::default()
+    }
+
+    /// Trust network
+    ///
+    /// Panics if prefix length is larger than address length.
+    pub fn network(mut self, addr: IpAddr, prefix: u8) -> Self {
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        assert!(prefix <= max, "Prefix length is too large: {}", prefix);
+        self.nets.push((addr, prefix));
+        self
+    }
+
+    /// Trust network in CIDR notation, for example "10.0.0.0/8" or "::1"
+    ///
+    /// Address without prefix length is a single host. Panics if CIDR
+    /// could not be parsed.
+    pub fn cidr(self, cidr: &str) -> Self {
+        let (addr, prefix) = match cidr.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (cidr, None),
+        };
+        let addr: IpAddr = addr
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("Cannot parse CIDR: {:?}", cidr));
+        let prefix = match prefix {
+            Some(p) => p
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Cannot parse CIDR: {:?}", cidr)),
+            None if addr.is_ipv4() => 32,
+            None => 128,
+        };
+        self.network(addr, prefix)
+    }
+
+    /// Trust number of proxies in front of the server
+    ///
+    /// Peer and the next `hops - 1` hops of forwarded chain are trusted
+    /// regardless of their addresses.
+    pub fn hops(mut self, hops: usize) -> Self {
+        self.hops = hops;
+        self
+    }
+
+    /// Check if address of the hop is trusted, peer is hop 0
+    fn is_trusted(&self, hop: usize, addr: IpAddr) -> bool {
+        hop < self.hops
+            || self
+                .nets
+                .iter()
+                .any(|(net, prefix)| net_contains(*net, *prefix, addr))
+    }
+
+    /// Select client from forwarded chain
+    fn select(&self, chain: &[Option<&str>]) -> usize {
+        for (idx, item) in chain.iter().enumerate().rev() {
+            match item.and_then(parse_ip) {
+                Some(ip) if self.is_trusted(chain.len() - idx, ip) => continue,
+                _ => return idx,
+            }
+        }
+        0
+    }
+}
+
+fn net_contains(net: IpAddr, prefix: u8, addr: IpAddr) -> bool {
+    let addr = match (net, addr) {
+        (IpAddr::V6(_), IpAddr::V4(addr)) => IpAddr::V6(addr.to_ipv6_mapped()),
+        (IpAddr::V4(_), IpAddr::V6(addr)) => match addr.to_ipv4_mapped() {
+            Some(addr) => IpAddr::V4(addr),
+            None => return false,
+        },
+        _ => addr,
+    };
+    match (net, addr) {
+        (IpAddr::V4(net), IpAddr::V4(addr)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(net) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(addr)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(net) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Parse ip address of forwarded node, node could contain port
+fn parse_ip(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Some(rest) = node.strip_prefix('[') {
+        rest.split(']').next()?.parse().ok()
+    } else if let Ok(ip) = node.parse() {
+        Some(ip)
+    } else {
+        node.split(':').next()?.parse().ok()
+    }
+}
+
+/// Element of `Forwarded` header
+#[derive(Default)]
+struct Forwarded<'a> {
+    for_: Option<&'a str>,
+    proto: Option<&'a str>,
+    host: Option<&'a str>,
+}
+
 /// `HttpRequest` connection information
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionInfo {
@@ -28,6 +169,10 @@ impl ConnectionInfo {
 
     #[allow(clippy::cognitive_complexity)]
     fn new(req: &RequestHead, cfg: &AppConfig) -> ConnectionInfo {
+        if let Some(proxies) = cfg.trusted_proxies() {
+            return ConnectionInfo::with_proxies(req, cfg, proxies);
+        }
+
         let mut host = None;
         let mut scheme = None;
         let mut remote = None;
@@ -77,10 +222,7 @@ impl ConnectionInfo {
                 }
             }
             if scheme.is_none() {
-                scheme = req.uri.scheme().map(|a| a.as_str());
-                if scheme.is_none() && cfg.secure() {
-                    scheme = Some("https")
-                }
+                scheme = Some(default_scheme(req, cfg));
             }
         }
 
@@ -95,15 +237,7 @@ impl ConnectionInfo {
                 }
             }
             if host.is_none() {
-                if let Some(h) = req.headers.get(&header::HOST) {
-                    host = h.to_str().ok();
-                }
-                if host.is_none() {
-                    host = req.uri.authority().map(|a| a.as_str());
-                    if host.is_none() {
-                        host = Some(cfg.host());
-                    }
-                }
+                host = Some(default_host(req, cfg));
             }
         }
 
@@ -131,6 +265,73 @@ impl ConnectionInfo {
         }
     }
 
+    fn with_proxies(
+        req: &RequestHead,
+        cfg: &AppConfig,
+        proxies: &TrustedProxies,
+    ) -> ConnectionInfo {
+        let peer = req.peer_addr();
+        let mut host = None;
+        let mut scheme = None;
+        let mut remote = None;
+
+        if matches!(peer, Some(addr) if proxies.is_trusted(0, addr.ip())) {
+            let mut elements = Vec::new();
+            for hdr in req.headers.get_all(&header::FORWARDED) {
+                if let Ok(val) = hdr.to_str() {
+                    for el in val.split(',') {
+                        let mut item = Forwarded::default();
+                        for pair in el.split(';') {
+                            if let Some((name, val)) = pair.trim().split_once('=') {
+                                let val = val.trim().trim_matches('"');
+                                match &name.trim().to_lowercase() as &str {
+                                    "for" => item.for_ = Some(val),
+                                    "proto" => item.proto = Some(val),
+                                    "host" => item.host = Some(val),
+                                    _ => (),
+                                }
+                            }
+                        }
+                        elements.push(item);
+                    }
+                }
+            }
+
+            if !elements.is_empty() {
+                let chain: Vec<_> = elements.iter().map(|el| el.for_).collect();
+                let el = &elements[proxies.select(&chain)];
+                remote = el.for_;
+                scheme = el.proto;
+                host = el.host;
+            } else {
+                let chain: Vec<_> = forwarded_values(req, X_FORWARDED_FOR)
+                    .into_iter()
+                    .map(Some)
+                    .collect();
+                if !chain.is_empty() {
+                    remote = chain[proxies.select(&chain)];
+                }
+            }
+
+            // values added by the nearest trusted proxy
+            if scheme.is_none() {
+                scheme = forwarded_values(req, X_FORWARDED_PROTO).pop();
+            }
+            if host.is_none() {
+                host = forwarded_values(req, X_FORWARDED_HOST).pop();
+            }
+        }
+
+        ConnectionInfo {
+            peer: peer.map(|addr| format!("{}", addr)),
+            scheme: scheme
+                .unwrap_or_else(|| default_scheme(req, cfg))
+                .to_owned(),
+            host: host.unwrap_or_else(|| default_host(req, cfg)).to_owned(),
+            remote: remote.map(|s| s.to_owned()),
+        }
+    }
+
     /// Scheme of the request.
     ///
     /// Scheme is resolved through the following headers, in this order:
@@ -166,7 +367,8 @@ impl ConnectionInfo {
     ///
     /// # Security
     /// Do not use this function for security purposes, unless you can ensure the Forwarded and
-    /// X-Forwarded-For headers cannot be spoofed by the client. If you want the client's socket
+    /// X-Forwarded-For headers cannot be spoofed by the client, see [`TrustedProxies`].
+    /// If you want the client's socket
     /// address explicitly, use
     /// [`HttpRequest::peer_addr()`](../web/struct.HttpRequest.html#method.peer_addr) instead.
     #[inline]
@@ -181,6 +383,37 @@ impl ConnectionInfo {
     }
 }
 
+fn default_scheme<'a>(req: &'a RequestHead, cfg: &AppConfig) -> &'a str {
+    if let Some(scheme) = req.uri.scheme() {
+        scheme.as_str()
+    } else if cfg.secure() {
+        "https"
+    } else {
+        "http"
+    }
+}
+
+fn default_host<'a>(req: &'a RequestHead, cfg: &'a AppConfig) -> &'a str {
+    if let Some(host) = req.headers.get(&header::HOST).and_then(|h| h.to_str().ok()) {
+        host
+    } else if let Some(auth) = req.uri.authority() {
+        auth.as_str()
+    } else {
+        cfg.host()
+    }
+}
+
+/// Comma separated values of all headers with the name
+fn forwarded_values<'a>(req: &'a RequestHead, name: &[u8]) -> Vec<&'a str> {
+    req.headers
+        .get_all(&HeaderName::from_lowercase(name).unwrap())
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,4 +466,107 @@ mod tests {
         let info = req.connection_info();
         assert_eq!(info.scheme(), "https");
     }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let cfg = AppConfig::default()
+            .with_trusted_proxies(TrustedProxies::new().cidr("10.0.0.0/8").cidr("::1"));
+        assert!(cfg.trusted_proxies().is_some());
+        let peer = "10.0.0.1:8080".parse().unwrap();
+
+        // untrusted peer
+        let req = TestRequest::default()
+            .header(
+                header::FORWARDED,
+                "for=192.0.2.60; proto=https; host=rust-lang.org",
+            )
+            .header(X_FORWARDED_FOR, "192.0.2.60")
+            .peer_addr("192.0.2.1:8080".parse().unwrap())
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "localhost:8080");
+        assert_eq!(info.remote(), Some("192.0.2.1:8080"));
+
+        // no peer address
+        let req = TestRequest::default()
+            .header(X_FORWARDED_FOR, "192.0.2.60")
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.remote(), None);
+
+        // right-most untrusted hop
+        let req = TestRequest::default()
+            .header(
+                header::FORWARDED,
+                "for=192.0.2.1;proto=http, for=192.0.2.60;proto=https;host=rust-lang.org, \
+                 for=10.1.1.1",
+            )
+            .peer_addr(peer)
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+
+        let req = TestRequest::default()
+            .header(header::FORWARDED, "for=\"[::1]:4711\"")
+            .peer_addr(peer)
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.remote(), Some("[::1]:4711"));
+
+        let req = TestRequest::default()
+            .header(X_FORWARDED_FOR, "192.0.2.1, 192.0.2.60, 10.0.0.2")
+            .header(X_FORWARDED_PROTO, "http, https")
+            .header(X_FORWARDED_HOST, "rust-lang.org")
+            .peer_addr(peer)
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+
+        // all hops are trusted
+        let req = TestRequest::default()
+            .header(X_FORWARDED_FOR, "10.0.0.3, 10.0.0.2")
+            .peer_addr(peer)
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.remote(), Some("10.0.0.3"));
+
+        // number of hops
+        let cfg = AppConfig::default().with_trusted_proxies(TrustedProxies::new().hops(2));
+        let req = TestRequest::default()
+            .header(X_FORWARDED_FOR, "192.0.2.1, 192.0.2.60, 192.0.2.61")
+            .peer_addr("192.0.2.100:8080".parse().unwrap())
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+    }
+
+    #[test]
+    fn test_net_contains() {
+        let net = "10.0.0.0".parse().unwrap();
+        assert!(net_contains(net, 8, "10.1.2.3".parse().unwrap()));
+        assert!(!net_contains(net, 8, "11.1.2.3".parse().unwrap()));
+        assert!(net_contains(net, 8, "::ffff:10.1.2.3".parse().unwrap()));
+        assert!(net_contains(net, 0, "1.1.1.1".parse().unwrap()));
+        assert!(!net_contains(net, 32, "10.0.0.1".parse().unwrap()));
+
+        let net = "2001:db8::".parse().unwrap();
+        assert!(net_contains(net, 32, "2001:db8:1::1".parse().unwrap()));
+        assert!(!net_contains(net, 32, "2001:db9::1".parse().unwrap()));
+        assert!(!net_contains(net, 32, "10.0.0.1".parse().unwrap()));
+
+        assert_eq!(parse_ip("\"[::1]:80\""), Some("::1".parse().unwrap()));
+        assert_eq!(parse_ip("127.0.0.1:80"), Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(parse_ip("unknown"), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_cidr() {
+        let _ = TrustedProxies::new().cidr("10.0.0.0/33");
+    }
 }
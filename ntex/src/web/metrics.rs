@@ -0,0 +1,71 @@
+//! Prometheus metrics endpoint
+use crate::http::{Response, StatusCode};
+use crate::server::Metrics;
+use crate::web::responder::{Ready, Responder};
+use crate::web::{ErrorRenderer, HttpRequest};
+
+/// Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Render server metrics in prometheus text format.
+///
+/// Handler responds with `404 Not Found` if application does not run
+/// within ntex server.
+///
+/// ```rust
+/// use ntex::web::{self, App};
+///
+/// fn main() {
+///     let app = App::new().route("/metrics", web::get().to(web::metrics));
+/// }
+/// ```
+pub async fn metrics() -> Response {
+    if let Some(metrics) = Metrics::current() {
+        render(&metrics)
+    } else {
+        Response::NotFound().finish()
+    }
+}
+
+impl<Err: ErrorRenderer> Responder<Err> for Metrics {
+    type Future = Ready<Response>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        render(&self).into()
+    }
+}
+
+fn render(metrics: &Metrics) -> Response {
+    Response::build(StatusCode::OK)
+        .content_type(CONTENT_TYPE)
+        .body(metrics.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header;
+    use crate::web::test::{
+        call_service, init_service, read_body, respond_to, TestRequest,
+    };
+    use crate::web::{self, App};
+
+    #[crate::rt_test]
+    async fn test_metrics() {
+        let req = TestRequest::default().to_http_request();
+        let resp = respond_to(Metrics::default(), &req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE
+        );
+
+        // not running in server worker
+        let srv =
+            init_service(App::new().route("/metrics", web::get().to(web::metrics))).await;
+        let req = TestRequest::with_uri("/metrics").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert!(read_body(resp).await.is_empty());
+    }
+}
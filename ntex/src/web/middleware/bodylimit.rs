@@ -0,0 +1,173 @@
+//! Middleware for limiting size of request's payload
+use crate::http::{payload, Response};
+use crate::service::{Middleware, Service, ServiceCall, ServiceCtx};
+use crate::util::{Either, Ready};
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` for limiting size of request's payload.
+///
+/// Requests with larger `Content-Length` are rejected with
+/// `413 Payload Too Large` response before they reach the service.
+/// Streamed payloads are terminated with `PayloadError::Overflow` error
+/// once they exceed the limit, so the limit also applies to handlers that
+/// read `Payload` manually and to buffering middlewares.
+///
+/// Limits could be nested, the smallest limit applies. `App`, `Scope` and
+/// `Resource` provide `body_limit()` shortcut for this middleware.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::BodyLimit::new(1024 * 1024))
+///         .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct BodyLimit {
+    limit: u64,
+}
+
+impl Default for BodyLimit {
+    fn default() -> Self {
+        BodyLimit { limit: u64::MAX }
+    }
+}
+
+impl BodyLimit {
+    /// Construct `BodyLimit` middleware, `limit` is max payload size in bytes.
+    ///
+    /// To disable limit set value to 0.
+    pub fn new(limit: u64) -> Self {
+        BodyLimit {
+            limit: if limit == 0 { u64::MAX } else { limit },
+        }
+    }
+}
+
+impl<S> Middleware<S> for BodyLimit {
+    type Service = BodyLimitMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        BodyLimitMiddleware {
+            service,
+            limit: self.limit,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BodyLimitMiddleware<S> {
+    service: S,
+    limit: u64,
+}
+
+impl<S, E> Service<WebRequest<E>> for BodyLimitMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    E: 'static,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future<'f> =
+        Either<ServiceCall<'f, S, WebRequest<E>>, Ready<WebResponse, S::Error>> where S: 'f, E: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        mut req: WebRequest<E>,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        if self.limit != u64::MAX {
+            if payload::content_length_exceeds(req.headers(), self.limit) {
+                log::trace!("Request payload is too large: {:?}", req.path());
+                return Either::Right(Ready::Ok(
+                    req.into_response(Response::PayloadTooLarge().finish()),
+                ));
+            }
+
+            let mut payload = req.take_payload();
+            payload.set_limit(self.limit);
+            req.set_payload(payload);
+        }
+        Either::Left(ctx.call(&self.service, req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{error::PayloadError, h1, header, StatusCode};
+    use crate::service::{IntoService, Pipeline};
+    use crate::util::{lazy, stream_recv, Bytes};
+    use crate::web::test::{ok_service, TestRequest};
+    use crate::web::{DefaultError, Error, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_content_length() {
+        let mw = Pipeline::new(BodyLimit::new(10).create(ok_service()));
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx).is_ready()).await);
+
+        let req = TestRequest::with_header(header::CONTENT_LENGTH, "11").to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::with_header(header::CONTENT_LENGTH, "10").to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let mw = Pipeline::new(BodyLimit::default().create(ok_service()));
+        let req = TestRequest::with_header(header::CONTENT_LENGTH, "11").to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // zero disables limit
+        let mw = Pipeline::new(BodyLimit::new(0).create(ok_service()));
+        let req = TestRequest::with_header(header::CONTENT_LENGTH, "11").to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_stream() {
+        let srv = |mut req: WebRequest<DefaultError>| async move {
+            let mut pl = req.take_payload();
+            let mut size = 0;
+            while let Some(item) = stream_recv(&mut pl).await {
+                match item {
+                    Ok(chunk) => size += chunk.len(),
+                    Err(PayloadError::Overflow) => {
+                        return Ok::<_, Error>(
+                            req.into_response(HttpResponse::PayloadTooLarge().finish()),
+                        )
+                    }
+                    Err(_) => panic!(),
+                }
+            }
+            Ok(req.into_response(HttpResponse::Ok().body(size.to_string())))
+        };
+        let mw = Pipeline::new(BodyLimit::new(10).create(srv.into_service()));
+
+        let (mut tx, pl) = h1::Payload::create(false);
+        tx.feed_data(Bytes::from_static(b"01234"));
+        tx.feed_data(Bytes::from_static(b"56789"));
+        tx.feed_eof();
+        let mut req = TestRequest::default().to_srv_request();
+        req.set_payload(pl.into());
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let (mut tx, pl) = h1::Payload::create(false);
+        tx.feed_data(Bytes::from_static(b"01234"));
+        tx.feed_data(Bytes::from_static(b"56789a"));
+        tx.feed_eof();
+        let mut req = TestRequest::default().to_srv_request();
+        req.set_payload(pl.into());
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,612 @@
+//! Middleware for conditional and range requests
+use std::time::SystemTime;
+use std::{fmt::Write, future::Future, pin::Pin, task::Context, task::Poll};
+
+use nanorand::{Rng, WyRand};
+
+use crate::http::body::{Body, ResponseBody};
+use crate::http::header::{self, EntityTag, HeaderValue, TypedHeader};
+use crate::http::header::{ETag, IfMatch, IfModifiedSince, IfNoneMatch, IfRange};
+use crate::http::header::{IfUnmodifiedSince, LastModified, Range};
+use crate::http::{Method, RequestHead, Response, ResponseHead, StatusCode};
+use crate::service::{Middleware, Service, ServiceCtx};
+use crate::util::{BoxFuture, Bytes, BytesMut};
+use crate::web::{ErrorRenderer, HttpRequest, Responder, WebRequest, WebResponse};
+
+/// Max number of ranges in `multipart/byteranges` response
+const MAX_RANGES: usize = 16;
+
+/// `Middleware` for conditional and range requests.
+///
+/// Request's `If-Match`, `If-None-Match`, `If-Modified-Since`,
+/// `If-Unmodified-Since`, `Range` and `If-Range` headers are evaluated against
+/// `ETag` and `Last-Modified` headers of the successful response. Depending on
+/// the result, response is replaced with `304 Not Modified`,
+/// `412 Precondition Failed`, `206 Partial Content` or
+/// `416 Range Not Satisfiable` response.
+///
+/// Validators are taken from the response, so handler is always executed.
+/// Range requests are supported only for responses with in-memory body,
+/// streaming responses are sent in full. Multiple ranges are sent as
+/// `multipart/byteranges` response.
+///
+/// ```rust
+/// use ntex::http::header::{self, EntityTag, ETag};
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// async fn index() -> HttpResponse {
+///     HttpResponse::Ok()
+///         .typed_header(ETag(EntityTag::strong("v1")))
+///         .body("large cached blob")
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::ConditionalGet::new())
+///         .service(web::resource("/blob").to(index));
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct ConditionalGet {
+    ranges: bool,
+}
+
+impl Default for ConditionalGet {
+    fn default() -> Self {
+        ConditionalGet { ranges: true }
+    }
+}
+
+impl ConditionalGet {
+    /// Construct `ConditionalGet` middleware.
+    pub fn new() -> Self {
+        ConditionalGet::default()
+    }
+
+    /// Enable or disable range requests handling.
+    ///
+    /// By default range requests are enabled.
+    pub fn ranges(mut self, enabled: bool) -> Self {
+        self.ranges = enabled;
+        self
+    }
+
+    /// Wrap responder, conditional headers are evaluated against
+    /// responder's response.
+    ///
+    /// ```rust
+    /// use ntex::http::header;
+    /// use ntex::web::{middleware::ConditionalGet, Responder};
+    ///
+    /// async fn index() -> impl Responder {
+    ///     ConditionalGet::new().responder(
+    ///         "large cached blob".with_header(header::ETAG, "\"v1\"")
+    ///     )
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn responder<T>(&self, responder: T) -> Conditional<T> {
+        Conditional {
+            responder,
+            cfg: *self,
+        }
+    }
+
+    /// Evaluate request's conditional headers against the response
+    pub fn evaluate(&self, req: &HttpRequest, res: Response) -> Response {
+        res.map_body(|head, body| self.process(req.head(), head, body))
+    }
+
+    fn process(
+        &self,
+        req: &RequestHead,
+        head: &mut ResponseHead,
+        body: ResponseBody<Body>,
+    ) -> ResponseBody<Body> {
+        if !head.status.is_success() {
+            return body;
+        }
+
+        let etag = typed::<ETag>(&head.headers).map(|t| t.0);
+        let last_modified = typed::<LastModified>(&head.headers).map(|lm| lm.0);
+        let is_get = req.method == Method::GET;
+
+        // check preconditions
+        match preconditions(req, etag.as_ref(), last_modified) {
+            Some(StatusCode::NOT_MODIFIED) => {
+                return empty(head, StatusCode::NOT_MODIFIED, Body::None)
+            }
+            Some(status) => return empty(head, status, Body::Empty),
+            None => (),
+        }
+
+        // range requests
+        let data = match body {
+            ResponseBody::Body(Body::Bytes(ref b))
+            | ResponseBody::Other(Body::Bytes(ref b)) => b.clone(),
+            _ => return body,
+        };
+        if !self.ranges
+            || head.status != StatusCode::OK
+            || head.headers.contains_key(header::CONTENT_RANGE)
+        {
+            return body;
+        }
+        if !head.headers.contains_key(header::ACCEPT_RANGES) {
+            head.headers
+                .insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        }
+
+        let range = match typed::<Range>(&req.headers) {
+            Some(range) if is_get => range,
+            _ => return body,
+        };
+        if !if_range(req, etag.as_ref(), last_modified) || range.0.len() > MAX_RANGES {
+            return body;
+        }
+
+        let size = data.len() as u64;
+        let ranges: Vec<_> = range
+            .0
+            .iter()
+            .filter_map(|spec| spec.to_satisfiable_range(size))
+            .collect();
+
+        match ranges.len() {
+            0 => {
+                let range = format!("bytes */{}", size);
+                head.status = StatusCode::RANGE_NOT_SATISFIABLE;
+                head.headers.remove(header::CONTENT_LENGTH);
+                head.headers
+                    .insert(header::CONTENT_RANGE, HeaderValue::try_from(range).unwrap());
+                ResponseBody::Body(Body::Empty)
+            }
+            1 => {
+                let (start, end) = ranges[0];
+                let range = format!("bytes {}-{}/{}", start, end, size);
+                head.status = StatusCode::PARTIAL_CONTENT;
+                head.headers.remove(header::CONTENT_LENGTH);
+                head.headers
+                    .insert(header::CONTENT_RANGE, HeaderValue::try_from(range).unwrap());
+                ResponseBody::Body(Body::Bytes(
+                    data.slice(start as usize..end as usize + 1),
+                ))
+            }
+            _ => {
+                let boundary = format!("{:016x}", WyRand::new().generate::<u64>());
+                let content_type = head.headers.get(header::CONTENT_TYPE).cloned();
+
+                let mut buf = BytesMut::new();
+                for (start, end) in ranges {
+                    let _ = write!(buf, "--{}\r\n", boundary);
+                    if let Some(ref ct) = content_type {
+                        buf.extend_from_slice(b"Content-Type: ");
+                        buf.extend_from_slice(ct.as_bytes());
+                        buf.extend_from_slice(b"\r\n");
+                    }
+                    let _ = write!(
+                        buf,
+                        "Content-Range: bytes {}-{}/{}\r\n\r\n",
+                        start, end, size
+                    );
+                    buf.extend_from_slice(&data[start as usize..end as usize + 1]);
+                    buf.extend_from_slice(b"\r\n");
+                }
+                let _ = write!(buf, "--{}--\r\n", boundary);
+
+                let ct = format!("multipart/byteranges; boundary={}", boundary);
+                head.status = StatusCode::PARTIAL_CONTENT;
+                head.headers.remove(header::CONTENT_LENGTH);
+                head.headers
+                    .insert(header::CONTENT_TYPE, HeaderValue::try_from(ct).unwrap());
+                ResponseBody::Body(Body::Bytes(buf.freeze()))
+            }
+        }
+    }
+}
+
+/// Evaluate request's preconditions against validators of the representation.
+///
+/// Returns status of the response that must be sent instead of
+/// the representation, `304 Not Modified` or `412 Precondition Failed`.
+pub(crate) fn preconditions(
+    req: &RequestHead,
+    etag: Option<&EntityTag>,
+    last_modified: Option<SystemTime>,
+) -> Option<StatusCode> {
+    let is_get_or_head = req.method == Method::GET || req.method == Method::HEAD;
+
+    let precondition_failed = if let Some(im) = typed::<IfMatch>(&req.headers) {
+        !etag.map(|tag| im.matches(tag)).unwrap_or(false)
+    } else if let Some(since) = typed::<IfUnmodifiedSince>(&req.headers) {
+        last_modified.map(|lm| lm > since.0).unwrap_or(false)
+    } else {
+        false
+    };
+    if precondition_failed {
+        return Some(StatusCode::PRECONDITION_FAILED);
+    }
+
+    if let Some(inm) = typed::<IfNoneMatch>(&req.headers) {
+        if etag.map(|tag| inm.matches(tag)).unwrap_or(false) {
+            return if is_get_or_head {
+                Some(StatusCode::NOT_MODIFIED)
+            } else {
+                Some(StatusCode::PRECONDITION_FAILED)
+            };
+        }
+    } else if is_get_or_head {
+        if let Some(since) = typed::<IfModifiedSince>(&req.headers) {
+            if last_modified.map(|lm| lm <= since.0).unwrap_or(false) {
+                return Some(StatusCode::NOT_MODIFIED);
+            }
+        }
+    }
+    None
+}
+
+/// Check if request's `If-Range` header allows partial response
+pub(crate) fn if_range(
+    req: &RequestHead,
+    etag: Option<&EntityTag>,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    match typed::<IfRange>(&req.headers) {
+        Some(IfRange::ETag(ref tag)) => etag.map(|t| t.strong_eq(tag)).unwrap_or(false),
+        Some(IfRange::Date(date)) => last_modified == Some(date),
+        None => true,
+    }
+}
+
+/// Get typed header, malformed headers are ignored
+pub(crate) fn typed<H: TypedHeader>(headers: &header::HeaderMap) -> Option<H> {
+    H::from_headers(headers).ok().flatten()
+}
+
+/// Replace response with empty response, validators are preserved
+fn empty(head: &mut ResponseHead, status: StatusCode, body: Body) -> ResponseBody<Body> {
+    head.status = status;
+    for name in [
+        header::CONTENT_TYPE,
+        header::CONTENT_LENGTH,
+        header::CONTENT_ENCODING,
+        header::CONTENT_RANGE,
+        header::CONTENT_DISPOSITION,
+    ] {
+        head.headers.remove(name);
+    }
+    ResponseBody::Body(body)
+}
+
+impl<S> Middleware<S> for ConditionalGet {
+    type Service = ConditionalGetMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        ConditionalGetMiddleware {
+            service,
+            cfg: *self,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConditionalGetMiddleware<S> {
+    service: S,
+    cfg: ConditionalGet,
+}
+
+impl<S, E> Service<WebRequest<E>> for ConditionalGetMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    E: 'static,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future<'f> = BoxFuture<'f, Result<Self::Response, Self::Error>> where S: 'f, E: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: WebRequest<E>,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        Box::pin(async move {
+            let res = ctx.call(&self.service, req).await?;
+            let req = res.request().clone();
+            Ok(res.map_body(|head, body| self.cfg.process(req.head(), head, body)))
+        })
+    }
+}
+
+/// Responder wrapper for conditional and range requests.
+///
+/// See [`ConditionalGet::responder()`]
+#[derive(Debug)]
+pub struct Conditional<T> {
+    responder: T,
+    cfg: ConditionalGet,
+}
+
+impl<T, Err> Responder<Err> for Conditional<T>
+where
+    T: Responder<Err>,
+    Err: ErrorRenderer,
+{
+    type Future = ConditionalFut<T::Future>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        ConditionalFut {
+            fut: self.responder.respond_to(req),
+            req: req.clone(),
+            cfg: self.cfg,
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    pub struct ConditionalFut<F> {
+        #[pin]
+        fut: F,
+        req: HttpRequest,
+        cfg: ConditionalGet,
+    }
+}
+
+impl<F: Future<Output = Response>> Future for ConditionalFut<F> {
+    type Output = Response;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = crate::util::ready!(this.fut.poll(cx));
+        Poll::Ready(this.cfg.evaluate(this.req, res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+    use crate::http::header::EntityTag;
+    use crate::web::test::{call_service, init_service, read_body, respond_to};
+    use crate::web::test::{ok_service, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+    use crate::{service::Pipeline, util::lazy};
+
+    fn modified() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    async fn index() -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type("text/plain")
+            .typed_header(ETag(EntityTag::strong("v1")))
+            .typed_header(LastModified(modified()))
+            .body("0123456789")
+    }
+
+    #[crate::rt_test]
+    async fn test_conditional_get() {
+        let mw = Pipeline::new(ConditionalGet::new().create(ok_service()));
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx).is_ready()).await);
+        assert!(format!("{:?}", mw).contains("ConditionalGetMiddleware"));
+
+        let srv = init_service(
+            App::new()
+                .wrap(ConditionalGet::new())
+                .service(web::resource("/").to(index)),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(read_body(res).await, Bytes::from_static(b"0123456789"));
+
+        // if-none-match
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "W/\"v1\"").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"v1\"");
+        assert!(!res.headers().contains_key(header::CONTENT_TYPE));
+        assert!(read_body(res).await.is_empty());
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "\"v2\"").to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "*")
+            .method(Method::POST)
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        // if-match
+        let req = TestRequest::with_header(header::IF_MATCH, "\"v2\", \"v1\"")
+            .method(Method::PUT)
+            .to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_MATCH, "W/\"v1\"")
+            .method(Method::PUT)
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        // if-modified-since
+        let req = TestRequest::default()
+            .header(
+                header::IF_MODIFIED_SINCE,
+                httpdate::fmt_http_date(modified()),
+            )
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let since = modified() - Duration::from_secs(60);
+        let req = TestRequest::default()
+            .header(header::IF_MODIFIED_SINCE, httpdate::fmt_http_date(since))
+            .to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+
+        // if-none-match takes precedence over if-modified-since
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "\"v2\"")
+            .header(
+                header::IF_MODIFIED_SINCE,
+                httpdate::fmt_http_date(modified()),
+            )
+            .to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+
+        // if-unmodified-since
+        let req = TestRequest::default()
+            .header(header::IF_UNMODIFIED_SINCE, httpdate::fmt_http_date(since))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        // malformed headers are ignored
+        let req =
+            TestRequest::with_header(header::IF_MODIFIED_SINCE, "yesterday").to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_range() {
+        let srv = init_service(
+            App::new()
+                .wrap(ConditionalGet::new())
+                .service(web::resource("/").to(index)),
+        )
+        .await;
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=2-4").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 2-4/10"
+        );
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert_eq!(read_body(res).await, Bytes::from_static(b"234"));
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=-3").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"789"));
+
+        // not satisfiable
+        let req = TestRequest::with_header(header::RANGE, "bytes=20-").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes */10"
+        );
+
+        // malformed range
+        let req = TestRequest::with_header(header::RANGE, "bytes=a-b").to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+
+        // if-range
+        let req = TestRequest::with_header(header::RANGE, "bytes=2-4")
+            .header(header::IF_RANGE, "\"v1\"")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=2-4")
+            .header(header::IF_RANGE, "\"v2\"")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"0123456789"));
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=2-4")
+            .header(header::IF_RANGE, httpdate::fmt_http_date(modified()))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+
+        // range is ignored for non-GET requests
+        let req = TestRequest::with_header(header::RANGE, "bytes=2-4")
+            .method(Method::POST)
+            .to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_multiple_ranges() {
+        let srv = init_service(
+            App::new()
+                .wrap(ConditionalGet::new())
+                .service(web::resource("/").to(index)),
+        )
+        .await;
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1,8-").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let ct = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        let boundary = ct
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            read_body(res).await,
+            Bytes::from(format!(
+                "--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+                 --{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 8-9/10\r\n\r\n89\r\n\
+                 --{0}--\r\n",
+                boundary
+            ))
+        );
+
+        // too many ranges
+        let range = format!("bytes={}", vec!["0-0"; MAX_RANGES + 1].join(","));
+        let req = TestRequest::with_header(header::RANGE, range).to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+
+        // ranges are disabled
+        let srv = init_service(
+            App::new()
+                .wrap(ConditionalGet::new().ranges(false))
+                .service(web::resource("/").to(index)),
+        )
+        .await;
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ACCEPT_RANGES));
+    }
+
+    #[crate::rt_test]
+    async fn test_responder() {
+        let req =
+            TestRequest::with_header(header::IF_NONE_MATCH, "\"v1\"").to_http_request();
+        let resp =
+            ConditionalGet::new().responder("blob".with_header(header::ETAG, "\"v1\""));
+        assert!(format!("{:?}", ConditionalGet::new().responder("blob"))
+            .contains("Conditional"));
+        let resp = respond_to(resp, &req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=1-2").to_http_request();
+        let resp = respond_to(ConditionalGet::new().responder("blob"), &req).await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 1-2/4"
+        );
+    }
+}
@@ -0,0 +1,766 @@
+//! Cross-origin resource sharing (CORS) middleware
+use std::{fmt, rc::Rc};
+
+use crate::http::error::HttpError;
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::{Method, RequestHead, Response};
+use crate::service::{Middleware, Service, ServiceCtx};
+use crate::util::BoxFuture;
+use crate::web::error::{CorsError, ErrorRenderer, WebResponseError};
+use crate::web::{HttpRequest, WebRequest, WebResponse};
+
+type OriginFn = dyn Fn(&HeaderValue, &RequestHead) -> bool + 'static;
+type ErrorFn = dyn Fn(CorsError, &HttpRequest) -> Response + 'static;
+
+/// `Middleware` for Cross-origin resource sharing support.
+///
+/// Preflight `OPTIONS` requests are handled by middleware itself, they never
+/// reach the wrapped service. If middleware is registered on application
+/// level, preflight requests are answered before routing, so resources
+/// do not need to define `OPTIONS` routes.
+///
+/// By default no origins are allowed, allowed methods are `GET`, `HEAD`
+/// and `POST`, and no request headers besides CORS-safelisted are allowed.
+///
+/// ```rust
+/// use ntex::http::header;
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(
+///             middleware::Cors::new()
+///                 .allowed_origin("https://www.rust-lang.org")
+///                 .allowed_origin("https://*.example.com")
+///                 .allowed_methods(vec!["GET", "POST"])
+///                 .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
+///                 .expose_headers(vec!["x-request-id"])
+///                 .supports_credentials()
+///                 .max_age(3600)
+///         )
+///         .service(
+///             web::resource("/index.html").to(|| async { HttpResponse::Ok() })
+///         );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Cors {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    any_origin: bool,
+    origins: Vec<Origin>,
+    any_method: bool,
+    methods: Vec<Method>,
+    methods_hdr: Option<HeaderValue>,
+    any_header: bool,
+    headers: Vec<HeaderName>,
+    headers_hdr: Option<HeaderValue>,
+    expose_hdr: Option<HeaderValue>,
+    credentials: bool,
+    max_age: Option<HeaderValue>,
+    error_fn: Option<Rc<ErrorFn>>,
+}
+
+enum Origin {
+    Exact(String),
+    Wildcard(String, String),
+    Fn(Rc<OriginFn>),
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        let methods = vec![Method::GET, Method::HEAD, Method::POST];
+        Cors {
+            inner: Rc::new(Inner {
+                any_origin: false,
+                origins: Vec::new(),
+                any_method: false,
+                methods_hdr: join(methods.iter().map(|m| m.as_str())),
+                methods,
+                any_header: false,
+                headers: Vec::new(),
+                headers_hdr: None,
+                expose_hdr: None,
+                credentials: false,
+                max_age: None,
+                error_fn: None,
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for Cors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cors")
+            .field("any_origin", &self.inner.any_origin)
+            .field("methods", &self.inner.methods)
+            .field("headers", &self.inner.headers)
+            .field("credentials", &self.inner.credentials)
+            .finish()
+    }
+}
+
+impl Cors {
+    /// Construct `Cors` middleware.
+    pub fn new() -> Cors {
+        Cors::default()
+    }
+
+    fn inner(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Allow requests from any origin.
+    ///
+    /// Cannot be combined with `supports_credentials()`, middleware panics
+    /// on construction.
+    pub fn allow_any_origin(mut self) -> Self {
+        self.inner().any_origin = true;
+        self
+    }
+
+    /// Add an allowed origin.
+    ///
+    /// Origin must be in form of `scheme://host[:port]`. Single `*` could be used
+    /// as a wildcard, i.e. `https://*.example.com`. Origin `*` allows any origin.
+    pub fn allowed_origin(mut self, origin: &str) -> Self {
+        let inner = self.inner();
+        if origin == "*" {
+            inner.any_origin = true;
+        } else if let Some((prefix, suffix)) = origin.split_once('*') {
+            if suffix.contains('*') {
+                panic!("Only one wildcard is supported: {}", origin);
+            }
+            inner.origins.push(Origin::Wildcard(
+                prefix.to_ascii_lowercase(),
+                suffix.to_ascii_lowercase(),
+            ));
+        } else {
+            inner
+                .origins
+                .push(Origin::Exact(origin.trim_end_matches('/').to_string()));
+        }
+        self
+    }
+
+    /// Add an origin predicate.
+    ///
+    /// Request's origin is allowed if predicate returns `true`.
+    pub fn allowed_origin_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HeaderValue, &RequestHead) -> bool + 'static,
+    {
+        self.inner().origins.push(Origin::Fn(Rc::new(f)));
+        self
+    }
+
+    /// Set a list of methods which allowed origins can perform.
+    ///
+    /// Default is `GET`, `HEAD` and `POST`.
+    pub fn allowed_methods<U, M>(mut self, methods: U) -> Self
+    where
+        U: IntoIterator<Item = M>,
+        Method: TryFrom<M>,
+        <Method as TryFrom<M>>::Error: Into<HttpError>,
+    {
+        let inner = self.inner();
+        inner.methods = methods
+            .into_iter()
+            .map(|m| match Method::try_from(m) {
+                Ok(m) => m,
+                Err(_) => panic!("Cannot create method"),
+            })
+            .collect();
+        inner.methods_hdr = join(inner.methods.iter().map(|m| m.as_str()));
+        self
+    }
+
+    /// Allow any method.
+    pub fn allow_any_method(mut self) -> Self {
+        self.inner().any_method = true;
+        self
+    }
+
+    /// Set a list of request headers which allowed origins can use.
+    pub fn allowed_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        let inner = self.inner();
+        inner.headers.extend(headers.into_iter().map(header_name));
+        inner.headers_hdr = join(inner.headers.iter().map(|h| h.as_str()));
+        self
+    }
+
+    /// Allow any request header.
+    pub fn allow_any_header(mut self) -> Self {
+        self.inner().any_header = true;
+        self
+    }
+
+    /// Set a list of response headers which are exposed to the client.
+    pub fn expose_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        let headers: Vec<_> = headers.into_iter().map(header_name).collect();
+        self.inner().expose_hdr = join(headers.iter().map(|h| h.as_str()));
+        self
+    }
+
+    /// Allow requests with credentials, sets `Access-Control-Allow-Credentials`
+    /// header.
+    ///
+    /// Allowed origins must be listed explicitly, any origin is not
+    /// supported with credentials.
+    pub fn supports_credentials(mut self) -> Self {
+        self.inner().credentials = true;
+        self
+    }
+
+    /// Set the number of seconds preflight response could be cached.
+    pub fn max_age(mut self, secs: u32) -> Self {
+        self.inner().max_age = Some(HeaderValue::from(secs));
+        self
+    }
+
+    /// Set custom response for rejected requests.
+    ///
+    /// By default rejected requests are rendered with `CorsError` error.
+    pub fn error_response<F>(mut self, f: F) -> Self
+    where
+        F: Fn(CorsError, &HttpRequest) -> Response + 'static,
+    {
+        self.inner().error_fn = Some(Rc::new(f));
+        self
+    }
+}
+
+fn header_name<H>(h: H) -> HeaderName
+where
+    HeaderName: TryFrom<H>,
+    <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+{
+    match HeaderName::try_from(h) {
+        Ok(h) => h,
+        Err(_) => panic!("Cannot create header name"),
+    }
+}
+
+fn join<'a, I: Iterator<Item = &'a str>>(items: I) -> Option<HeaderValue> {
+    let s = items.collect::<Vec<_>>().join(", ");
+    if s.is_empty() {
+        None
+    } else {
+        Some(HeaderValue::try_from(s).expect("Cannot create header value"))
+    }
+}
+
+impl Inner {
+    fn validate_origin(&self, origin: &HeaderValue, head: &RequestHead) -> bool {
+        if self.any_origin {
+            return true;
+        }
+        let s = match origin.to_str() {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        self.origins.iter().any(|o| match o {
+            Origin::Exact(ref o) => o.eq_ignore_ascii_case(s),
+            Origin::Wildcard(ref prefix, ref suffix) => {
+                let s = s.to_ascii_lowercase();
+                s.len() > prefix.len() + suffix.len()
+                    && s.starts_with(prefix.as_str())
+                    && s.ends_with(suffix.as_str())
+            }
+            Origin::Fn(ref f) => f(origin, head),
+        })
+    }
+
+    fn allow_origin(&self, origin: &HeaderValue) -> HeaderValue {
+        if self.any_origin {
+            HeaderValue::from_static("*")
+        } else {
+            origin.clone()
+        }
+    }
+
+    fn preflight(
+        &self,
+        origin: &HeaderValue,
+        head: &RequestHead,
+    ) -> Result<Response, CorsError> {
+        if !self.validate_origin(origin, head) {
+            return Err(CorsError::OriginNotAllowed);
+        }
+
+        // requested method
+        let method = head
+            .headers
+            .get(&header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|hdr| Method::from_bytes(hdr.as_bytes()).ok())
+            .ok_or(CorsError::BadRequestMethod)?;
+        if !self.any_method && !self.methods.contains(&method) {
+            return Err(CorsError::MethodNotAllowed);
+        }
+
+        // requested headers
+        let req_headers = head.headers.get(&header::ACCESS_CONTROL_REQUEST_HEADERS);
+        if let Some(hdr) = req_headers {
+            let hdr = hdr.to_str().map_err(|_| CorsError::BadRequestHeaders)?;
+            for name in hdr.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+                let name =
+                    HeaderName::try_from(name).map_err(|_| CorsError::BadRequestHeaders)?;
+                if !self.any_header && !self.headers.contains(&name) {
+                    return Err(CorsError::HeadersNotAllowed);
+                }
+            }
+        }
+
+        let mut res = Response::Ok();
+        res.header(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin(origin),
+        );
+        if self.any_method {
+            res.header(header::ACCESS_CONTROL_ALLOW_METHODS, method.as_str());
+        } else if let Some(ref methods) = self.methods_hdr {
+            res.header(header::ACCESS_CONTROL_ALLOW_METHODS, methods.clone());
+        }
+        if self.any_header {
+            if let Some(hdr) = req_headers {
+                res.header(header::ACCESS_CONTROL_ALLOW_HEADERS, hdr.clone());
+            }
+        } else if let Some(ref headers) = self.headers_hdr {
+            res.header(header::ACCESS_CONTROL_ALLOW_HEADERS, headers.clone());
+        }
+        if self.credentials {
+            res.header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+        }
+        if let Some(ref max_age) = self.max_age {
+            res.header(header::ACCESS_CONTROL_MAX_AGE, max_age.clone());
+        }
+        Ok(res
+            .header(
+                header::VARY,
+                "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+            )
+            .finish())
+    }
+
+    fn set_headers(&self, origin: &HeaderValue, headers: &mut HeaderMap) {
+        let allow_origin = self.allow_origin(origin);
+        if allow_origin != "*" {
+            headers.append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if self.credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+        if let Some(ref expose) = self.expose_hdr {
+            headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, expose.clone());
+        }
+    }
+
+    fn reject<Err>(&self, req: WebRequest<Err>, err: CorsError) -> WebResponse
+    where
+        Err: ErrorRenderer,
+        CorsError: WebResponseError<Err>,
+    {
+        log::debug!("CORS request is rejected: {}", err);
+
+        if let Some(ref f) = self.error_fn {
+            let (req, _) = req.into_parts();
+            let res = f(err, &req);
+            WebResponse::new(res, req)
+        } else {
+            req.render_error(err)
+        }
+    }
+}
+
+impl<S> Middleware<S> for Cors {
+    type Service = CorsMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        if self.inner.any_origin && self.inner.credentials {
+            panic!("Any origin cannot be allowed for requests with credentials");
+        }
+        CorsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for CorsMiddleware<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CorsMiddleware")
+            .field("service", &self.service)
+            .finish()
+    }
+}
+
+impl<S, Err> Service<WebRequest<Err>> for CorsMiddleware<S>
+where
+    S: Service<WebRequest<Err>, Response = WebResponse>,
+    Err: ErrorRenderer,
+    CorsError: WebResponseError<Err>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future<'f> =
+        BoxFuture<'f, Result<Self::Response, Self::Error>> where S: 'f, Err: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: WebRequest<Err>,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        Box::pin(async move {
+            let origin = if let Some(origin) = req.headers().get(&header::ORIGIN) {
+                origin.clone()
+            } else {
+                // not a cors request
+                return ctx.call(&self.service, req).await;
+            };
+
+            // preflight request
+            if req.method() == Method::OPTIONS
+                && req
+                    .headers()
+                    .contains_key(&header::ACCESS_CONTROL_REQUEST_METHOD)
+            {
+                return Ok(match self.inner.preflight(&origin, req.head()) {
+                    Ok(res) => req.into_response(res),
+                    Err(err) => self.inner.reject(req, err),
+                });
+            }
+
+            if !self.inner.validate_origin(&origin, req.head()) {
+                return Ok(self.inner.reject(req, CorsError::OriginNotAllowed));
+            }
+
+            let mut res = ctx.call(&self.service, req).await?;
+            self.inner.set_headers(&origin, res.headers_mut());
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header::*, StatusCode};
+    use crate::service::{IntoService, Pipeline};
+    use crate::util::lazy;
+    use crate::web::test::{self, ok_service, TestRequest};
+    use crate::web::{self, App, DefaultError, Error, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_not_cors() {
+        let mw = Pipeline::new(Cors::new().create(ok_service()));
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx).is_ready()).await);
+
+        let req = TestRequest::default().to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[crate::rt_test]
+    async fn test_origins() {
+        let mw = Pipeline::new(
+            Cors::new()
+                .allowed_origin("https://www.rust-lang.org")
+                .allowed_origin("https://*.example.com")
+                .allowed_origin_fn(|origin, _| origin.as_bytes().ends_with(b".local"))
+                .create(ok_service()),
+        );
+
+        for origin in [
+            "https://www.rust-lang.org",
+            "https://api.example.com",
+            "http://test.local",
+        ] {
+            let req = TestRequest::default()
+                .header(ORIGIN, origin)
+                .to_srv_request();
+            let resp = mw.call(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(
+                resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+                origin
+            );
+            assert_eq!(resp.headers().get(VARY).unwrap(), "Origin");
+            assert!(!resp
+                .headers()
+                .contains_key(ACCESS_CONTROL_ALLOW_CREDENTIALS));
+        }
+
+        for origin in [
+            "https://www.rust-lang.org.evil.com",
+            "https://example.com",
+            "http://api.example.com",
+            "https://.example.com",
+        ] {
+            let req = TestRequest::default()
+                .header(ORIGIN, origin)
+                .to_srv_request();
+            let resp = mw.call(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+            assert!(!resp.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_any_origin() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(
+                req.into_response(HttpResponse::Ok().header(VARY, "Accept").finish()),
+            )
+        };
+        let mw = Pipeline::new(
+            Cors::new()
+                .allow_any_origin()
+                .expose_headers(vec!["x-request-id", "x-version"])
+                .create(srv.into_service()),
+        );
+        let req = TestRequest::default()
+            .header(ORIGIN, "https://www.rust-lang.org")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "*"
+        );
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_EXPOSE_HEADERS).unwrap(),
+            "x-request-id, x-version"
+        );
+        assert_eq!(resp.headers().get_all(VARY).count(), 1);
+
+        let mw = Pipeline::new(
+            Cors::new()
+                .allowed_origin("https://*.rust-lang.org")
+                .supports_credentials()
+                .create(srv.into_service()),
+        );
+        let req = TestRequest::default()
+            .header(ORIGIN, "https://www.rust-lang.org")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://www.rust-lang.org"
+        );
+        assert_eq!(
+            resp.headers()
+                .get(ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+        let vary: Vec<_> = resp
+            .headers()
+            .get_all(VARY)
+            .map(|v| v.to_str().unwrap())
+            .collect();
+        assert_eq!(vary, vec!["Accept", "Origin"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Any origin cannot be allowed")]
+    fn test_any_origin_with_credentials() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let _ = Cors::new()
+            .allowed_origin("*")
+            .supports_credentials()
+            .create(srv.into_service());
+    }
+
+    #[crate::rt_test]
+    async fn test_preflight() {
+        let mw = Pipeline::new(
+            Cors::new()
+                .allowed_origin("https://www.rust-lang.org")
+                .allowed_methods(vec![Method::GET, Method::PUT])
+                .allowed_headers(vec![AUTHORIZATION, ACCEPT])
+                .supports_credentials()
+                .max_age(3600)
+                .create(ok_service()),
+        );
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(ORIGIN, "https://www.rust-lang.org")
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .header(ACCESS_CONTROL_REQUEST_HEADERS, "authorization, Accept")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://www.rust-lang.org"
+        );
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "GET, PUT"
+        );
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "authorization, accept"
+        );
+        assert_eq!(
+            resp.headers()
+                .get(ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+        assert_eq!(resp.headers().get(ACCESS_CONTROL_MAX_AGE).unwrap(), "3600");
+
+        let cases = [
+            (
+                "https://www.rust-lang.org",
+                "DELETE",
+                None,
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                "https://www.rust-lang.org",
+                "GET",
+                Some("x-custom"),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                "https://www.rust-lang.org",
+                "GET",
+                Some("bad header"),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                "https://www.rust-lang.org",
+                "",
+                None,
+                StatusCode::BAD_REQUEST,
+            ),
+            ("https://example.com", "GET", None, StatusCode::FORBIDDEN),
+        ];
+        for (origin, method, headers, status) in cases {
+            let mut req = TestRequest::default()
+                .method(Method::OPTIONS)
+                .header(ORIGIN, origin)
+                .header(ACCESS_CONTROL_REQUEST_METHOD, method);
+            if let Some(headers) = headers {
+                req = req.header(ACCESS_CONTROL_REQUEST_HEADERS, headers);
+            }
+            let resp = mw.call(req.to_srv_request()).await.unwrap();
+            assert_eq!(resp.status(), status);
+            assert!(!resp.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+        }
+
+        // any method and headers
+        let mw = Pipeline::new(
+            Cors::new()
+                .allow_any_origin()
+                .allow_any_method()
+                .allow_any_header()
+                .create(ok_service()),
+        );
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(ORIGIN, "https://www.rust-lang.org")
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "PATCH")
+            .header(ACCESS_CONTROL_REQUEST_HEADERS, "x-custom")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "*"
+        );
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "PATCH"
+        );
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "x-custom"
+        );
+        assert!(!resp.headers().contains_key(ACCESS_CONTROL_MAX_AGE));
+    }
+
+    #[crate::rt_test]
+    async fn test_error_response() {
+        let mw = Pipeline::new(
+            Cors::new()
+                .error_response(|err, _| {
+                    HttpResponse::Unauthorized().body(format!("{}", err))
+                })
+                .create(ok_service()),
+        );
+        let req = TestRequest::default()
+            .header(ORIGIN, "https://www.rust-lang.org")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            test::read_body(resp).await,
+            "Origin is not allowed to make this request"
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_app_preflight() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(Cors::new().allowed_origin("https://www.rust-lang.org"))
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(|| async { HttpResponse::Ok() })),
+                ),
+        )
+        .await;
+
+        // preflight is handled before routing
+        let req = TestRequest::with_uri("/test")
+            .method(Method::OPTIONS)
+            .header(ORIGIN, "https://www.rust-lang.org")
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .to_request();
+        let resp = test::call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "GET, HEAD, POST"
+        );
+
+        let req = TestRequest::with_uri("/test")
+            .header(ORIGIN, "https://www.rust-lang.org")
+            .to_request();
+        let resp = test::call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://www.rust-lang.org"
+        );
+    }
+}
@@ -6,10 +6,12 @@ use std::{fmt, fmt::Display, marker::PhantomData, pin::Pin, rc::Rc, time};
 use regex::Regex;
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
-use crate::http::header::HeaderName;
+use crate::http::header::{HeaderMap, HeaderName};
 use crate::service::{Middleware, Service, ServiceCall, ServiceCtx};
 use crate::util::{Bytes, Either, HashSet};
-use crate::web::{HttpResponse, WebRequest, WebResponse};
+use crate::web::{HttpRequest, HttpResponse, WebRequest, WebResponse};
+
+use super::requestid::{RequestId, TraceContext};
 
 /// `Middleware` for logging request and response info to the terminal.
 ///
@@ -67,6 +69,12 @@ use crate::web::{HttpResponse, WebRequest, WebResponse};
 ///
 /// `%{FOO}e`  os.environ['FOO']
 ///
+/// `%{request-id}x`  Request id, set by `RequestIdentifier` middleware
+///
+/// `%{trace-id}x`  Trace id, set by `RequestIdentifier` middleware
+///
+/// `%{span-id}x`  Span id, set by `RequestIdentifier` middleware
+///
 #[derive(Debug)]
 pub struct Logger {
     inner: Rc<Inner>,
@@ -195,6 +203,7 @@ where
         if let Some(ref mut format) = this.format {
             for unit in &mut format.0 {
                 unit.render_response(res.response());
+                unit.render_extensions(res.request());
             }
         }
 
@@ -250,6 +259,10 @@ impl MessageBody for StreamLog {
             val => val,
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.body.trailers()
+    }
 }
 
 /// A formatting style for the `Logger`, consisting of multiple
@@ -271,7 +284,7 @@ impl Format {
     /// Returns `None` if the format string syntax is incorrect.
     fn new(s: &str) -> Format {
         log::trace!("Access log format: {}", s);
-        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([ioe])|[atPrUsbTD]?)").unwrap();
+        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([ioex])|[atPrUsbTD]?)").unwrap();
 
         let mut idx = 0;
         let mut results = Vec::new();
@@ -292,6 +305,12 @@ impl Format {
                         HeaderName::try_from(key.as_str()).unwrap(),
                     ),
                     "e" => FormatText::EnvironHeader(key.as_str().to_owned()),
+                    "x" => match key.as_str() {
+                        "request-id" => FormatText::RequestId,
+                        "trace-id" => FormatText::TraceId,
+                        "span-id" => FormatText::SpanId,
+                        _ => FormatText::Str("-".to_owned()),
+                    },
                     _ => unreachable!(),
                 })
             } else {
@@ -336,6 +355,9 @@ enum FormatText {
     RequestHeader(HeaderName),
     ResponseHeader(HeaderName),
     EnvironHeader(String),
+    RequestId,
+    TraceId,
+    SpanId,
 }
 
 impl FormatText {
@@ -391,6 +413,24 @@ impl FormatText {
         }
     }
 
+    fn render_extensions(&mut self, req: &HttpRequest) {
+        let s = match *self {
+            FormatText::RequestId => {
+                req.extensions().get::<RequestId>().map(|id| id.to_string())
+            }
+            FormatText::TraceId => req
+                .extensions()
+                .get::<TraceContext>()
+                .map(|ctx| ctx.trace_id.clone()),
+            FormatText::SpanId => req
+                .extensions()
+                .get::<TraceContext>()
+                .map(|ctx| ctx.span_id.clone()),
+            _ => return,
+        };
+        *self = FormatText::Str(s.unwrap_or_else(|| "-".to_string()));
+    }
+
     fn render_request<E>(&mut self, now: time::SystemTime, req: &WebRequest<E>) {
         match *self {
             FormatText::RequestLine => {
@@ -589,6 +629,43 @@ mod tests {
         assert!(s.contains("NTEX"));
     }
 
+    #[crate::rt_test]
+    async fn test_request_id_format() {
+        let mut format = Format::new("%{request-id}x %{trace-id}x %{span-id}x %{foo}x");
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(RequestId("abc-123".into()));
+
+        for unit in &mut format.0 {
+            unit.render_extensions(&req);
+        }
+        let now = time::SystemTime::now();
+        let render = |fmt: &mut fmt::Formatter<'_>| {
+            for unit in &format.0 {
+                unit.render(fmt, 1024, now)?;
+            }
+            Ok(())
+        };
+        assert_eq!(format!("{}", FormatDisplay(&render)), "abc-123 - - -");
+
+        let mut format = Format::new("%{request-id}x %{trace-id}x %{span-id}x");
+        let req = TestRequest::default().to_http_request();
+        let ctx = TraceContext::generate();
+        req.extensions_mut().insert(ctx.clone());
+        for unit in &mut format.0 {
+            unit.render_extensions(&req);
+        }
+        let render = |fmt: &mut fmt::Formatter<'_>| {
+            for unit in &format.0 {
+                unit.render(fmt, 1024, now)?;
+            }
+            Ok(())
+        };
+        assert_eq!(
+            format!("{}", FormatDisplay(&render)),
+            format!("- {} {}", ctx.trace_id, ctx.span_id)
+        );
+    }
+
     #[crate::rt_test]
     async fn test_request_time_format() {
         let mut format = Format::new("%t");
@@ -1,12 +1,29 @@
 //! Middlewares
 
+mod bodylimit;
+pub use self::bodylimit::BodyLimit;
+pub(crate) use self::bodylimit::BodyLimitMiddleware;
+
 #[cfg(feature = "compress")]
 mod compress;
 #[cfg(feature = "compress")]
 pub use self::compress::Compress;
 
+mod conditional;
+pub use self::conditional::{Conditional, ConditionalGet};
+pub(crate) use self::conditional::{if_range, preconditions, typed};
+
+mod cors;
+pub use self::cors::Cors;
+
 mod logger;
 pub use self::logger::Logger;
 
+mod ratelimit;
+pub use self::ratelimit::RateLimit;
+
+mod requestid;
+pub use self::requestid::{RequestId, RequestIdentifier, TraceContext};
+
 mod defaultheaders;
 pub use self::defaultheaders::DefaultHeaders;
@@ -0,0 +1,296 @@
+//! Rate limiting middleware
+use std::{fmt, rc::Rc, time::Duration};
+
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::{RequestHead, Response, StatusCode};
+use crate::service::{Middleware, Service, ServiceCtx};
+use crate::time::Millis;
+use crate::util::ratelimit::{RateLimitInfo, RateLimiter};
+use crate::util::BoxFuture;
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` for limiting rate of requests.
+///
+/// Middleware implements token bucket algorithm, each client could make
+/// `limit` requests per `period`. By default clients are identified by peer
+/// ip address, key could be taken from request header or could be selected
+/// by custom function. Requests without key are not limited.
+///
+/// If limit is exceeded, `429 Too Many Requests` response is returned with
+/// `Retry-After` header. `RateLimit-Limit`, `RateLimit-Remaining` and
+/// `RateLimit-Reset` headers are added to all limited responses.
+///
+/// Limiter state is not shared between workers unless same [`RateLimiter`]
+/// is passed to all middleware instances with `RateLimit::with_limiter()`.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::RateLimit::new(100, ntex::time::Seconds(60)))
+///         .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+pub struct RateLimit {
+    inner: Rc<Inner>,
+}
+
+enum Key {
+    Peer,
+    Header(HeaderName),
+    Fn(Box<dyn Fn(&RequestHead) -> Option<String>>),
+}
+
+struct Inner {
+    limiter: RateLimiter<String>,
+    key: Key,
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("limiter", &self.inner.limiter)
+            .finish()
+    }
+}
+
+impl RateLimit {
+    /// Construct `RateLimit` middleware, allows `limit` requests per `period`.
+    ///
+    /// Panics if `limit` or `period` is zero.
+    pub fn new<T: Into<Millis>>(limit: u32, period: T) -> Self {
+        RateLimit::with_limiter(RateLimiter::new(limit, period))
+    }
+
+    /// Construct `RateLimit` middleware with existing limiter.
+    pub fn with_limiter(limiter: RateLimiter<String>) -> Self {
+        RateLimit {
+            inner: Rc::new(Inner {
+                limiter,
+                key: Key::Peer,
+            }),
+        }
+    }
+
+    fn inner(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Use request header value as rate limit key.
+    pub fn key_header<K>(mut self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+    {
+        match HeaderName::try_from(name) {
+            Ok(name) => self.inner().key = Key::Header(name),
+            Err(_) => panic!("Cannot create header name"),
+        }
+        self
+    }
+
+    /// Use custom function for selecting rate limit key.
+    pub fn key_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&RequestHead) -> Option<String> + 'static,
+    {
+        self.inner().key = Key::Fn(Box::new(f));
+        self
+    }
+}
+
+impl Inner {
+    fn key(&self, head: &RequestHead) -> Option<String> {
+        match self.key {
+            Key::Peer => head.peer_addr().map(|addr| addr.ip().to_string()),
+            Key::Header(ref name) => head
+                .headers
+                .get(name)
+                .and_then(|val| val.to_str().ok())
+                .map(|val| val.to_string()),
+            Key::Fn(ref f) => f(head),
+        }
+    }
+}
+
+/// Duration in seconds, rounded up
+fn secs(d: Duration) -> u64 {
+    d.as_secs() + u64::from(d.subsec_nanos() > 0)
+}
+
+fn set_headers(headers: &mut HeaderMap, info: &RateLimitInfo) {
+    headers.insert(
+        HeaderName::from_static("ratelimit-limit"),
+        HeaderValue::from(info.limit()),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-remaining"),
+        HeaderValue::from(info.remaining()),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-reset"),
+        HeaderValue::from(secs(info.reset())),
+    );
+}
+
+impl<S> Middleware<S> for RateLimit {
+    type Service = RateLimitMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RateLimitMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for RateLimitMiddleware<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitMiddleware")
+            .field("service", &self.service)
+            .finish()
+    }
+}
+
+impl<S, E> Service<WebRequest<E>> for RateLimitMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    E: 'static,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future<'f> = BoxFuture<'f, Result<Self::Response, Self::Error>> where S: 'f, E: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: WebRequest<E>,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        let info = match self.inner.key(req.head()) {
+            Some(key) => match self.inner.limiter.check(key) {
+                Ok(info) => Some(info),
+                Err(info) => {
+                    log::debug!(
+                        "Rate limit exceeded for {:?}, retry after {:?}",
+                        req.peer_addr(),
+                        info.retry_after()
+                    );
+                    let mut res = Response::new(StatusCode::TOO_MANY_REQUESTS);
+                    set_headers(res.headers_mut(), &info);
+                    res.headers_mut().insert(
+                        header::RETRY_AFTER,
+                        HeaderValue::from(secs(info.retry_after())),
+                    );
+                    return Box::pin(async move { Ok(req.into_response(res)) });
+                }
+            },
+            None => None,
+        };
+
+        Box::pin(async move {
+            let mut res = ctx.call(&self.service, req).await?;
+            if let Some(info) = info {
+                set_headers(res.headers_mut(), &info);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::{IntoService, Pipeline};
+    use crate::time::Seconds;
+    use crate::util::lazy;
+    use crate::web::test::{ok_service, TestRequest};
+    use crate::web::{DefaultError, Error, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_rate_limit() {
+        let mw = Pipeline::new(RateLimit::new(2, Seconds(60)).create(ok_service()));
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx).is_ready()).await);
+
+        let addr = "127.0.0.1:8080".parse().unwrap();
+        let req = TestRequest::default().peer_addr(addr).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("ratelimit-limit").unwrap(), "2");
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "1");
+        assert_eq!(res.headers().get("ratelimit-reset").unwrap(), "30");
+
+        let req = TestRequest::default().peer_addr(addr).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+
+        let req = TestRequest::default().peer_addr(addr).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "30");
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+        assert_eq!(res.headers().get("ratelimit-reset").unwrap(), "60");
+
+        // other peer
+        let req = TestRequest::default()
+            .peer_addr("127.0.0.2:8080".parse().unwrap())
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // no key
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key("ratelimit-limit"));
+    }
+
+    #[crate::rt_test]
+    async fn test_rate_limit_key() {
+        let limiter = RateLimiter::new(1, Seconds(60));
+        let mw = Pipeline::new(
+            RateLimit::with_limiter(limiter.clone())
+                .key_header("x-api-key")
+                .create(ok_service()),
+        );
+        assert!(format!("{:?}", mw).contains("RateLimitMiddleware"));
+
+        let req = TestRequest::default()
+            .header("x-api-key", "key1")
+            .to_srv_request();
+        assert_eq!(mw.call(req).await.unwrap().status(), StatusCode::OK);
+        let req = TestRequest::default()
+            .header("x-api-key", "key1")
+            .to_srv_request();
+        assert_eq!(
+            mw.call(req).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(limiter.len(), 1);
+
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let rl = RateLimit::new(1, Seconds(60)).key_fn(|head| Some(head.uri.path().into()));
+        assert!(format!("{:?}", rl).contains("RateLimit"));
+        let mw = Pipeline::new(rl.create(srv.into_service()));
+
+        let req = TestRequest::with_uri("/a").to_srv_request();
+        assert_eq!(mw.call(req).await.unwrap().status(), StatusCode::OK);
+        let req = TestRequest::with_uri("/b").to_srv_request();
+        assert_eq!(mw.call(req).await.unwrap().status(), StatusCode::OK);
+        let req = TestRequest::with_uri("/a").to_srv_request();
+        assert_eq!(
+            mw.call(req).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+}
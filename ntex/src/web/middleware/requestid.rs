@@ -0,0 +1,548 @@
+//! Request id middleware
+use std::{fmt, rc::Rc};
+
+use nanorand::{Rng, WyRand};
+
+use crate::http::header::{HeaderName, HeaderValue};
+use crate::http::RequestHead;
+use crate::service::{Middleware, Service, ServiceCtx};
+use crate::util::BoxFuture;
+use crate::web::{WebRequest, WebResponse};
+
+/// Max length of the incoming request id
+const MAX_ID_SIZE: usize = 128;
+
+/// Request id.
+///
+/// Request id is stored in request extensions by [`RequestIdentifier`] middleware.
+///
+/// ```rust
+/// use ntex::web::{self, middleware::RequestId, HttpRequest};
+///
+/// async fn index(req: HttpRequest) -> String {
+///     if let Some(id) = req.extensions().get::<RequestId>() {
+///         format!("Request id: {}", id)
+///     } else {
+///         "No id".to_string()
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RequestId(pub(super) Rc<str>);
+
+impl RequestId {
+    /// Get request id as str
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// W3C trace context of the request.
+///
+/// Trace context is stored in request extensions by [`RequestIdentifier`]
+/// middleware if `traceparent` support is enabled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    /// Trace id, 32 lowercase hex digits
+    pub trace_id: String,
+    /// Id of the caller's span, if request contains `traceparent` header
+    pub parent_id: Option<String>,
+    /// Id of the current span, 16 lowercase hex digits
+    pub span_id: String,
+    /// Trace flags
+    pub flags: u8,
+}
+
+impl TraceContext {
+    /// Parse `traceparent` header value
+    pub fn parse(val: &str) -> Option<TraceContext> {
+        let mut parts = val.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+
+        if version.len() != 2
+            || version == "ff"
+            || (version == "00" && parts.next().is_some())
+            || !is_hex(version)
+            || trace_id.len() != 32
+            || !is_hex(trace_id)
+            || trace_id.bytes().all(|b| b == b'0')
+            || parent_id.len() != 16
+            || !is_hex(parent_id)
+            || parent_id.bytes().all(|b| b == b'0')
+            || flags.len() != 2
+            || !is_hex(flags)
+        {
+            return None;
+        }
+
+        Some(TraceContext {
+            trace_id: trace_id.to_string(),
+            parent_id: Some(parent_id.to_string()),
+            span_id: random_hex(1),
+            flags: u8::from_str_radix(flags, 16).ok()?,
+        })
+    }
+
+    /// Generate new trace context
+    pub fn generate() -> TraceContext {
+        TraceContext {
+            trace_id: random_hex(2),
+            parent_id: None,
+            span_id: random_hex(1),
+            flags: 0,
+        }
+    }
+
+    /// Is trace sampled by the caller
+    pub fn is_sampled(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    /// `traceparent` header value for outgoing requests
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.flags)
+    }
+}
+
+fn is_hex(s: &str) -> bool {
+    s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn random_hex(n: usize) -> String {
+    let mut rng = WyRand::new();
+    let mut s = String::with_capacity(n * 16);
+    for _ in 0..n {
+        let mut val: u64 = rng.generate();
+        if val == 0 {
+            val = 1;
+        }
+        s.push_str(&format!("{:016x}", val));
+    }
+    s
+}
+
+#[cfg(feature = "tracing")]
+type SpanFn = dyn Fn(&RequestHead, &RequestId) -> tracing::Span;
+
+/// `Middleware` for request identification.
+///
+/// Middleware accepts request id from `X-Request-Id` request header or
+/// generates new one. Request id is stored in request extensions as [`RequestId`]
+/// and is sent back to the client in the same header. With `traceparent()`
+/// enabled, W3C trace context is parsed from `traceparent` header (or
+/// generated) and stored in request extensions as [`TraceContext`], trace id
+/// is used as request id if request does not contain request id header.
+///
+/// Request id could be logged with `Logger` middleware via `%{request-id}x`
+/// format token.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::RequestIdentifier::new().traceparent())
+///         .wrap(middleware::Logger::new("%{request-id}x %r %s"))
+///         .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+pub struct RequestIdentifier {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    header: HeaderName,
+    trust_incoming: bool,
+    traceparent: bool,
+    generator: Option<Box<dyn Fn() -> String>>,
+    #[cfg(feature = "tracing")]
+    span: Option<Box<SpanFn>>,
+}
+
+impl Default for RequestIdentifier {
+    fn default() -> Self {
+        RequestIdentifier {
+            inner: Rc::new(Inner {
+                header: HeaderName::from_static("x-request-id"),
+                trust_incoming: true,
+                traceparent: false,
+                generator: None,
+                #[cfg(feature = "tracing")]
+                span: None,
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for RequestIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdentifier")
+            .field("header", &self.inner.header)
+            .field("trust_incoming", &self.inner.trust_incoming)
+            .field("traceparent", &self.inner.traceparent)
+            .finish()
+    }
+}
+
+impl RequestIdentifier {
+    /// Construct `RequestIdentifier` middleware.
+    pub fn new() -> Self {
+        RequestIdentifier::default()
+    }
+
+    fn inner(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Set request id header name.
+    ///
+    /// Default is `X-Request-Id`.
+    pub fn header<K>(mut self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+    {
+        match HeaderName::try_from(name) {
+            Ok(name) => self.inner().header = name,
+            Err(_) => panic!("Cannot create header name"),
+        }
+        self
+    }
+
+    /// Accept request id from the incoming request.
+    ///
+    /// If disabled, request id is always generated. Default is `true`.
+    pub fn trust_incoming(mut self, value: bool) -> Self {
+        self.inner().trust_incoming = value;
+        self
+    }
+
+    /// Enable W3C `traceparent` header support.
+    pub fn traceparent(mut self) -> Self {
+        self.inner().traceparent = true;
+        self
+    }
+
+    /// Set custom request id generator.
+    ///
+    /// By default random 32 hex digits id is generated.
+    pub fn generator<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> String + 'static,
+    {
+        self.inner().generator = Some(Box::new(f));
+        self
+    }
+
+    #[cfg(feature = "tracing")]
+    /// Open `tracing` span for each request.
+    ///
+    /// Span contains `request_id`, `method` and `path` fields.
+    pub fn span(self) -> Self {
+        self.span_fn(|head, id| {
+            tracing::info_span!(
+                "request",
+                request_id = %id,
+                method = %head.method,
+                path = head.uri.path()
+            )
+        })
+    }
+
+    #[cfg(feature = "tracing")]
+    /// Open custom `tracing` span for each request.
+    pub fn span_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&RequestHead, &RequestId) -> tracing::Span + 'static,
+    {
+        self.inner().span = Some(Box::new(f));
+        self
+    }
+}
+
+impl Inner {
+    fn identify(&self, head: &RequestHead) -> (RequestId, Option<TraceContext>) {
+        let trace = if self.traceparent {
+            let ctx = if self.trust_incoming {
+                head.headers
+                    .get("traceparent")
+                    .and_then(|val| val.to_str().ok())
+                    .and_then(TraceContext::parse)
+            } else {
+                None
+            };
+            Some(ctx.unwrap_or_else(TraceContext::generate))
+        } else {
+            None
+        };
+
+        let incoming = if self.trust_incoming {
+            head.headers
+                .get(&self.header)
+                .and_then(|val| val.to_str().ok())
+                .map(|val| val.trim())
+                .filter(|val| {
+                    !val.is_empty()
+                        && val.len() <= MAX_ID_SIZE
+                        && val.bytes().all(|b| b.is_ascii_graphic())
+                })
+        } else {
+            None
+        };
+
+        let id = if let Some(id) = incoming {
+            id.into()
+        } else if let Some(ref trace) = trace {
+            trace.trace_id.as_str().into()
+        } else if let Some(ref f) = self.generator {
+            f().into()
+        } else {
+            random_hex(2).into()
+        };
+        (RequestId(id), trace)
+    }
+}
+
+impl<S> Middleware<S> for RequestIdentifier {
+    type Service = RequestIdentifierMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RequestIdentifierMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct RequestIdentifierMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for RequestIdentifierMiddleware<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdentifierMiddleware")
+            .field("service", &self.service)
+            .finish()
+    }
+}
+
+impl<S, E> Service<WebRequest<E>> for RequestIdentifierMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    E: 'static,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future<'f> = BoxFuture<'f, Result<Self::Response, Self::Error>> where S: 'f, E: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: WebRequest<E>,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        let (id, trace) = self.inner.identify(req.head());
+
+        #[cfg(feature = "tracing")]
+        let span = self.inner.span.as_ref().map(|f| f(req.head(), &id));
+
+        {
+            let mut ext = req.extensions_mut();
+            ext.insert(id.clone());
+            if let Some(trace) = trace {
+                ext.insert(trace);
+            }
+        }
+
+        let fut = async move {
+            let mut res = ctx.call(&self.service, req).await?;
+            if !res.headers().contains_key(&self.inner.header) {
+                if let Ok(val) = HeaderValue::from_str(id.as_str()) {
+                    res.headers_mut().insert(self.inner.header.clone(), val);
+                }
+            }
+            Ok(res)
+        };
+
+        #[cfg(feature = "tracing")]
+        if let Some(span) = span {
+            return Box::pin(tracing::Instrument::instrument(fut, span));
+        }
+        Box::pin(fut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::{IntoService, Pipeline};
+    use crate::util::lazy;
+    use crate::web::test::{ok_service, TestRequest};
+    use crate::web::{DefaultError, Error, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_request_id() {
+        let mw = Pipeline::new(RequestIdentifier::new().create(ok_service()));
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx).is_ready()).await);
+
+        // generated
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let id = res
+            .request()
+            .extensions()
+            .get::<RequestId>()
+            .unwrap()
+            .clone();
+        assert_eq!(id.as_str().len(), 32);
+        assert_eq!(res.headers().get("x-request-id").unwrap(), id.as_str());
+        assert!(res.request().extensions().get::<TraceContext>().is_none());
+
+        // incoming
+        let req = TestRequest::default()
+            .header("x-request-id", "abc-123")
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "abc-123");
+        assert_eq!(
+            res.request()
+                .extensions()
+                .get::<RequestId>()
+                .unwrap()
+                .to_string(),
+            "abc-123"
+        );
+
+        // invalid incoming
+        let req = TestRequest::default()
+            .header("x-request-id", "a".repeat(MAX_ID_SIZE + 1))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-request-id").unwrap().len(), 32);
+    }
+
+    #[crate::rt_test]
+    async fn test_request_id_settings() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            let id = req.extensions().get::<RequestId>().unwrap().clone();
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().body(id.to_string())))
+        };
+        let mw = Pipeline::new(
+            RequestIdentifier::new()
+                .header("x-correlation-id")
+                .trust_incoming(false)
+                .generator(|| "generated".to_string())
+                .create(srv.into_service()),
+        );
+
+        let req = TestRequest::default()
+            .header("x-correlation-id", "abc-123")
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-correlation-id").unwrap(), "generated");
+        assert!(!res.headers().contains_key("x-request-id"));
+        assert_eq!(crate::web::test::read_body(res).await, "generated");
+    }
+
+    #[crate::rt_test]
+    async fn test_traceparent() {
+        let mw = Pipeline::new(RequestIdentifier::new().traceparent().create(ok_service()));
+
+        let req = TestRequest::default()
+            .header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(
+            res.headers().get("x-request-id").unwrap(),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        let ctx = res
+            .request()
+            .extensions()
+            .get::<TraceContext>()
+            .unwrap()
+            .clone();
+        assert_eq!(ctx.trace_id, "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(ctx.parent_id.as_deref(), Some("b7ad6b7169203331"));
+        assert_ne!(ctx.span_id, "b7ad6b7169203331");
+        assert!(ctx.is_sampled());
+        assert_eq!(
+            ctx.traceparent(),
+            format!("00-0af7651916cd43dd8448eb211c80319c-{}-01", ctx.span_id)
+        );
+
+        // request id header has priority
+        let req = TestRequest::default()
+            .header("x-request-id", "abc-123")
+            .header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "abc-123");
+
+        // generated trace context
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        let ctx = res
+            .request()
+            .extensions()
+            .get::<TraceContext>()
+            .unwrap()
+            .clone();
+        assert_eq!(
+            res.headers().get("x-request-id").unwrap(),
+            ctx.trace_id.as_str()
+        );
+        assert_eq!(ctx.parent_id, None);
+        assert!(!ctx.is_sampled());
+    }
+
+    #[test]
+    fn test_parse_traceparent() {
+        let ctx =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00")
+                .unwrap();
+        assert_eq!(ctx.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(ctx.span_id.len(), 16);
+        assert_eq!(ctx.flags, 0);
+
+        // future versions could contain more fields
+        assert!(TraceContext::parse(
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra"
+        )
+        .is_some());
+
+        for val in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-1",
+        ] {
+            assert!(TraceContext::parse(val).is_none(), "{}", val);
+        }
+    }
+}
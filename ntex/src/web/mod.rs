@@ -68,10 +68,12 @@ mod config;
 pub mod error;
 mod error_default;
 mod extract;
+pub mod files;
 pub mod guard;
 mod handler;
 mod httprequest;
 mod info;
+mod metrics;
 pub mod middleware;
 mod request;
 mod resource;
@@ -82,6 +84,9 @@ mod route;
 mod scope;
 mod server;
 mod service;
+pub mod sse;
+#[cfg(feature = "cookie")]
+pub mod session;
 pub mod test;
 pub mod types;
 mod util;
@@ -109,6 +114,8 @@ pub use self::error::{
 pub use self::extract::FromRequest;
 pub use self::handler::Handler;
 pub use self::httprequest::HttpRequest;
+pub use self::info::TrustedProxies;
+pub use self::metrics::metrics;
 pub use self::request::WebRequest;
 pub use self::resource::Resource;
 pub use self::responder::Responder;
@@ -117,6 +124,7 @@ pub use self::route::Route;
 pub use self::scope::Scope;
 pub use self::server::HttpServer;
 pub use self::service::WebServiceFactory;
+pub use self::sse::Sse;
 pub use self::util::*;
 
 pub mod dev {
@@ -13,6 +13,7 @@ use crate::util::{BoxFuture, Either, Extensions, Ready};
 use super::dev::{insert_slash, WebServiceConfig, WebServiceFactory};
 use super::extract::FromRequest;
 use super::handler::Handler;
+use super::middleware::{BodyLimit, BodyLimitMiddleware};
 use super::request::WebRequest;
 use super::response::WebResponse;
 use super::route::{IntoRoutes, Route, RouteService};
@@ -58,6 +59,7 @@ pub struct Resource<Err: ErrorRenderer, M = Identity, T = Filter<Err>> {
     state: Option<Extensions>,
     guards: Vec<Box<dyn Guard>>,
     default: Rc<RefCell<Option<Rc<HttpNewService<Err>>>>>,
+    body_limit: BodyLimit,
 }
 
 impl<Err: ErrorRenderer> Resource<Err> {
@@ -71,6 +73,7 @@ impl<Err: ErrorRenderer> Resource<Err> {
             filter: chain_factory(Filter::new()),
             guards: Vec::new(),
             default: Rc::new(RefCell::new(None)),
+            body_limit: BodyLimit::default(),
         }
     }
 }
@@ -267,9 +270,23 @@ where
             guards: self.guards,
             routes: self.routes,
             default: self.default,
+            body_limit: self.body_limit,
         }
     }
 
+    /// Set max size of request's payload for the resource.
+    ///
+    /// Requests with larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` response before resource's middlewares run.
+    /// If application or scope sets smaller limit, smaller limit applies.
+    /// To disable limit set value to 0.
+    ///
+    /// By default payload size is not limited.
+    pub fn body_limit(mut self, limit: u64) -> Self {
+        self.body_limit = BodyLimit::new(limit);
+        self
+    }
+
     /// Register a resource middleware.
     ///
     /// This is similar to `App's` middlewares, but middleware get invoked on resource level.
@@ -287,6 +304,7 @@ where
             guards: self.guards,
             routes: self.routes,
             default: self.default,
+            body_limit: self.body_limit,
         }
     }
 
@@ -356,6 +374,7 @@ where
             guards,
             ResourceServiceFactory {
                 middleware: self.middleware,
+                body_limit: self.body_limit,
                 filter: self.filter,
                 routing: router_factory,
             },
@@ -391,6 +410,7 @@ where
 
         ResourceServiceFactory {
             middleware: self.middleware,
+            body_limit: self.body_limit,
             filter: self.filter,
             routing: router_factory,
         }
@@ -400,6 +420,7 @@ where
 /// Resource service
 pub struct ResourceServiceFactory<Err: ErrorRenderer, M, F> {
     middleware: M,
+    body_limit: BodyLimit,
     filter: F,
     routing: ResourceRouterFactory<Err>,
 }
@@ -418,7 +439,7 @@ where
 {
     type Response = WebResponse;
     type Error = Err::Container;
-    type Service = M::Service;
+    type Service = BodyLimitMiddleware<M::Service>;
     type InitError = ();
     type Future<'f> = BoxFuture<'f, Result<Self::Service, Self::InitError>>;
 
@@ -426,7 +447,8 @@ where
         Box::pin(async move {
             let filter = self.filter.create(()).await?;
             let routing = self.routing.create(()).await?;
-            Ok(self.middleware.create(filter.chain().and_then(routing)))
+            let service = self.middleware.create(filter.chain().and_then(routing));
+            Ok(self.body_limit.create(service))
         })
     }
 }
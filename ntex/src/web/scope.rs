@@ -16,6 +16,7 @@ use super::config::ServiceConfig;
 use super::dev::{WebServiceConfig, WebServiceFactory};
 use super::error::ErrorRenderer;
 use super::guard::Guard;
+use super::middleware::{BodyLimit, BodyLimitMiddleware};
 use super::request::WebRequest;
 use super::resource::Resource;
 use super::response::WebResponse;
@@ -70,6 +71,7 @@ pub struct Scope<Err: ErrorRenderer, M = Identity, T = Filter<Err>> {
     default: Rc<RefCell<Option<Rc<HttpNewService<Err>>>>>,
     external: Vec<ResourceDef>,
     case_insensitive: bool,
+    body_limit: BodyLimit,
 }
 
 impl<Err: ErrorRenderer> Scope<Err> {
@@ -85,6 +87,7 @@ impl<Err: ErrorRenderer> Scope<Err> {
             default: Rc::new(RefCell::new(None)),
             external: Vec::new(),
             case_insensitive: false,
+            body_limit: BodyLimit::default(),
         }
     }
 }
@@ -166,6 +169,19 @@ where
         self
     }
 
+    /// Set max size of request's payload for scope's services.
+    ///
+    /// Requests with larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` response before scope's middlewares run.
+    /// If application or parent scope sets smaller limit, smaller limit applies.
+    /// To disable limit set value to 0.
+    ///
+    /// By default payload size is not limited.
+    pub fn body_limit(mut self, limit: u64) -> Self {
+        self.body_limit = BodyLimit::new(limit);
+        self
+    }
+
     /// Run external configuration as part of the scope building
     /// process
     ///
@@ -334,6 +350,7 @@ where
             default: self.default,
             external: self.external,
             case_insensitive: self.case_insensitive,
+            body_limit: self.body_limit,
         }
     }
 
@@ -358,6 +375,7 @@ where
             default: self.default,
             external: self.external,
             case_insensitive: self.case_insensitive,
+            body_limit: self.body_limit,
         }
     }
 }
@@ -437,6 +455,7 @@ where
             guards,
             ScopeServiceFactory {
                 middleware: self.middleware,
+                body_limit: self.body_limit,
                 filter: self.filter,
                 routing: router_factory,
             },
@@ -448,6 +467,7 @@ where
 /// Scope service
 struct ScopeServiceFactory<M, F, Err: ErrorRenderer> {
     middleware: M,
+    body_limit: BodyLimit,
     filter: F,
     routing: ScopeRouterFactory<Err>,
 }
@@ -466,16 +486,17 @@ where
 {
     type Response = WebResponse;
     type Error = Err::Container;
-    type Service = M::Service;
+    type Service = BodyLimitMiddleware<M::Service>;
     type InitError = ();
     type Future<'f> = BoxFuture<'f, Result<Self::Service, Self::InitError>>;
 
     fn create(&self, _: ()) -> Self::Future<'_> {
         Box::pin(async move {
-            Ok(self.middleware.create(ScopeService {
+            let service = self.middleware.create(ScopeService {
                 filter: self.filter.create(()).await?,
                 routing: self.routing.create(()).await?,
-            }))
+            });
+            Ok(self.body_limit.create(service))
         })
     }
 }
@@ -645,7 +666,7 @@ impl<Err: ErrorRenderer> Service<WebRequest<Err>> for ScopeRouter<Err> {
 #[cfg(test)]
 mod tests {
     use crate::http::body::{Body, ResponseBody};
-    use crate::http::header::{HeaderValue, CONTENT_TYPE};
+    use crate::http::header::{self, HeaderValue, CONTENT_TYPE};
     use crate::http::{Method, StatusCode};
     use crate::service::fn_service;
     use crate::util::{Bytes, Ready};
@@ -1193,6 +1214,57 @@ mod tests {
         );
     }
 
+    #[crate::rt_test]
+    async fn test_body_limit() {
+        let srv = init_service(
+            App::new().body_limit(100).service(
+                web::scope("app")
+                    .body_limit(10)
+                    .wrap(
+                        DefaultHeaders::new()
+                            .header(CONTENT_TYPE, HeaderValue::from_static("0001")),
+                    )
+                    .service(
+                        web::resource("/test")
+                            .to(|body: Bytes| async move { HttpResponse::Ok().body(body) }),
+                    )
+                    .service(
+                        web::resource("/small")
+                            .body_limit(5)
+                            .to(|body: Bytes| async move { HttpResponse::Ok().body(body) }),
+                    ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/app/test")
+            .header(header::CONTENT_LENGTH, "10")
+            .set_payload(Bytes::from_static(b"0123456789"))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // scope limit applies before scope middlewares
+        let req = TestRequest::with_uri("/app/test")
+            .header(header::CONTENT_LENGTH, "11")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!resp.headers().contains_key(CONTENT_TYPE));
+
+        let req = TestRequest::with_uri("/app/small")
+            .header(header::CONTENT_LENGTH, "6")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::with_uri("/app/unknown")
+            .header(header::CONTENT_LENGTH, "101")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
     #[crate::rt_test]
     async fn test_scope_config() {
         let srv = init_service(App::new().service(web::scope("/app").configure(|s| {
@@ -12,7 +12,7 @@ use crate::server::{Server, ServerBuilder};
 use crate::service::{map_config, IntoServiceFactory, ServiceFactory};
 use crate::{time::Seconds, util::PoolId};
 
-use super::config::AppConfig;
+use super::{config::AppConfig, info::TrustedProxies};
 
 struct Config {
     host: Option<String>,
@@ -21,7 +21,10 @@ struct Config {
     ssl_handshake_timeout: Seconds,
     headers_read_rate: Option<ReadRate>,
     payload_read_rate: Option<ReadRate>,
+    payload_limit: u64,
+    h2c: bool,
     pool: PoolId,
+    proxies: Option<TrustedProxies>,
 }
 
 #[derive(Default, Copy, Clone)]
@@ -44,8 +47,20 @@ impl Config {
         if let Some(hdrs) = self.payload_read_rate {
             svc_cfg.payload_read_rate(hdrs.timeout, hdrs.max_timeout, hdrs.rate);
         }
+        svc_cfg.payload_limit(self.payload_limit);
+        svc_cfg.h2c(self.h2c);
         svc_cfg
     }
+
+    fn app_config(&self, secure: bool, addr: net::SocketAddr) -> AppConfig {
+        let host = self.host.clone().unwrap_or_else(|| format!("{}", addr));
+        let cfg = AppConfig::new(secure, addr, host);
+        if let Some(ref proxies) = self.proxies {
+            cfg.with_trusted_proxies(proxies.clone())
+        } else {
+            cfg
+        }
+    }
 }
 
 /// An HTTP Server.
@@ -107,7 +122,10 @@ where
                     max_timeout: Seconds(13),
                 }),
                 payload_read_rate: None,
+                payload_limit: 0,
+                h2c: false,
                 pool: PoolId::P0,
+                proxies: None,
             })),
             backlog: 1024,
             builder: ServerBuilder::default(),
@@ -268,6 +286,29 @@ where
         self
     }
 
+    /// Set max size of request's payload.
+    ///
+    /// Requests with larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` response, streamed payloads are terminated
+    /// once they exceed the limit. To disable limit set value to 0.
+    ///
+    /// By default payload size is not limited.
+    pub fn payload_limit(self, limit: u64) -> Self {
+        self.config.lock().unwrap().payload_limit = limit;
+        self
+    }
+
+    /// Enable http/2 over cleartext connections (h2c).
+    ///
+    /// Cleartext connections are switched to http/2 if connection starts
+    /// with http/2 preface or first request is `Upgrade: h2c` request.
+    ///
+    /// By default h2c is disabled.
+    pub fn h2c(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().h2c = enabled;
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -280,6 +321,18 @@ where
         self
     }
 
+    /// Set trusted proxies.
+    ///
+    /// Forwarded headers are honoured only for requests from trusted proxies.
+    /// Check [TrustedProxies](./struct.TrustedProxies.html) documentation
+    /// for more information.
+    ///
+    /// By default forwarded headers of any request are honoured.
+    pub fn trusted_proxies(self, proxies: TrustedProxies) -> Self {
+        self.config.lock().unwrap().proxies = Some(proxies);
+        self
+    }
+
     /// Stop ntex runtime when server get dropped.
     ///
     /// By default "stop runtime" is disabled.
@@ -316,6 +369,18 @@ where
         self
     }
 
+    /// Adopt listeners inherited from the parent process.
+    ///
+    /// Listeners passed in by systemd socket activation or by a supervising
+    /// process are used by `bind()` methods if listener's address matches,
+    /// otherwise new socket is bound.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn from_env_listeners(mut self) -> io::Result<Self> {
+        self.builder = self.builder.from_env_listeners()?;
+        Ok(self)
+    }
+
     /// Use listener for accepting incoming connection requests
     ///
     /// HttpServer does not change any configuration for TcpListener,
@@ -329,11 +394,7 @@ where
             self.builder
                 .listen(format!("ntex-web-service-{}", addr), lst, move |r| {
                     let c = cfg.lock().unwrap();
-                    let cfg = AppConfig::new(
-                        false,
-                        addr,
-                        c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                    );
+                    let cfg = c.app_config(false, addr);
                     r.memory_pool(c.pool);
 
                     HttpService::build_with_config(c.into_cfg())
@@ -368,11 +429,7 @@ where
             self.builder
                 .listen(format!("ntex-web-service-{}", addr), lst, move |r| {
                     let c = cfg.lock().unwrap();
-                    let cfg = AppConfig::new(
-                        true,
-                        addr,
-                        c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                    );
+                    let cfg = c.app_config(true, addr);
                     r.memory_pool(c.pool);
 
                     HttpService::build_with_config(c.into_cfg())
@@ -409,11 +466,7 @@ where
             lst,
             move |r| {
                 let c = cfg.lock().unwrap();
-                let cfg = AppConfig::new(
-                    true,
-                    addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                );
+                let cfg = c.app_config(true, addr);
                 r.memory_pool(c.pool);
 
                 HttpService::build_with_config(c.into_cfg())
@@ -437,11 +490,19 @@ where
         Ok(self)
     }
 
-    fn bind2<A: net::ToSocketAddrs>(&self, addr: A) -> io::Result<Vec<net::TcpListener>> {
+    fn bind2<A: net::ToSocketAddrs>(
+        &mut self,
+        addr: A,
+    ) -> io::Result<Vec<net::TcpListener>> {
         let mut err = None;
         let mut succ = false;
         let mut sockets = Vec::new();
         for addr in addr.to_socket_addrs()? {
+            if let Some(lst) = self.builder.take_inherited(&addr) {
+                succ = true;
+                sockets.push(lst);
+                continue;
+            }
             match crate::server::create_tcp_listener(addr, self.backlog) {
                 Ok(lst) => {
                     succ = true;
@@ -517,11 +578,7 @@ where
 
         self.builder = self.builder.listen_uds(addr, lst, move |r| {
             let c = cfg.lock().unwrap();
-            let config = AppConfig::new(
-                false,
-                socket_addr,
-                c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-            );
+            let config = c.app_config(false, socket_addr);
             r.memory_pool(c.pool);
 
             HttpService::build_with_config(c.into_cfg())
@@ -548,11 +605,7 @@ where
             addr,
             move |r| {
                 let c = cfg.lock().unwrap();
-                let config = AppConfig::new(
-                    false,
-                    socket_addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-                );
+                let config = c.app_config(false, socket_addr);
                 r.memory_pool(c.pool);
 
                 HttpService::build_with_config(c.into_cfg())
@@ -0,0 +1,519 @@
+use std::{fmt, rc::Rc, time::Duration};
+
+use coo_kie::{time, Cookie, CookieJar, Key, SameSite};
+
+use super::{Session, SessionState, SessionStatus, SessionStore};
+use crate::http::header::{self, HeaderValue};
+use crate::http::HttpMessage;
+use crate::service::{Middleware, Service, ServiceCtx};
+use crate::util::BoxFuture;
+use crate::web::error::{ErrorRenderer, SessionError};
+use crate::web::{WebRequest, WebResponse};
+
+/// Max size of the encoded cookie, most browsers limit cookie size to 4096 bytes
+const COOKIE_LIMIT: usize = 4064;
+
+/// `Middleware` for session management.
+///
+/// Middleware loads session state from the store before request
+/// processing and writes it back if session has been changed.
+/// Session key is sent to the client in a cookie, cookie content is encrypted
+/// by default, use `signed()` method if only integrity protection is needed.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use ntex::web::{self, session, App};
+///
+/// fn main() {
+///     let app = App::new().wrap(
+///         session::Sessions::new(session::MemorySessionStore::default(), session::Key::generate())
+///             .cookie_name("session")
+///             .cookie_same_site(session::SameSite::Strict)
+///             .ttl(Duration::from_secs(3600))
+///             .renewal(true)
+///     );
+/// }
+/// ```
+pub struct Sessions<St> {
+    inner: Rc<Inner<St>>,
+}
+
+struct Inner<St> {
+    store: St,
+    key: Key,
+    signed: bool,
+    name: String,
+    path: String,
+    domain: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    ttl: Duration,
+    renewal: bool,
+}
+
+impl<St: SessionStore> Sessions<St> {
+    /// Construct `Sessions` middleware with specified store and cookie key.
+    pub fn new(store: St, key: Key) -> Self {
+        Sessions {
+            inner: Rc::new(Inner {
+                store,
+                key,
+                signed: false,
+                name: "id".to_string(),
+                path: "/".to_string(),
+                domain: None,
+                secure: true,
+                http_only: true,
+                same_site: Some(SameSite::Lax),
+                ttl: Duration::from_secs(86_400),
+                renewal: false,
+            }),
+        }
+    }
+
+    fn inner(&mut self) -> &mut Inner<St> {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Sign session cookie instead of encrypting it.
+    ///
+    /// Client would be able to read cookie content, but cannot modify it.
+    pub fn signed(mut self) -> Self {
+        self.inner().signed = true;
+        self
+    }
+
+    /// Set session cookie name.
+    ///
+    /// Default is `id`.
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        self.inner().name = name.to_string();
+        self
+    }
+
+    /// Set session cookie path.
+    ///
+    /// Default is `/`.
+    pub fn cookie_path(mut self, path: &str) -> Self {
+        self.inner().path = path.to_string();
+        self
+    }
+
+    /// Set session cookie domain.
+    pub fn cookie_domain(mut self, domain: &str) -> Self {
+        self.inner().domain = Some(domain.to_string());
+        self
+    }
+
+    /// Set session cookie `Secure` attribute.
+    ///
+    /// Default is `true`.
+    pub fn cookie_secure(mut self, value: bool) -> Self {
+        self.inner().secure = value;
+        self
+    }
+
+    /// Set session cookie `HttpOnly` attribute.
+    ///
+    /// Default is `true`.
+    pub fn cookie_http_only(mut self, value: bool) -> Self {
+        self.inner().http_only = value;
+        self
+    }
+
+    /// Set session cookie `SameSite` attribute.
+    ///
+    /// Default is `Lax`.
+    pub fn cookie_same_site(mut self, value: SameSite) -> Self {
+        self.inner().same_site = Some(value);
+        self
+    }
+
+    /// Set session time-to-live.
+    ///
+    /// Session cookie max age is set to the same value. Default is 1 day.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.inner().ttl = ttl;
+        self
+    }
+
+    /// Extend session's time-to-live on every request.
+    ///
+    /// By default ttl is extended only if session state changes.
+    pub fn renewal(mut self, value: bool) -> Self {
+        self.inner().renewal = value;
+        self
+    }
+}
+
+impl<St> fmt::Debug for Sessions<St> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sessions")
+            .field("name", &self.inner.name)
+            .field("path", &self.inner.path)
+            .field("ttl", &self.inner.ttl)
+            .field("renewal", &self.inner.renewal)
+            .finish()
+    }
+}
+
+impl<St> Inner<St> {
+    fn set_cookie(&self, key: String, res: &mut WebResponse) -> Result<(), SessionError> {
+        let mut cookie = Cookie::new(self.name.clone(), key);
+        cookie.set_max_age(time::Duration::seconds(self.ttl.as_secs() as i64));
+
+        let mut jar = CookieJar::new();
+        if self.signed {
+            jar.signed_mut(&self.key).add(cookie);
+        } else {
+            jar.private_mut(&self.key).add(cookie);
+        }
+        let cookie = jar.get(&self.name).unwrap().clone();
+        if cookie.encoded().stripped().to_string().len() > COOKIE_LIMIT {
+            return Err(SessionError::Overflow);
+        }
+        self.add_cookie(cookie, res);
+        Ok(())
+    }
+
+    fn remove_cookie(&self, res: &mut WebResponse) {
+        let mut cookie = Cookie::named(self.name.clone());
+        cookie.make_removal();
+        self.add_cookie(cookie, res);
+    }
+
+    fn add_cookie(&self, mut cookie: Cookie<'static>, res: &mut WebResponse) {
+        cookie.set_path(self.path.clone());
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(self.http_only);
+        cookie.set_same_site(self.same_site);
+        if let Some(ref domain) = self.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        match HeaderValue::from_str(&cookie.encoded().to_string()) {
+            Ok(val) => res.headers_mut().append(header::SET_COOKIE, val),
+            Err(e) => log::error!("Cannot set session cookie: {}", e),
+        }
+    }
+}
+
+impl<St: SessionStore> Inner<St> {
+    /// Load session state, returns session key, state and stale cookie flag
+    async fn load<Err>(
+        &self,
+        req: &WebRequest<Err>,
+    ) -> (Option<String>, SessionState, bool) {
+        let cookie = if let Some(cookie) = req.cookie(&self.name) {
+            cookie
+        } else {
+            return (None, SessionState::new(), false);
+        };
+
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie);
+        let key = if self.signed {
+            jar.signed(&self.key).get(&self.name)
+        } else {
+            jar.private(&self.key).get(&self.name)
+        };
+
+        if let Some(key) = key.map(|c| c.value().to_string()) {
+            match self.store.load(&key).await {
+                Ok(Some(state)) => return (Some(key), state, false),
+                Ok(None) => (),
+                Err(e) => log::error!("Cannot load session state: {}", e),
+            }
+        }
+        // session cookie is invalid or session is expired
+        (None, SessionState::new(), true)
+    }
+
+    async fn write(
+        &self,
+        key: Option<String>,
+        stale: bool,
+        status: SessionStatus,
+        state: SessionState,
+        res: &mut WebResponse,
+    ) -> Result<(), SessionError> {
+        match status {
+            SessionStatus::Changed => {
+                if let Some(key) = key {
+                    let key = self.store.update(key, state, self.ttl).await?;
+                    self.set_cookie(key, res)?;
+                } else if !state.is_empty() {
+                    let key = self.store.save(state, self.ttl).await?;
+                    self.set_cookie(key, res)?;
+                } else if stale {
+                    self.remove_cookie(res);
+                }
+            }
+            SessionStatus::Renewed => {
+                if let Some(key) = key {
+                    self.store.delete(&key).await?;
+                }
+                let key = self.store.save(state, self.ttl).await?;
+                self.set_cookie(key, res)?;
+            }
+            SessionStatus::Purged => {
+                if let Some(key) = key {
+                    self.store.delete(&key).await?;
+                    self.remove_cookie(res);
+                } else if stale {
+                    self.remove_cookie(res);
+                }
+            }
+            SessionStatus::Unchanged => {
+                if let Some(key) = key {
+                    if self.renewal {
+                        self.store.update_ttl(&key, self.ttl).await?;
+                        self.set_cookie(key, res)?;
+                    }
+                } else if stale {
+                    self.remove_cookie(res);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S, St> Middleware<S> for Sessions<St> {
+    type Service = SessionsMiddleware<S, St>;
+
+    fn create(&self, service: S) -> Self::Service {
+        SessionsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct SessionsMiddleware<S, St> {
+    service: S,
+    inner: Rc<Inner<St>>,
+}
+
+impl<S: fmt::Debug, St> fmt::Debug for SessionsMiddleware<S, St> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionsMiddleware")
+            .field("service", &self.service)
+            .finish()
+    }
+}
+
+impl<S, St, Err> Service<WebRequest<Err>> for SessionsMiddleware<S, St>
+where
+    S: Service<WebRequest<Err>, Response = WebResponse, Error = Err::Container>,
+    St: SessionStore,
+    Err: ErrorRenderer,
+    Err::Container: From<SessionError>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future<'f> =
+        BoxFuture<'f, Result<Self::Response, Self::Error>> where S: 'f, Err: 'f;
+
+    crate::forward_poll_ready!(service);
+    crate::forward_poll_shutdown!(service);
+
+    fn call<'a>(
+        &'a self,
+        req: WebRequest<Err>,
+        ctx: ServiceCtx<'a, Self>,
+    ) -> Self::Future<'a> {
+        Box::pin(async move {
+            let (key, state, stale) = self.inner.load(&req).await;
+
+            let session = Session::new(state);
+            req.extensions_mut().insert(session.clone());
+
+            let mut res = ctx.call(&self.service, req).await?;
+
+            let (status, state) = session.into_parts();
+            if let Err(e) = self.inner.write(key, stale, status, state, &mut res).await {
+                let req = res.request().clone();
+                Ok(WebResponse::from_err::<Err, _>(e, req))
+            } else {
+                Ok(res)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::web::session::{CookieSessionStore, MemorySessionStore};
+    use crate::web::test::{self, TestRequest};
+    use crate::web::{self, App, Error, HttpResponse};
+
+    async fn counter(session: Session) -> Result<String, Error> {
+        let count = session.get::<u32>("counter")?.unwrap_or(0) + 1;
+        session.insert("counter", count)?;
+        Ok(count.to_string())
+    }
+
+    async fn get(session: Session) -> Result<String, Error> {
+        Ok(session.get::<u32>("counter")?.unwrap_or(0).to_string())
+    }
+
+    async fn renew(session: Session) -> HttpResponse {
+        session.renew();
+        HttpResponse::Ok().finish()
+    }
+
+    async fn purge(session: Session) -> HttpResponse {
+        session.purge();
+        HttpResponse::Ok().finish()
+    }
+
+    fn session_cookie(res: &WebResponse) -> Option<Cookie<'static>> {
+        res.response()
+            .cookies()
+            .find(|c| c.name() == "id")
+            .map(|c| c.into_owned())
+    }
+
+    macro_rules! app {
+        ($mw:expr) => {
+            test::init_service(
+                App::new()
+                    .wrap($mw)
+                    .service(web::resource("/").to(counter))
+                    .service(web::resource("/get").to(get))
+                    .service(web::resource("/renew").to(renew))
+                    .service(web::resource("/purge").to(purge)),
+            )
+            .await
+        };
+    }
+
+    #[crate::rt_test]
+    async fn test_cookie_session() {
+        for signed in [false, true] {
+            let mut mw = Sessions::new(CookieSessionStore, Key::generate())
+                .cookie_path("/")
+                .cookie_domain("www.rust-lang.org")
+                .cookie_same_site(SameSite::Strict)
+                .ttl(Duration::from_secs(60));
+            if signed {
+                mw = mw.signed();
+            }
+            let srv = app!(mw);
+
+            let res =
+                test::call_service(&srv, TestRequest::with_uri("/").to_request()).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let cookie = session_cookie(&res).unwrap();
+            assert_eq!(cookie.path(), Some("/"));
+            assert_eq!(cookie.domain(), Some("www.rust-lang.org"));
+            assert_eq!(cookie.secure(), Some(true));
+            assert_eq!(cookie.http_only(), Some(true));
+            assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+            assert_eq!(cookie.max_age(), Some(time::Duration::seconds(60)));
+            assert_eq!(cookie.value().contains("counter"), signed);
+            assert_eq!(test::read_body(res).await, "1");
+
+            let req = TestRequest::with_uri("/").cookie(cookie).to_request();
+            let res = test::call_service(&srv, req).await;
+            let cookie = session_cookie(&res).unwrap();
+            assert_eq!(test::read_body(res).await, "2");
+
+            // unchanged session
+            let req = TestRequest::with_uri("/get")
+                .cookie(cookie.clone())
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert!(session_cookie(&res).is_none());
+            assert_eq!(test::read_body(res).await, "2");
+
+            // tampered cookie
+            let mut bad = cookie.clone();
+            bad.set_value(format!("{}x", cookie.value()));
+            let req = TestRequest::with_uri("/get").cookie(bad).to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(session_cookie(&res).unwrap().value(), "");
+            assert_eq!(test::read_body(res).await, "0");
+
+            // purge
+            let req = TestRequest::with_uri("/purge").cookie(cookie).to_request();
+            let res = test::call_service(&srv, req).await;
+            let cookie = session_cookie(&res).unwrap();
+            assert_eq!(cookie.value(), "");
+            assert_eq!(cookie.max_age(), Some(time::Duration::ZERO));
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_memory_session() {
+        let store = MemorySessionStore::default();
+        let srv = app!(Sessions::new(store.clone(), Key::generate()).renewal(true));
+
+        // empty session is not stored
+        let res =
+            test::call_service(&srv, TestRequest::with_uri("/get").to_request()).await;
+        assert!(session_cookie(&res).is_none());
+        assert!(store.is_empty());
+
+        let res = test::call_service(&srv, TestRequest::with_uri("/").to_request()).await;
+        let cookie = session_cookie(&res).unwrap();
+        assert_eq!(store.len(), 1);
+
+        // renewal is enabled, cookie is sent on every request
+        let req = TestRequest::with_uri("/get")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(session_cookie(&res).is_some());
+        assert_eq!(test::read_body(res).await, "1");
+
+        // renew session key
+        let req = TestRequest::with_uri("/renew")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie2 = session_cookie(&res).unwrap();
+        assert_ne!(cookie.value(), cookie2.value());
+        assert_eq!(store.len(), 1);
+
+        // old key is not valid anymore
+        let req = TestRequest::with_uri("/get").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "0");
+
+        let req = TestRequest::with_uri("/get")
+            .cookie(cookie2.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "1");
+
+        // purge
+        let req = TestRequest::with_uri("/purge").cookie(cookie2).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(session_cookie(&res).unwrap().value(), "");
+        assert!(store.is_empty());
+    }
+
+    #[crate::rt_test]
+    async fn test_store_error() {
+        async fn big(session: Session) -> Result<HttpResponse, Error> {
+            // raw state fits into the limit, encrypted cookie does not
+            session.insert("data", "x".repeat(3500))?;
+            Ok(HttpResponse::Ok().finish())
+        }
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(Sessions::new(CookieSessionStore, Key::generate()))
+                .service(web::resource("/").to(big)),
+        )
+        .await;
+        let res = test::call_service(&srv, TestRequest::with_uri("/").to_request()).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(session_cookie(&res).is_none());
+    }
+}
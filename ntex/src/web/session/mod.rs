@@ -0,0 +1,277 @@
+//! Session support
+//!
+//! Session state is a map of string keys to json encoded values. State is
+//! loaded by [`Sessions`] middleware from a [`SessionStore`] and could be
+//! accessed with the [`Session`] extractor. Store is updated only if
+//! session has been changed during request processing.
+//!
+//! ```rust
+//! use ntex::web::{self, session, App, HttpResponse, Error};
+//!
+//! async fn index(session: session::Session) -> Result<&'static str, Error> {
+//!     // access session data
+//!     if let Some(count) = session.get::<i32>("counter")? {
+//!         session.insert("counter", count + 1)?;
+//!     } else {
+//!         session.insert("counter", 1)?;
+//!     }
+//!     Ok("Welcome!")
+//! }
+//!
+//! fn main() {
+//!     let key = session::Key::generate();
+//!     let app = App::new()
+//!         .wrap(session::Sessions::new(session::CookieSessionStore::default(), key))
+//!         .service(web::resource("/").to(index));
+//! }
+//! ```
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc, time::Duration};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::http::Payload;
+use crate::util::{BoxFuture, Ready};
+use crate::web::error::{ErrorRenderer, SessionError};
+use crate::web::{FromRequest, HttpRequest};
+
+mod middleware;
+mod store;
+
+pub use self::middleware::{Sessions, SessionsMiddleware};
+pub use self::store::{CookieSessionStore, MemorySessionStore};
+pub use coo_kie::{Key, SameSite};
+
+/// Session state, a map of keys to json encoded values
+pub type SessionState = HashMap<String, String>;
+
+/// Storage backend for session state
+pub trait SessionStore: 'static {
+    /// Load session state by session key.
+    ///
+    /// Returns `None` if session does not exist or is expired.
+    fn load<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<SessionState>, SessionError>>;
+
+    /// Persist state of the new session, returns session key.
+    fn save<'a>(
+        &'a self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<String, SessionError>>;
+
+    /// Update state of the existing session, returns session key.
+    fn update<'a>(
+        &'a self,
+        key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<String, SessionError>>;
+
+    /// Extend session's time-to-live.
+    fn update_ttl<'a>(
+        &'a self,
+        key: &'a str,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<(), SessionError>>;
+
+    /// Delete session.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), SessionError>>;
+}
+
+/// Status of the session
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SessionStatus {
+    /// Session state has not been modified
+    Unchanged,
+    /// Session state has been modified
+    Changed,
+    /// Session has been removed
+    Purged,
+    /// Session key has been regenerated
+    Renewed,
+}
+
+/// The high-level interface you use to modify session data.
+///
+/// Session object is obtained with the extractor. Session state is shared
+/// between all copies of the session object within the same request.
+#[derive(Clone)]
+pub struct Session(Rc<RefCell<Inner>>);
+
+struct Inner {
+    state: SessionState,
+    status: SessionStatus,
+}
+
+impl Session {
+    fn new(state: SessionState) -> Self {
+        Session(Rc::new(RefCell::new(Inner {
+            state,
+            status: SessionStatus::Unchanged,
+        })))
+    }
+
+    /// Get a `value` from the session.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, SessionError> {
+        if let Some(val) = self.0.borrow().state.get(key) {
+            Ok(Some(
+                serde_json::from_str(val).map_err(SessionError::Deserialize)?,
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get all session entries.
+    pub fn entries(&self) -> SessionState {
+        self.0.borrow().state.clone()
+    }
+
+    /// Set a `value` from the session.
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) -> Result<(), SessionError> {
+        let mut inner = self.0.borrow_mut();
+        if inner.status != SessionStatus::Purged {
+            let val = serde_json::to_string(&value).map_err(SessionError::Serialize)?;
+            inner.state.insert(key.to_owned(), val);
+            inner.changed();
+        }
+        Ok(())
+    }
+
+    /// Remove value from the session.
+    pub fn remove(&self, key: &str) {
+        let mut inner = self.0.borrow_mut();
+        if inner.status != SessionStatus::Purged && inner.state.remove(key).is_some() {
+            inner.changed();
+        }
+    }
+
+    /// Clear the session.
+    pub fn clear(&self) {
+        let mut inner = self.0.borrow_mut();
+        if inner.status != SessionStatus::Purged && !inner.state.is_empty() {
+            inner.state.clear();
+            inner.changed();
+        }
+    }
+
+    /// Removes session, both client and server side.
+    pub fn purge(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.status = SessionStatus::Purged;
+        inner.state.clear();
+    }
+
+    /// Renews the session key, assigning existing session state to new key.
+    ///
+    /// Should be used after privilege level change, i.e. after login.
+    pub fn renew(&self) {
+        let mut inner = self.0.borrow_mut();
+        if inner.status != SessionStatus::Purged {
+            inner.status = SessionStatus::Renewed;
+        }
+    }
+
+    /// Returns session status
+    pub fn status(&self) -> SessionStatus {
+        self.0.borrow().status
+    }
+
+    fn into_parts(self) -> (SessionStatus, SessionState) {
+        let mut inner = self.0.borrow_mut();
+        (inner.status, std::mem::take(&mut inner.state))
+    }
+
+    /// Get session from request, creates empty session if it does not exist
+    fn get_session(req: &HttpRequest) -> Session {
+        if let Some(session) = req.extensions().get::<Session>() {
+            return session.clone();
+        }
+        let session = Session::new(SessionState::new());
+        req.extensions_mut().insert(session.clone());
+        session
+    }
+}
+
+impl Inner {
+    fn changed(&mut self) {
+        if self.status == SessionStatus::Unchanged {
+            self.status = SessionStatus::Changed;
+        }
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.0.borrow();
+        f.debug_struct("Session")
+            .field("status", &inner.status)
+            .field("state", &inner.state)
+            .finish()
+    }
+}
+
+/// Extractor implementation for Session type.
+///
+/// If [`Sessions`] middleware is not registered, changes to the session
+/// are not persisted.
+impl<Err: ErrorRenderer> FromRequest<Err> for Session {
+    type Error = Err::Container;
+    type Future = Ready<Session, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        Ready::Ok(Session::get_session(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{from_request, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_session() {
+        let req = TestRequest::default().to_http_request();
+        let session = from_request::<Session>(&req, &mut Payload::None)
+            .await
+            .unwrap();
+        assert_eq!(session.status(), SessionStatus::Unchanged);
+        assert!(format!("{:?}", session).contains("Session"));
+
+        session.insert("key", "value").unwrap();
+        session.insert("num", 10).unwrap();
+        assert_eq!(session.status(), SessionStatus::Changed);
+
+        // same session state
+        let session = from_request::<Session>(&req, &mut Payload::None)
+            .await
+            .unwrap();
+        assert_eq!(
+            session.get::<String>("key").unwrap(),
+            Some("value".to_string())
+        );
+        assert_eq!(session.get::<u32>("num").unwrap(), Some(10));
+        assert_eq!(session.get::<u32>("missing").unwrap(), None);
+        assert!(session.get::<u32>("key").is_err());
+        assert_eq!(session.entries().len(), 2);
+
+        session.remove("key");
+        assert_eq!(session.entries().len(), 1);
+        session.renew();
+        assert_eq!(session.status(), SessionStatus::Renewed);
+        session.insert("key2", "value").unwrap();
+        assert_eq!(session.status(), SessionStatus::Renewed);
+        session.clear();
+        assert!(session.entries().is_empty());
+
+        session.purge();
+        assert_eq!(session.status(), SessionStatus::Purged);
+        session.insert("key", "value").unwrap();
+        session.renew();
+        assert_eq!(session.status(), SessionStatus::Purged);
+        assert!(session.entries().is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+use std::sync::{Arc, Mutex};
+use std::{collections::HashMap, time::Duration, time::Instant};
+
+use rand::{distributions::Alphanumeric, Rng};
+
+use super::{SessionState, SessionStore};
+use crate::util::BoxFuture;
+use crate::web::error::SessionError;
+
+/// Period for removing expired sessions
+const SWEEP_PERIOD: Duration = Duration::from_secs(60);
+
+/// Session store that keeps whole session state in the session cookie.
+///
+/// Session state is serialized to json and sent to the client. Cookie content
+/// is signed or encrypted by [`Sessions`](super::Sessions) middleware, encoded
+/// cookie size is limited to 4Kb. Removed session could not be invalidated on
+/// server side, so client can re-use previous cookie value until it expires.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CookieSessionStore;
+
+impl SessionStore for CookieSessionStore {
+    fn load<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<SessionState>, SessionError>> {
+        Box::pin(async move { Ok(serde_json::from_str(key).ok()) })
+    }
+
+    fn save<'a>(
+        &'a self,
+        state: SessionState,
+        _: Duration,
+    ) -> BoxFuture<'a, Result<String, SessionError>> {
+        Box::pin(
+            async move { serde_json::to_string(&state).map_err(SessionError::Serialize) },
+        )
+    }
+
+    fn update<'a>(
+        &'a self,
+        _: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<String, SessionError>> {
+        self.save(state, ttl)
+    }
+
+    fn update_ttl<'a>(
+        &'a self,
+        _: &'a str,
+        _: Duration,
+    ) -> BoxFuture<'a, Result<(), SessionError>> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn delete<'a>(&'a self, _: &'a str) -> BoxFuture<'a, Result<(), SessionError>> {
+        Box::pin(async move { Ok(()) })
+    }
+}
+
+/// In-memory session store.
+///
+/// Sessions are stored in the process memory and are lost on restart.
+/// Store could be cloned, all clones share same sessions, so single
+/// store instance could be used by all server workers.
+///
+/// ```rust
+/// use ntex::web::{self, session, App, HttpServer};
+///
+/// fn main() {
+///     let key = session::Key::generate();
+///     let store = session::MemorySessionStore::default();
+///
+///     let srv = HttpServer::new(move || {
+///         App::new().wrap(session::Sessions::new(store.clone(), key.clone()))
+///     });
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemorySessionStore {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    sessions: HashMap<String, (SessionState, Instant)>,
+    last_sweep: Option<Instant>,
+}
+
+impl MemorySessionStore {
+    /// Number of active sessions
+    pub fn len(&self) -> usize {
+        let now = Instant::now();
+        let inner = self.inner.lock().unwrap();
+        inner
+            .sessions
+            .values()
+            .filter(|(_, exp)| *exp > now)
+            .count()
+    }
+
+    /// Returns `true` if the store has no active sessions
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn insert(&self, key: String, state: SessionState, ttl: Duration) {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+
+        // remove expired sessions
+        if inner
+            .last_sweep
+            .map(|t| now.duration_since(t) >= SWEEP_PERIOD)
+            .unwrap_or(true)
+        {
+            inner.sessions.retain(|_, (_, exp)| *exp > now);
+            inner.last_sweep = Some(now);
+        }
+        inner.sessions.insert(key, (state, now + ttl));
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn load<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<SessionState>, SessionError>> {
+        Box::pin(async move {
+            let inner = self.inner.lock().unwrap();
+            Ok(inner.sessions.get(key).and_then(|(state, exp)| {
+                if *exp > Instant::now() {
+                    Some(state.clone())
+                } else {
+                    None
+                }
+            }))
+        })
+    }
+
+    fn save<'a>(
+        &'a self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<String, SessionError>> {
+        Box::pin(async move {
+            let key: String = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(64)
+                .map(char::from)
+                .collect();
+            self.insert(key.clone(), state, ttl);
+            Ok(key)
+        })
+    }
+
+    fn update<'a>(
+        &'a self,
+        key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<String, SessionError>> {
+        Box::pin(async move {
+            self.insert(key.clone(), state, ttl);
+            Ok(key)
+        })
+    }
+
+    fn update_ttl<'a>(
+        &'a self,
+        key: &'a str,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<(), SessionError>> {
+        Box::pin(async move {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some((_, exp)) = inner.sessions.get_mut(key) {
+                *exp = Instant::now() + ttl;
+            }
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), SessionError>> {
+        Box::pin(async move {
+            self.inner.lock().unwrap().sessions.remove(key);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[crate::rt_test]
+    async fn test_cookie_store() {
+        let store = CookieSessionStore;
+        let mut state = SessionState::new();
+        state.insert("key".to_string(), "\"value\"".to_string());
+
+        let key = store
+            .save(state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.load(&key).await.unwrap(), Some(state.clone()));
+        assert_eq!(store.load("{bad").await.unwrap(), None);
+        assert!(store.update_ttl(&key, Duration::ZERO).await.is_ok());
+        assert!(store.delete(&key).await.is_ok());
+
+        state.insert("key2".to_string(), "1".to_string());
+        let key = store
+            .update(key, state, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(key.contains("key2"));
+    }
+
+    #[crate::rt_test]
+    async fn test_memory_store() {
+        let store = MemorySessionStore::default();
+        assert!(store.is_empty());
+
+        let mut state = SessionState::new();
+        state.insert("key".to_string(), "\"value\"".to_string());
+
+        let key = store
+            .save(state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(key.len(), 64);
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.clone().load(&key).await.unwrap(), Some(state.clone()));
+        assert_eq!(store.load("unknown").await.unwrap(), None);
+
+        state.insert("key2".to_string(), "1".to_string());
+        let key2 = store
+            .update(key.clone(), state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(key, key2);
+        assert_eq!(store.load(&key).await.unwrap(), Some(state.clone()));
+
+        // expired
+        store.update_ttl(&key, Duration::ZERO).await.unwrap();
+        assert_eq!(store.load(&key).await.unwrap(), None);
+        assert!(store.is_empty());
+
+        let key = store.save(state, Duration::from_secs(60)).await.unwrap();
+        store.delete(&key).await.unwrap();
+        assert_eq!(store.load(&key).await.unwrap(), None);
+    }
+}
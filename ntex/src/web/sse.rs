@@ -0,0 +1,253 @@
+//! Server-sent events responder
+use std::{error::Error, fmt, fmt::Write, task::Context, task::Poll, time::Duration};
+
+use crate::channel::mpsc::{self, SendError};
+use crate::http::body::{Body, BodySize, MessageBody};
+use crate::http::header::{self, HeaderValue};
+use crate::http::{sse, Response};
+use crate::time::{Interval, Millis, Seconds};
+use crate::util::{Bytes, BytesMut};
+use crate::web::responder::{Ready, Responder};
+use crate::web::{ErrorRenderer, HttpRequest};
+
+pub use crate::http::sse::Event;
+
+/// Server-sent events responder.
+///
+/// Events are sent to the client with [`SseSender`]. Responder periodically
+/// sends keep-alive comments, so disconnected clients are detected even if
+/// there are no events. Sender fails with an error after client has
+/// disconnected.
+///
+/// ```rust
+/// use ntex::web::{self, sse, App};
+///
+/// async fn events() -> web::Sse {
+///     let (tx, stream) = web::Sse::channel();
+///     ntex::rt::spawn(async move {
+///         let mut counter = 0;
+///         loop {
+///             counter += 1;
+///             let event = sse::Event::new(counter.to_string()).event("counter");
+///             if tx.send(event).is_err() {
+///                 // client is disconnected
+///                 break;
+///             }
+///             ntex::time::sleep(ntex::time::Seconds(1)).await;
+///         }
+///     });
+///     stream
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(web::resource("/events").to(events));
+/// }
+/// ```
+pub struct Sse {
+    rx: mpsc::Receiver<Event>,
+    keep_alive: Millis,
+    retry: Option<Duration>,
+}
+
+/// Sending side of the server-sent events stream
+#[derive(Clone, Debug)]
+pub struct SseSender {
+    tx: mpsc::Sender<Event>,
+}
+
+impl fmt::Debug for Sse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sse")
+            .field("keep_alive", &self.keep_alive)
+            .field("retry", &self.retry)
+            .finish()
+    }
+}
+
+impl Sse {
+    /// Create server-sent events responder and sender
+    pub fn channel() -> (SseSender, Sse) {
+        let (tx, rx) = mpsc::channel();
+        (
+            SseSender { tx },
+            Sse {
+                rx,
+                keep_alive: Seconds(15).into(),
+                retry: None,
+            },
+        )
+    }
+
+    /// Set keep-alive period.
+    ///
+    /// Keep-alive comment is sent to the client every period.
+    /// Set 0 to disable keep-alive. By default keep-alive is set to 15 seconds.
+    pub fn keep_alive<T: Into<Millis>>(mut self, period: T) -> Self {
+        self.keep_alive = period.into();
+        self
+    }
+
+    /// Set client's reconnection time
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl SseSender {
+    /// Send event to the client.
+    ///
+    /// Returns error if client is disconnected.
+    pub fn send(&self, event: Event) -> Result<(), SendError<Event>> {
+        self.tx.send(event)
+    }
+
+    /// Send event with data only
+    pub fn data<T: Into<String>>(&self, data: T) -> Result<(), SendError<Event>> {
+        self.send(Event::new(data))
+    }
+
+    /// Check if client is disconnected
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    /// Close events stream
+    pub fn close(&self) {
+        self.tx.close()
+    }
+}
+
+impl<Err: ErrorRenderer> Responder<Err> for Sse {
+    type Future = Ready<Response>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let mut buf = BytesMut::new();
+        if let Some(retry) = self.retry {
+            let _ = write!(buf, "retry: {}\n\n", retry.as_millis());
+        }
+
+        Response::Ok()
+            .content_type("text/event-stream")
+            .set_header(header::CACHE_CONTROL, "no-cache")
+            // compression buffers events
+            .set_header(
+                header::CONTENT_ENCODING,
+                HeaderValue::from_static("identity"),
+            )
+            .body(Body::from_message(SseBody {
+                buf,
+                rx: self.rx,
+                keep_alive: self.keep_alive.map(Interval::new),
+                closed: false,
+            }))
+            .into()
+    }
+}
+
+struct SseBody {
+    rx: mpsc::Receiver<Event>,
+    buf: BytesMut,
+    keep_alive: Option<Interval>,
+    closed: bool,
+}
+
+impl MessageBody for SseBody {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        while !self.closed {
+            match self.rx.poll_recv(cx) {
+                Poll::Ready(Some(ev)) => ev.encode(&mut self.buf),
+                Poll::Ready(None) => self.closed = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if !self.buf.is_empty() {
+            Poll::Ready(Some(Ok(self.buf.split().freeze())))
+        } else if self.closed {
+            Poll::Ready(None)
+        } else if let Some(ref ka) = self.keep_alive {
+            if ka.poll_tick(cx).is_ready() {
+                sse::encode_comment(&mut self.buf, "ping");
+                Poll::Ready(Some(Ok(self.buf.split().freeze())))
+            } else {
+                Poll::Pending
+            }
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::lazy;
+    use crate::web::test::{respond_to, TestRequest};
+    use crate::{http::StatusCode, time::sleep};
+
+    #[crate::rt_test]
+    async fn test_sse() {
+        let (tx, sse) = Sse::channel();
+        let sse = sse.retry(Duration::from_secs(1));
+        assert!(format!("{:?}", sse).contains("Sse"));
+
+        let req = TestRequest::default().to_http_request();
+        let mut resp = respond_to(sse, &req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+
+        let mut body = resp.take_body();
+        assert_eq!(body.size(), BodySize::Stream);
+        let chunk = lazy(|cx| body.poll_next_chunk(cx)).await;
+        assert_eq!(
+            chunk.unwrap().unwrap(),
+            Bytes::from_static(b"retry: 1000\n\n")
+        );
+        assert!(lazy(|cx| body.poll_next_chunk(cx)).await.is_pending());
+
+        tx.data("1").unwrap();
+        tx.clone().send(Event::new("2").event("e")).unwrap();
+        let chunk = lazy(|cx| body.poll_next_chunk(cx)).await;
+        assert_eq!(
+            chunk.unwrap().unwrap(),
+            Bytes::from_static(b"data: 1\n\nevent: e\ndata: 2\n\n")
+        );
+
+        tx.close();
+        assert!(lazy(|cx| body.poll_next_chunk(cx)).await.is_ready());
+
+        // disconnect
+        let (tx, sse) = Sse::channel();
+        drop(respond_to(sse, &req).await);
+        assert!(tx.is_closed());
+        assert!(tx.data("1").is_err());
+    }
+
+    #[crate::rt_test]
+    async fn test_sse_keep_alive() {
+        let (_tx, sse) = Sse::channel();
+        let req = TestRequest::default().to_http_request();
+        let mut resp = respond_to(sse.keep_alive(Millis(50)), &req).await;
+        let mut body = resp.take_body();
+
+        assert!(lazy(|cx| body.poll_next_chunk(cx)).await.is_pending());
+        sleep(Millis(100)).await;
+        let chunk = lazy(|cx| body.poll_next_chunk(cx)).await;
+        assert_eq!(chunk.unwrap().unwrap(), Bytes::from_static(b": ping\n\n"));
+    }
+}
@@ -0,0 +1,140 @@
+//! Typed header extractor
+use std::{fmt, ops};
+
+use crate::http::header::{TypedHeader, TypedHeaderError};
+use crate::web::error::ErrorRenderer;
+use crate::web::{FromRequest, HttpRequest};
+use crate::{http::Payload, util::Ready};
+
+/// Extract typed header from the request.
+///
+/// Request is rejected with `400 Bad Request` response if header is missing
+/// or malformed. Use `Option<Header<T>>` for optional headers.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::http::header::{Accept, UserAgent};
+/// use ntex::web::{self, types::Header};
+///
+/// async fn index(ua: Header<UserAgent>, accept: Option<Header<Accept>>) -> String {
+///     format!("User agent: {}, accept: {:?}", ua.0, accept)
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///        web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+#[derive(PartialEq, Eq, Clone)]
+pub struct Header<T>(pub T);
+
+impl<T> Header<T> {
+    /// Deconstruct to a inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Header<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Header<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Header<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T, Err> FromRequest<Err> for Header<T>
+where
+    T: TypedHeader,
+    Err: ErrorRenderer,
+{
+    type Error = TypedHeaderError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match T::from_headers(req.headers()) {
+            Ok(Some(hdr)) => Ready::Ok(Header(hdr)),
+            Ok(None) => Ready::Err(TypedHeaderError::Missing(T::name())),
+            Err(e) => {
+                log::debug!(
+                    "Failed during Header extractor parsing. \
+                     Request path: {:?}",
+                    req.path()
+                );
+                Ready::Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::{self, ContentLength, Server, UserAgent};
+    use crate::http::StatusCode;
+    use crate::web::test::{call_service, from_request, init_service, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_header_extract() {
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_LENGTH, "10")
+            .to_srv_request()
+            .into_parts();
+        let mut len = from_request::<Header<ContentLength>>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(*len, ContentLength(10));
+        assert_eq!(format!("{:?}", len), "ContentLength(10)");
+        len.0 = 11;
+        assert_eq!(len.into_inner(), ContentLength(11));
+
+        let res = from_request::<Header<UserAgent>>(&req, &mut pl).await;
+        assert_eq!(res.unwrap_err().to_string(), "Header user-agent is missing");
+        let res = from_request::<Option<Header<UserAgent>>>(&req, &mut pl).await;
+        assert_eq!(res.unwrap(), None);
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_LENGTH, "abc")
+            .to_srv_request()
+            .into_parts();
+        let res = from_request::<Header<ContentLength>>(&req, &mut pl).await;
+        assert_eq!(
+            res.unwrap_err(),
+            TypedHeaderError::Invalid(header::CONTENT_LENGTH)
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_header_response() {
+        let srv = init_service(App::new().service(web::resource("/").to(
+            |ua: Header<UserAgent>| async move {
+                HttpResponse::Ok()
+                    .typed_header(Server(ua.into_inner().0))
+                    .finish()
+            },
+        )))
+        .await;
+
+        let req = TestRequest::with_header(header::USER_AGENT, "ntex").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::SERVER).unwrap(), "ntex");
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
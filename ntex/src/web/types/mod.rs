@@ -1,14 +1,18 @@
 //! Extractor types
 
 pub(in crate::web) mod form;
+mod header;
 pub(in crate::web) mod json;
+pub(in crate::web) mod multipart;
 mod path;
 pub(in crate::web) mod payload;
 mod query;
 pub(in crate::web) mod state;
 
 pub use self::form::{Form, FormConfig};
+pub use self::header::Header;
 pub use self::json::{Json, JsonConfig};
+pub use self::multipart::{Field, Multipart, MultipartConfig};
 pub use self::path::Path;
 pub use self::payload::{Payload, PayloadConfig};
 pub use self::query::Query;
@@ -0,0 +1,864 @@
+//! Multipart form extractor
+use std::{cell::RefCell, fmt, pin::Pin, rc::Rc, task::Context, task::Poll};
+
+use mime::Mime;
+
+#[cfg(feature = "compress")]
+use crate::http::encoding::Decoder;
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::{HttpMessage, Payload};
+use crate::util::{poll_fn, ready, Bytes, BytesMut, Ready, Stream};
+use crate::web::error::{ErrorRenderer, MultipartError};
+use crate::web::{FromRequest, HttpRequest};
+
+const MAX_HEADERS: usize = 32;
+const MAX_HEADERS_SIZE: usize = 8192;
+const MAX_PADDING_SIZE: usize = 128;
+
+/// Multipart form data helper (`multipart/form-data`)
+///
+/// Streaming extractor for `multipart/form-data` request bodies. `Multipart`
+/// yields form fields one by one, each field is a stream of body chunks.
+/// Unread part of the current field is skipped when next field is requested.
+///
+/// [**MultipartConfig**](struct.MultipartConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, error::MultipartError};
+///
+/// async fn index(mut form: web::types::Multipart) -> Result<String, MultipartError> {
+///     let mut fields = Vec::new();
+///
+///     while let Some(field) = form.recv().await {
+///         let mut field = field?;
+///         let mut size = 0;
+///         while let Some(chunk) = field.recv().await {
+///             size += chunk?.len();
+///         }
+///         fields.push(format!(
+///             "{} ({:?}): {} bytes", field.name(), field.filename(), size
+///         ));
+///     }
+///     Ok(fields.join("\n"))
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/upload").route(web::post().to(index))
+///     );
+/// }
+/// ```
+pub struct Multipart {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl Multipart {
+    fn new(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        cfg: &MultipartConfig,
+    ) -> Result<Multipart, MultipartError> {
+        // check content type
+        let mt = match req.mime_type() {
+            Ok(Some(mt)) => mt,
+            _ => return Err(MultipartError::ContentType),
+        };
+        if mt.type_() != mime::MULTIPART || mt.subtype() != mime::FORM_DATA {
+            return Err(MultipartError::ContentType);
+        }
+        let boundary = match mt.get_param(mime::BOUNDARY) {
+            Some(b) if !b.as_str().is_empty() && b.as_str().len() <= 70 => b.as_str(),
+            _ => return Err(MultipartError::Boundary),
+        };
+
+        // check content length
+        if let Some(l) = req.headers().get(&header::CONTENT_LENGTH) {
+            if let Ok(Ok(l)) = l.to_str().map(|s| s.parse::<usize>()) {
+                if l > cfg.limit {
+                    return Err(MultipartError::Overflow { limit: cfg.limit });
+                }
+            }
+        }
+
+        #[cfg(feature = "compress")]
+        let stream = Decoder::from_headers(payload.take(), req.headers());
+        #[cfg(not(feature = "compress"))]
+        let stream = payload.take();
+
+        Ok(Multipart {
+            inner: Rc::new(RefCell::new(Inner {
+                stream,
+                buf: BytesMut::new(),
+                eof: false,
+                boundary: Bytes::from(format!("--{}", boundary)),
+                delimiter: Bytes::from(format!("\r\n--{}", boundary)),
+                state: State::Boundary,
+                field: 0,
+                field_size: 0,
+                field_limit: cfg.field_limit,
+                size: 0,
+                limit: cfg.limit,
+            })),
+        })
+    }
+
+    #[inline]
+    /// Attempt to pull out the next field of the multipart form.
+    pub async fn recv(&mut self) -> Option<Result<Field, MultipartError>> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Attempt to pull out the next field of the multipart form, registering
+    /// the current task for wakeup if the field is not yet available,
+    /// and returning None if the form is exhausted.
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Field, MultipartError>>> {
+        let mut inner = self.inner.borrow_mut();
+        match ready!(inner.poll_next_field(cx)) {
+            Some(Ok(headers)) => match Field::new(inner.field, headers, self.inner.clone())
+            {
+                Ok(field) => Poll::Ready(Some(Ok(field))),
+                Err(e) => {
+                    inner.state = State::Failed;
+                    Poll::Ready(Some(Err(e)))
+                }
+            },
+            Some(Err(e)) => Poll::Ready(Some(Err(e))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl Stream for Multipart {
+    type Item = Result<Field, MultipartError>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx)
+    }
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("state", &self.inner.borrow().state)
+            .finish()
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for Multipart {
+    type Error = MultipartError;
+    type Future = Ready<Multipart, MultipartError>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let tmp;
+        let cfg = if let Some(cfg) = req.app_state::<MultipartConfig>() {
+            cfg
+        } else {
+            tmp = MultipartConfig::default();
+            &tmp
+        };
+
+        Multipart::new(req, payload, cfg).into()
+    }
+}
+
+/// A single field of the multipart form
+///
+/// Field's body is a stream of chunks. Field stops yielding chunks
+/// as soon as the next field get requested from `Multipart`.
+pub struct Field {
+    id: usize,
+    name: String,
+    filename: Option<String>,
+    content_type: Option<Mime>,
+    headers: HeaderMap,
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl Field {
+    fn new(
+        id: usize,
+        headers: HeaderMap,
+        inner: Rc<RefCell<Inner>>,
+    ) -> Result<Field, MultipartError> {
+        let (name, filename) = headers
+            .get(&header::CONTENT_DISPOSITION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(MultipartError::ContentDisposition)
+            .and_then(parse_content_disposition)?;
+
+        let content_type = if let Some(ct) = headers.get(&header::CONTENT_TYPE) {
+            match ct.to_str().ok().and_then(|s| s.parse::<Mime>().ok()) {
+                Some(mt) => Some(mt),
+                None => return Err(MultipartError::ContentType),
+            }
+        } else {
+            None
+        };
+
+        Ok(Field {
+            id,
+            name,
+            filename,
+            content_type,
+            headers,
+            inner,
+        })
+    }
+
+    #[inline]
+    /// Field's headers
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    #[inline]
+    /// Field's name, from `Content-Disposition` header
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    /// Field's filename, from `Content-Disposition` header
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    #[inline]
+    /// Field's content type
+    ///
+    /// Returns `None` if part does not contain *Content-Type* header.
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    #[inline]
+    /// Attempt to pull out the next chunk of the field's body.
+    pub async fn recv(&mut self) -> Option<Result<Bytes, MultipartError>> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    #[inline]
+    /// Attempt to pull out the next chunk of the field's body, registering
+    /// the current task for wakeup if the chunk is not yet available,
+    /// and returning None if the field is exhausted.
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, MultipartError>>> {
+        self.inner.borrow_mut().poll_field_chunk(self.id, cx)
+    }
+}
+
+impl Stream for Field {
+    type Item = Result<Bytes, MultipartError>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx)
+    }
+}
+
+impl fmt::Debug for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("name", &self.name)
+            .field("filename", &self.filename)
+            .field("content_type", &self.content_type)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+/// Multipart extractor configuration
+///
+/// ```rust
+/// use ntex::web::{self, App};
+///
+/// async fn index(form: web::types::Multipart) -> &'static str {
+///     "Uploaded"
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/upload")
+///             // change `Multipart` extractor configuration
+///             .state(
+///                 web::types::MultipartConfig::default()
+///                     .limit(32 * 1024 * 1024)
+///                     .field_limit(16 * 1024 * 1024)
+///             )
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultipartConfig {
+    limit: usize,
+    field_limit: usize,
+}
+
+impl MultipartConfig {
+    /// Change max size of the whole multipart payload. By default max size is 8Mb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Change max size of a single field's body. By default max size is 2Mb
+    pub fn field_limit(mut self, limit: usize) -> Self {
+        self.field_limit = limit;
+        self
+    }
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        MultipartConfig {
+            limit: 8_388_608,
+            field_limit: 2_097_152,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    /// Looking for boundary
+    Boundary,
+    /// Reading part headers
+    Headers,
+    /// Reading part body
+    Body,
+    /// Final boundary is found
+    Eof,
+    /// Error occured, stream is unusable
+    Failed,
+}
+
+struct Inner {
+    #[cfg(feature = "compress")]
+    stream: Decoder<Payload>,
+    #[cfg(not(feature = "compress"))]
+    stream: Payload,
+    buf: BytesMut,
+    eof: bool,
+    boundary: Bytes,
+    delimiter: Bytes,
+    state: State,
+    field: usize,
+    field_size: usize,
+    field_limit: usize,
+    size: usize,
+    limit: usize,
+}
+
+impl Inner {
+    /// Read next chunk of the payload into the buffer
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), MultipartError>> {
+        if self.eof {
+            return Poll::Ready(Err(MultipartError::Incomplete));
+        }
+
+        match ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                self.size += chunk.len();
+                if self.size > self.limit {
+                    Poll::Ready(Err(MultipartError::Overflow { limit: self.limit }))
+                } else {
+                    self.buf.extend_from_slice(&chunk);
+                    Poll::Ready(Ok(()))
+                }
+            }
+            Some(Err(e)) => Poll::Ready(Err(e.into())),
+            None => {
+                self.eof = true;
+                Poll::Ready(Ok(()))
+            }
+        }
+    }
+
+    fn poll_next_field(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<HeaderMap, MultipartError>>> {
+        let result = ready!(self.poll_next_field_inner(cx));
+        if let Some(Err(_)) = result {
+            self.state = State::Failed;
+        }
+        Poll::Ready(result)
+    }
+
+    fn poll_next_field_inner(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<HeaderMap, MultipartError>>> {
+        loop {
+            let need_data = match self.state {
+                State::Eof | State::Failed => return Poll::Ready(None),
+                State::Body => {
+                    // skip unread part of the current field
+                    let id = self.field;
+                    match ready!(self.poll_field_chunk(id, cx)) {
+                        Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                        _ => false,
+                    }
+                }
+                State::Boundary => match self.read_boundary() {
+                    Ok(Some(true)) => {
+                        self.state = State::Headers;
+                        false
+                    }
+                    Ok(Some(false)) => {
+                        self.state = State::Eof;
+                        return Poll::Ready(None);
+                    }
+                    Ok(None) => true,
+                    Err(e) => return Poll::Ready(Some(Err(e))),
+                },
+                State::Headers => match self.read_headers() {
+                    Ok(Some(headers)) => {
+                        self.state = State::Body;
+                        self.field += 1;
+                        self.field_size = 0;
+                        return Poll::Ready(Some(Ok(headers)));
+                    }
+                    Ok(None) => true,
+                    Err(e) => return Poll::Ready(Some(Err(e))),
+                },
+            };
+
+            if need_data {
+                if let Err(e) = ready!(self.poll_fill(cx)) {
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+
+    fn poll_field_chunk(
+        &mut self,
+        id: usize,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, MultipartError>>> {
+        if self.field != id || self.state != State::Body {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            let chunk = if let Some(pos) = find(&self.buf, &self.delimiter) {
+                let chunk = self.buf.split_to(pos).freeze();
+                // leading CRLF belongs to the delimiter
+                let _ = self.buf.split_to(2);
+                self.state = State::Boundary;
+                chunk
+            } else if self.buf.len() >= self.delimiter.len() {
+                // tail of the buffer could contain part of the delimiter
+                let len = self.buf.len() + 1 - self.delimiter.len();
+                self.buf.split_to(len).freeze()
+            } else {
+                Bytes::new()
+            };
+
+            if !chunk.is_empty() {
+                self.field_size += chunk.len();
+                return if self.field_size > self.field_limit {
+                    self.state = State::Failed;
+                    Poll::Ready(Some(Err(MultipartError::FieldOverflow {
+                        limit: self.field_limit,
+                    })))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                };
+            } else if self.state != State::Body {
+                return Poll::Ready(None);
+            }
+
+            if let Err(e) = ready!(self.poll_fill(cx)) {
+                self.state = State::Failed;
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+
+    /// Find boundary in the buffer
+    ///
+    /// Returns `Some(true)` for part boundary and `Some(false)` for final boundary
+    fn read_boundary(&mut self) -> Result<Option<bool>, MultipartError> {
+        let pos = if let Some(pos) = find(&self.buf, &self.boundary) {
+            pos
+        } else {
+            // drop preamble, keep possible part of the boundary
+            let keep = self.boundary.len() - 1;
+            if self.buf.len() > keep {
+                let len = self.buf.len() - keep;
+                let _ = self.buf.split_to(len);
+            }
+            return Ok(None);
+        };
+
+        let rest = &self.buf[pos + self.boundary.len()..];
+        if rest.len() < 2 {
+            Ok(None)
+        } else if &rest[..2] == b"--" {
+            // epilogue is ignored
+            self.buf.clear();
+            Ok(Some(false))
+        } else if let Some(idx) = find(rest, b"\r\n") {
+            // boundary could be followed by transport padding
+            if rest[..idx].iter().any(|c| *c != b' ' && *c != b'\t') {
+                Err(MultipartError::Boundary)
+            } else {
+                let _ = self.buf.split_to(pos + self.boundary.len() + idx + 2);
+                Ok(Some(true))
+            }
+        } else if rest.len() > MAX_PADDING_SIZE {
+            Err(MultipartError::Boundary)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse part headers
+    fn read_headers(&mut self) -> Result<Option<HeaderMap>, MultipartError> {
+        let mut parsed = [httparse::EMPTY_HEADER; MAX_HEADERS];
+
+        match httparse::parse_headers(&self.buf, &mut parsed) {
+            Ok(httparse::Status::Complete((len, hdrs))) => {
+                let mut headers = HeaderMap::with_capacity(hdrs.len());
+                for h in hdrs {
+                    let name = HeaderName::from_bytes(h.name.as_bytes())
+                        .map_err(|_| MultipartError::Headers)?;
+                    let value = HeaderValue::from_bytes(h.value)
+                        .map_err(|_| MultipartError::Headers)?;
+                    headers.append(name, value);
+                }
+                let _ = self.buf.split_to(len);
+                Ok(Some(headers))
+            }
+            Ok(httparse::Status::Partial) => {
+                if self.buf.len() > MAX_HEADERS_SIZE {
+                    Err(MultipartError::Headers)
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(_) => Err(MultipartError::Headers),
+        }
+    }
+}
+
+fn find(buf: &[u8], needle: &[u8]) -> Option<usize> {
+    buf.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Parse `Content-Disposition` header of the form part,
+/// returns field's name and filename
+fn parse_content_disposition(
+    val: &str,
+) -> Result<(String, Option<String>), MultipartError> {
+    let (kind, mut rest) = match val.find(';') {
+        Some(idx) => (&val[..idx], &val[idx + 1..]),
+        None => (val, ""),
+    };
+    if !kind.trim().eq_ignore_ascii_case("form-data") {
+        return Err(MultipartError::ContentDisposition);
+    }
+
+    let mut name = None;
+    let mut filename = None;
+    let mut filename_ext = None;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ' ' || c == '\t' || c == ';');
+        if rest.is_empty() {
+            break;
+        }
+        let idx = rest.find('=').ok_or(MultipartError::ContentDisposition)?;
+        let key = rest[..idx].trim();
+        rest = rest[idx + 1..].trim_start();
+
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut end = None;
+            let mut chars = quoted.char_indices().peekable();
+            while let Some((idx, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        end = Some(idx + 1);
+                        break;
+                    }
+                    // browsers do not escape backslashes in filenames
+                    '\\' if matches!(chars.peek(), Some((_, '"' | '\\'))) => {
+                        value.push(chars.next().unwrap().1);
+                    }
+                    c => value.push(c),
+                }
+            }
+            rest = &quoted[end.ok_or(MultipartError::ContentDisposition)?..];
+            value
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+            let value = rest[..end].trim().to_string();
+            rest = &rest[end..];
+            value
+        };
+
+        if key.eq_ignore_ascii_case("name") {
+            name = Some(value);
+        } else if key.eq_ignore_ascii_case("filename") {
+            filename = Some(value);
+        } else if key.eq_ignore_ascii_case("filename*") {
+            filename_ext = decode_ext_value(&value);
+        }
+    }
+
+    match name {
+        Some(name) => Ok((name, filename_ext.or(filename))),
+        None => Err(MultipartError::ContentDisposition),
+    }
+}
+
+/// Decode RFC 5987 extended value, only utf-8 charset is supported
+fn decode_ext_value(val: &str) -> Option<String> {
+    let mut parts = val.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _lang = parts.next()?;
+    let value = parts.next()?;
+
+    if charset.eq_ignore_ascii_case("utf-8") {
+        percent_encoding::percent_decode_str(value)
+            .decode_utf8()
+            .ok()
+            .map(|s| s.into_owned())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use crate::http::{error, StatusCode};
+    use crate::web::test::{from_request, TestRequest};
+    use crate::web::{DefaultError, WebResponseError};
+
+    const BODY: &[u8] = b"preamble\r\n\
+        --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        test title\r\n\
+        --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"fn.txt\"\r\n\
+        Content-Type: text/plain; charset=utf-8\r\n\
+        \r\n\
+        file\r\ncontent\r\n\
+        --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+        Content-Disposition: form-data; name=\"empty\"\r\n\
+        \r\n\
+        \r\n\
+        --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n\
+        epilogue";
+
+    const CT: &str = "multipart/form-data; boundary=abbc761f78ff4d7cb7573b5a23f96ef0";
+
+    async fn read_field(field: &mut Field) -> Result<Bytes, MultipartError> {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = field.recv().await {
+            buf.extend_from_slice(&chunk?);
+        }
+        Ok(buf.freeze())
+    }
+
+    fn split_payload(body: &'static [u8], size: usize) -> Payload {
+        Payload::from_stream(futures_util::stream::iter(
+            body.chunks(size)
+                .map(|c| Ok::<_, error::PayloadError>(Bytes::from_static(c)))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    #[crate::rt_test]
+    async fn test_multipart() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, CT)
+            .set_payload(Bytes::from_static(BODY))
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(field.name(), "title");
+        assert_eq!(field.filename(), None);
+        assert!(field.content_type().is_none());
+        assert_eq!(read_field(&mut field).await.unwrap(), "test title");
+
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(field.name(), "file");
+        assert_eq!(field.filename(), Some("fn.txt"));
+        assert_eq!(field.content_type().unwrap().essence_str(), "text/plain");
+        assert!(field.headers().contains_key(CONTENT_TYPE));
+        assert!(format!("{:?}", field).contains("fn.txt"));
+        assert_eq!(read_field(&mut field).await.unwrap(), "file\r\ncontent");
+
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(field.name(), "empty");
+        assert_eq!(read_field(&mut field).await.unwrap(), "");
+
+        assert!(form.recv().await.is_none());
+        assert!(form.recv().await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_multipart_chunked() {
+        for size in [1, 3, 7, 16, 50] {
+            let (req, _) = TestRequest::with_header(CONTENT_TYPE, CT).to_http_parts();
+            let mut pl = split_payload(BODY, size);
+            let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+
+            let mut fields = Vec::new();
+            while let Some(field) = form.recv().await {
+                let mut field = field.unwrap();
+                let body = read_field(&mut field).await.unwrap();
+                fields.push((field.name().to_string(), body));
+            }
+            assert_eq!(
+                fields,
+                vec![
+                    ("title".to_string(), Bytes::from_static(b"test title")),
+                    ("file".to_string(), Bytes::from_static(b"file\r\ncontent")),
+                    ("empty".to_string(), Bytes::new()),
+                ]
+            );
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_multipart_skip_field() {
+        let (req, _) = TestRequest::with_header(CONTENT_TYPE, CT).to_http_parts();
+        let mut pl = split_payload(BODY, 5);
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+
+        let mut first = form.recv().await.unwrap().unwrap();
+        let mut second = form.recv().await.unwrap().unwrap();
+        assert_eq!(second.name(), "file");
+        assert!(first.recv().await.is_none());
+        assert_eq!(read_field(&mut second).await.unwrap(), "file\r\ncontent");
+    }
+
+    #[crate::rt_test]
+    async fn test_multipart_errors() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "text/plain")
+            .set_payload(Bytes::from_static(BODY))
+            .to_http_parts();
+        let res = from_request::<Multipart>(&req, &mut pl).await;
+        assert!(matches!(res, Err(MultipartError::ContentType)));
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "multipart/form-data")
+            .set_payload(Bytes::from_static(BODY))
+            .to_http_parts();
+        let res = from_request::<Multipart>(&req, &mut pl).await;
+        assert!(matches!(res, Err(MultipartError::Boundary)));
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, CT)
+            .header(CONTENT_LENGTH, "1000000000")
+            .to_http_parts();
+        let res = from_request::<Multipart>(&req, &mut pl).await;
+        assert!(matches!(res, Err(MultipartError::Overflow { .. })));
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, CT)
+            .set_payload(Bytes::from_static(&BODY[..255]))
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(read_field(&mut field).await.unwrap(), "test title");
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert!(matches!(
+            read_field(&mut field).await,
+            Err(MultipartError::Incomplete)
+        ));
+        assert!(form.recv().await.is_none());
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, CT)
+            .set_payload(Bytes::from_static(
+                b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+                  Content-Type: text/plain\r\n\r\ntest\r\n\
+                  --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+            ))
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+        assert!(matches!(
+            form.recv().await.unwrap(),
+            Err(MultipartError::ContentDisposition)
+        ));
+        assert!(form.recv().await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_multipart_limits() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, CT)
+            .set_payload(Bytes::from_static(BODY))
+            .state(MultipartConfig::default().field_limit(5))
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+        let mut field = form.recv().await.unwrap().unwrap();
+        let err = read_field(&mut field).await.err().unwrap();
+        assert!(matches!(err, MultipartError::FieldOverflow { limit: 5 }));
+        assert_eq!(
+            WebResponseError::<DefaultError>::status_code(&err),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert!(form.recv().await.is_none());
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, CT)
+            .set_payload(Bytes::from_static(BODY))
+            .state(MultipartConfig::default().limit(64))
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+        let err = form.recv().await.unwrap().err().unwrap();
+        assert!(matches!(err, MultipartError::Overflow { limit: 64 }));
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        assert_eq!(
+            parse_content_disposition("form-data; name=\"field\"").unwrap(),
+            ("field".to_string(), None)
+        );
+        assert_eq!(
+            parse_content_disposition(
+                "form-data; name=upload; filename=\"a;b \\\"c\\\".txt\""
+            )
+            .unwrap(),
+            ("upload".to_string(), Some("a;b \"c\".txt".to_string()))
+        );
+        assert_eq!(
+            parse_content_disposition(
+                "form-data; name=\"f\"; filename=\"C:\\dir\\file.txt\""
+            )
+            .unwrap(),
+            ("f".to_string(), Some("C:\\dir\\file.txt".to_string()))
+        );
+        assert_eq!(
+            parse_content_disposition(
+                "Form-Data; name=\"f\"; filename=\"euro.txt\"; filename*=UTF-8''%e2%82%ac.txt"
+            )
+            .unwrap(),
+            ("f".to_string(), Some("€.txt".to_string()))
+        );
+        assert!(parse_content_disposition("attachment; name=\"f\"").is_err());
+        assert!(parse_content_disposition("form-data; filename=\"f\"").is_err());
+        assert!(parse_content_disposition("form-data; name=\"f").is_err());
+    }
+}
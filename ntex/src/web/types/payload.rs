@@ -47,6 +47,14 @@ impl Payload {
         self.0
     }
 
+    #[inline]
+    /// Trailer headers of the payload.
+    ///
+    /// Trailers are available after payload is fully read.
+    pub fn trailers(&self) -> Option<header::HeaderMap> {
+        self.0.trailers()
+    }
+
     #[inline]
     /// Attempt to pull out the next value of this payload.
     pub async fn recv(&mut self) -> Option<Result<Bytes, error::PayloadError>> {
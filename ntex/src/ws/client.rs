@@ -1,5 +1,5 @@
 //! Websockets client
-use std::{cell::RefCell, fmt, marker, net, rc::Rc, str};
+use std::{cell::RefCell, fmt, future::Future, io, marker, net, rc::Rc, str};
 
 #[cfg(feature = "openssl")]
 use crate::connect::openssl;
@@ -12,22 +12,38 @@ use base64::{engine::general_purpose::STANDARD as base64, Engine};
 use nanorand::{Rng, WyRand};
 
 use crate::connect::{Connect, ConnectError, Connector};
+use crate::http::client::{proxy, ClientResponse, Proxy};
 use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
-use crate::http::{body::BodySize, client::ClientResponse, error::HttpError, h1};
+use crate::http::{body::BodySize, error::HttpError, h1};
 use crate::http::{ConnectionType, RequestHead, RequestHeadType, StatusCode, Uri};
 use crate::io::{
     Base, DispatchItem, Dispatcher, DispatcherConfig, Filter, Io, Layer, Sealed,
 };
 use crate::service::{apply_fn, into_service, IntoService, Pipeline, Service};
 use crate::time::{timeout, Millis, Seconds};
-use crate::{channel::mpsc, rt, util::Ready, ws};
+use crate::util::{BoxFuture, Ready};
+use crate::{channel::mpsc, rt, ws};
 
 use super::error::{WsClientBuilderError, WsClientError, WsError};
 use super::transport::WsTransport;
 
+/// Opens connection to the proxy server with configured connector
+type ProxyConnect<T> =
+    Rc<dyn Fn(&Pipeline<T>, Uri) -> BoxFuture<'static, Result<Io, ConnectError>>>;
+
+/// Starts client handshake over tunnelled connection
+type Handshake<F> = Rc<dyn Fn(Io, String) -> BoxFuture<'static, io::Result<Io<F>>>>;
+
+struct Tunnel<F, T> {
+    connect: ProxyConnect<T>,
+    handshake: Handshake<F>,
+}
+
 /// `WebSocket` client builder
 pub struct WsClient<F, T> {
     connector: Pipeline<T>,
+    proxy: Option<Proxy>,
+    tunnel: Option<Tunnel<F, T>>,
     head: Rc<RequestHead>,
     addr: Option<net::SocketAddr>,
     max_size: usize,
@@ -50,6 +66,8 @@ pub struct WsClientBuilder<F, T> {
 
 struct Inner<F, T> {
     connector: T,
+    proxy: Option<Proxy>,
+    tunnel: Option<Tunnel<F, T>>,
     pub(crate) head: RequestHead,
     addr: Option<net::SocketAddr>,
     max_size: usize,
@@ -152,10 +170,29 @@ where
             HeaderValue::try_from(key.as_str()).unwrap(),
         );
 
-        let msg = Connect::new(head.uri.clone()).set_addr(self.addr);
-        log::trace!("Open ws connection to {:?} addr: {:?}", head.uri, self.addr);
+        let server = self
+            .proxy
+            .as_ref()
+            .and_then(|proxy| proxy.select(&head.uri));
+        let io = if let Some(server) = server {
+            log::trace!("Open ws connection to {:?} via proxy", head.uri);
+
+            let tunnel = self.tunnel.as_ref().unwrap();
+            let io = (tunnel.connect)(&self.connector, server.uri().clone()).await?;
+            let response = proxy::tunnel(&io, &head.uri, server).await?;
+            if !response.status.is_success() {
+                return Err(WsClientError::ProxyTunnel(response.status));
+            }
 
-        let io = self.connector.call(msg).await?;
+            (tunnel.handshake)(io, head.uri.host().unwrap_or_default().to_string())
+                .await
+                .map_err(|e| WsClientError::Connect(ConnectError::Io(e)))?
+        } else {
+            let msg = Connect::new(head.uri.clone()).set_addr(self.addr);
+            log::trace!("Open ws connection to {:?} addr: {:?}", head.uri, self.addr);
+
+            self.connector.call(msg).await?
+        };
 
         // create Framed and send request
         let codec = h1::ClientCodec::default();
@@ -295,6 +332,14 @@ impl WsClientBuilder<Base, ()> {
                 head,
                 config,
                 connector: Connector::<Uri>::default(),
+                proxy: None,
+                tunnel: Some(Tunnel {
+                    connect: proxy_connect(|conn: &Pipeline<Connector<Uri>>, uri| {
+                        let conn = conn.clone();
+                        async move { conn.call(Connect::new(uri)).await }
+                    }),
+                    handshake: handshake(|io, _| async move { Ok(io) }),
+                }),
                 addr: None,
                 max_size: 65_536,
                 server_mode: false,
@@ -496,6 +541,19 @@ where
         self
     }
 
+    /// Use proxy for connection.
+    ///
+    /// Connection is tunnelled through the proxy server with `CONNECT` method.
+    /// Connection to the proxy server is opened with configured connector.
+    /// Proxy is not supported with custom connectors, only default, openssl
+    /// and rustls connectors could be used.
+    pub fn proxy(&mut self, proxy: Proxy) -> &mut Self {
+        if let Some(parts) = parts(&mut self.inner, &self.err) {
+            parts.proxy = Some(proxy);
+        }
+        self
+    }
+
     /// Use custom connector
     pub fn connector<F1, T1>(&mut self, connector: T1) -> WsClientBuilder<F1, T1>
     where
@@ -507,6 +565,8 @@ where
         WsClientBuilder {
             inner: Some(Inner {
                 connector,
+                proxy: inner.proxy,
+                tunnel: None,
                 head: inner.head,
                 addr: inner.addr,
                 max_size: inner.max_size,
@@ -529,7 +589,19 @@ where
         &mut self,
         connector: openssl::SslConnector,
     ) -> WsClientBuilder<Layer<openssl::SslFilter>, openssl::Connector<Uri>> {
-        self.connector(openssl::Connector::new(connector))
+        let tcp = Connector::<Uri>::default();
+        let mut builder =
+            self.connector(openssl::Connector::new(connector.clone()).connector(tcp));
+        if let Some(ref mut inner) = builder.inner {
+            inner.tunnel = Some(Tunnel {
+                connect: proxy_connect(move |_, uri| async move { tcp.connect(uri).await }),
+                handshake: handshake(move |io, host| {
+                    let connector = connector.clone();
+                    async move { proxy::openssl_handshake(&connector, io, &host).await }
+                }),
+            });
+        }
+        builder
     }
 
     #[cfg(feature = "rustls")]
@@ -538,7 +610,19 @@ where
         &mut self,
         config: std::sync::Arc<rustls::ClientConfig>,
     ) -> WsClientBuilder<Layer<rustls::TlsFilter>, rustls::Connector<Uri>> {
-        self.connector(rustls::Connector::from(config))
+        let tcp = Connector::<Uri>::default();
+        let mut builder =
+            self.connector(rustls::Connector::from(config.clone()).connector(tcp));
+        if let Some(ref mut inner) = builder.inner {
+            inner.tunnel = Some(Tunnel {
+                connect: proxy_connect(move |_, uri| async move { tcp.connect(uri).await }),
+                handshake: handshake(move |io, host| {
+                    let config = config.clone();
+                    async move { proxy::rustls_handshake(config, io, &host).await }
+                }),
+            });
+        }
+        builder
     }
 
     /// This method construct new `WsClientBuilder`
@@ -562,6 +646,10 @@ where
         // #[allow(unused_mut)]
         let mut inner = self.inner.take().expect("cannot reuse WsClient builder");
 
+        if inner.proxy.is_some() && inner.tunnel.is_none() {
+            return Err(WsClientBuilderError::ProxyNotSupported);
+        }
+
         // validate uri
         let uri = &inner.head.uri;
         if uri.host().is_none() {
@@ -632,6 +720,8 @@ where
 
         Ok(WsClient {
             connector: inner.connector.into(),
+            proxy: inner.proxy,
+            tunnel: inner.tunnel,
             head: Rc::new(inner.head),
             addr: inner.addr,
             max_size: inner.max_size,
@@ -644,6 +734,32 @@ where
     }
 }
 
+fn proxy_connect<T, H, R>(f: H) -> ProxyConnect<T>
+where
+    H: Fn(&Pipeline<T>, Uri) -> R + 'static,
+    R: Future<Output = Result<Io, ConnectError>> + 'static,
+{
+    Rc::new(
+        move |conn: &Pipeline<T>,
+              uri: Uri|
+              -> BoxFuture<'static, Result<Io, ConnectError>> {
+            Box::pin(f(conn, uri))
+        },
+    )
+}
+
+fn handshake<F, H, R>(f: H) -> Handshake<F>
+where
+    H: Fn(Io, String) -> R + 'static,
+    R: Future<Output = io::Result<Io<F>>> + 'static,
+{
+    Rc::new(
+        move |io: Io, host: String| -> BoxFuture<'static, io::Result<Io<F>>> {
+            Box::pin(f(io, host))
+        },
+    )
+}
+
 #[inline]
 fn parts<'a, F, T>(
     parts: &'a mut Option<Inner<F, T>>,
@@ -851,6 +967,25 @@ mod tests {
         assert!(matches!(err, WsClientBuilderError::MissingHost));
     }
 
+    #[crate::rt_test]
+    async fn proxy() {
+        let proxy = Proxy::all("http://localhost:3128").unwrap();
+        let client = WsClient::build("ws://localhost")
+            .proxy(proxy.clone())
+            .finish()
+            .unwrap();
+        assert!(client.proxy.is_some());
+        assert!(client.tunnel.is_some());
+
+        let err = WsClient::build("ws://localhost")
+            .proxy(proxy)
+            .connector(Connector::<Uri>::default())
+            .finish()
+            .err()
+            .unwrap();
+        assert!(matches!(err, WsClientBuilderError::ProxyNotSupported));
+    }
+
     #[crate::rt_test]
     async fn basic_auth() {
         let client = WsClient::build("http://localhost")
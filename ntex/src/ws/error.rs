@@ -71,6 +71,8 @@ pub enum WsClientBuilderError {
     MissingHost,
     #[error("Url parse error: {0}")]
     Http(#[from] HttpError),
+    #[error("Proxy is not supported by custom connector")]
+    ProxyNotSupported,
 }
 
 /// Websocket client error
@@ -106,6 +108,9 @@ pub enum WsClientError {
     /// Failed to connect to host
     #[error("Failed to connect to host: {0}")]
     Connect(#[from] ConnectError),
+    /// Proxy server refused to open tunnel
+    #[error("Proxy tunnel request failed with status: {0}")]
+    ProxyTunnel(StatusCode),
     /// Connector has been disconnected
     #[error("Connector has been disconnected: {0:?}")]
     Disconnected(Option<io::Error>),
@@ -87,3 +87,185 @@ async fn test_with_query_parameter() {
     let response = request.send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[ntex::test]
+async fn test_resolver() {
+    use ntex::connect::CachingResolver;
+    use ntex::http::client::{Client, Connector};
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .h1(|_| Ready::Ok::<_, io::Error>(Response::Ok().finish()))
+            .map(|_| ())
+    });
+
+    let resolver = CachingResolver::new().host("ntex.test", "127.0.0.1".parse().unwrap());
+    let client = Client::build()
+        .connector(Connector::default().resolver(resolver).finish())
+        .finish();
+
+    let response = client
+        .get(format!("http://ntex.test:{}/", srv.addr().port()))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[ntex::test]
+async fn test_proxy() {
+    use ntex::http::client::{Client, Proxy};
+    use ntex::http::header;
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .finish(|req: Request| {
+                let auth = req.headers().get(header::PROXY_AUTHORIZATION);
+                if auth.map(|v| v == "Basic dXNlcjpwYXNz").unwrap_or(false) {
+                    Ready::Ok::<_, io::Error>(Response::Ok().body(req.uri().to_string()))
+                } else {
+                    Ready::Ok::<_, io::Error>(
+                        Response::ProxyAuthenticationRequired().finish(),
+                    )
+                }
+            })
+            .map(|_| ())
+    });
+
+    let proxy = Proxy::http(format!("http://{}", srv.addr()))
+        .unwrap()
+        .basic_auth("user", Some("pass"));
+    let client = Client::build().proxy(proxy).finish();
+
+    let mut response = client
+        .get("http://example.com/test?q=1")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"http://example.com/test?q=1"));
+}
+
+#[cfg(feature = "cookie")]
+#[ntex::test]
+async fn test_cookie_jar() {
+    use ntex::http::client::{Client, CookieJar};
+    use ntex::http::header;
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .finish(|req: Request| {
+                if req.path() == "/login" {
+                    Ready::Ok::<_, io::Error>(
+                        Response::Ok()
+                            .header(header::SET_COOKIE, "session=abc; Path=/; HttpOnly")
+                            .header(header::SET_COOKIE, "tmp=1; Path=/login")
+                            .finish(),
+                    )
+                } else {
+                    let cookies = req
+                        .headers()
+                        .get(header::COOKIE)
+                        .map(|v| Bytes::copy_from_slice(v.as_bytes()))
+                        .unwrap_or_default();
+                    Ready::Ok::<_, io::Error>(Response::Ok().body(cookies))
+                }
+            })
+            .map(|_| ())
+    });
+
+    let jar = CookieJar::new();
+    let client = Client::build().cookie_jar(jar.clone()).finish();
+
+    let response = client.get(srv.url("/login")).send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(jar.len(), 2);
+
+    let mut response = client.get(srv.url("/")).send().await.unwrap();
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"session=abc"));
+
+    // merge with request cookies
+    let mut response = client
+        .get(srv.url("/"))
+        .header(header::COOKIE, "a=b")
+        .send()
+        .await
+        .unwrap();
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"a=b; session=abc"));
+
+    // restore jar
+    let state = serde_json::to_string(&jar).unwrap();
+    let jar: CookieJar = serde_json::from_str(&state).unwrap();
+    let client = Client::build().cookie_jar(jar).finish();
+    let mut response = client.get(srv.url("/")).send().await.unwrap();
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"session=abc"));
+}
+
+#[ntex::test]
+async fn test_client_middleware() {
+    use std::{cell::Cell, rc::Rc};
+
+    use ntex::http::client::{Client, ClientService, ServiceRequest};
+    use ntex::http::{header, StatusCode};
+    use ntex::service::{boxed, fn_service, Middleware, Pipeline};
+
+    struct Signer(Rc<Cell<usize>>);
+
+    impl Middleware<ClientService> for Signer {
+        type Service = ClientService;
+
+        fn create(&self, service: ClientService) -> ClientService {
+            let service = Pipeline::new(service);
+            let counter = self.0.clone();
+            boxed::service(fn_service(move |mut req: ServiceRequest| {
+                let service = service.clone();
+                counter.set(counter.get() + 1);
+                async move {
+                    req.head_mut().headers.insert(
+                        header::AUTHORIZATION,
+                        header::HeaderValue::from_static("signed"),
+                    );
+                    let res = service.call(req).await?;
+                    assert_eq!(res.status(), StatusCode::OK);
+                    Ok(res)
+                }
+            }))
+        }
+    }
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .finish(|req: Request| {
+                if req.path() == "/redirect" {
+                    Ready::Ok::<_, io::Error>(
+                        Response::Found()
+                            .header(header::LOCATION, "/target")
+                            .finish(),
+                    )
+                } else if req.headers().get(header::AUTHORIZATION).is_some() {
+                    Ready::Ok::<_, io::Error>(Response::Ok().body(req.path().to_string()))
+                } else {
+                    Ready::Ok::<_, io::Error>(Response::Unauthorized().finish())
+                }
+            })
+            .map(|_| ())
+    });
+
+    let counter = Rc::new(Cell::new(0));
+    let client = Client::build().wrap(Signer(counter.clone())).finish();
+
+    let mut response = client.get(srv.url("/redirect")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"/target"));
+    assert_eq!(counter.get(), 1);
+
+    // redirects are disabled
+    let client = Client::build().disable_redirects().finish();
+    let response = client.get(srv.url("/redirect")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::FOUND);
+}
@@ -92,6 +92,41 @@ async fn test_h1() -> io::Result<()> {
     Ok(())
 }
 
+#[ntex::test]
+async fn test_resolver() -> io::Result<()> {
+    use ntex::connect::CachingResolver;
+    use ntex::http::client::{Client, Connector};
+    use tls_openssl::ssl::{SslConnector, SslVerifyMode};
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .h1(|_| Ready::Ok::<_, io::Error>(Response::Ok().finish()))
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let mut ssl = SslConnector::builder(SslMethod::tls()).unwrap();
+    ssl.set_verify(SslVerifyMode::NONE);
+    let resolver = CachingResolver::new().host("ntex.test", "127.0.0.1".parse().unwrap());
+    let client = Client::build()
+        .connector(
+            Connector::default()
+                .openssl(ssl.build())
+                .resolver(resolver)
+                .attempt_delay(Millis(100))
+                .finish(),
+        )
+        .finish();
+
+    let response = client
+        .get(format!("https://ntex.test:{}/", srv.addr().port()))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    Ok(())
+}
+
 #[ntex::test]
 async fn test_h2_1() -> io::Result<()> {
     let srv = test_server(move || {
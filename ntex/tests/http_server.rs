@@ -10,8 +10,10 @@ use ntex::http::test::server as test_server;
 use ntex::http::{
     body, HttpService, KeepAlive, Method, Request, Response, StatusCode, Version,
 };
+use ntex::io::{filter, ProxyProtocol};
+use ntex::service::{chain_factory, fn_service, ServiceFactory};
 use ntex::time::{sleep, timeout, Millis, Seconds};
-use ntex::{service::fn_service, util::Bytes, util::Ready, web::error};
+use ntex::{util::Bytes, util::Ready, web::error};
 
 #[ntex::test]
 async fn test_h1() {
@@ -30,6 +32,38 @@ async fn test_h1() {
     assert!(response.status().is_success());
 }
 
+#[ntex::test]
+async fn test_h1_proxy_protocol() {
+    let srv = test_server(|| {
+        chain_factory(filter(ProxyProtocol::new()))
+            .map_err(|_| ())
+            .and_then(
+                HttpService::build()
+                    .h1(|req: Request| {
+                        let addr = req.peer_addr().unwrap();
+                        Ready::Ok::<_, io::Error>(Response::Ok().body(addr.to_string()))
+                    })
+                    .map_err(|_| ()),
+            )
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"PROXY TCP4 10.0.0.1 10.0.0.2 1234 80\r\nGET / HTTP/1.1\r\nconnection: close\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.ends_with("10.0.0.1:1234"));
+
+    // connections without header are rejected
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.is_empty());
+}
+
 #[ntex::test]
 async fn test_h1_2() {
     let srv = test_server(|| {
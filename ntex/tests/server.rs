@@ -3,7 +3,7 @@ use std::{io, io::Read, net, sync::mpsc, sync::Arc, thread, time};
 
 use ntex::codec::BytesCodec;
 use ntex::io::Io;
-use ntex::server::{Server, TestServer};
+use ntex::server::{ConnectionLimits, Server, TestServer};
 use ntex::service::fn_service;
 use ntex::util::{Bytes, Ready};
 
@@ -18,6 +18,7 @@ fn test_bind() {
             let srv = Server::build()
                 .workers(1)
                 .disable_signals()
+                .handover_timeout(ntex::time::Millis(300))
                 .bind("test", addr, move |_| {
                     fn_service(|_| Ready::Ok::<_, ()>(()))
                 })
@@ -128,6 +129,246 @@ async fn test_run() {
     let _ = h.join();
 }
 
+#[ntex::test]
+#[cfg(unix)]
+async fn test_handover_failed() {
+    let addr = TestServer::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        let sys = ntex::rt::System::new("test");
+        sys.run(move || {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind("test", addr, move |_| {
+                    fn_service(|_| Ready::Ok::<_, ()>(()))
+                })
+                .unwrap()
+                .run();
+            let _ = tx.send((srv, ntex::rt::System::current()));
+            Ok(())
+        })
+    });
+    let (srv, sys) = rx.recv().unwrap();
+    thread::sleep(time::Duration::from_millis(300));
+
+    // child process exits without adopting listeners
+    let res = srv.handover(std::process::Command::new("true")).await;
+    assert!(res.is_err());
+
+    // child process does not report readiness in time
+    let mut cmd = std::process::Command::new("sleep");
+    cmd.arg("10");
+    let res = srv.handover(cmd).await;
+    assert_eq!(res.unwrap_err().kind(), io::ErrorKind::TimedOut);
+
+    // server keeps accepting connections
+    thread::sleep(time::Duration::from_millis(100));
+    assert!(net::TcpStream::connect(addr).is_ok());
+
+    srv.stop(false).await;
+    sys.stop();
+    let _ = h.join();
+}
+
+#[ntex::test]
+#[cfg(unix)]
+async fn test_handover() {
+    const ADDR: &str = "NTEX_TEST_HANDOVER_ADDR";
+
+    // child process, test binary is re-executed by the parent
+    if let Ok(addr) = std::env::var(ADDR) {
+        let addr: net::SocketAddr = addr.parse().unwrap();
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let sys = ntex::rt::System::new("child");
+            sys.run(move || {
+                let srv = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .from_env_listeners()?
+                    .bind("test", addr, move |_| {
+                        fn_service(|io: Io| async move {
+                            io.send(Bytes::from_static(b"child"), &BytesCodec)
+                                .await
+                                .unwrap();
+                            Ok::<_, ()>(())
+                        })
+                    })?
+                    .run();
+                let _ = tx.send(srv);
+                Ok(())
+            })
+        });
+        let srv = rx.recv().unwrap();
+        ntex::time::sleep(ntex::time::Millis(2_000)).await;
+        srv.stop(true).await;
+        return;
+    }
+
+    let addr = TestServer::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        let sys = ntex::rt::System::new("test");
+        sys.run(move || {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind("test", addr, move |_| {
+                    fn_service(|io: Io| async move {
+                        // in-flight request
+                        ntex::time::sleep(ntex::time::Millis(500)).await;
+                        io.send(Bytes::from_static(b"parent"), &BytesCodec)
+                            .await
+                            .unwrap();
+                        Ok::<_, ()>(())
+                    })
+                })
+                .unwrap()
+                .run();
+            let _ = tx.send((srv, ntex::rt::System::current()));
+            Ok(())
+        })
+    });
+    let (srv, sys) = rx.recv().unwrap();
+    thread::sleep(time::Duration::from_millis(300));
+
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(time::Duration::from_secs(5)))
+        .unwrap();
+    thread::sleep(time::Duration::from_millis(100));
+
+    let mut cmd = std::process::Command::new(std::env::current_exe().unwrap());
+    cmd.args(["--exact", "test_handover"])
+        .env(ADDR, addr.to_string())
+        .stdout(std::process::Stdio::null());
+    srv.handover(cmd).await.unwrap();
+
+    // parent drains in-flight connection
+    let mut buf = [0u8; 6];
+    conn.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"parent");
+
+    // child accepts new connections
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(time::Duration::from_secs(5)))
+        .unwrap();
+    let mut buf = [0u8; 5];
+    conn.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"child");
+
+    sys.stop();
+    let _ = h.join();
+}
+
+#[ntex::test]
+async fn test_metrics() {
+    let addr = TestServer::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        let sys = ntex::rt::System::new("test");
+        sys.run(move || {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind("test", addr, move |_| {
+                    fn_service(|io: Io| async move {
+                        io.send(Bytes::from_static(b"test"), &BytesCodec)
+                            .await
+                            .unwrap();
+                        Ok::<_, ()>(())
+                    })
+                })
+                .unwrap()
+                .run();
+            let _ = tx.send((srv, ntex::rt::System::current()));
+            Ok(())
+        })
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut buf = [0u8; 4];
+    for _ in 0..2 {
+        let mut conn = net::TcpStream::connect(addr).unwrap();
+        let _ = conn.read_exact(&mut buf);
+        assert_eq!(buf, b"test"[..]);
+    }
+    thread::sleep(time::Duration::from_millis(100));
+
+    let metrics = srv.metrics().await.unwrap();
+    assert_eq!(metrics.accepted("test"), 2);
+    assert_eq!(metrics.accepted("unknown"), 0);
+    assert_eq!(metrics.backpressure_pauses(), 0);
+    assert!(metrics
+        .render()
+        .contains("ntex_connections_accepted_total{listener=\"test\""));
+
+    srv.stop(false).await;
+    sys.stop();
+    let _ = h.join();
+}
+
+#[ntex::test]
+async fn test_limits() {
+    let addr = TestServer::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        let sys = ntex::rt::System::new("test");
+        sys.run(move || {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind("test", addr, move |_| {
+                    fn_service(|io: Io| async move {
+                        io.send(Bytes::from_static(b"test"), &BytesCodec)
+                            .await
+                            .unwrap();
+                        ntex::time::sleep(ntex::time::Millis(500)).await;
+                        Ok::<_, ()>(())
+                    })
+                })
+                .unwrap()
+                .set_limits("test", ConnectionLimits::new().maxconn(1))
+                .run();
+            let _ = tx.send((srv, ntex::rt::System::current()));
+            Ok(())
+        })
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut buf = [0u8; 4];
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    let _ = conn.read_exact(&mut buf);
+    assert_eq!(buf, b"test"[..]);
+
+    // second connection is over the limit
+    let mut conn2 = net::TcpStream::connect(addr).unwrap();
+    conn2
+        .set_read_timeout(Some(time::Duration::from_millis(300)))
+        .unwrap();
+    assert!(conn2.read_exact(&mut buf).is_err());
+
+    // connection slot is released
+    thread::sleep(time::Duration::from_millis(500));
+    let mut buf = [0u8; 4];
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    let _ = conn.read_exact(&mut buf);
+    assert_eq!(buf, b"test"[..]);
+
+    let metrics = srv.metrics().await.unwrap();
+    assert_eq!(metrics.accepted("test"), 3);
+    assert_eq!(metrics.rejected("test"), 1);
+
+    srv.stop(false).await;
+    sys.stop();
+    let _ = h.join();
+}
+
 #[test]
 #[cfg(feature = "tokio")]
 fn test_on_worker_start() {
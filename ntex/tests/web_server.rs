@@ -7,15 +7,17 @@ use flate2::Compression;
 use rand::{distributions::Alphanumeric, Rng};
 use thiserror::Error;
 
+use ntex::http::body::{Body, BodySize, MessageBody};
 use ntex::http::header::{
-    ContentEncoding, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE,
-    TRANSFER_ENCODING,
+    ContentEncoding, HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING,
+    CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING,
 };
-use ntex::http::{body::Body, client, ConnectionType, Method, StatusCode};
+use ntex::http::{client, ConnectionType, Method, StatusCode};
 use ntex::time::{sleep, Millis, Seconds, Sleep};
 use ntex::util::{ready, Bytes, Ready, Stream};
 
-use ntex::web::{self, middleware::Compress, test};
+use ntex::web::middleware::{Compress, Logger};
+use ntex::web::{self, test};
 use ntex::web::{App, BodyEncoding, HttpRequest, HttpResponse, WebResponseError};
 
 const STR: &str = "Hello World Hello World Hello World Hello World Hello World \
@@ -345,6 +347,73 @@ async fn test_body_br_streaming() {
     assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
 }
 
+struct TrailersBody(Option<Bytes>, Option<HeaderMap>);
+
+impl MessageBody for TrailersBody {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn std::error::Error>>>> {
+        Poll::Ready(self.0.take().map(Ok))
+    }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.1.take()
+    }
+}
+
+#[ntex::test]
+async fn test_body_trailers_logger_compress() {
+    let srv = test::server_with(test::config().h1(), || {
+        App::new()
+            .wrap(Compress::new(ContentEncoding::Gzip))
+            .wrap(Logger::default())
+            .service(web::resource("/").route(web::get().to(move || async {
+                let mut trailers = HeaderMap::new();
+                trailers.insert(
+                    HeaderName::from_static("x-checksum"),
+                    HeaderValue::from_static("1234"),
+                );
+                HttpResponse::Ok().body(Body::from_message(TrailersBody(
+                    Some(Bytes::from_static(STR.as_ref())),
+                    Some(trailers),
+                )))
+            })))
+    });
+
+    let mut response = srv
+        .get("/")
+        .no_decompress()
+        .header(ACCEPT_ENCODING, "gzip")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(TRANSFER_ENCODING).unwrap(),
+        &b"chunked"[..]
+    );
+    assert_eq!(
+        response.headers().get(CONTENT_ENCODING).unwrap(),
+        &b"gzip"[..]
+    );
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    let trailers = response.trailers().unwrap();
+    assert_eq!(trailers.get("x-checksum").unwrap(), "1234");
+
+    // decode
+    let mut e = GzDecoder::new(&bytes[..]);
+    let mut dec = Vec::new();
+    e.read_to_end(&mut dec).unwrap();
+    assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
+}
+
 #[ntex::test]
 async fn test_head_binary() {
     let srv = test::server_with(test::config().h1(), || {
@@ -1236,3 +1305,57 @@ async fn web_no_ws_payload() {
     let body = response.body().await.unwrap();
     assert_eq!(body, STR);
 }
+
+#[ntex::test]
+async fn test_client_multipart() {
+    use ntex::http::body::SizedStream;
+    use ntex::web::{error::MultipartError, types::Multipart};
+
+    async fn upload(mut form: Multipart) -> Result<String, MultipartError> {
+        let mut fields = Vec::new();
+        while let Some(field) = form.recv().await {
+            let mut field = field?;
+            let mut data = Vec::new();
+            while let Some(chunk) = field.recv().await {
+                data.extend_from_slice(&chunk?);
+            }
+            fields.push(format!(
+                "{}:{:?}:{}",
+                field.name(),
+                field.filename(),
+                String::from_utf8_lossy(&data)
+            ));
+        }
+        Ok(fields.join("\n"))
+    }
+
+    let srv = test::server(|| App::new().service(web::resource("/").to(upload)));
+
+    let (tx, rx) = ntex::channel::mpsc::channel::<Result<_, Box<dyn std::error::Error>>>();
+    let _ = tx.send(Ok(Bytes::from_static(b"str")));
+    let _ = tx.send(Ok(Bytes::from_static(b"eam")));
+    drop(tx);
+
+    let form = client::Multipart::new()
+        .text("name", "ntex")
+        .part("file", client::Part::bytes("content").file_name("test.txt"))
+        .part(
+            "stream",
+            client::Part::stream(SizedStream::new(6, rx)).file_name("test.bin"),
+        );
+    let mut response = srv
+        .post("/")
+        .content_type(form.content_type())
+        .send_body(form)
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let bytes = response.body().await.unwrap();
+    assert_eq!(
+        bytes,
+        Bytes::from_static(
+            b"name:None:ntex\nfile:Some(\"test.txt\"):content\nstream:Some(\"test.bin\"):stream"
+        )
+    );
+}